@@ -0,0 +1,262 @@
+mod sentry;
+
+use std::fs;
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use twox_hash::XxHash64;
+
+use crate::build::shell_command;
+use crate::settings::toml::{Target, TargetType, UploadFormat};
+use crate::terminal::message::{Message, StdErr};
+use crate::wranglerjs;
+
+use sentry::SentryUploader;
+
+/// A single file emitted by the build.
+#[derive(Clone, Debug, Serialize)]
+pub struct Artifact {
+    pub path: PathBuf,
+    /// Name of the file relative to the directory it was emitted into.
+    pub name: String,
+    pub hash: String,
+    /// Size in bytes.
+    pub size: u64,
+}
+
+/// Everything a post-build hook needs to know about a build: the bundles that were
+/// uploaded, the source maps that sit next to them, and a version identifying the script.
+#[derive(Clone, Debug, Serialize)]
+pub struct BuildArtifacts {
+    pub script_name: String,
+    pub script_version: String,
+    pub bundles: Vec<Artifact>,
+    pub source_maps: Vec<Artifact>,
+}
+
+/// An integration that consumes build artifacts once a worker has been built,
+/// e.g. to forward source maps to an error tracker.
+pub trait PostBuildHook {
+    fn name(&self) -> String;
+    fn run(&self, artifacts: &BuildArtifacts) -> Result<()>;
+}
+
+/// Runs an arbitrary command, writing the build artifacts as JSON to its stdin.
+struct CommandHook {
+    command: String,
+}
+
+impl PostBuildHook for CommandHook {
+    fn name(&self) -> String {
+        self.command.clone()
+    }
+
+    fn run(&self, artifacts: &BuildArtifacts) -> Result<()> {
+        let mut child = shell_command(&self.command)
+            .env("WRANGLER_SCRIPT_NAME", &artifacts.script_name)
+            .env("WRANGLER_SCRIPT_VERSION", &artifacts.script_version)
+            .stdin(Stdio::piped())
+            .spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(serde_json::to_string(artifacts)?.as_bytes())?;
+        }
+
+        let status = child.wait()?;
+        if !status.success() {
+            anyhow::bail!("exited with {}", status);
+        }
+
+        Ok(())
+    }
+}
+
+/// Collects the build artifacts for `target` and hands them to every configured hook.
+/// Hook failures are reported but never fail the build they follow.
+pub fn run_post_build_hooks(target: &Target) -> Result<()> {
+    let hooks = match &target.hooks {
+        Some(hooks) if !hooks.is_empty() => hooks,
+        _ => return Ok(()),
+    };
+
+    let artifacts = match BuildArtifacts::collect(target) {
+        Ok(artifacts) => artifacts,
+        Err(e) => {
+            StdErr::warn(&format!(
+                "Skipping post-build hooks, the build artifacts could not be collected: {}",
+                e
+            ));
+            return Ok(());
+        }
+    };
+    log::info!("collected build artifacts {:?}", artifacts);
+
+    let mut runners: Vec<Box<dyn PostBuildHook>> = Vec::new();
+    if let Some(config) = &hooks.sentry {
+        match SentryUploader::new(config.clone()) {
+            Ok(uploader) => runners.push(Box::new(uploader)),
+            Err(e) => StdErr::warn(&format!("Post-build hook sentry failed: {}", e)),
+        }
+    }
+    for command in &hooks.post_build {
+        runners.push(Box::new(CommandHook {
+            command: command.clone(),
+        }));
+    }
+
+    for runner in runners {
+        StdErr::working(&format!("Running post-build hook {}", runner.name()));
+        match runner.run(&artifacts) {
+            Ok(_) => StdErr::success(&format!("Post-build hook {} finished", runner.name())),
+            Err(e) => StdErr::warn(&format!("Post-build hook {} failed: {}", runner.name(), e)),
+        }
+    }
+
+    Ok(())
+}
+
+impl BuildArtifacts {
+    pub fn collect(target: &Target) -> Result<BuildArtifacts> {
+        let mut bundles = Vec::new();
+        let mut source_maps = Vec::new();
+
+        match entry_point(target)? {
+            Entry::Script(path) => {
+                let dir = path.parent().unwrap_or_else(|| Path::new("."));
+                bundles.push(Artifact::new(&path, dir)?);
+
+                let mut map_path = path.clone().into_os_string();
+                map_path.push(".map");
+                let map_path = PathBuf::from(map_path);
+                if map_path.is_file() {
+                    source_maps.push(Artifact::new(&map_path, dir)?);
+                }
+            }
+            Entry::Directory(dir) => {
+                for path in walk_files(&dir)? {
+                    let artifact = Artifact::new(&path, &dir)?;
+                    match path.extension().and_then(|ext| ext.to_str()) {
+                        Some("map") => source_maps.push(artifact),
+                        Some("js") | Some("mjs") | Some("cjs") => bundles.push(artifact),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(BuildArtifacts {
+            script_name: target.name.clone(),
+            script_version: script_version(&bundles),
+            bundles,
+            source_maps,
+        })
+    }
+
+    /// The total size of the bundles, i.e. roughly what was uploaded.
+    pub fn script_size(&self) -> u64 {
+        self.bundles.iter().map(|bundle| bundle.size).sum()
+    }
+}
+
+impl Artifact {
+    fn new(path: &Path, root: &Path) -> Result<Artifact> {
+        let contents = fs::read(path)
+            .map_err(|e| anyhow!("could not read build output {}: {}", path.display(), e))?;
+        let name = path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+
+        Ok(Artifact {
+            path: path.to_path_buf(),
+            name,
+            hash: digest(&contents),
+            size: contents.len() as u64,
+        })
+    }
+}
+
+enum Entry {
+    Script(PathBuf),
+    Directory(PathBuf),
+}
+
+// Mirrors the script resolution in `upload::form::build`.
+fn entry_point(target: &Target) -> Result<Entry> {
+    let entry = match target.target_type {
+        TargetType::Rust => Entry::Script(PathBuf::from("./worker/generated/script.js")),
+        TargetType::Webpack => {
+            let package_dir = target.package_dir()?;
+            Entry::Script(wranglerjs::Bundle::new(&package_dir).script_path())
+        }
+        TargetType::JavaScript => match target.build.as_ref().map(|b| &b.upload) {
+            Some(UploadFormat::Modules { dir, .. }) => Entry::Directory(dir.clone()),
+            _ => Entry::Script(target.service_worker_entry()?),
+        },
+    };
+
+    Ok(entry)
+}
+
+fn walk_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(walk_files(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn digest(contents: &[u8]) -> String {
+    let mut hasher = XxHash64::default();
+    hasher.write(contents);
+    format!("{:x}", hasher.finish())
+}
+
+// The script version only depends on what was uploaded, so rebuilding identical
+// sources yields the same version and hooks can skip duplicate uploads.
+fn script_version(bundles: &[Artifact]) -> String {
+    let mut hasher = XxHash64::default();
+    for bundle in bundles {
+        hasher.write(bundle.name.as_bytes());
+        hasher.write(bundle.hash.as_bytes());
+    }
+    format!("{:x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(name: &str, hash: &str) -> Artifact {
+        Artifact {
+            path: PathBuf::from(name),
+            name: name.to_string(),
+            hash: hash.to_string(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn script_version_is_stable() {
+        let bundles = vec![artifact("index.mjs", "abc"), artifact("chunk.mjs", "def")];
+        assert_eq!(script_version(&bundles), script_version(&bundles.clone()));
+    }
+
+    #[test]
+    fn script_version_changes_with_contents() {
+        let before = vec![artifact("index.mjs", "abc")];
+        let after = vec![artifact("index.mjs", "abd")];
+        assert_ne!(script_version(&before), script_version(&after));
+    }
+}
@@ -0,0 +1,114 @@
+use std::env;
+
+use anyhow::Result;
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::blocking::Client;
+use serde_json::json;
+
+use super::{BuildArtifacts, PostBuildHook};
+use crate::http;
+use crate::settings::toml::SentryConfig;
+
+const SENTRY_AUTH_TOKEN: &str = "SENTRY_AUTH_TOKEN";
+
+/// Creates a Sentry release named after the script version and attaches the
+/// bundles and source maps to it, so stack traces can be symbolicated.
+pub struct SentryUploader {
+    config: SentryConfig,
+    token: String,
+    client: Client,
+}
+
+impl SentryUploader {
+    pub fn new(config: SentryConfig) -> Result<SentryUploader> {
+        let token = env::var(SENTRY_AUTH_TOKEN).map_err(|_| {
+            anyhow::anyhow!(
+                "{} must be set to upload source maps to Sentry",
+                SENTRY_AUTH_TOKEN
+            )
+        })?;
+
+        Ok(SentryUploader {
+            config,
+            token,
+            client: http::client(),
+        })
+    }
+
+    fn releases_url(&self) -> String {
+        format!(
+            "{}/api/0/organizations/{}/releases/",
+            self.config.url.trim_end_matches('/'),
+            self.config.org
+        )
+    }
+
+    fn create_release(&self, version: &str) -> Result<()> {
+        let res = self
+            .client
+            .post(&self.releases_url())
+            .bearer_auth(&self.token)
+            .json(&json!({
+                "version": version,
+                "projects": [self.config.project],
+            }))
+            .send()?;
+
+        // 208 means the release already exists, which is fine when re-publishing.
+        if !res.status().is_success() {
+            anyhow::bail!(
+                "could not create Sentry release {}: {}",
+                version,
+                res.text()?
+            )
+        }
+
+        Ok(())
+    }
+
+    fn upload_file(&self, version: &str, name: &str, path: &std::path::Path) -> Result<()> {
+        let url = format!("{}{}/files/", self.releases_url(), version);
+        let form = Form::new()
+            .text("name", format!("{}{}", self.config.url_prefix, name))
+            .part("file", Part::file(path)?);
+
+        let res = self
+            .client
+            .post(&url)
+            .bearer_auth(&self.token)
+            .multipart(form)
+            .send()?;
+
+        // Sentry answers 409 for files that were already uploaded to this release.
+        if !res.status().is_success() && res.status().as_u16() != 409 {
+            anyhow::bail!("could not upload {} to Sentry: {}", name, res.text()?)
+        }
+
+        Ok(())
+    }
+}
+
+impl PostBuildHook for SentryUploader {
+    fn name(&self) -> String {
+        "sentry".to_string()
+    }
+
+    fn run(&self, artifacts: &BuildArtifacts) -> Result<()> {
+        if artifacts.source_maps.is_empty() {
+            log::info!("no source maps found, skipping Sentry upload");
+            return Ok(());
+        }
+
+        let version =
+            self.config.release.clone().unwrap_or_else(|| {
+                format!("{}@{}", artifacts.script_name, artifacts.script_version)
+            });
+
+        self.create_release(&version)?;
+        for artifact in artifacts.bundles.iter().chain(&artifacts.source_maps) {
+            self.upload_file(&version, &artifact.name, &artifact.path)?;
+        }
+
+        Ok(())
+    }
+}
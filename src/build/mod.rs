@@ -1,4 +1,9 @@
+pub mod hooks;
+pub mod signing;
+
+use crate::error::{Categorize, ErrorCategory};
 use crate::settings::toml::{Target, TargetType};
+use crate::terminal::events::{self, Event};
 use crate::terminal::message::{Message, StdErr};
 use crate::terminal::styles;
 use crate::wranglerjs;
@@ -9,9 +14,30 @@ use std::process::Command;
 
 use anyhow::{anyhow, Result};
 
-// Internal build logic, called by both `build` and `publish`
+// Internal build logic, called by `build`, `dev`, `preview` and `publish`. The post-build
+// hooks run after every successful build.
 // TODO: return a struct containing optional build info and construct output at command layer
 pub fn build_target(target: &Target) -> Result<String> {
+    let msg = build_target_without_hooks(target)?;
+    hooks::run_post_build_hooks(target)?;
+    Ok(msg)
+}
+
+/// Builds without running the post-build hooks, for builds shared by several environments,
+/// whose hooks run for each of them.
+pub fn build_target_without_hooks(target: &Target) -> Result<String> {
+    events::emit(Event::BuildStarted {
+        script: &target.name,
+    });
+    let result = build(target);
+    events::emit(Event::BuildFinished {
+        script: &target.name,
+        success: result.is_ok(),
+    });
+    result.categorize(ErrorCategory::Build, "build.failed")
+}
+
+fn build(target: &Target) -> Result<String> {
     let target_type = &target.target_type;
     match target_type {
         TargetType::JavaScript => match &target.build {
@@ -81,3 +107,19 @@ pub fn command(args: &[&str], binary_path: &Path) -> Command {
     c.args(args);
     c
 }
+
+/// Runs `command` through the platform's shell, so it can use pipes, quotes and the like.
+pub fn shell_command(command: &str) -> Command {
+    let mut c = if cfg!(target_os = "windows") {
+        let mut c = Command::new("cmd");
+        c.arg("/C");
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c");
+        c
+    };
+
+    c.arg(command);
+    c
+}
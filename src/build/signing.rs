@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ring::digest::{digest, SHA256};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+
+use super::hooks::BuildArtifacts;
+use crate::settings::toml::Target;
+
+/// Holds a base64 signing key, for CI where writing the key to a file is awkward.
+pub const SIGNING_KEY_VAR: &str = "WRANGLER_SIGNING_KEY";
+
+/// What gets signed: the SHA-256 of every bundle uploaded for a script.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SignedBundles {
+    pub script: String,
+    pub bundles: BTreeMap<String, String>,
+}
+
+/// A detached signature of the bundles of a publish, written to `<script>.sig`.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Signature {
+    #[serde(flatten)]
+    pub signed: SignedBundles,
+    pub algorithm: String,
+    /// Base64 Ed25519 public key of the key that signed the bundles.
+    pub public_key: String,
+    pub signature: String,
+}
+
+impl SignedBundles {
+    pub fn collect(target: &Target) -> Result<SignedBundles> {
+        let artifacts = BuildArtifacts::collect(target)?;
+        let mut bundles = BTreeMap::new();
+        for bundle in artifacts.bundles {
+            bundles.insert(bundle.name, sha256(&fs::read(&bundle.path)?));
+        }
+        Ok(SignedBundles {
+            script: artifacts.script_name,
+            bundles,
+        })
+    }
+
+    // serde_json keeps the field order and the map is sorted, so the same bundles
+    // always serialize to the same bytes
+    fn message(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+impl Signature {
+    pub fn sign(signed: SignedBundles, key: &Ed25519KeyPair) -> Result<Signature> {
+        let signature = key.sign(&signed.message()?);
+        Ok(Signature {
+            signed,
+            algorithm: "ed25519".to_string(),
+            public_key: base64::encode(key.public_key().as_ref()),
+            signature: base64::encode(signature.as_ref()),
+        })
+    }
+
+    /// Checks the signature against `public_key`, or against the key it names when there's
+    /// none, which only proves the file wasn't altered rather than who signed it.
+    pub fn verify(&self, public_key: Option<&str>) -> Result<()> {
+        if self.algorithm != "ed25519" {
+            anyhow::bail!("Unsupported signature algorithm {}", self.algorithm);
+        }
+        let public_key = base64::decode(public_key.unwrap_or(&self.public_key).trim())
+            .map_err(|e| anyhow!("The public key isn't valid base64: {}", e))?;
+        let signature = base64::decode(&self.signature)
+            .map_err(|e| anyhow!("The signature isn't valid base64: {}", e))?;
+        UnparsedPublicKey::new(&ED25519, public_key)
+            .verify(&self.signed.message()?, &signature)
+            .map_err(|_| anyhow!("The signature doesn't match the bundles it lists or the key"))
+    }
+
+    pub fn read(path: &Path) -> Result<Signature> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| anyhow!("could not read {}: {}", path.display(), e))?;
+        serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("{} isn't a wrangler signature: {}", path.display(), e))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Where `wrangler publish --sign` writes the signature of `script`, and `wrangler verify`
+/// looks for it.
+pub fn default_path(script: &str) -> PathBuf {
+    PathBuf::from(format!("{}.sig", script))
+}
+
+/// Creates a new Ed25519 key, returned as base64 PKCS#8.
+pub fn generate_key() -> Result<String> {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+        .map_err(|_| anyhow!("could not generate a signing key"))?;
+    Ok(base64::encode(pkcs8.as_ref()))
+}
+
+/// Reads a base64 PKCS#8 Ed25519 key from `path`, or from `WRANGLER_SIGNING_KEY` when no
+/// path is given.
+pub fn load_key(path: Option<&Path>) -> Result<Ed25519KeyPair> {
+    let encoded = match path {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| anyhow!("could not read the signing key {}: {}", path.display(), e))?,
+        None => std::env::var(SIGNING_KEY_VAR)
+            .map_err(|_| anyhow!("Pass --signing-key or set {}", SIGNING_KEY_VAR))?,
+    };
+    parse_key(&encoded)
+}
+
+pub fn public_key(key: &Ed25519KeyPair) -> String {
+    base64::encode(key.public_key().as_ref())
+}
+
+pub fn parse_key(encoded: &str) -> Result<Ed25519KeyPair> {
+    let pkcs8 = base64::decode(encoded.trim())
+        .map_err(|e| anyhow!("The signing key isn't valid base64: {}", e))?;
+    Ed25519KeyPair::from_pkcs8(&pkcs8)
+        .map_err(|_| anyhow!("The signing key isn't a PKCS#8 Ed25519 key"))
+}
+
+pub fn sha256(contents: &[u8]) -> String {
+    digest(&SHA256, contents)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed() -> SignedBundles {
+        let mut bundles = BTreeMap::new();
+        bundles.insert("index.mjs".to_string(), sha256(b"export default {}"));
+        SignedBundles {
+            script: "worker".to_string(),
+            bundles,
+        }
+    }
+
+    #[test]
+    fn it_verifies_what_it_signed() {
+        let key = parse_key(&generate_key().unwrap()).unwrap();
+        let signature = Signature::sign(signed(), &key).unwrap();
+        assert!(signature.verify(None).is_ok());
+        assert!(signature.verify(Some(&public_key(&key))).is_ok());
+    }
+
+    #[test]
+    fn it_rejects_altered_bundles_and_other_keys() {
+        let key = parse_key(&generate_key().unwrap()).unwrap();
+        let mut signature = Signature::sign(signed(), &key).unwrap();
+
+        let other = parse_key(&generate_key().unwrap()).unwrap();
+        assert!(signature.verify(Some(&public_key(&other))).is_err());
+
+        signature
+            .signed
+            .bundles
+            .insert("index.mjs".to_string(), sha256(b"export default { fetch }"));
+        assert!(signature.verify(None).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash64;
+
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::global_user::GlobalUser;
+
+/// How long lookups that rarely change (accounts, namespaces) are reused for.
+pub const LOOKUP_TTL: Duration = Duration::from_secs(5 * 60);
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Deserialize, Serialize)]
+struct Entry<T> {
+    stored_at: SystemTime,
+    value: T,
+}
+
+/// Turns the cache off for this run, e.g. with `--no-cache`. Nothing is read from it, and
+/// what's fetched isn't written to it either.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed)
+}
+
+/// Returns what `fetch` returned for `key` less than `ttl` ago, or calls it and keeps the
+/// result in `~/.wrangler/cache`. Errors aren't cached, and a cache that can't be read or
+/// written just means fetching every time.
+pub fn get_or_fetch<T, F>(key: &str, ttl: Duration, fetch: F) -> Result<T>
+where
+    T: DeserializeOwned + Serialize,
+    F: FnOnce() -> Result<T>,
+{
+    if !enabled() {
+        return fetch();
+    }
+
+    let path = entry_path(key);
+    if let Some(value) = read(&path, ttl) {
+        log::info!("using cached {}", key);
+        return Ok(value);
+    }
+
+    let value = fetch()?;
+    let entry = Entry {
+        stored_at: SystemTime::now(),
+        value,
+    };
+    if let Err(e) = write(&path, &entry) {
+        log::debug!("could not cache {}: {}", key, e);
+    }
+    Ok(entry.value)
+}
+
+/// Forgets `key`, for when something changed it, e.g. a namespace was created.
+pub fn invalidate(key: &str) {
+    let _ = fs::remove_file(entry_path(key));
+}
+
+/// A key for data that depends on who's asking, without putting credentials on disk.
+pub fn user_key(name: &str, user: &GlobalUser) -> String {
+    let credentials = serde_json::to_string(user).unwrap_or_default();
+    format!("{}-{}", name, digest(credentials.as_bytes()))
+}
+
+fn read<T: DeserializeOwned>(path: &Path, ttl: Duration) -> Option<T> {
+    let contents = fs::read(path).ok()?;
+    let entry: Entry<T> = serde_json::from_slice(&contents).ok()?;
+    let age = entry.stored_at.elapsed().ok()?;
+    if age < ttl {
+        Some(entry.value)
+    } else {
+        None
+    }
+}
+
+fn write<T: Serialize>(path: &Path, entry: &Entry<T>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_vec(entry)?)?;
+    Ok(())
+}
+
+fn entry_path(key: &str) -> PathBuf {
+    get_wrangler_home_dir()
+        .join("cache")
+        .join(format!("{}.json", digest(key.as_bytes())))
+}
+
+fn digest(contents: &[u8]) -> String {
+    let mut hasher = XxHash64::default();
+    hasher.write(contents);
+    format!("{:x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn old_entries_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("entry.json");
+        let entry = Entry {
+            stored_at: SystemTime::now() - Duration::from_secs(120),
+            value: vec!["a".to_string()],
+        };
+        write(&path, &entry).unwrap();
+
+        assert_eq!(
+            read::<Vec<String>>(&path, Duration::from_secs(300)),
+            Some(vec!["a".to_string()])
+        );
+        assert_eq!(read::<Vec<String>>(&path, Duration::from_secs(60)), None);
+    }
+}
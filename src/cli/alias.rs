@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+const DEFAULT_CONFIG_PATH: &str = "wrangler.toml";
+
+#[derive(Default, Deserialize)]
+struct AliasConfig {
+    #[serde(default)]
+    alias: BTreeMap<String, String>,
+}
+
+/// The `[alias]` table of the configuration file, e.g. `deploy = "publish --env production"`.
+/// This is read before the arguments are parsed, so a configuration file that doesn't parse
+/// just has no aliases; the real error shows up once a command loads it.
+pub fn load(args: &[OsString]) -> BTreeMap<String, String> {
+    fs::read_to_string(config_path(args))
+        .ok()
+        .and_then(|config| toml::from_str::<AliasConfig>(&config).ok())
+        .unwrap_or_default()
+        .alias
+}
+
+/// Lists the aliases at the end of `wrangler help`.
+pub fn help(aliases: &BTreeMap<String, String>) -> String {
+    if aliases.is_empty() {
+        return String::new();
+    }
+
+    let width = aliases.keys().map(|name| name.len()).max().unwrap_or(0);
+    let mut help = "ALIASES (from wrangler.toml):".to_string();
+    for (name, command) in aliases {
+        help.push_str(&format!(
+            "\n    {:<width$}    {}",
+            name,
+            command,
+            width = width
+        ));
+    }
+    help
+}
+
+/// Replaces the command name with what it's an alias for, or returns `None` if the command
+/// isn't an alias. Built-in commands always win, since this is only tried when the
+/// arguments don't parse as they are.
+pub fn expand(args: &[OsString], aliases: &BTreeMap<String, String>) -> Option<Vec<OsString>> {
+    let position = command_position(args)?;
+    let command = aliases.get(args[position].to_str()?)?;
+
+    let mut expanded = args[..position].to_vec();
+    expanded.extend(split(command).into_iter().map(OsString::from));
+    expanded.extend_from_slice(&args[position + 1..]);
+    Some(expanded)
+}
+
+fn config_path(args: &[OsString]) -> PathBuf {
+    let mut args = args.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "-c" || arg == "--config" {
+            if let Some(path) = args.next() {
+                return PathBuf::from(path);
+            }
+        } else if let Some(path) = arg.strip_prefix("--config=") {
+            return PathBuf::from(path);
+        }
+    }
+    PathBuf::from(DEFAULT_CONFIG_PATH)
+}
+
+// The first argument that isn't a flag or the value of one
+fn command_position(args: &[OsString]) -> Option<usize> {
+    let mut i = 1;
+    while i < args.len() {
+        let arg = args[i].to_string_lossy();
+        match arg.as_ref() {
+            "-c" | "--config" | "-e" | "--env" => i += 2,
+            arg if arg.starts_with('-') => i += 1,
+            _ => return Some(i),
+        }
+    }
+    None
+}
+
+// Splits on whitespace, keeping quoted strings together
+fn split(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn it_expands_aliases() {
+        let mut aliases = BTreeMap::new();
+        aliases.insert(
+            "deploy".to_string(),
+            "publish --env production --json".to_string(),
+        );
+
+        assert_eq!(
+            expand(
+                &args(&["wrangler", "-c", "w.toml", "deploy", "--verbose"]),
+                &aliases
+            ),
+            Some(args(&[
+                "wrangler",
+                "-c",
+                "w.toml",
+                "publish",
+                "--env",
+                "production",
+                "--json",
+                "--verbose"
+            ]))
+        );
+        assert_eq!(expand(&args(&["wrangler", "publish"]), &aliases), None);
+    }
+
+    #[test]
+    fn it_splits_quoted_words() {
+        assert_eq!(
+            split(r#"kv:key put "my key" 'a value'  --binding KV"#),
+            vec!["kv:key", "put", "my key", "a value", "--binding", "KV"]
+        );
+    }
+
+    #[test]
+    fn it_finds_the_config_path() {
+        assert_eq!(
+            config_path(&args(&["wrangler", "publish", "--config=a.toml"])),
+            PathBuf::from("a.toml")
+        );
+        assert_eq!(
+            config_path(&args(&["wrangler", "publish"])),
+            PathBuf::from(DEFAULT_CONFIG_PATH)
+        );
+    }
+}
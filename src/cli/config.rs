@@ -4,8 +4,19 @@ use crate::terminal::message::{Message, StdOut};
 use crate::terminal::{interactive, styles};
 
 use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+pub enum ConfigCommand {
+    /// Print the JSON Schema of wrangler.toml, for editors to validate and autocomplete it with
+    Schema,
+}
+
+pub fn configure(api_key: bool, no_verify: bool, subcommand: Option<ConfigCommand>) -> Result<()> {
+    if let Some(ConfigCommand::Schema) = subcommand {
+        return commands::config::print_schema();
+    }
 
-pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
     let user: GlobalUser = if !api_key {
         // API Tokens are the default
         StdOut::billboard(&format!(
@@ -23,7 +34,7 @@ pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
             styles::warning("(Not Recommended)"),
             styles::highlight("`wrangler config --api-key`")
         ));
-        let api_token: String = interactive::get_user_input("Enter API Token: ");
+        let api_token: String = interactive::get_user_input("Enter API Token: ")?;
         GlobalUser::TokenAuth { api_token }
     } else {
         StdOut::billboard(&format!(concat!(
@@ -35,8 +46,8 @@ pub fn configure(api_key: bool, no_verify: bool) -> Result<()> {
                 "https://support.cloudflare.com/hc/en-us/articles/200167836-Managing-API-Tokens-and-Keys",
             )
         ));
-        let email: String = interactive::get_user_input("Enter Email: ");
-        let api_key: String = interactive::get_user_input("Enter Global API Key: ");
+        let email: String = interactive::get_user_input("Enter Email: ")?;
+        let api_key: String = interactive::get_user_input("Enter Global API Key: ")?;
 
         GlobalUser::GlobalKeyAuth { email, api_key }
     };
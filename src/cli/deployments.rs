@@ -0,0 +1,27 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::TableOptions;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Deployments {
+    /// List the deployments of the worker and where each was built from
+    List {
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+}
+
+pub fn deployments(deployments: Deployments, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    match deployments {
+        Deployments::List { options } => commands::deployments::list(&user, &target, &options),
+    }
+}
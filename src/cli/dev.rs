@@ -1,28 +1,76 @@
-use std::net::{IpAddr, Ipv4Addr};
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::path::PathBuf;
 
-use super::Cli;
+use super::{Cli, ShareOptions};
 use crate::commands::{self, dev::Protocol};
+use crate::settings::toml::explain::Source;
+use crate::settings::toml::CacheEmulation;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::share_url;
+use crate::upload::form::dev_shim::{self, MockResponse};
+use crate::upload::form::DevShim;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 
+#[allow(clippy::too_many_arguments)]
 pub fn dev(
     host: Option<String>,
     mut ip: Option<IpAddr>,
     mut port: Option<u16>,
     mut local_protocol: Option<Protocol>,
     mut upstream_protocol: Option<Protocol>,
+    cf_json: Option<PathBuf>,
+    fake_time: Option<DateTime<Utc>>,
+    share: ShareOptions,
     cli_params: &Cli,
 ) -> Result<()> {
     log::info!("Starting dev server");
     let manifest = Manifest::new(&cli_params.config)?;
+    let environment = cli_params.environment.as_deref();
+    if let Some(environment) = environment {
+        warn_ignored(&manifest, environment)?;
+    }
 
     // Check if arg not given but present in wrangler.toml
-    if let Some(d) = &manifest.dev {
+    let mut shim = DevShim::default();
+    if let Some(d) = &manifest.dev_config(environment)? {
         ip = ip.or(d.ip);
         port = port.or(d.port);
         local_protocol = local_protocol.or(d.local_protocol);
         upstream_protocol = upstream_protocol.or(d.upstream_protocol);
+        shim.cf = d.cf.clone().unwrap_or_default();
+        shim.connecting_ip = d.connecting_ip.clone();
+        shim.cache = d.cache;
+        if let Some(mocks) = &d.mocks {
+            shim.mocks = MockResponse::from_config(mocks)?;
+        }
+    }
+    if let Some(path) = cf_json {
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| anyhow!("Could not read {}: {}", path.display(), e))?;
+        shim.cf = serde_json::from_str(&contents)
+            .map_err(|e| anyhow!("{} isn't a JSON object: {}", path.display(), e))?;
+    }
+    if let Some(time) = fake_time {
+        StdOut::info(&format!(
+            "The worker's clock is stopped at {}, GET {}?seconds=<n> moves it forward",
+            time.to_rfc3339(),
+            dev_shim::TIME_ADVANCE_PATH
+        ));
+        shim.fake_time = Some(time);
+    }
+    if let Some(cache) = &shim.cache {
+        StdOut::info(&format!(
+            "Using {} cache emulation, GET {} empties it",
+            match cache.mode {
+                CacheEmulation::Memory => "in-memory",
+                CacheEmulation::Disabled => "disabled",
+            },
+            dev_shim::CACHE_PURGE_PATH
+        ));
     }
 
     let ip = ip.unwrap_or_else(|| Ipv4Addr::new(127, 0, 0, 1).into());
@@ -30,12 +78,40 @@ pub fn dev(
     let local_protocol = local_protocol.unwrap_or(Protocol::Http);
     let upstream_protocol = upstream_protocol.unwrap_or(Protocol::Https);
 
-    let deployments = manifest.get_deployments(cli_params.environment.as_deref())?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), true)?;
+    let deployments = manifest.get_deployments(environment)?;
+    let target = manifest.get_target(environment, true)?;
+    // Only the classes this worker exports can be wrapped, not those of other scripts
+    if let Some(classes) = target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.as_ref())
+    {
+        shim.alarms = classes
+            .iter()
+            .filter(|class| class.script_name.is_none())
+            .map(|class| (class.class_name.clone(), class.binding.clone()))
+            .collect();
+    }
+    if !shim.alarms.is_empty() {
+        StdOut::info(&format!(
+            "Durable Object alarms are logged when set and fired, GET {} fires one",
+            dev_shim::ALARM_PATH
+        ));
+    }
+    shim.apply_to_uploads();
     let user = GlobalUser::new().ok();
 
     let server_config = commands::dev::ServerConfig::new(host, ip, port, upstream_protocol)?;
 
+    commands::summary::print(
+        "Starting a dev session for",
+        &target,
+        environment,
+        &deployments,
+    );
+    if share.qr || share.copy {
+        share_url(&shared_url(ip, port, local_protocol), share.qr, share.copy);
+    }
     commands::dev::dev(
         target,
         deployments,
@@ -46,3 +122,48 @@ pub fn dev(
         cli_params.verbose,
     )
 }
+
+pub fn parse_fake_time(time: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| anyhow!("{} isn't an RFC 3339 time, e.g. 2023-01-01T00:00:00Z", time))
+}
+
+// The URL other devices on the network reach the session at. A server listening on every
+// interface is shared by this machine's address on the LAN.
+fn shared_url(ip: IpAddr, port: u16, protocol: Protocol) -> String {
+    let ip = if ip.is_unspecified() {
+        lan_ip().unwrap_or(ip)
+    } else {
+        if ip.is_loopback() {
+            StdOut::warn("The dev server only listens on this machine, pass --ip 0.0.0.0 to reach it from other devices");
+        }
+        ip
+    };
+    let scheme = if protocol.is_https() { "https" } else { "http" };
+    match ip {
+        IpAddr::V4(ip) => format!("{}://{}:{}", scheme, ip, port),
+        IpAddr::V6(ip) => format!("{}://[{}]:{}", scheme, ip, port),
+    }
+}
+
+// Connecting a UDP socket sends nothing, it only picks the interface that routes outward
+fn lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("198.51.100.1:80").ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+// The session runs with what publish would use, so settings of the environment that publish
+// ignores are pointed out rather than silently left out
+fn warn_ignored(manifest: &Manifest, environment: &str) -> Result<()> {
+    for resolved in manifest.explain(environment)? {
+        if resolved.source == Source::Ignored {
+            StdOut::warn(&format!(
+                "[env.{}] sets {}, but it can only be set at the top level. The top-level value is used",
+                environment, resolved.key
+            ));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,9 @@
+use super::Cli;
+use crate::commands;
+
+use anyhow::Result;
+
+pub fn doctor(cli_params: &Cli) -> Result<()> {
+    log::info!("Running diagnostics");
+    commands::doctor::run(&cli_params.config, cli_params.environment.as_deref())
+}
@@ -0,0 +1,16 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::TableOptions;
+
+use anyhow::Result;
+
+pub fn drift(secrets: Option<Vec<String>>, options: TableOptions, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let environment = cli_params.environment.as_deref();
+    let target = manifest.get_target(environment, false)?;
+    let deployments = manifest.get_deployments(environment)?;
+
+    commands::drift::drift(&user, &target, &deployments, secrets.as_deref(), &options)
+}
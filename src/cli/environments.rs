@@ -0,0 +1,38 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::toml::Manifest;
+use crate::terminal::table::TableOptions;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Environments {
+    /// List the environments, their workers and the keys each sets itself
+    List {
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+    /// Show the configuration an environment is published with, and which values it inherits
+    /// from the top level
+    Show {
+        /// The environment to show
+        #[structopt(index = 1)]
+        name: String,
+
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+}
+
+pub fn environments(environments: Environments, cli_params: &Cli) -> Result<()> {
+    let manifest = Manifest::new(&cli_params.config)?;
+
+    match environments {
+        Environments::List { options } => commands::environments::list(&manifest, &options),
+        Environments::Show { name, options } => {
+            commands::environments::show(&manifest, &name, &options)
+        }
+    }
+}
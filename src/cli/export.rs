@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::commands;
+use crate::commands::export::ExportFormat;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+
+pub fn export(
+    name: Option<String>,
+    zone_id: Option<String>,
+    output: Option<PathBuf>,
+    format: ExportFormat,
+    cli_params: &Cli,
+) -> Result<()> {
+    let user = GlobalUser::new()?;
+
+    // exporting is how a worker without a configuration file gets one, so it's optional here
+    let manifest = if cli_params.config.exists() {
+        Manifest::new(&cli_params.config)?
+    } else {
+        Manifest::default()
+    };
+    let environment = manifest.get_environment(cli_params.environment.as_deref())?;
+
+    let name = match name {
+        Some(name) => name,
+        None => manifest
+            .get_target(cli_params.environment.as_deref(), false)
+            .ok()
+            .map(|target| target.name)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("Pass the name of the worker to export"))?,
+    };
+    let zone_id = zone_id
+        .or_else(|| environment.and_then(|e| e.zone_id.clone()))
+        .or_else(|| manifest.zone_id.clone());
+    let account_id = manifest.account_id.load()?;
+
+    commands::export::export(
+        &user,
+        account_id,
+        &name,
+        zone_id.as_deref(),
+        output.as_deref(),
+        format,
+    )
+}
@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+
 use crate::commands;
+use crate::commands::generate::template;
 use crate::settings::toml::TargetType;
 
 use anyhow::Result;
@@ -8,19 +11,22 @@ pub fn generate(
     site: bool,
     template: Option<String>,
     target_type: Option<TargetType>,
+    list: bool,
+    define: Vec<String>,
 ) -> Result<()> {
-    const DEFAULT_TEMPLATE: &str = "https://github.com/cloudflare/worker-template";
-    const RUST_TEMPLATE: &str = "https://github.com/cloudflare/rustwasm-worker-template";
-    const SITES_TEMPLATE: &str = "https://github.com/cloudflare/worker-sites-template";
+    if list {
+        return template::list();
+    }
+    let defines = parse_defines(&define)?;
 
     let template = if site {
-        SITES_TEMPLATE
+        template::SITES_TEMPLATE
     } else if let Some(template) = template.as_deref() {
         template
     } else if let Some(TargetType::Rust) = target_type {
-        RUST_TEMPLATE
+        template::RUST_TEMPLATE
     } else {
-        DEFAULT_TEMPLATE
+        template::DEFAULT_TEMPLATE
     };
 
     log::info!(
@@ -29,5 +35,15 @@ pub fn generate(
         name
     );
 
-    commands::generate(&name, template, target_type, site)
+    commands::generate(&name, template, target_type, site, &defines)
+}
+
+fn parse_defines(defines: &[String]) -> Result<HashMap<String, String>> {
+    defines
+        .iter()
+        .map(|define| match define.split_once('=') {
+            Some((name, value)) => Ok((name.trim().to_string(), value.to_string())),
+            None => anyhow::bail!("--define {} should look like name=value", define),
+        })
+        .collect()
 }
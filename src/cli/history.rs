@@ -0,0 +1,54 @@
+use super::Cli;
+use crate::history::{self, Query};
+use crate::settings::toml::parse_duration;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::{Format, Table, TableOptions};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+
+pub fn history(
+    command: Option<String>,
+    since: Option<DateTime<Utc>>,
+    failed: bool,
+    mut options: TableOptions,
+    cli_params: &Cli,
+) -> Result<()> {
+    if cli_params.json {
+        options.format = Format::Json;
+    }
+    let query = Query {
+        command,
+        since,
+        failed_only: failed,
+    };
+    // newest first, so --limit keeps the latest
+    let entries: Vec<_> = history::read()?
+        .into_iter()
+        .rev()
+        .filter(|entry| query.matches(entry))
+        .collect();
+    if entries.is_empty() {
+        StdOut::info("No commands in the history match");
+        return Ok(());
+    }
+    Table::from_serialize(&["time", "command", "args", "success", "error"], &entries)?
+        .print(&options)
+}
+
+/// Either an RFC 3339 time or how long ago, e.g. "30m".
+pub fn parse_since(since: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(since) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let ago = parse_duration(since)
+        .ok()
+        .and_then(|ago| Duration::from_std(ago).ok())
+        .ok_or_else(|| {
+            anyhow!(
+                "{} is neither an RFC 3339 time nor a duration, use e.g. 2021-11-01T00:00:00Z or \"30m\"",
+                since
+            )
+        })?;
+    Ok(Utc::now() - ago)
+}
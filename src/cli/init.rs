@@ -1,7 +1,12 @@
+use crate::terminal::interactive;
 use crate::{commands, settings::toml::TargetType};
 use anyhow::Result;
 
 pub fn init(name: Option<String>, site: bool, target_type: Option<TargetType>) -> Result<()> {
+    if name.is_none() && !site && target_type.is_none() && interactive::is_interactive() {
+        return commands::init::wizard();
+    }
+
     let target_type = if site {
         // Workers Sites projects are always webpack for now
         Some(TargetType::Webpack)
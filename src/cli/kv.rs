@@ -1,9 +1,11 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use super::Cli;
 use crate::commands;
 use crate::commands::kv::key::{parse_metadata, KVMetaData};
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::TableOptions;
 
 use anyhow::{anyhow, Result};
 use clap::ArgGroup;
@@ -20,6 +22,13 @@ pub enum KvNamespace {
         /// Applies the command to the preview namespace
         #[structopt(name = "preview", long)]
         preview: bool,
+        /// How to title the namespace, from {script}, {name}, {env} and {binding}.
+        /// Defaults to "{script}-{binding}"
+        #[structopt(name = "title-template", long)]
+        title_template: Option<String>,
+        /// Bind the namespace in the configuration file instead of printing what to add
+        #[structopt(name = "update-config", long)]
+        update_config: bool,
     },
     /// Delete namespace
     Delete {
@@ -27,7 +36,18 @@ pub enum KvNamespace {
         namespace: Namespace,
     },
     /// List all namespaces on your Cloudflare account
-    List,
+    List {
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+    /// Show how many keys a namespace holds, how much it stores and how often it's used
+    Stats {
+        #[structopt(flatten)]
+        namespace: Namespace,
+        /// How many days of operations to count
+        #[structopt(long, default_value = "30")]
+        days: u32,
+    },
 }
 
 #[derive(Debug, Clone, StructOpt)]
@@ -102,7 +122,24 @@ pub enum KvKey {
         #[structopt(name = "key", index = 1)]
         key: String,
     },
-    /// List all keys in a namespace. Produces JSON output
+    /// Print changes to a key's value and metadata as they happen
+    Watch {
+        #[structopt(flatten)]
+        namespace: Namespace,
+
+        /// Key to watch
+        #[structopt(name = "key", index = 1, required_unless = "prefix")]
+        key: Option<String>,
+
+        /// Watch every key starting with this instead
+        #[structopt(name = "prefix", long, short = "p", conflicts_with = "key")]
+        prefix: Option<String>,
+
+        /// Seconds between checks for changes
+        #[structopt(long, default_value = "5")]
+        interval: u64,
+    },
+    /// List all keys in a namespace
     List {
         #[structopt(flatten)]
         namespace: Namespace,
@@ -110,6 +147,9 @@ pub enum KvKey {
         /// The prefix for filtering listed keys
         #[structopt(name = "prefix", long, short = "p")]
         prefix: Option<String>,
+
+        #[structopt(flatten)]
+        options: TableOptions,
     },
 }
 
@@ -142,9 +182,21 @@ pub fn kv_namespace(namespace: KvNamespace, cli_params: &Cli) -> Result<()> {
     let env = cli_params.environment.as_deref();
 
     match namespace {
-        KvNamespace::Create { binding, preview } => {
-            commands::kv::namespace::create(&manifest, preview, env, &user, &binding)
-        }
+        KvNamespace::Create {
+            binding,
+            preview,
+            title_template,
+            update_config,
+        } => commands::kv::namespace::create(
+            &manifest,
+            &cli_params.config,
+            preview,
+            env,
+            &user,
+            &binding,
+            title_template.as_deref(),
+            update_config,
+        ),
         KvNamespace::Delete { namespace } => {
             let target = manifest.get_target(env, namespace.preview)?;
             let id = if let Some(binding) = namespace.binding {
@@ -156,9 +208,20 @@ pub fn kv_namespace(namespace: KvNamespace, cli_params: &Cli) -> Result<()> {
             };
             commands::kv::namespace::delete(&target, &user, &id)
         }
-        KvNamespace::List => {
+        KvNamespace::List { options } => {
             let target = manifest.get_target(env, false)?;
-            commands::kv::namespace::list(&target, &user)
+            commands::kv::namespace::list(&target, &user, &options)
+        }
+        KvNamespace::Stats { namespace, days } => {
+            let target = manifest.get_target(env, namespace.preview)?;
+            let id = if let Some(binding) = namespace.binding {
+                commands::kv::get_namespace_id(&target, &binding)?
+            } else {
+                namespace
+                    .namespace_id
+                    .expect("Namespace ID is required if binding isn't supplied")
+            };
+            commands::kv::namespace::stats(&target, &user, &id, days)
         }
     }
 }
@@ -218,9 +281,33 @@ pub fn kv_key(key: KvKey, cli_params: &Cli) -> Result<()> {
             let (target, namespace_id) = target_and_namespace(namespace)?;
             commands::kv::key::delete(&target, &user, &namespace_id, &key)
         }
-        KvKey::List { namespace, prefix } => {
+        KvKey::Watch {
+            namespace,
+            key,
+            prefix,
+            interval,
+        } => {
+            let (target, namespace_id) = target_and_namespace(namespace)?;
+            let watched = match (key, prefix) {
+                (Some(key), _) => commands::kv::key::Watched::Key(key),
+                (None, Some(prefix)) => commands::kv::key::Watched::Prefix(prefix),
+                (None, None) => anyhow::bail!("Pass a key or --prefix to watch"),
+            };
+            commands::kv::key::watch(
+                &target,
+                &user,
+                &namespace_id,
+                &watched,
+                Duration::from_secs(interval.max(1)),
+            )
+        }
+        KvKey::List {
+            namespace,
+            prefix,
+            options,
+        } => {
             let (target, namespace_id) = target_and_namespace(namespace)?;
-            commands::kv::key::list(&target, &user, &namespace_id, prefix.as_deref())
+            commands::kv::key::list(&target, &user, &namespace_id, prefix.as_deref(), &options)
         }
     }
 }
@@ -0,0 +1,18 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::{Format, TableOptions};
+
+use anyhow::Result;
+
+pub fn list(tag: Option<String>, mut options: TableOptions, cli_params: &Cli) -> Result<()> {
+    if cli_params.json {
+        options.format = Format::Json;
+    }
+    let user = GlobalUser::new()?;
+    // the configuration file is only read for the account id
+    let manifest = Manifest::new(&cli_params.config)?;
+    let account_id = manifest.account_id.load()?;
+
+    commands::list::list(&user, account_id, tag.as_deref(), &options)
+}
@@ -0,0 +1,20 @@
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::commands;
+use crate::settings::toml::Manifest;
+
+use anyhow::Result;
+
+pub fn migrate_format(
+    out: Option<PathBuf>,
+    bindings: Vec<String>,
+    dry_run: bool,
+    cli_params: &Cli,
+) -> Result<()> {
+    log::info!("Getting project settings");
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    commands::migrate_format::migrate_format(&target, &cli_params.config, out, bindings, dry_run)
+}
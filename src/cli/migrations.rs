@@ -0,0 +1,43 @@
+use super::{AdhocMigration, Cli};
+use crate::commands;
+use crate::settings::toml::migrations::Migrations as ConfiguredMigrations;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::TableOptions;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Migrations {
+    /// Show which Durable Objects migrations the next publish would apply, without publishing
+    Plan {
+        #[structopt(flatten)]
+        migration: AdhocMigration,
+    },
+    /// List the migrations in the configuration file, and which ones the deployed worker has
+    List {
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+}
+
+pub fn migrations(migrations: Migrations, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    match migrations {
+        Migrations::Plan { migration } => {
+            // like `wrangler publish`, a migration on the command line replaces the configured ones
+            if let Some(migration) = migration.into_migration_config() {
+                target.migrations = Some(ConfiguredMigrations {
+                    migrations: vec![migration],
+                    old_tag: None,
+                });
+            }
+            commands::migrations::plan(&user, &target)
+        }
+        Migrations::List { options } => commands::migrations::list(&user, &target, &options),
+    }
+}
@@ -1,45 +1,84 @@
+pub mod alias;
 pub mod build;
 pub mod config;
+pub mod deployments;
 pub mod dev;
+pub mod doctor;
+pub mod drift;
+pub mod environments;
+pub mod export;
 pub mod generate;
+pub mod history;
 pub mod init;
 pub mod kv;
+pub mod list;
+pub mod migrate_format;
+pub mod migrations;
+pub mod pages;
 pub mod preview;
+pub mod preview_alias;
 pub mod publish;
+pub mod r2;
+pub mod rename;
 pub mod route;
 pub mod secret;
 pub mod subdomain;
 pub mod tail;
+pub mod telemetry;
+pub mod verify;
 pub mod whoami;
+pub mod zone;
 
 pub mod exec {
     pub use super::build::build;
     pub use super::config::configure;
+    pub use super::deployments::deployments;
     pub use super::dev::dev;
+    pub use super::doctor::doctor;
+    pub use super::drift::drift;
+    pub use super::environments::environments;
+    pub use super::export::export;
     pub use super::generate::generate;
+    pub use super::history::history;
     pub use super::init::init;
     pub use super::kv::kv_bulk;
     pub use super::kv::kv_key;
     pub use super::kv::kv_namespace;
+    pub use super::list::list;
+    pub use super::migrate_format::migrate_format;
+    pub use super::migrations::migrations;
+    pub use super::pages::pages;
     pub use super::preview::preview;
+    pub use super::preview_alias::preview_alias;
     pub use super::publish::publish;
+    pub use super::r2::r2;
+    pub use super::rename::rename;
     pub use super::route::route;
     pub use super::secret::secret;
     pub use super::subdomain::subdomain;
     pub use super::tail::tail;
+    pub use super::telemetry::telemetry;
+    pub use super::verify::keygen;
+    pub use super::verify::verify;
     pub use super::whoami::whoami;
+    pub use super::zone::zone;
 }
 
 use std::net::IpAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use crate::commands::dev::Protocol;
+use crate::commands::export::ExportFormat;
+use crate::commands::generate::template::Template;
 use crate::preview::HttpMethod;
 use crate::settings::toml::migrations::{
     DurableObjectsMigration, Migration, MigrationConfig, Migrations, RenameClass, TransferClass,
 };
-use crate::settings::toml::TargetType;
+use crate::settings::toml::{parse_duration, TargetType};
+use crate::terminal::table::TableOptions;
 
+use chrono::{DateTime, Utc};
 use clap::AppSettings;
 use structopt::StructOpt;
 use url::Url;
@@ -57,6 +96,10 @@ pub struct Cli {
     #[structopt(long, global = true)]
     pub verbose: bool,
 
+    /// Only print warnings, errors and results
+    #[structopt(long, short = "q", global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
     /// Path to configuration file.
     #[structopt(long, short = "c", default_value = "wrangler.toml", global = true)]
     pub config: PathBuf,
@@ -65,6 +108,38 @@ pub struct Cli {
     #[structopt(name = "env", long, short = "e", global = true)]
     pub environment: Option<String>,
 
+    /// Never prompt; fail instead of asking for input that wasn't piped to stdin, or for a
+    /// confirmation. Without it, confirmations are read from stdin when it isn't a terminal,
+    /// e.g. `yes | wrangler ...`
+    #[structopt(name = "non-interactive", long, global = true)]
+    pub non_interactive: bool,
+
+    /// Answer yes to every confirmation, e.g. before deleting something
+    #[structopt(long, short = "y", global = true)]
+    pub yes: bool,
+
+    /// Print plain text without colors or emoji. Also set by the NO_COLOR environment variable
+    #[structopt(name = "no-color", long, global = true)]
+    pub no_color: bool,
+
+    /// Don't reuse cached account and namespace lookups
+    #[structopt(name = "no-cache", long, global = true)]
+    pub no_cache: bool,
+
+    /// Print results as JSON on stdout, and all other output on stderr
+    #[structopt(long, global = true)]
+    pub json: bool,
+
+    /// Never use the network: commands that need it fail straight away, e.g. in air-gapped
+    /// builds
+    #[structopt(long, global = true)]
+    pub offline: bool,
+
+    /// Write progress events as JSON lines to this open file descriptor, for tools that run
+    /// wrangler, e.g. --progress-fd 3
+    #[structopt(name = "progress-fd", long, global = true, value_name = "n")]
+    pub progress_fd: Option<i32>,
+
     #[structopt(subcommand)]
     pub command: Command,
 }
@@ -87,6 +162,84 @@ pub enum Command {
     #[structopt(name = "route", setting = AppSettings::SubcommandRequiredElseHelp)]
     Route(route::Route),
 
+    /// Write a configuration file for a deployed worker, from its live settings
+    Export {
+        /// Name of the worker, defaults to the one in the configuration file
+        #[structopt(index = 1)]
+        name: Option<String>,
+
+        /// Zone to export the worker's routes from, defaults to the configuration file's zone_id
+        #[structopt(name = "zone-id", long)]
+        zone_id: Option<String>,
+
+        /// Where to write the configuration, instead of printing it
+        #[structopt(long, short = "o")]
+        output: Option<PathBuf>,
+
+        /// Write a wrangler.toml, or Terraform resources for the Cloudflare provider
+        #[structopt(long, default_value = "toml", possible_values = &["toml", "terraform"])]
+        format: ExportFormat,
+    },
+
+    /// List the workers on the account with their size, usage model and routes
+    List {
+        /// Only list the workers with this tag, set with `tags` in the configuration file
+        #[structopt(long)]
+        tag: Option<String>,
+
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+
+    /// Compare the configuration file with the deployed worker, and exit with code 7 if
+    /// they differ
+    Drift {
+        /// The secrets the worker should have, e.g. --secrets API_TOKEN,SIGNING_KEY. Secrets
+        /// aren't compared without it
+        #[structopt(long, use_delimiter = true)]
+        secrets: Option<Vec<String>>,
+
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+
+    /// List the environments in your configuration file and see what each inherits
+    #[structopt(name = "env", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Env(environments::Environments),
+
+    /// See the deployments of your worker and where they were built from
+    #[structopt(name = "deployments", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Deployments(deployments::Deployments),
+
+    /// Inspect the workers routes of a zone
+    #[structopt(name = "zone", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Zone(zone::Zone),
+
+    /// Review the Durable Objects migrations of your worker
+    #[structopt(name = "migrations", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Migrations(migrations::Migrations),
+
+    /// Manage R2 buckets, where their events go, and sync directories to them
+    #[structopt(name = "r2", setting = AppSettings::SubcommandRequiredElseHelp)]
+    R2(r2::R2),
+
+    /// Create Cloudflare Pages projects and deploy static sites to them
+    #[structopt(name = "pages", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Pages(pages::Pages),
+
+    /// Publish your worker under a new name, move its routes and workers.dev over to it and
+    /// update the configuration file
+    #[structopt(name = "rename")]
+    Rename {
+        /// The new name of the worker
+        #[structopt(index = 1)]
+        new_name: String,
+    },
+
+    /// Clean up the workers published with `wrangler publish --preview-alias`
+    #[structopt(name = "preview-alias", setting = AppSettings::SubcommandRequiredElseHelp)]
+    PreviewAlias(preview_alias::PreviewAlias),
+
     /// Generate a secret that can be referenced in the worker script
     #[structopt(name = "secret", setting = AppSettings::SubcommandRequiredElseHelp)]
     Secret(secret::Secret),
@@ -97,7 +250,8 @@ pub enum Command {
         #[structopt(index = 1, default_value = "worker")]
         name: String,
 
-        /// A link to a GitHub template! Defaults to https://github.com/cloudflare/worker-template
+        /// The name of a template from `wrangler generate --list`, a template directory or a
+        /// link to a git repository. Defaults to https://github.com/cloudflare/worker-template
         #[structopt(index = 2)]
         template: Option<String>,
 
@@ -108,9 +262,17 @@ pub enum Command {
         /// Initializes a Workers Sites project. Overrides 'type' and 'template'
         #[structopt(long, short = "s")]
         site: bool,
+
+        /// List the templates that can be generated by name, and whether they work offline
+        #[structopt(long)]
+        list: bool,
+
+        /// Fill in a placeholder of the template instead of being asked for it, as name=value
+        #[structopt(long, short = "d", number_of_values = 1)]
+        define: Vec<String>,
     },
 
-    /// Create a wrangler.toml for an existing project
+    /// Create a wrangler.toml for an existing project. Without arguments, asks how to set it up
     Init {
         /// The name of your worker!
         #[structopt(index = 1)]
@@ -149,6 +311,12 @@ pub enum Command {
         /// Don't open the browser on preview
         #[structopt(long)]
         headless: bool,
+
+        #[structopt(flatten)]
+        checks: PreviewChecks,
+
+        #[structopt(flatten)]
+        share: ShareOptions,
     },
 
     /// Start a local server for developing your worker
@@ -175,6 +343,24 @@ pub enum Command {
         /// but can be set to http
         #[structopt(name = "upstream-protocol")]
         upstream_protocol: Option<Protocol>,
+
+        /// A JSON file of `request.cf` fields the worker should see, e.g.
+        /// {"country": "DE", "botManagement": {"score": 1}}. Replaces [dev.cf]
+        #[structopt(name = "cf-json", long, value_name = "file")]
+        cf_json: Option<PathBuf>,
+
+        /// Stops the worker's clock at this RFC 3339 time, e.g. 2023-01-01T00:00:00Z. GET
+        /// /__time/advance?seconds=<n> moves it forward
+        #[structopt(
+            name = "fake-time",
+            long,
+            value_name = "time",
+            parse(try_from_str = dev::parse_fake_time)
+        )]
+        fake_time: Option<DateTime<Utc>>,
+
+        #[structopt(flatten)]
+        share: ShareOptions,
     },
 
     /// Publish your worker to the orange cloud
@@ -189,6 +375,121 @@ pub enum Command {
 
         #[structopt(flatten)]
         migration: AdhocMigration,
+
+        /// Don't upload the script if it's the same as the last one published from here
+        #[structopt(name = "skip-unchanged", long)]
+        skip_unchanged: bool,
+
+        /// Publish several environments at once, e.g. --envs staging,production
+        #[structopt(long, use_delimiter = true, conflicts_with = "all-envs")]
+        envs: Vec<String>,
+
+        /// Publish every environment defined in the configuration file at once
+        #[structopt(name = "all-envs", long)]
+        all_envs: bool,
+
+        /// Publish a preview of a branch as a separate worker on workers.dev, named after the
+        /// branch
+        #[structopt(
+            name = "preview-alias",
+            long,
+            value_name = "branch",
+            conflicts_with_all = &["envs", "all-envs"]
+        )]
+        preview_alias: Option<String>,
+
+        /// Apply migrations that delete Durable Object classes, and all their objects, without
+        /// asking
+        #[structopt(name = "accept-data-loss", long)]
+        accept_data_loss: bool,
+
+        /// Add a migration to the configuration file for Durable Object classes that no
+        /// migration creates yet, without asking
+        #[structopt(name = "add-migrations", long, visible_alias = "update-config")]
+        add_migrations: bool,
+
+        /// Fail instead of warning when a route overlaps a route of another worker on the zone
+        #[structopt(long)]
+        strict: bool,
+
+        /// Delete the routes on the zone that point at this worker but aren't in the
+        /// configuration file anymore
+        #[structopt(name = "prune-routes", long, conflicts_with = "preview-alias")]
+        prune_routes: bool,
+
+        /// Sign the published bundles with the key in WRANGLER_SIGNING_KEY, writing the
+        /// signature to <name>.sig
+        #[structopt(long)]
+        sign: bool,
+
+        /// Sign the published bundles with the key in this file, created by `wrangler keygen`
+        #[structopt(name = "signing-key", long)]
+        signing_key: Option<PathBuf>,
+
+        /// Keep retrying for up to this long, e.g. "10m", while the Cloudflare API is
+        /// unavailable, instead of failing
+        #[structopt(
+            name = "wait-for-api",
+            long,
+            value_name = "duration",
+            parse(try_from_str = parse_duration)
+        )]
+        wait_for_api: Option<Duration>,
+
+        /// Only update bindings, vars, compatibility settings, routes and cron triggers,
+        /// keeping the deployed code. Secrets, text blobs and wasm modules stay as they are
+        #[structopt(
+            name = "settings-only",
+            long,
+            conflicts_with_all = &["code-only", "preview-alias", "skip-unchanged", "sign", "signing-key"]
+        )]
+        settings_only: bool,
+
+        /// Only upload the code and its bindings, leaving routes, workers.dev and cron
+        /// triggers as they are
+        #[structopt(name = "code-only", long, conflicts_with = "prune-routes")]
+        code_only: bool,
+
+        /// Publish to environments marked `protected = true` without typing the script name to
+        /// confirm, e.g. in CI
+        #[structopt(name = "confirm-protected", long)]
+        confirm_protected: bool,
+    },
+
+    /// Check that the deployed worker is exactly what was signed by `wrangler publish --sign`
+    Verify {
+        /// The signature to check against. Defaults to <name>.sig
+        #[structopt(long)]
+        signature: Option<PathBuf>,
+
+        /// The base64 public key the signature must have been made with, printed by
+        /// `wrangler keygen`
+        #[structopt(name = "public-key", long)]
+        public_key: Option<String>,
+    },
+
+    /// Create a key to sign published workers with
+    Keygen {
+        /// Where to write the key. Keep it secret
+        #[structopt(parse(from_os_str))]
+        path: PathBuf,
+    },
+
+    /// Convert a service-worker project to the modules format
+    #[structopt(name = "migrate-format")]
+    MigrateFormat {
+        /// Where to write the converted script. Defaults to the current entrypoint with an
+        /// `.mjs` extension
+        #[structopt(long, short = "o")]
+        out: Option<PathBuf>,
+
+        /// Additional global bindings (e.g. secrets) to read from `env`
+        #[structopt(long, number_of_values = 1)]
+        binding: Vec<String>,
+
+        /// Print the converted script instead of writing it and updating the configuration file
+        #[structopt(name = "dry-run", long)]
+        dry_run: bool,
     },
 
     /// Authenticate Wrangler with a Cloudflare API Token or Global API Key
@@ -201,6 +502,8 @@ pub enum Command {
         /// Do not verify provided credentials before writing out Wrangler config file
         #[structopt(name = "no-verify", long)]
         no_verify: bool,
+        #[structopt(subcommand)]
+        subcommand: Option<config::ConfigCommand>,
     },
 
     /// Configure your workers.dev subdomain
@@ -215,26 +518,41 @@ pub enum Command {
     #[structopt(name = "whoami")]
     Whoami,
 
+    /// Check your configuration, credentials and network for common problems
+    #[structopt(name = "doctor")]
+    Doctor,
+
     /// Aggregate logs from production worker
     #[structopt(name = "tail")]
-    Tail {
-        /// Specify an output format
-        #[structopt(long, short = "f", default_value = "json", possible_values = &["json", "pretty"])]
-        format: String,
-
-        /// Port to accept tail log requests
-        #[structopt(long = "port", short = "p")]
-        tunnel_port: Option<u16>,
-
-        /// Provides endpoint for cloudflared metrics. Used to retrieve tunnel url
-        #[structopt(long = "metrics")]
-        metrics_port: Option<u16>,
-    },
+    Tail(tail::Tail),
 
     /// Authenticate Wrangler with your Cloudflare username and password
     #[structopt(name = "login")]
     Login,
 
+    /// Turn anonymous usage metrics on or off
+    #[structopt(name = "telemetry", setting = AppSettings::SubcommandRequiredElseHelp)]
+    Telemetry(telemetry::Telemetry),
+
+    /// Show the commands that changed something, e.g. publishes and secrets, run from this
+    /// machine
+    #[structopt(name = "history")]
+    History {
+        /// Only show commands starting with this, e.g. "secret" or "kv:key put"
+        command: Option<String>,
+
+        /// Only show commands run since this RFC 3339 time, or for this long, e.g. "30m"
+        #[structopt(long, parse(try_from_str = history::parse_since))]
+        since: Option<DateTime<Utc>>,
+
+        /// Only show commands that failed
+        #[structopt(long)]
+        failed: bool,
+
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+
     /// Report an error caught by wrangler to Cloudflare
     #[structopt(name = "report")]
     Report {
@@ -244,6 +562,65 @@ pub enum Command {
     },
 }
 
+impl Command {
+    /// Whether the command can need the network. Commands that don't skip the update check
+    /// and telemetry so they start, and finish, without waiting on anything. They're also the
+    /// only ones that run with `--offline`.
+    pub fn uses_network(&self) -> bool {
+        match self {
+            Command::Build
+            | Command::MigrateFormat { .. }
+            | Command::Telemetry(_)
+            | Command::Keygen { .. }
+            | Command::Env(_)
+            | Command::History { .. } => false,
+            // the wizard only offers to create a KV namespace when it's online
+            Command::Init { .. } => false,
+            Command::Generate { list: true, .. } => false,
+            Command::Generate {
+                template: Some(template),
+                ..
+            } => !matches!(Template::resolve(template), Template::Local(_)),
+            Command::Config {
+                subcommand: Some(config::ConfigCommand::Schema),
+                ..
+            } => false,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct PreviewChecks {
+    /// Check the preview's responses against the [[test]] tables in this file and exit
+    /// with an error if any don't match
+    #[structopt(name = "test-file", long)]
+    pub test_file: Option<PathBuf>,
+
+    /// Fail unless the preview responds with this status
+    #[structopt(name = "expect-status", long)]
+    pub expect_status: Option<u16>,
+
+    /// Fail unless the preview's response body contains this
+    #[structopt(name = "expect-body", long)]
+    pub expect_body: Option<String>,
+
+    /// Header to send with the request, e.g. --header "Accept: text/html"
+    #[structopt(name = "header", long, short = "H", number_of_values = 1)]
+    pub headers: Vec<String>,
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct ShareOptions {
+    /// Print the URL as a QR code, to open it on a phone
+    #[structopt(long)]
+    pub qr: bool,
+
+    /// Copy the URL to the clipboard
+    #[structopt(long)]
+    pub copy: bool,
+}
+
 #[derive(Debug, Clone, StructOpt)]
 pub struct AdhocMigration {
     /// Allow durable objects to be created from a class in your script
@@ -337,6 +714,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn only_commands_that_reach_cloudflare_use_the_network() {
+        let uses_network = |args: &[&str]| {
+            Cli::from_iter(std::iter::once(&"wrangler").chain(args))
+                .command
+                .uses_network()
+        };
+
+        assert!(!uses_network(&["init"]));
+        assert!(!uses_network(&["init", "my-worker", "--site"]));
+        assert!(!uses_network(&["generate", "--list"]));
+        assert!(!uses_network(&["generate", "my-worker", "."]));
+        assert!(!uses_network(&["config", "schema"]));
+
+        assert!(uses_network(&["generate", "my-worker"]));
+        assert!(uses_network(&["generate", "my-worker", "router"]));
+        assert!(uses_network(&["config"]));
+        assert!(uses_network(&["publish"]));
+    }
+
     #[test]
     fn adhoc_migration_parsing() {
         let command = Cli::from_iter(&[
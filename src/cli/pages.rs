@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::TableOptions;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Pages {
+    /// Create or list Pages projects
+    Project(PagesProject),
+    /// List the deployments of a Pages project
+    Deployment(PagesDeployment),
+    /// Deploy a directory of static files to a Pages project
+    Deploy {
+        /// The directory with the files to serve
+        #[structopt(index = 1, parse(from_os_str))]
+        directory: PathBuf,
+        /// The directory of Pages Functions to compile into the site's worker. Defaults to
+        /// ./functions when there is one
+        #[structopt(long, parse(from_os_str))]
+        functions: Option<PathBuf>,
+        #[structopt(name = "project-name", long)]
+        project_name: String,
+        /// The branch the deployment is for. Deployments of the production branch go live,
+        /// others get a preview URL
+        #[structopt(long)]
+        branch: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum PagesProject {
+    /// Create a Pages project to deploy to
+    Create {
+        #[structopt(index = 1)]
+        name: String,
+        /// The branch whose deployments are served at the project's pages.dev domain
+        #[structopt(name = "production-branch", long, default_value = "main")]
+        production_branch: String,
+    },
+    /// List the Pages projects of the account
+    List {
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum PagesDeployment {
+    /// List the deployments of a Pages project, newest first
+    List {
+        #[structopt(name = "project-name", long)]
+        project_name: String,
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+}
+
+pub fn pages(pages: Pages, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+
+    // Pages projects aren't workers, the configuration file is only read for the account id
+    let manifest = if cli_params.config.exists() {
+        Manifest::new(&cli_params.config)?
+    } else {
+        Manifest::default()
+    };
+    let account_id = manifest.account_id.load()?;
+
+    match pages {
+        Pages::Project(PagesProject::Create {
+            name,
+            production_branch,
+        }) => commands::pages::create_project(&user, account_id, &name, &production_branch),
+        Pages::Project(PagesProject::List { options }) => {
+            commands::pages::list_projects(&user, account_id, &options)
+        }
+        Pages::Deployment(PagesDeployment::List {
+            project_name,
+            options,
+        }) => commands::pages::list_deployments(&user, account_id, &project_name, &options),
+        Pages::Deploy {
+            directory,
+            functions,
+            project_name,
+            branch,
+        } => {
+            let functions = functions.or_else(|| {
+                let default = PathBuf::from("functions");
+                Some(default).filter(|dir| dir.is_dir())
+            });
+            commands::pages::deploy(
+                &user,
+                account_id,
+                &project_name,
+                &directory,
+                functions.as_deref(),
+                branch.as_deref(),
+            )
+        }
+    }
+}
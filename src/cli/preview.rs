@@ -1,17 +1,23 @@
-use super::Cli;
+use std::collections::BTreeMap;
+
+use super::{Cli, PreviewChecks, ShareOptions};
 use crate::commands;
+use crate::preview::smoke::{self, SmokeTest};
 use crate::preview::{HttpMethod, PreviewOpt};
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
 
-use anyhow::{ensure, Result};
+use anyhow::{anyhow, ensure, Result};
 use url::Url;
 
+#[allow(clippy::too_many_arguments)]
 pub fn preview(
     method: HttpMethod,
     url: Url,
     body: Option<String>,
     watch: bool,
     headless: bool,
+    checks: PreviewChecks,
+    share: ShareOptions,
     cli_params: &Cli,
 ) -> Result<()> {
     log::info!("Getting project settings");
@@ -28,13 +34,77 @@ pub fn preview(
         "Invalid URL scheme (use either \"https\" or \"http\")"
     );
 
+    let checks = smoke_tests(checks, &method, &url, &body)?;
+    ensure!(
+        checks.is_empty() || !watch,
+        "--watch can't be combined with checking the preview's responses"
+    );
+
     let options = PreviewOpt {
         method,
         url,
         body,
         livereload: watch,
-        headless,
+        // checks are for CI, there's no one to look at a browser
+        headless: headless || !checks.is_empty(),
+        checks,
+        qr: share.qr,
+        copy: share.copy,
     };
 
     commands::preview(target, user, options, cli_params.verbose)
 }
+
+/// The tests from `--test-file`, or a single one for the request described on the command line
+/// if any `--expect-*` or `--header` flags were given.
+fn smoke_tests(
+    checks: PreviewChecks,
+    method: &HttpMethod,
+    url: &Url,
+    body: &Option<String>,
+) -> Result<Vec<SmokeTest>> {
+    if let Some(path) = &checks.test_file {
+        ensure!(
+            checks.expect_status.is_none()
+                && checks.expect_body.is_none()
+                && checks.headers.is_empty(),
+            "--test-file can't be combined with --expect-status, --expect-body or --header"
+        );
+        return smoke::load(path);
+    }
+
+    if checks.expect_status.is_none() && checks.expect_body.is_none() && checks.headers.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let headers = checks
+        .headers
+        .iter()
+        .map(|header| parse_header(header))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    Ok(vec![SmokeTest {
+        name: None,
+        method: match method {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+        }
+        .to_string(),
+        path,
+        headers,
+        body: body.clone(),
+        expect_status: checks.expect_status,
+        expect_body: checks.expect_body,
+    }])
+}
+
+fn parse_header(header: &str) -> Result<(String, String)> {
+    let (name, value) = header
+        .split_once(':')
+        .ok_or_else(|| anyhow!("{:?} isn't a header, use \"Name: value\"", header))?;
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
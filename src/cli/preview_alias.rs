@@ -0,0 +1,27 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum PreviewAlias {
+    /// Delete the worker published with `wrangler publish --preview-alias <branch>`
+    Delete {
+        /// The branch the preview was published for
+        #[structopt(index = 1)]
+        branch: String,
+    },
+}
+
+pub fn preview_alias(preview_alias: PreviewAlias, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    match preview_alias {
+        PreviewAlias::Delete { branch } => commands::preview_alias::delete(&user, &target, &branch),
+    }
+}
@@ -1,20 +1,49 @@
 use super::Cli;
 use super::{AdhocMigration, Migrations};
+use crate::build::signing;
 use crate::commands;
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::commands::publish::{EnvironmentTarget, PublishMode};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{check_environments, Manifest, Target};
 use crate::terminal::message::{Message, Output, StdOut};
-use crate::terminal::styles;
+use crate::terminal::{interactive, styles};
+
+use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::Result;
 
+/// The `wrangler publish` flags that don't change what gets built.
+pub struct PublishOptions {
+    pub skip_unchanged: bool,
+    pub envs: Vec<String>,
+    pub all_envs: bool,
+    pub preview_alias: Option<String>,
+    pub accept_data_loss: bool,
+    pub add_migrations: bool,
+    pub strict: bool,
+    pub prune_routes: bool,
+    pub sign: bool,
+    pub signing_key: Option<PathBuf>,
+    pub wait_for_api: Option<Duration>,
+    pub settings_only: bool,
+    pub code_only: bool,
+    pub confirm_protected: bool,
+}
+
 pub fn publish(
     release: bool,
     output: Option<String>,
     migration: AdhocMigration,
+    options: PublishOptions,
     cli_params: &Cli,
 ) -> Result<()> {
     log::info!("Getting User settings");
     let user = GlobalUser::new()?;
+    if let Some(wait_for_api) = options.wait_for_api {
+        http::retry::wait_for_api(wait_for_api);
+    }
 
     if release {
         StdOut::warn(&format!(concat!(
@@ -28,19 +57,221 @@ pub fn publish(
 
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
-
-    if let Some(migration) = migration.into_migration_config() {
-        target.migrations = Some(Migrations {
+    check_durable_objects(&manifest)?;
+    // the key is loaded first so a missing key fails before anything is published
+    let signing_key = if options.sign || options.signing_key.is_some() {
+        Some(signing::load_key(options.signing_key.as_deref())?)
+    } else {
+        None
+    };
+    let migrations = migration
+        .into_migration_config()
+        .map(|migration| Migrations {
             migrations: vec![migration],
+            old_tag: None,
         });
-    }
 
-    let output = if output.as_deref() == Some("json") {
+    let mode = if options.settings_only {
+        PublishMode::SettingsOnly
+    } else if options.code_only {
+        PublishMode::CodeOnly
+    } else {
+        PublishMode::Full
+    };
+
+    let output = if output.as_deref() == Some("json") || cli_params.json {
         Output::Json
     } else {
         Output::PlainText
     };
+
+    if !options.envs.is_empty() || options.all_envs {
+        if cli_params.environment.is_some() {
+            anyhow::bail!("--env can't be combined with --envs or --all-envs, list every environment in --envs instead");
+        }
+        let names = if options.all_envs {
+            let mut names = manifest
+                .env
+                .as_ref()
+                .map(|envs| envs.keys().cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            names.sort();
+            names
+        } else {
+            options.envs
+        };
+        if names.is_empty() {
+            anyhow::bail!("There are no environments in your configuration file to publish");
+        }
+
+        let mut environments = Vec::new();
+        for name in names {
+            let mut target = manifest.get_target(Some(&name), false)?;
+            confirm_protected(&manifest, Some(&name), &target, options.confirm_protected)?;
+            if migrations.is_some() {
+                target.migrations = migrations.clone();
+            }
+            let deployments = manifest.get_deployments(Some(&name))?;
+            commands::route::check_conflicts(&user, &deployments, options.strict)?;
+            environments.push(EnvironmentTarget {
+                deployments,
+                environment: name,
+                target,
+            });
+        }
+        let pruned: Vec<_> = environments
+            .iter()
+            .map(|env| env.deployments.clone())
+            .collect();
+        let targets: Vec<_> = environments.iter().map(|env| env.target.clone()).collect();
+        commands::publish::publish_environments(
+            &user,
+            environments,
+            output,
+            options.skip_unchanged,
+            options.accept_data_loss,
+            mode,
+        )?;
+        if options.prune_routes {
+            for deployments in pruned {
+                commands::route::prune(&user, &deployments)?;
+            }
+        }
+        if let Some(key) = &signing_key {
+            for target in &targets {
+                commands::verify::sign(target, key)?;
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(branch) = options.preview_alias {
+        let environment = cli_params.environment.as_deref();
+        // previews use the preview bindings when every KV namespace has one, the production ones otherwise
+        let mut target = match manifest.get_target(environment, true) {
+            Ok(target) => target,
+            Err(_) => {
+                let target = manifest.get_target(environment, false)?;
+                StdOut::warn("Not every KV namespace has a preview_id, so the preview alias uses the production namespaces");
+                target
+            }
+        };
+        if migrations.is_some() {
+            target.migrations = migrations;
+        }
+        target.name = commands::preview_alias::script_name(&target.name, &branch)?;
+        commands::migrations::offer_new_classes(
+            &user,
+            &cli_params.config,
+            &mut target,
+            options.add_migrations,
+        )?;
+        let deployments = commands::preview_alias::deployments(&target)?;
+        commands::publish(
+            &user,
+            &mut target,
+            environment,
+            deployments,
+            output,
+            options.skip_unchanged,
+            options.accept_data_loss,
+            mode,
+        )?;
+        if let Some(key) = &signing_key {
+            commands::verify::sign(&target, key)?;
+        }
+        return Ok(());
+    }
+
+    let mut target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    confirm_protected(
+        &manifest,
+        cli_params.environment.as_deref(),
+        &target,
+        options.confirm_protected,
+    )?;
+    if migrations.is_some() {
+        target.migrations = migrations;
+    }
+    commands::migrations::offer_new_classes(
+        &user,
+        &cli_params.config,
+        &mut target,
+        options.add_migrations,
+    )?;
     let deploy_config = manifest.get_deployments(cli_params.environment.as_deref())?;
-    commands::publish(&user, &mut target, deploy_config, output)
+    commands::route::check_conflicts(&user, &deploy_config, options.strict)?;
+    commands::publish(
+        &user,
+        &mut target,
+        cli_params.environment.as_deref(),
+        deploy_config.clone(),
+        output,
+        options.skip_unchanged,
+        options.accept_data_loss,
+        mode,
+    )?;
+    if options.prune_routes {
+        commands::route::prune(&user, &deploy_config)?;
+    }
+    if let Some(key) = &signing_key {
+        commands::verify::sign(&target, key)?;
+    }
+    Ok(())
+}
+
+// Publishing to a protected environment takes typing the script name, so a publish with the
+// wrong --env can't go through on a reflex. Preview aliases never need it
+fn confirm_protected(
+    manifest: &Manifest,
+    environment: Option<&str>,
+    target: &Target,
+    confirmed: bool,
+) -> Result<()> {
+    if !manifest.is_protected(environment)? {
+        return Ok(());
+    }
+    let environment = environment.unwrap_or_default();
+    if confirmed {
+        StdOut::info(&format!(
+            "Publishing to protected environment {} (--confirm-protected)",
+            environment
+        ));
+        return Ok(());
+    }
+    if !interactive::is_interactive() {
+        anyhow::bail!(
+            "{} is a protected environment. Pass --confirm-protected to publish to it non-interactively",
+            environment
+        );
+    }
+    let answer = interactive::get_user_input(&format!(
+        "{} is a protected environment. Type the name of the script, {}, to publish to it:",
+        environment, target.name
+    ))?;
+    if answer.trim() != target.name {
+        anyhow::bail!(
+            "\"{}\" isn't {}, nothing was published",
+            answer.trim(),
+            target.name
+        );
+    }
+    Ok(())
+}
+
+// Reports every Durable Objects problem across environments at once, rather than one API
+// error per publish
+fn check_durable_objects(manifest: &Manifest) -> Result<()> {
+    let report = check_environments(manifest);
+    for warning in &report.warnings {
+        StdOut::warn(warning);
+    }
+    if !report.errors.is_empty() {
+        anyhow::bail!(
+            "Your Durable Objects configuration has {} problem(s):\n{}",
+            report.errors.len(),
+            report.errors.join("\n")
+        );
+    }
+    Ok(())
 }
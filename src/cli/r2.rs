@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use super::Cli;
+use crate::commands;
+use crate::commands::r2::bucket::LocationHint;
+use crate::commands::r2::sync::SyncOptions;
+use crate::settings::toml::{R2EventType, R2Notification};
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::TableOptions;
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum R2 {
+    /// Manage R2 buckets
+    Bucket(R2Bucket),
+    /// Upload the files of a directory that differ from the objects in a bucket
+    Sync {
+        #[structopt(index = 1, parse(from_os_str))]
+        directory: PathBuf,
+        /// The bucket to sync to, optionally followed by a prefix for the keys, e.g.
+        /// assets/static
+        #[structopt(index = 2)]
+        destination: String,
+        /// Only sync the files matching this glob, relative to the directory
+        #[structopt(long, number_of_values = 1)]
+        include: Vec<String>,
+        /// Don't sync the files matching this glob, relative to the directory
+        #[structopt(long, number_of_values = 1)]
+        exclude: Vec<String>,
+        /// Delete the objects under the prefix that aren't in the directory
+        #[structopt(long)]
+        delete: bool,
+        /// How many files to upload at once
+        #[structopt(long, default_value = "8")]
+        concurrency: usize,
+    },
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum R2Bucket {
+    /// Create a bucket
+    Create {
+        #[structopt(index = 1)]
+        name: String,
+        /// Where the bucket should keep its data
+        #[structopt(long, possible_values = LocationHint::VARIANTS)]
+        location: Option<LocationHint>,
+    },
+    /// List the buckets of the account
+    List {
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+    /// Delete an empty bucket
+    Delete {
+        #[structopt(index = 1)]
+        name: String,
+    },
+    /// Send the events of a bucket to a queue
+    Notification(R2BucketNotification),
+}
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum R2BucketNotification {
+    /// Send the events of a bucket to a queue, replacing the rule the bucket had for the queue
+    Create {
+        #[structopt(index = 1)]
+        bucket: String,
+        /// The queue to send the events to
+        #[structopt(long)]
+        queue: String,
+        /// The events to send: object-create, object-delete or both, comma separated
+        #[structopt(name = "event-types", long, use_delimiter = true, required = true)]
+        event_types: Vec<R2EventType>,
+        /// Only send events for keys starting with this
+        #[structopt(long)]
+        prefix: Option<String>,
+        /// Only send events for keys ending with this
+        #[structopt(long)]
+        suffix: Option<String>,
+    },
+    /// List the queues a bucket sends events to
+    List {
+        #[structopt(index = 1)]
+        bucket: String,
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+    /// Stop sending the events of a bucket to a queue
+    Delete {
+        #[structopt(index = 1)]
+        bucket: String,
+        #[structopt(long)]
+        queue: String,
+    },
+}
+
+pub fn r2(r2: R2, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    // buckets aren't tied to a worker, the configuration file is only read for the account id
+    let manifest = Manifest::new(&cli_params.config)?;
+    let account_id = manifest.account_id.load()?;
+
+    match r2 {
+        R2::Sync {
+            directory,
+            destination,
+            include,
+            exclude,
+            delete,
+            concurrency,
+        } => commands::r2::sync::sync(
+            &user,
+            account_id,
+            &directory,
+            &destination,
+            &SyncOptions {
+                include,
+                exclude,
+                delete,
+                concurrency,
+            },
+        ),
+        R2::Bucket(R2Bucket::Create { name, location }) => {
+            commands::r2::bucket::create(&user, account_id, &name, location)
+        }
+        R2::Bucket(R2Bucket::List { options }) => {
+            commands::r2::bucket::list(&user, account_id, &options)
+        }
+        R2::Bucket(R2Bucket::Delete { name }) => {
+            commands::r2::bucket::delete(&user, account_id, &name)
+        }
+        R2::Bucket(R2Bucket::Notification(notification)) => match notification {
+            R2BucketNotification::Create {
+                bucket,
+                queue,
+                event_types,
+                prefix,
+                suffix,
+            } => commands::r2::notification::create(
+                &user,
+                account_id,
+                &R2Notification {
+                    bucket,
+                    queue,
+                    event_types,
+                    prefix,
+                    suffix,
+                },
+            ),
+            R2BucketNotification::List { bucket, options } => {
+                commands::r2::notification::list(&user, account_id, &bucket, &options)
+            }
+            R2BucketNotification::Delete { bucket, queue } => {
+                commands::r2::notification::delete(&user, account_id, &bucket, &queue)
+            }
+        },
+    }
+}
@@ -0,0 +1,18 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+
+pub fn rename(new_name: &str, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+
+    commands::rename::rename(
+        &user,
+        &manifest,
+        &cli_params.config,
+        cli_params.environment.as_deref(),
+        new_name,
+    )
+}
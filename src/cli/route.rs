@@ -1,6 +1,9 @@
+use std::path::PathBuf;
+
 use super::Cli;
 use crate::commands;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::terminal::table::TableOptions;
 
 use anyhow::Result;
 use structopt::StructOpt;
@@ -8,19 +11,45 @@ use structopt::StructOpt;
 #[derive(Debug, Clone, StructOpt)]
 #[structopt(rename_all = "lower")]
 pub enum Route {
-    /// List all routes associated with a zone (outputs json)
-    List,
+    /// List all routes associated with a zone
+    List {
+        /// List every route, custom domain and workers.dev URL of the configured worker,
+        /// across all zones of the account, instead
+        #[structopt(long)]
+        script: bool,
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
     /// Delete a route by ID
     Delete {
         /// The ID associated with the route you want to delete (find using `wrangler route list`)
         #[structopt(index = 1)]
         route_id: String,
     },
+    /// Print every route of the zone as JSON, to restore with `wrangler route import`
+    Export,
+    /// Create the routes of a `wrangler route export` file that the zone doesn't have
+    Import {
+        /// The file `wrangler route export` wrote
+        #[structopt(index = 1)]
+        path: PathBuf,
+    },
 }
 
 pub fn route(route: Route, cli_params: &Cli) -> Result<()> {
     let user = GlobalUser::new()?;
     let manifest = Manifest::new(&cli_params.config)?;
+
+    if let Route::List {
+        script: true,
+        options,
+    } = &route
+    {
+        let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+        let account_id = target.account_id.load()?;
+        return commands::route::list_for_script(&user, account_id, &target.name, options);
+    }
+
     let zone_id = manifest
         .get_environment(cli_params.environment.as_deref())?
         .and_then(|e| e.zone_id.as_ref())
@@ -33,7 +62,9 @@ pub fn route(route: Route, cli_params: &Cli) -> Result<()> {
     })?;
 
     match route {
-        Route::List => commands::route::list(zone_id, &user),
+        Route::List { options, .. } => commands::route::list(zone_id, &user, &options),
         Route::Delete { route_id } => commands::route::delete(zone_id, &user, &route_id),
+        Route::Export => commands::route::export(zone_id, &user),
+        Route::Import { path } => commands::route::import(zone_id, &user, &path),
     }
 }
@@ -1,6 +1,11 @@
 use super::Cli;
 use crate::commands;
-use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::commands::secret::SecretInput;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Manifest, Target};
+use crate::terminal::table::TableOptions;
+
+use std::path::PathBuf;
 
 use anyhow::Result;
 use structopt::StructOpt;
@@ -11,14 +16,47 @@ pub enum Secret {
     Put {
         #[structopt(long, short = "n", index = 1)]
         name: String,
+        #[structopt(flatten)]
+        envs: SecretEnvs,
+        /// Ask for the value twice, to catch typos
+        #[structopt(long)]
+        confirm: bool,
+        /// Decode the value from base64 before uploading it. Secrets hold text, so the decoded
+        /// value has to be UTF-8
+        #[structopt(long)]
+        base64: bool,
     },
     /// Delete a secret variable from a script
     Delete {
         #[structopt(long, short = "n", index = 1)]
         name: String,
+        #[structopt(flatten)]
+        envs: SecretEnvs,
     },
     /// List all secrets for a script
-    List,
+    List {
+        #[structopt(flatten)]
+        envs: SecretEnvs,
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+    /// Create or update every secret in a JSON file of names and values
+    Sync {
+        /// The JSON file with the secrets, e.g. {"API_KEY": "..."}
+        #[structopt(long, parse(from_os_str))]
+        from: PathBuf,
+        /// Also delete the secrets the file doesn't list, after confirming
+        #[structopt(long)]
+        delete: bool,
+    },
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct SecretEnvs {
+    /// Environments to operate on at once, e.g. --envs staging,production. The value is only
+    /// asked for once
+    #[structopt(long, use_delimiter = true)]
+    envs: Vec<String>,
 }
 
 pub fn secret(secret: Secret, cli_params: &Cli) -> Result<()> {
@@ -27,10 +65,43 @@ pub fn secret(secret: Secret, cli_params: &Cli) -> Result<()> {
 
     log::info!("Getting project settings");
     let manifest = Manifest::new(&cli_params.config)?;
-    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+    let targets = |envs: SecretEnvs| -> Result<Vec<Target>> {
+        if envs.envs.is_empty() {
+            return Ok(vec![
+                manifest.get_target(cli_params.environment.as_deref(), false)?
+            ]);
+        }
+        if cli_params.environment.is_some() {
+            anyhow::bail!(
+                "--env can't be combined with --envs, list every environment in --envs instead"
+            );
+        }
+        envs.envs
+            .iter()
+            .map(|env| manifest.get_target(Some(env), false))
+            .collect()
+    };
     match secret {
-        Secret::Put { name } => commands::secret::create_secret(&name, &user, &target),
-        Secret::Delete { name } => commands::secret::delete_secret(&name, &user, &target),
-        Secret::List => commands::secret::list_secrets(&user, &target),
+        Secret::Put {
+            name,
+            envs,
+            confirm,
+            base64,
+        } => commands::secret::create_secret(
+            &name,
+            &user,
+            &targets(envs)?,
+            SecretInput { confirm, base64 },
+        ),
+        Secret::Delete { name, envs } => {
+            commands::secret::delete_secret(&name, &user, &targets(envs)?)
+        }
+        Secret::List { envs, options } => {
+            commands::secret::list_secrets(&user, &targets(envs)?, &options)
+        }
+        Secret::Sync { from, delete } => {
+            let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+            commands::secret::sync_secrets(&from, delete, &user, &target)
+        }
     }
 }
@@ -1,25 +1,129 @@
+use std::path::PathBuf;
+
 use super::Cli;
 use crate::commands;
+use crate::commands::tail::TailScripts;
+use crate::deploy::DeployTarget;
 use crate::settings::{global_user::GlobalUser, toml::Manifest};
+use crate::tail::{TailFilters, TailOptions, STATUSES};
+use crate::terminal::message::{Message, StdErr};
 
 use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct Tail {
+    /// Name of a Worker script to tail instead of the one in your configuration file.
+    /// Can be given several times to tail multiple scripts at once
+    #[structopt(long, number_of_values = 1)]
+    name: Vec<String>,
+
+    /// Tail every Worker script on your account
+    #[structopt(long, conflicts_with = "name")]
+    all: bool,
+
+    /// Only show events with this outcome. Can be given several times
+    #[structopt(long, number_of_values = 1, possible_values = STATUSES)]
+    status: Vec<String>,
+
+    /// Only show requests to URLs matching this route pattern, e.g. example.com/api/*.
+    /// Can be given several times
+    #[structopt(long, number_of_values = 1)]
+    route: Vec<String>,
 
-pub fn tail(
+    /// Specify an output format
+    #[structopt(long, short = "f", default_value = "json", possible_values = &["json", "pretty"])]
     format: String,
+
+    /// Pipe every event as a line of JSON to this command instead of printing it,
+    /// e.g. --exec "jq .logs"
+    #[structopt(long)]
+    exec: Option<String>,
+
+    /// Also append every event as a line of JSON to this file
+    #[structopt(long, short = "o")]
+    out: Option<PathBuf>,
+
+    /// Rotate the output file once it grows past this many megabytes
+    #[structopt(long = "max-size", default_value = "100")]
+    max_size: u64,
+
+    /// Port to accept tail log requests
+    #[structopt(long = "port", short = "p")]
     tunnel_port: Option<u16>,
+
+    /// Provides endpoint for cloudflared metrics. Used to retrieve tunnel url
+    #[structopt(long = "metrics")]
     metrics_port: Option<u16>,
-    cli_params: &Cli,
-) -> Result<()> {
+}
+
+pub fn tail(tail: Tail, cli_params: &Cli) -> Result<()> {
     let manifest = Manifest::new(&cli_params.config)?;
     let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
     let user = GlobalUser::new()?;
 
+    if !tail.route.is_empty() {
+        check_routes(&manifest, cli_params.environment.as_deref(), &tail.route);
+    }
+
+    let options = TailOptions {
+        format: tail.format,
+        out: tail.out,
+        max_size: tail.max_size,
+        show_script_name: false,
+        filters: TailFilters::new(&tail.status, &tail.route),
+        exec: tail.exec,
+    };
+
+    let scripts = if tail.all {
+        TailScripts::All
+    } else if !tail.name.is_empty() {
+        TailScripts::Named(tail.name)
+    } else {
+        if let Some(environment) = &cli_params.environment {
+            StdErr::info(&format!(
+                "Tailing \"{}\", the script for environment \"{}\"",
+                target.name, environment
+            ));
+        }
+        TailScripts::Target
+    };
+
     commands::tail::start(
         &target,
         &user,
-        format,
-        tunnel_port,
-        metrics_port,
+        scripts,
+        options,
+        tail.tunnel_port,
+        tail.metrics_port,
         cli_params.verbose,
     )
 }
+
+// A typo in --route would silently hide every event, so point out routes the worker
+// isn't deployed on. Filtering still goes ahead, since routes can also be set up outside
+// of the configuration file.
+fn check_routes(manifest: &Manifest, environment: Option<&str>, routes: &[String]) {
+    let configured: Vec<String> = match manifest.get_deployments(environment) {
+        Ok(deployments) => deployments
+            .into_iter()
+            .filter_map(|deployment| match deployment {
+                DeployTarget::Zoned(zoned) => Some(zoned.routes),
+                _ => None,
+            })
+            .flatten()
+            .map(|route| route.pattern)
+            .collect(),
+        Err(_) => return,
+    };
+
+    for route in routes {
+        if !configured.is_empty() && !configured.contains(route) {
+            StdErr::warn(&format!(
+                "{} is not one of the routes in your configuration file: {}",
+                route,
+                configured.join(", ")
+            ));
+        }
+    }
+}
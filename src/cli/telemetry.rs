@@ -0,0 +1,41 @@
+use crate::telemetry;
+use crate::terminal::message::{Message, StdOut};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Telemetry {
+    /// Send anonymous usage metrics
+    Enable,
+    /// Stop sending anonymous usage metrics
+    Disable,
+    /// Show whether anonymous usage metrics are sent
+    Status,
+}
+
+pub fn telemetry(telemetry: Telemetry) -> Result<()> {
+    match telemetry {
+        Telemetry::Enable => {
+            telemetry::set_enabled(true)?;
+            StdOut::success("Thanks! wrangler will send anonymous usage metrics.");
+            if telemetry::disabled_by_env() {
+                StdOut::warn("WRANGLER_TELEMETRY or DO_NOT_TRACK is set, so nothing will be sent while it is.");
+            }
+        }
+        Telemetry::Disable => {
+            telemetry::set_enabled(false)?;
+            StdOut::success("wrangler won't send usage metrics.");
+        }
+        Telemetry::Status => {
+            let status = if telemetry::is_enabled() {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            StdOut::info(&format!("Telemetry is {}.", status));
+        }
+    }
+    Ok(())
+}
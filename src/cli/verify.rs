@@ -0,0 +1,23 @@
+use std::path::{Path, PathBuf};
+
+use super::Cli;
+use crate::commands;
+use crate::settings::{global_user::GlobalUser, toml::Manifest};
+
+use anyhow::Result;
+
+pub fn verify(
+    signature: Option<PathBuf>,
+    public_key: Option<String>,
+    cli_params: &Cli,
+) -> Result<()> {
+    let user = GlobalUser::new()?;
+    let manifest = Manifest::new(&cli_params.config)?;
+    let target = manifest.get_target(cli_params.environment.as_deref(), false)?;
+
+    commands::verify::verify(&user, &target, signature.as_deref(), public_key.as_deref())
+}
+
+pub fn keygen(path: &Path) -> Result<()> {
+    commands::verify::keygen(path)
+}
@@ -0,0 +1,34 @@
+use super::Cli;
+use crate::commands;
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::table::{Format, TableOptions};
+
+use anyhow::Result;
+use structopt::StructOpt;
+
+#[derive(Debug, Clone, StructOpt)]
+#[structopt(rename_all = "lower")]
+pub enum Zone {
+    /// List every route on a zone and the worker serving it, pointing out routes to workers
+    /// that don't exist anymore
+    Inspect {
+        /// The name or ID of the zone, e.g. example.com
+        #[structopt(index = 1)]
+        zone: String,
+        #[structopt(flatten)]
+        options: TableOptions,
+    },
+}
+
+pub fn zone(zone: Zone, cli_params: &Cli) -> Result<()> {
+    let user = GlobalUser::new()?;
+
+    match zone {
+        Zone::Inspect { zone, mut options } => {
+            if cli_params.json {
+                options.format = Format::Json;
+            }
+            commands::zone::inspect(&user, &zone, &options)
+        }
+    }
+}
@@ -8,8 +8,10 @@ use std::path::Path;
 use anyhow::Result;
 use cloudflare::endpoints::user::{GetUserDetails, GetUserTokenStatus};
 use cloudflare::framework::apiclient::ApiClient;
+use schemars::schema::RootSchema;
 
 use crate::http;
+use crate::settings::toml::Manifest;
 use crate::settings::{get_global_config_path, global_user::GlobalUser};
 use crate::terminal::message::{Message, StdOut};
 use crate::terminal::styles;
@@ -74,3 +76,41 @@ pub fn validate_credentials(user: &GlobalUser) -> Result<()> {
         },
     }
 }
+
+/// The JSON Schema of the configuration file, generated from the types it's read into.
+pub fn schema() -> RootSchema {
+    let mut schema = schemars::schema_for!(Manifest);
+    let metadata = schema.schema.metadata();
+    metadata.title = Some("wrangler.toml".to_string());
+    metadata.description = Some("Configuration of a Cloudflare Workers project".to_string());
+    schema
+}
+
+/// Prints the schema, for editors to validate and complete the configuration file with, e.g.
+/// through a `#:schema ./wrangler.schema.json` comment at the top of it.
+pub fn print_schema() -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(&schema())?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_schema_describes_the_config_file() {
+        let schema = serde_json::to_value(schema()).unwrap();
+        assert_eq!(schema["title"], "wrangler.toml");
+
+        let properties = &schema["properties"];
+        assert!(properties["kv_namespaces"].is_object());
+        assert!(properties["durable_objects"].is_object());
+        // fields go by the names they have in the file
+        assert!(properties["type"].is_object());
+        assert!(properties["target_type"].is_null());
+        assert_eq!(
+            schema["definitions"]["TargetType"]["enum"],
+            serde_json::json!(["javascript", "rust", "webpack"])
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::table::{Table, TableOptions};
+
+#[derive(Deserialize)]
+struct Deployments {
+    items: Vec<Deployment>,
+}
+
+#[derive(Deserialize)]
+struct Deployment {
+    id: String,
+    metadata: DeploymentMetadata,
+    #[serde(default)]
+    annotations: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct DeploymentMetadata {
+    #[serde(default)]
+    author_email: Option<String>,
+    #[serde(default)]
+    source: Option<String>,
+    created_on: String,
+}
+
+/// Lists the deployments of the target's worker, newest first, with the commit, branch and CI
+/// run each was published from.
+pub fn list(user: &GlobalUser, target: &Target, options: &TableOptions) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let account_id = target.account_id.load()?;
+    let tag = match deployed::script_tag(&client, account_id, &target.name)? {
+        Some(tag) => tag,
+        None => anyhow::bail!("{} hasn't been published yet", target.name),
+    };
+
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/deployments/by-script/{}",
+        account_id, tag
+    );
+    let mut deployments = deployed::get::<Deployments>(&client, &addr)?
+        .map(|deployments| deployments.items)
+        .unwrap_or_default();
+    deployments.sort_by(|a, b| b.metadata.created_on.cmp(&a.metadata.created_on));
+
+    let rows = deployments
+        .iter()
+        .map(|deployment| {
+            json!({
+                "id": deployment.id,
+                "created_on": deployment.metadata.created_on,
+                "author": deployment.metadata.author_email,
+                "source": deployment.metadata.source,
+                "commit": deployment.annotations.get("workers/tag"),
+                "provenance": deployment.annotations.get("workers/message"),
+            })
+        })
+        .collect();
+    Table::new(
+        &[
+            "id",
+            "created_on",
+            "author",
+            "source",
+            "commit",
+            "provenance",
+        ],
+        rows,
+    )
+    .print(options)
+}
@@ -33,19 +33,15 @@ pub fn dev(
 ) -> Result<()> {
     let runtime = TokioRuntime::new()?;
     loop {
-        let target = target.clone();
-        let user = user.clone();
-        let server_config = server_config.clone();
-        let deploy_target = deploy_target.clone();
         let (sender, receiver) = mpsc::channel();
         let (tx_init_shutdown, rx_init_shutdown) = oneshot::channel();
         let (tx_ack_shutdown, rx_ack_shutdown) = oneshot::channel();
 
         let tasks = dev_once(
-            target,
-            user,
-            server_config,
-            deploy_target,
+            target.clone(),
+            user.clone(),
+            server_config.clone(),
+            deploy_target.clone(),
             local_protocol,
             upstream_protocol,
             verbose,
@@ -66,6 +62,7 @@ pub fn dev(
         for task in tasks {
             task.abort();
         }
+        Session::forget(&target, &user, &deploy_target);
         StdOut::info("Starting a new session because the existing token has expired");
     }
 }
@@ -83,17 +80,37 @@ fn dev_once(
     refresh_session_sender: Sender<Option<()>>,
     shutdown_channel: (oneshot::Receiver<()>, oneshot::Sender<()>),
 ) -> Result<Vec<JoinHandle<Result<()>>>> {
-    let session = Session::new(&target, &user, &deploy_target)?;
+    let mut session = Session::reuse_or_new(&target, &user, &deploy_target)?;
+    // uploading adds the site's namespace to the target, a second attempt starts over
+    let unuploaded = target.clone();
 
-    let preview_token = upload(
+    let preview_token = match upload(
         &mut target,
         &deploy_target,
         &user,
         session.preview_token.clone(),
         verbose,
-    )?;
+    ) {
+        Ok(preview_token) => preview_token,
+        // the session of an earlier run may have expired in the meantime
+        Err(e) if session.reused => {
+            log::info!("could not reuse the last preview session: {}", e);
+            Session::forget(&target, &user, &deploy_target);
+            session = Session::reuse_or_new(&target, &user, &deploy_target)?;
+            target = unuploaded;
+            upload(
+                &mut target,
+                &deploy_target,
+                &user,
+                session.preview_token.clone(),
+                verbose,
+            )?
+        }
+        Err(e) => return Err(e),
+    };
 
     let preview_token = Arc::new(Mutex::new(preview_token));
+    let socket_target = target.clone();
 
     {
         let preview_token = preview_token.clone();
@@ -116,6 +133,7 @@ fn dev_once(
     let devtools_listener = runtime.spawn(socket::listen(
         session.websocket_url,
         Some(refresh_session_sender),
+        socket_target,
     ));
     let server = match local_protocol {
         Protocol::Https => runtime.spawn(server::https(
@@ -1,5 +1,7 @@
 use std::path::Path;
+use std::time::Duration;
 
+use crate::cache;
 use crate::deploy::DeployTarget;
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
@@ -34,7 +36,7 @@ pub(super) fn upload(
             StdOut::info("Uploading updated files...");
         }
 
-        bulk::put(target, user, &site_namespace.id, to_upload, &None)?;
+        bulk::put(target, user, &site_namespace.id, to_upload, None)?;
         (to_delete, Some(asset_manifest), Some(site_namespace.id))
     } else {
         (Vec::new(), None, None)
@@ -43,7 +45,8 @@ pub(super) fn upload(
     let session_config = get_session_config(deploy_target);
     let address = get_upload_address(target)?;
 
-    let script_upload_form = upload::form::build(target, asset_manifest, Some(session_config))?;
+    let script_upload_form =
+        upload::form::build(target, asset_manifest, Some(session_config), None)?;
 
     let response = client
         .post(&address)
@@ -57,7 +60,7 @@ pub(super) fn upload(
             StdOut::info("Deleting stale files...");
         }
 
-        bulk::delete(target, user, &site_namespace_id.unwrap(), to_delete, &None)?;
+        bulk::delete(target, user, &site_namespace_id.unwrap(), to_delete, None)?;
     }
 
     let text = &response.text()?;
@@ -67,14 +70,65 @@ pub(super) fn upload(
     Ok(response.result.preview_token)
 }
 
+// Preview sessions outlive a `wrangler dev` run, so a restart picks the last one up again for
+// a while instead of starting another
+const SESSION_TTL: Duration = Duration::from_secs(45 * 60);
+
 #[derive(Debug, Clone)]
 pub struct Session {
     pub host: String,
     pub websocket_url: Url,
     pub preview_token: String,
+    /// Whether this session was started by an earlier run, it may have expired since.
+    pub reused: bool,
+}
+
+// A session as it's kept between runs
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    host: String,
+    websocket_url: String,
+    preview_token: String,
 }
 
 impl Session {
+    /// The session the last `wrangler dev` of the same script, on the same routes or
+    /// workers.dev, started if it's recent enough, or a new one.
+    pub fn reuse_or_new(
+        target: &Target,
+        user: &GlobalUser,
+        deploy_target: &DeployTarget,
+    ) -> Result<Session> {
+        let mut started = false;
+        let stored = cache::get_or_fetch(
+            &session_key(target, user, deploy_target),
+            SESSION_TTL,
+            || {
+                started = true;
+                let session = Session::new(target, user, deploy_target)?;
+                Ok(StoredSession {
+                    host: session.host,
+                    websocket_url: session.websocket_url.to_string(),
+                    preview_token: session.preview_token,
+                })
+            },
+        )?;
+        if !started {
+            log::info!("reusing the preview session of the last run");
+        }
+        Ok(Session {
+            host: stored.host,
+            websocket_url: Url::parse(&stored.websocket_url)?,
+            preview_token: stored.preview_token,
+            reused: !started,
+        })
+    }
+
+    /// Stops the session of `target` from being reused, e.g. once it expired.
+    pub fn forget(target: &Target, user: &GlobalUser, deploy_target: &DeployTarget) {
+        cache::invalidate(&session_key(target, user, deploy_target));
+    }
+
     pub fn new(
         target: &Target,
         user: &GlobalUser,
@@ -111,10 +165,20 @@ impl Session {
             host,
             websocket_url,
             preview_token,
+            reused: false,
         })
     }
 }
 
+fn session_key(target: &Target, user: &GlobalUser, deploy_target: &DeployTarget) -> String {
+    let name = format!(
+        "dev-session-{}-{}",
+        target.name,
+        get_session_config(deploy_target)
+    );
+    cache::user_key(&name, user)
+}
+
 fn get_session_config(target: &DeployTarget) -> serde_json::Value {
     match target {
         DeployTarget::Zoned(config) => {
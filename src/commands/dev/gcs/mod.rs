@@ -48,6 +48,7 @@ pub fn dev(
     // preview ID into an Arc<Mutex so that the server waits on the
     // file watcher to release the lock before routing a request
     let preview_id = Arc::new(Mutex::new(preview_id));
+    let socket_target = target.clone();
     // a new scope is created to satisfy the borrow checker
     {
         // we must clone each of these variables in order to
@@ -74,7 +75,8 @@ pub fn dev(
     // and we must block the main thread on the completion of
     // said futures
     runtime.block_on(async {
-        let devtools_listener = tokio::spawn(socket::listen(socket_url.clone(), None));
+        let devtools_listener =
+            tokio::spawn(socket::listen(socket_url.clone(), None, socket_target));
 
         let server = match local_protocol {
             Protocol::Https => tokio::spawn(server::https(
@@ -8,7 +8,10 @@ use futures_util::sink::SinkExt;
 use futures_util::stream::{SplitStream, StreamExt};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 
+use crate::settings::toml::Target;
+use crate::sourcemap::SourceMaps;
 use crate::terminal::colored_json_string;
+use crate::terminal::console;
 use crate::terminal::message::{Message, StdErr, StdOut};
 use protocol::domain::runtime::event::Event::ExceptionThrown;
 use tokio::net::TcpStream;
@@ -24,9 +27,11 @@ const KEEP_ALIVE_INTERVAL: u64 = 10;
 
 /// connect to a Workers runtime WebSocket emitting the Chrome Devtools Protocol
 /// parse all console messages, and print them to stdout
+/// exception stack traces are mapped through the source maps of the latest build of `target`
 pub async fn listen(
     socket_url: Url,
     refresh_session_sender: Option<Sender<Option<()>>>,
+    target: Target,
 ) -> Result<()> {
     // we loop here so we can issue a reconnect when something
     // goes wrong with the websocket connection
@@ -60,7 +65,7 @@ pub async fn listen(
             .map_err(Into::into);
 
         // parse all incoming messages and print them to stdout
-        let printer = print_ws_messages(read);
+        let printer = print_ws_messages(read, &target);
 
         // run the heartbeat and message printer in parallel
         if tokio::try_join!(heartbeat, keep_alive_to_ws, printer).is_ok() {
@@ -127,14 +132,47 @@ fn print_json(value: Result<serde_json::Value, serde_json::Error>, fallback: Str
     }
 }
 
+// console arguments arrive as devtools RemoteObjects; primitives carry their value,
+// everything else only a description
+fn console_message(params: &serde_json::Value) -> Option<String> {
+    let level = params.get("type")?.as_str()?;
+    let args = params
+        .get("args")?
+        .as_array()?
+        .iter()
+        .map(|arg| {
+            arg.get("value")
+                .or_else(|| arg.get("unserializableValue"))
+                .or_else(|| arg.get("description"))
+                .cloned()
+                .unwrap_or(serde_json::Value::Null)
+        })
+        .collect();
+
+    Some(console::format_log(
+        level,
+        &console::format_args(&serde_json::Value::Array(args)),
+    ))
+}
+
 async fn print_ws_messages(
     mut read: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    target: &Target,
 ) -> Result<()> {
     while let Some(message) = read.next().await {
         let message = message?;
         let message_text = message.into_text().unwrap();
         log::info!("{}", &message_text);
 
+        if let Ok(raw) = serde_json::from_str::<serde_json::Value>(&message_text) {
+            if raw["method"] == "Runtime.consoleAPICalled" {
+                if let Some(message) = console_message(&raw["params"]) {
+                    StdOut::message(&message);
+                    continue;
+                }
+            }
+        }
+
         let parsed_message: Result<protocol::Runtime> = serde_json::from_str(&message_text)
             .map_err(|e| anyhow!("Failed to parse event:\n{}", e));
 
@@ -148,15 +186,21 @@ async fn print_ws_messages(
                     .as_ref()
                     .unwrap_or(&default_description);
 
-                StdOut::message(&format!(
-                    "{} at line {:?}, col {:?}",
-                    description,
-                    params.exception_details.line_number,
-                    params.exception_details.column_number,
-                ));
-
-                let json_parse = serde_json::to_value(params.clone());
-                print_json(json_parse, format!("{:?}", params));
+                // The worker may have been rebuilt since the last exception, so reload every time
+                let source_maps = SourceMaps::load(target);
+                if source_maps.is_empty() {
+                    StdOut::message(&format!(
+                        "{} at line {:?}, col {:?}",
+                        description,
+                        params.exception_details.line_number,
+                        params.exception_details.column_number,
+                    ));
+
+                    let json_parse = serde_json::to_value(params.clone());
+                    print_json(json_parse, format!("{:?}", params));
+                } else {
+                    StdOut::message(&source_maps.map_stack(description));
+                }
             }
             Ok(protocol::Runtime::Event(event)) => {
                 // Try to parse json to pretty print, otherwise just print string
@@ -0,0 +1,336 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::commands::config::validate_credentials;
+use crate::commands::whoami::{fetch_accounts, fetch_api_token_email};
+use crate::error::{ErrorCategory, WranglerError};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Manifest, TargetType};
+use crate::terminal::message::{self, Message, Output, StdOut};
+use crate::terminal::{emoji, styles};
+
+const API_URL: &str = "https://api.cloudflare.com/client/v4/";
+// Signed requests and token checks start failing well before this, but small drift is normal
+const MAX_CLOCK_SKEW_SECONDS: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+    Skip,
+}
+
+/// The result of one `wrangler doctor` check, with a suggested fix when it didn't pass.
+#[derive(Debug, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: String) -> Check {
+        Check {
+            name,
+            status: Status::Pass,
+            detail,
+            fix: None,
+        }
+    }
+
+    fn skip(name: &'static str, detail: &str) -> Check {
+        Check {
+            name,
+            status: Status::Skip,
+            detail: detail.to_string(),
+            fix: None,
+        }
+    }
+
+    fn problem(name: &'static str, status: Status, detail: String, fix: &str) -> Check {
+        Check {
+            name,
+            status,
+            detail,
+            fix: Some(fix.to_string()),
+        }
+    }
+}
+
+/// Checks the local environment for the usual reasons wrangler doesn't work, and prints how
+/// to fix each problem it finds.
+pub fn run(config_path: &Path, environment: Option<&str>) -> Result<()> {
+    let (config, target_type) = check_config(config_path, environment);
+    let mut checks = vec![config, check_node(target_type)];
+    checks.extend(check_network());
+    checks.extend(check_credentials());
+
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+
+    if message::output() == Output::Json {
+        StdOut::as_json(&checks);
+    } else {
+        for check in &checks {
+            print_check(check);
+        }
+    }
+
+    if failed > 0 {
+        return Err(WranglerError::new(
+            ErrorCategory::Internal,
+            "doctor.failed",
+            anyhow::anyhow!("{} of {} checks failed", failed, checks.len()),
+        ));
+    }
+
+    StdOut::success("Everything looks good!");
+    Ok(())
+}
+
+fn print_check(check: &Check) {
+    let mark = match check.status {
+        Status::Pass => styles::bold("ok  "),
+        Status::Warn => styles::highlight("warn"),
+        Status::Fail => styles::warning("FAIL"),
+        Status::Skip => styles::cyan("skip"),
+    };
+    StdOut::message(&format!("{} {}: {}", mark, check.name, check.detail));
+    if let Some(fix) = &check.fix {
+        StdOut::message(&format!("       {}{}", emoji::INFO, fix));
+    }
+}
+
+fn check_config(config_path: &Path, environment: Option<&str>) -> (Check, Option<TargetType>) {
+    const NAME: &str = "config";
+
+    if !config_path.exists() {
+        let check = Check::problem(
+            NAME,
+            Status::Warn,
+            format!("{} not found", config_path.display()),
+            "Run `wrangler init` to create one, or pass its path with --config",
+        );
+        return (check, None);
+    }
+
+    let target =
+        Manifest::new(config_path).and_then(|manifest| manifest.get_target(environment, false));
+    match target {
+        Ok(target) => {
+            let check = Check::pass(
+                NAME,
+                format!("{} is valid ({})", config_path.display(), target.name),
+            );
+            (check, Some(target.target_type))
+        }
+        Err(e) => {
+            let check = Check::problem(
+                NAME,
+                Status::Fail,
+                format!("{:#}", e),
+                "Fix the error above in your configuration file",
+            );
+            (check, None)
+        }
+    }
+}
+
+fn check_node(target_type: Option<TargetType>) -> Check {
+    const NAME: &str = "node";
+
+    if target_type != Some(TargetType::Webpack) {
+        return Check::skip(NAME, "only needed for webpack projects");
+    }
+
+    for tool in &["node", "npm"] {
+        if which::which(tool).is_err() {
+            return Check::problem(
+                NAME,
+                Status::Fail,
+                format!("`{}` isn't on your PATH", tool),
+                "Install Node.js and npm from https://nodejs.org",
+            );
+        }
+    }
+
+    match Command::new("node").arg("--version").output() {
+        Ok(output) if output.status.success() => Check::pass(
+            NAME,
+            format!("node {}", String::from_utf8_lossy(&output.stdout).trim()),
+        ),
+        _ => Check::problem(
+            NAME,
+            Status::Fail,
+            "`node --version` failed".to_string(),
+            "Reinstall Node.js from https://nodejs.org",
+        ),
+    }
+}
+
+// Any response at all means the API is reachable; its Date header tells us the clock skew.
+fn check_network() -> Vec<Check> {
+    const NAME: &str = "network";
+
+    let response = http::client().get(API_URL).send();
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            return vec![
+                Check::problem(
+                    NAME,
+                    Status::Fail,
+                    format!("couldn't reach {}: {}", API_URL, e),
+                    "Check your internet connection, and HTTPS_PROXY if you're behind a proxy",
+                ),
+                Check::skip("clock", "needs the API to be reachable"),
+            ]
+        }
+    };
+
+    let server_time = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|date| date.to_str().ok())
+        .and_then(|date| DateTime::parse_from_rfc2822(date).ok());
+
+    let clock = match server_time {
+        Some(server_time) => check_clock(Utc::now(), server_time.with_timezone(&Utc)),
+        None => Check::skip("clock", "the API didn't send its time"),
+    };
+
+    vec![
+        Check::pass(NAME, format!("{} is reachable", API_URL)),
+        clock,
+    ]
+}
+
+fn check_clock(local: DateTime<Utc>, server: DateTime<Utc>) -> Check {
+    const NAME: &str = "clock";
+
+    let skew = (local - server).num_seconds();
+    if skew.abs() <= MAX_CLOCK_SKEW_SECONDS {
+        return Check::pass(NAME, "in sync with the API".to_string());
+    }
+
+    let direction = if skew > 0 { "ahead of" } else { "behind" };
+    Check::problem(
+        NAME,
+        Status::Warn,
+        format!("{}s {} the API", skew.abs(), direction),
+        "Sync your system clock, e.g. by turning on automatic time",
+    )
+}
+
+fn check_credentials() -> Vec<Check> {
+    const NAME: &str = "credentials";
+
+    let user = match GlobalUser::new() {
+        Ok(user) => user,
+        Err(e) => {
+            return vec![
+                Check::problem(
+                    NAME,
+                    Status::Fail,
+                    e.to_string(),
+                    "Run `wrangler login`, `wrangler config`, or set CF_API_TOKEN",
+                ),
+                Check::skip("permissions", "needs credentials"),
+            ]
+        }
+    };
+
+    if let Err(e) = validate_credentials(&user) {
+        return vec![
+            Check::problem(
+                NAME,
+                Status::Fail,
+                e.to_string(),
+                "Run `wrangler login` or `wrangler config` with working credentials",
+            ),
+            Check::skip("permissions", "needs valid credentials"),
+        ];
+    }
+
+    let auth_type = match user {
+        GlobalUser::TokenAuth { .. } => "API token",
+        GlobalUser::GlobalKeyAuth { .. } => "global API key",
+    };
+    vec![
+        Check::pass(NAME, format!("{} is valid", auth_type)),
+        check_permissions(&user),
+    ]
+}
+
+fn check_permissions(user: &GlobalUser) -> Check {
+    const NAME: &str = "permissions";
+
+    if let GlobalUser::GlobalKeyAuth { .. } = user {
+        return Check::skip(NAME, "global API keys have every permission");
+    }
+
+    let mut missing = Vec::new();
+    let accounts = fetch_api_token_email(user, &mut missing).and_then(|_| fetch_accounts(user));
+    match accounts {
+        Ok(accounts) if accounts.is_empty() => missing.push("Account Settings: Read".to_string()),
+        Ok(_) => {}
+        Err(e) => {
+            return Check::problem(
+                NAME,
+                Status::Warn,
+                e.to_string(),
+                "Run `wrangler whoami` to see what your token can access",
+            )
+        }
+    }
+
+    if missing.is_empty() {
+        Check::pass(NAME, "token can read your user and accounts".to_string())
+    } else {
+        Check::problem(
+            NAME,
+            Status::Warn,
+            format!("token is missing {}", missing.join(", ")),
+            "Create a token with these permissions and run `wrangler config`",
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn small_clock_skew_passes() {
+        let server = Utc.ymd(2021, 7, 1).and_hms(12, 0, 0);
+        let local = Utc.ymd(2021, 7, 1).and_hms(12, 0, 30);
+        assert_eq!(check_clock(local, server).status, Status::Pass);
+    }
+
+    #[test]
+    fn large_clock_skew_warns() {
+        let server = Utc.ymd(2021, 7, 1).and_hms(12, 0, 0);
+        let local = Utc.ymd(2021, 7, 1).and_hms(11, 50, 0);
+        let check = check_clock(local, server);
+        assert_eq!(check.status, Status::Warn);
+        assert_eq!(check.detail, "600s behind the API");
+    }
+
+    #[test]
+    fn node_is_only_checked_for_webpack() {
+        assert_eq!(check_node(None).status, Status::Skip);
+        assert_eq!(
+            check_node(Some(TargetType::JavaScript)).status,
+            Status::Skip
+        );
+    }
+}
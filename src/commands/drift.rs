@@ -0,0 +1,323 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+use crate::deploy::deployed::{self, DeployedScript};
+use crate::deploy::{DeployTarget, DeploymentSet};
+use crate::error::{ErrorCategory, WranglerError};
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::table::{Table, TableOptions};
+
+/// A setting that's different in the configuration file than on the deployed worker. Either
+/// side is `None` when the setting only exists on the other.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Difference {
+    pub setting: &'static str,
+    pub name: String,
+    pub configured: Option<String>,
+    pub deployed: Option<String>,
+}
+
+/// Compares the configuration of `target` with what's deployed. Prints the differences and
+/// fails with the drift exit code if there are any, so a scheduled CI job can alert on it.
+pub fn drift(
+    user: &GlobalUser,
+    target: &Target,
+    deployments: &DeploymentSet,
+    secrets: Option<&[String]>,
+    options: &TableOptions,
+) -> Result<()> {
+    let zone_id = deployments.iter().find_map(|deployment| match deployment {
+        DeployTarget::Zoned(zoned) => Some(zoned.zone_id.as_str()),
+        _ => None,
+    });
+    let deployed = deployed::fetch(user, target.account_id.load()?, &target.name, zone_id)?;
+    if zone_id.is_none() {
+        StdErr::info("No routes are configured, so routes added outside wrangler aren't checked");
+    }
+
+    let differences = compare(target, deployments, secrets, &deployed);
+    if differences.is_empty() {
+        StdErr::success(&format!("{} matches its configuration", target.name));
+        return Ok(());
+    }
+
+    Table::from_serialize(&["setting", "name", "configured", "deployed"], &differences)?
+        .print(options)?;
+    Err(WranglerError::new(
+        ErrorCategory::Drift,
+        "drift.detected",
+        anyhow!(
+            "{} has drifted from its configuration in {} place{}",
+            target.name,
+            differences.len(),
+            if differences.len() == 1 { "" } else { "s" }
+        ),
+    ))
+}
+
+/// Everything that differs between the configuration and the deployed worker. Secrets are only
+/// compared when the expected ones are given, since they aren't part of the configuration.
+pub fn compare(
+    target: &Target,
+    deployments: &DeploymentSet,
+    secrets: Option<&[String]>,
+    deployed: &DeployedScript,
+) -> Vec<Difference> {
+    let mut differences = Vec::new();
+
+    let mut routes = BTreeMap::new();
+    let mut crons = BTreeMap::new();
+    let mut workers_dev = false;
+    for deployment in deployments {
+        match deployment {
+            DeployTarget::Zoned(zoned) => {
+                for route in &zoned.routes {
+                    routes.insert(route.pattern.clone(), String::new());
+                }
+            }
+            DeployTarget::Zoneless(_) => workers_dev = true,
+            DeployTarget::Schedule(schedule) => {
+                for cron in &schedule.crons {
+                    crons.insert(cron.clone(), String::new());
+                }
+            }
+        }
+    }
+
+    if let Some(deployed_routes) = &deployed.routes {
+        let deployed_routes = deployed_routes
+            .iter()
+            .map(|pattern| (pattern.clone(), String::new()))
+            .collect();
+        compare_maps("route", &routes, &deployed_routes, &mut differences);
+    }
+    let deployed_crons = deployed
+        .crons
+        .iter()
+        .map(|cron| (cron.clone(), String::new()))
+        .collect();
+    compare_maps("cron", &crons, &deployed_crons, &mut differences);
+
+    compare_values(
+        "workers_dev",
+        Some(workers_dev.to_string()),
+        deployed.workers_dev.map(|enabled| enabled.to_string()),
+        &mut differences,
+    );
+    if target.usage_model.is_some() {
+        compare_values(
+            "usage_model",
+            target.usage_model.map(|model| model.as_ref().to_string()),
+            deployed.usage_model.clone(),
+            &mut differences,
+        );
+    }
+    if target.compatibility_date.is_some() && deployed.compatibility_date.is_some() {
+        compare_values(
+            "compatibility_date",
+            target.compatibility_date.clone(),
+            deployed.compatibility_date.clone(),
+            &mut differences,
+        );
+    }
+
+    let vars = target
+        .vars
+        .iter()
+        .flatten()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    compare_maps("var", &vars, &deployed.vars, &mut differences);
+
+    // the site's namespace is added at publish time, it isn't configured
+    let kv_namespaces = |namespaces: &[crate::settings::toml::KvNamespace]| {
+        namespaces
+            .iter()
+            .filter(|ns| ns.binding != "__STATIC_CONTENT")
+            .map(|ns| (ns.binding.clone(), ns.id.clone()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    compare_maps(
+        "kv_namespace",
+        &kv_namespaces(&target.kv_namespaces),
+        &kv_namespaces(&deployed.kv_namespaces),
+        &mut differences,
+    );
+
+    let r2_buckets = |buckets: &[crate::settings::toml::R2Bucket]| {
+        buckets
+            .iter()
+            .map(|bucket| (bucket.binding.clone(), bucket.bucket_name.clone()))
+            .collect::<BTreeMap<_, _>>()
+    };
+    compare_maps(
+        "r2_bucket",
+        &r2_buckets(&target.r2_buckets),
+        &r2_buckets(&deployed.r2_buckets),
+        &mut differences,
+    );
+
+    let durable_object = |class_name: &str, script_name: &Option<String>| match script_name {
+        Some(script_name) => format!("{} in {}", class_name, script_name),
+        None => class_name.to_string(),
+    };
+    let durable_objects = target
+        .durable_objects
+        .iter()
+        .flat_map(|durable_objects| durable_objects.classes.iter().flatten())
+        .map(|class| {
+            (
+                class.binding.clone(),
+                durable_object(&class.class_name, &class.script_name),
+            )
+        })
+        .collect();
+    let deployed_durable_objects = deployed
+        .durable_objects
+        .iter()
+        .map(|class| {
+            (
+                class.binding.clone(),
+                // a binding to the script's own class comes back with the script's name
+                durable_object(
+                    &class.class_name,
+                    &class
+                        .script_name
+                        .clone()
+                        .filter(|script| script != &target.name),
+                ),
+            )
+        })
+        .collect();
+    compare_maps(
+        "durable_object",
+        &durable_objects,
+        &deployed_durable_objects,
+        &mut differences,
+    );
+
+    if let Some(secrets) = secrets {
+        let expected = secrets
+            .iter()
+            .map(|name| (name.clone(), String::new()))
+            .collect();
+        let deployed_secrets = deployed
+            .secrets
+            .iter()
+            .map(|name| (name.clone(), String::new()))
+            .collect();
+        compare_maps("secret", &expected, &deployed_secrets, &mut differences);
+    }
+
+    differences
+}
+
+fn compare_values(
+    setting: &'static str,
+    configured: Option<String>,
+    deployed: Option<String>,
+    differences: &mut Vec<Difference>,
+) {
+    if configured != deployed {
+        differences.push(Difference {
+            setting,
+            name: setting.to_string(),
+            configured,
+            deployed,
+        });
+    }
+}
+
+// For settings that are sets, such as routes, the values are empty and only presence counts
+fn compare_maps(
+    setting: &'static str,
+    configured: &BTreeMap<String, String>,
+    deployed: &BTreeMap<String, String>,
+    differences: &mut Vec<Difference>,
+) {
+    let names: BTreeSet<&String> = configured.keys().chain(deployed.keys()).collect();
+    for name in names {
+        let (configured, deployed) = (configured.get(name), deployed.get(name));
+        if configured != deployed {
+            let shown = |value: Option<&String>| {
+                value.map(|value| {
+                    if value.is_empty() {
+                        "present".to_string()
+                    } else {
+                        value.clone()
+                    }
+                })
+            };
+            differences.push(Difference {
+                setting,
+                name: name.clone(),
+                configured: shown(configured),
+                deployed: shown(deployed),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deploy::ScheduleTarget;
+    use crate::settings::toml::Manifest;
+    use std::str::FromStr;
+
+    #[test]
+    fn differences_are_found() {
+        let manifest = Manifest::from_str(
+            r#"
+            name = "worker"
+            type = "javascript"
+            account_id = "account"
+
+            [vars]
+            MODE = "production"
+            "#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+        let deployments = vec![DeployTarget::Schedule(ScheduleTarget {
+            account_id: "account".to_string(),
+            script_name: "worker".to_string(),
+            crons: vec!["0 * * * *".to_string()],
+        })];
+
+        let mut deployed = DeployedScript {
+            workers_dev: Some(true),
+            crons: vec!["0 * * * *".to_string()],
+            secrets: vec!["TOKEN".to_string(), "EXTRA".to_string()],
+            ..DeployedScript::default()
+        };
+        deployed
+            .vars
+            .insert("MODE".to_string(), "staging".to_string());
+
+        let differences = compare(
+            &target,
+            &deployments,
+            Some(&["TOKEN".to_string()]),
+            &deployed,
+        );
+        let found: Vec<_> = differences
+            .iter()
+            .map(|d| (d.setting, d.name.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("workers_dev", "workers_dev"),
+                ("var", "MODE"),
+                ("secret", "EXTRA")
+            ]
+        );
+        assert_eq!(differences[2].configured, None);
+        assert_eq!(differences[2].deployed.as_deref(), Some("present"));
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::settings::toml::explain::{Resolved, Source};
+use crate::settings::toml::Manifest;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::table::{Table, TableOptions};
+
+/// Lists the environments in the configuration file, the worker each publishes and the keys
+/// each sets itself.
+pub fn list(manifest: &Manifest, options: &TableOptions) -> Result<()> {
+    let mut names: Vec<&String> = manifest
+        .env
+        .iter()
+        .flat_map(|environments| environments.keys())
+        .collect();
+    if names.is_empty() {
+        StdErr::info("There are no environments in the configuration file");
+        return Ok(());
+    }
+    names.sort();
+
+    let mut rows = Vec::new();
+    for name in names {
+        let resolved = manifest.explain(name)?;
+        let value = |key: &str| {
+            resolved
+                .iter()
+                .find(|r| r.key == key && r.source != Source::Ignored)
+                .map(|r| r.value.clone())
+                .unwrap_or(Value::Null)
+        };
+        let routes: Vec<Value> = value("route")
+            .as_str()
+            .map(Value::from)
+            .into_iter()
+            .chain(value("routes").as_array().cloned().unwrap_or_default())
+            .collect();
+        rows.push(json!({
+            "name": name,
+            "worker": value("name"),
+            "workers_dev": value("workers_dev").as_bool().unwrap_or_default(),
+            "routes": routes,
+            "sets": own_keys(&resolved),
+        }));
+    }
+    Table::new(&["name", "worker", "workers_dev", "routes", "sets"], rows).print(options)
+}
+
+/// Prints the configuration environment `name` is published with, and whether each value is
+/// its own or inherited from the top level.
+pub fn show(manifest: &Manifest, name: &str, options: &TableOptions) -> Result<()> {
+    let rows = manifest
+        .explain(name)?
+        .into_iter()
+        .map(|resolved| {
+            json!({
+                "key": resolved.key,
+                "value": resolved.value,
+                "source": resolved.source,
+            })
+        })
+        .collect();
+    Table::new(&["key", "value", "source"], rows).print(options)
+}
+
+// The keys the environment sets itself, rather than inheriting
+fn own_keys(resolved: &[Resolved]) -> Vec<&'static str> {
+    resolved
+        .iter()
+        .filter(|r| matches!(r.source, Source::Environment | Source::Overridden))
+        .map(|r| r.key)
+        .collect()
+}
@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::deploy::deployed::{self, DeployedScript};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{DurableObjectsClass, TargetType};
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+/// What `wrangler export` writes the worker's settings as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    Toml,
+    Terraform,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "toml" => Ok(ExportFormat::Toml),
+            "terraform" => Ok(ExportFormat::Terraform),
+            _ => anyhow::bail!("{} is not an export format, use toml or terraform", s),
+        }
+    }
+}
+
+/// wrangler.toml as `wrangler export` writes it. Values have to come before tables for the
+/// TOML serializer, hence the field order.
+#[derive(Serialize)]
+struct ExportedConfig {
+    name: String,
+    #[serde(rename = "type")]
+    target_type: TargetType,
+    account_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workers_dev: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    compatibility_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    zone_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routes: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    kv_namespaces: Vec<ExportedKvNamespace>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    vars: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    durable_objects: Option<ExportedDurableObjects>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    triggers: Option<ExportedTriggers>,
+}
+
+#[derive(Serialize)]
+struct ExportedKvNamespace {
+    binding: String,
+    id: String,
+}
+
+#[derive(Serialize)]
+struct ExportedDurableObjects {
+    bindings: Vec<DurableObjectsClass>,
+}
+
+#[derive(Serialize)]
+struct ExportedTriggers {
+    crons: Vec<String>,
+}
+
+/// Writes a wrangler.toml, or Terraform resources, for the deployed script `name` to `out`, or
+/// prints it if there's no `out`. Secrets and bindings to local files can't be exported to
+/// wrangler.toml, they're listed instead.
+pub fn export(
+    user: &GlobalUser,
+    account_id: &str,
+    name: &str,
+    zone_id: Option<&str>,
+    out: Option<&Path>,
+    format: ExportFormat,
+) -> Result<()> {
+    StdErr::working(&format!("Reading the settings of {}", name));
+    let deployed = deployed::fetch(user, account_id, name, zone_id)?;
+    let config = match format {
+        ExportFormat::Toml => to_toml(&deployed, zone_id)?,
+        ExportFormat::Terraform => {
+            let titles = namespace_titles(user, account_id)?;
+            return write(&to_terraform(&deployed, zone_id, &titles), out);
+        }
+    };
+
+    if !deployed.secrets.is_empty() {
+        StdErr::info(&format!(
+            "Secrets aren't exported, set them with `wrangler secret put`: {}",
+            deployed.secrets.join(", ")
+        ));
+    }
+    if !deployed.other_bindings.is_empty() {
+        StdErr::warn(&format!(
+            "These bindings need local files and aren't exported: {}",
+            deployed.other_bindings.join(", ")
+        ));
+    }
+    if zone_id.is_none() {
+        StdErr::info("Routes aren't exported without a zone, pass --zone-id to include them");
+    }
+
+    write(&config, out)
+}
+
+fn write(config: &str, out: Option<&Path>) -> Result<()> {
+    match out {
+        Some(out) => {
+            anyhow::ensure!(
+                !out.exists(),
+                "{} already exists, choose another --output",
+                out.display()
+            );
+            fs::write(out, config)?;
+            StdErr::success(&format!("Wrote {}", out.display()));
+        }
+        None => StdOut::message(config),
+    }
+    Ok(())
+}
+
+fn to_toml(deployed: &DeployedScript, zone_id: Option<&str>) -> Result<String> {
+    let config = ExportedConfig {
+        name: deployed.name.clone(),
+        // the API doesn't say how a script was built, and a bundle is plain JavaScript
+        target_type: TargetType::JavaScript,
+        account_id: deployed.account_id.clone(),
+        workers_dev: deployed.workers_dev,
+        compatibility_date: deployed.compatibility_date.clone(),
+        usage_model: deployed.usage_model.clone(),
+        zone_id: zone_id.map(|zone_id| zone_id.to_string()),
+        routes: deployed.routes.clone().filter(|routes| !routes.is_empty()),
+        kv_namespaces: deployed
+            .kv_namespaces
+            .iter()
+            .map(|ns| ExportedKvNamespace {
+                binding: ns.binding.clone(),
+                id: ns.id.clone(),
+            })
+            .collect(),
+        vars: deployed.vars.clone(),
+        durable_objects: Some(ExportedDurableObjects {
+            bindings: deployed.durable_objects.clone(),
+        })
+        .filter(|durable_objects| !durable_objects.bindings.is_empty()),
+        triggers: Some(ExportedTriggers {
+            crons: deployed.crons.clone(),
+        })
+        .filter(|triggers| !triggers.crons.is_empty()),
+    };
+    Ok(toml::to_string(&config)?)
+}
+
+#[derive(Deserialize)]
+struct Namespace {
+    id: String,
+    title: String,
+}
+
+// The titles of the account's KV namespaces by id, which Terraform needs to manage them
+fn namespace_titles(user: &GlobalUser, account_id: &str) -> Result<HashMap<String, String>> {
+    let client = http::legacy_auth_client(user);
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces?per_page=1000",
+        account_id
+    );
+    let namespaces: Vec<Namespace> = deployed::get(&client, &addr)?.unwrap_or_default();
+    Ok(namespaces.into_iter().map(|ns| (ns.id, ns.title)).collect())
+}
+
+// Terraform resource names can only have letters, digits, underscores and dashes
+fn resource_name(name: &str) -> String {
+    let name: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        _ => format!("r_{}", name),
+    }
+}
+
+// The resources of the Cloudflare Terraform provider that describe the deployed script. The
+// existing resources have to be imported before Terraform can manage them.
+fn to_terraform(
+    deployed: &DeployedScript,
+    zone_id: Option<&str>,
+    titles: &HashMap<String, String>,
+) -> String {
+    let script = resource_name(&deployed.name);
+    let mut hcl = String::new();
+
+    for ns in &deployed.kv_namespaces {
+        let title = titles.get(&ns.id).unwrap_or(&ns.binding);
+        let _ = write!(
+            hcl,
+            "# id: {}\nresource \"cloudflare_workers_kv_namespace\" \"{}\" {{\n  title = {:?}\n}}\n\n",
+            ns.id,
+            resource_name(&ns.binding),
+            title
+        );
+    }
+    for secret in &deployed.secrets {
+        let _ = write!(
+            hcl,
+            "variable \"{}\" {{\n  type      = string\n  sensitive = true\n}}\n\n",
+            resource_name(secret)
+        );
+    }
+
+    let _ = writeln!(
+        hcl,
+        "resource \"cloudflare_worker_script\" \"{}\" {{",
+        script
+    );
+    let _ = writeln!(hcl, "  name    = {:?}", deployed.name);
+    let _ = writeln!(
+        hcl,
+        "  # the built script, e.g. what `wrangler build` writes"
+    );
+    let _ = writeln!(hcl, "  content = file(\"worker.js\")");
+    for ns in &deployed.kv_namespaces {
+        let _ = write!(
+            hcl,
+            "\n  kv_namespace_binding {{\n    name         = {:?}\n    namespace_id = cloudflare_workers_kv_namespace.{}.id\n  }}\n",
+            ns.binding,
+            resource_name(&ns.binding)
+        );
+    }
+    for (name, text) in &deployed.vars {
+        let _ = write!(
+            hcl,
+            "\n  plain_text_binding {{\n    name = {:?}\n    text = {:?}\n  }}\n",
+            name, text
+        );
+    }
+    for secret in &deployed.secrets {
+        let _ = write!(
+            hcl,
+            "\n  secret_text_binding {{\n    name = {:?}\n    text = var.{}\n  }}\n",
+            secret,
+            resource_name(secret)
+        );
+    }
+    let unsupported: Vec<String> = deployed
+        .durable_objects
+        .iter()
+        .map(|class| format!("{} (durable_object_namespace)", class.binding))
+        .chain(deployed.other_bindings.iter().cloned())
+        .collect();
+    if !unsupported.is_empty() {
+        let _ = write!(
+            hcl,
+            "\n  # not exported, add these bindings by hand: {}\n",
+            unsupported.join(", ")
+        );
+    }
+    hcl.push_str("}\n");
+
+    if let Some(zone_id) = zone_id {
+        for pattern in deployed.routes.iter().flatten() {
+            let _ = write!(
+                hcl,
+                "\nresource \"cloudflare_worker_route\" \"{}\" {{\n  zone_id     = {:?}\n  pattern     = {:?}\n  script_name = cloudflare_worker_script.{}.name\n}}\n",
+                resource_name(pattern),
+                zone_id,
+                pattern,
+                script
+            );
+        }
+    }
+    if !deployed.crons.is_empty() {
+        let _ = write!(
+            hcl,
+            "\nresource \"cloudflare_worker_cron_trigger\" \"{}\" {{\n  script_name = cloudflare_worker_script.{}.name\n  schedules   = {:?}\n}}\n",
+            script, script, deployed.crons
+        );
+    }
+
+    hcl
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::{KvNamespace, Manifest};
+    use std::str::FromStr;
+
+    #[test]
+    fn exported_config_can_be_read_back() {
+        let mut deployed = DeployedScript {
+            name: "my-worker".to_string(),
+            account_id: "abc".to_string(),
+            workers_dev: Some(true),
+            usage_model: Some("bundled".to_string()),
+            routes: Some(vec!["example.com/*".to_string()]),
+            crons: vec!["0 * * * *".to_string()],
+            ..DeployedScript::default()
+        };
+        deployed.kv_namespaces.push(KvNamespace {
+            binding: "CACHE".to_string(),
+            id: "123".to_string(),
+        });
+        deployed
+            .vars
+            .insert("MODE".to_string(), "production".to_string());
+
+        let config = to_toml(&deployed, Some("zone")).unwrap();
+        let manifest = Manifest::from_str(&config).unwrap();
+        assert_eq!(manifest.name, "my-worker");
+        assert_eq!(manifest.zone_id.as_deref(), Some("zone"));
+        assert_eq!(manifest.routes, Some(vec!["example.com/*".to_string()]));
+        assert_eq!(manifest.kv_namespaces.unwrap()[0].binding, "CACHE");
+        assert_eq!(manifest.vars.unwrap()["MODE"], "production");
+        assert_eq!(manifest.triggers.unwrap().crons, vec!["0 * * * *"]);
+    }
+
+    #[test]
+    fn terraform_resources_reference_each_other() {
+        let mut deployed = DeployedScript {
+            name: "my-worker".to_string(),
+            account_id: "abc".to_string(),
+            routes: Some(vec!["example.com/*".to_string()]),
+            secrets: vec!["API_TOKEN".to_string()],
+            ..DeployedScript::default()
+        };
+        deployed.kv_namespaces.push(KvNamespace {
+            binding: "CACHE".to_string(),
+            id: "123".to_string(),
+        });
+        let titles = vec![("123".to_string(), "my-worker-CACHE".to_string())]
+            .into_iter()
+            .collect();
+
+        let hcl = to_terraform(&deployed, Some("zone"), &titles);
+        assert!(hcl.contains("resource \"cloudflare_workers_kv_namespace\" \"cache\" {\n  title = \"my-worker-CACHE\"\n}"));
+        assert!(hcl.contains("namespace_id = cloudflare_workers_kv_namespace.cache.id"));
+        assert!(hcl.contains("variable \"api_token\""));
+        assert!(hcl.contains("text = var.api_token"));
+        assert!(hcl.contains("resource \"cloudflare_worker_route\" \"example_com__\""));
+        assert!(hcl.contains("script_name = cloudflare_worker_script.my_worker.name"));
+        assert_eq!(resource_name("1st"), "r_1st");
+    }
+}
@@ -1,3 +1,7 @@
+pub mod placeholders;
+pub mod template;
+
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::path::PathBuf;
 use std::process::Command;
@@ -8,12 +12,14 @@ use anyhow::Result;
 use crate::commands::validate_worker_name;
 use crate::settings::toml::{Manifest, Site, TargetType};
 use crate::{commands, install};
+use template::Template;
 
 pub fn generate(
     name: &str,
     template: &str,
     target_type: Option<TargetType>,
     site: bool,
+    defines: &HashMap<String, String>,
 ) -> Result<()> {
     validate_worker_name(name)?;
 
@@ -33,7 +39,7 @@ pub fn generate(
     };
 
     log::info!("Generating a new worker project with name '{}'", new_name);
-    run_generate(&new_name, template)?;
+    run_generate(&new_name, template, defines)?;
 
     let config_path = PathBuf::from("./").join(&name);
     // TODO: this is tightly coupled to our site template. Need to remove once
@@ -48,10 +54,28 @@ pub fn generate(
     Ok(())
 }
 
-pub fn run_generate(name: &str, template: &str) -> Result<()> {
+/// Generates `name` from a registered template, a template directory or a git URL, and fills
+/// in its placeholders.
+pub fn run_generate(name: &str, template: &str, defines: &HashMap<String, String>) -> Result<()> {
+    let destination = PathBuf::from(name);
+    match Template::resolve(template) {
+        Template::Local(dir) => template::copy(&dir, &destination)?,
+        Template::Registered(url) => match template::fetch(&url)? {
+            Some(dir) => {
+                template::copy(&dir, &destination)?;
+                template::init_repository(&destination);
+            }
+            None => cargo_generate(name, &url)?,
+        },
+        Template::Git(url) => cargo_generate(name, &url)?,
+    }
+    placeholders::fill(&destination, name, defines)
+}
+
+fn cargo_generate(name: &str, url: &str) -> Result<()> {
     let binary_path = install::install_cargo_generate()?;
 
-    let args = ["generate", "--git", template, "--name", name, "--force"];
+    let args = ["generate", "--git", url, "--name", name, "--force"];
 
     let command = command(binary_path, &args);
     let command_name = format!("{:?}", command);
@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{anyhow, Result};
+use ignore::overrides::OverrideBuilder;
+use ignore::WalkBuilder;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use crate::terminal::interactive;
+
+/// Describes the placeholders of a template, beyond the ones every project gets, and the files
+/// they're filled in. It's removed from the generated project.
+///
+/// ```toml
+/// files = ["wrangler.toml", "src/**/*.js"]
+///
+/// [[placeholders]]
+/// name = "account_id"
+/// prompt = "Your Cloudflare account id"
+///
+/// [[placeholders]]
+/// name = "kv"
+/// prompt = "Bind a KV namespace?"
+/// type = "bool"
+/// default = false
+/// ```
+pub const TEMPLATE_CONFIG: &str = "template.toml";
+
+// The files placeholders are filled in when the template doesn't list them, the ones that carry
+// the name of the project
+const DEFAULT_FILES: &[&str] = &["wrangler.toml", "package.json", "Cargo.toml"];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplateConfig {
+    /// Globs of the files to fill placeholders in, relative to the template
+    files: Option<Vec<String>>,
+    #[serde(default)]
+    placeholders: Vec<Placeholder>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Placeholder {
+    name: String,
+    prompt: String,
+    #[serde(rename = "type", default)]
+    kind: PlaceholderType,
+    default: Option<toml::Value>,
+    /// The answers to pick from, for a string placeholder.
+    choices: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum PlaceholderType {
+    String,
+    Bool,
+}
+
+impl Default for PlaceholderType {
+    fn default() -> Self {
+        PlaceholderType::String
+    }
+}
+
+impl Placeholder {
+    fn default(&self) -> Option<String> {
+        self.default.as_ref().map(|value| match value {
+            toml::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        })
+    }
+
+    // --define wins, then the answer to the prompt, then the default when there's no one to ask
+    fn value(&self, defines: &HashMap<String, String>) -> Result<String> {
+        let value = match defines.get(&self.name) {
+            Some(value) => value.clone(),
+            None if interactive::is_interactive() => self.ask()?,
+            None => self.default().ok_or_else(|| {
+                anyhow!(
+                    "The template needs a value for {} ({}), pass --define {}=<value>",
+                    self.name,
+                    self.prompt,
+                    self.name
+                )
+            })?,
+        };
+        self.check(value)
+    }
+
+    fn ask(&self) -> Result<String> {
+        let default = self.default();
+        let choices: Vec<&str> = match (&self.kind, &self.choices) {
+            (PlaceholderType::Bool, _) => vec!["true", "false"],
+            (PlaceholderType::String, Some(choices)) => {
+                choices.iter().map(|c| c.as_str()).collect()
+            }
+            (PlaceholderType::String, None) => {
+                let prompt = match &default {
+                    Some(default) => format!("{} [{}]:", self.prompt, default),
+                    None => format!("{}:", self.prompt),
+                };
+                let answer = interactive::get_user_input(&prompt)?;
+                return Ok(match default {
+                    Some(default) if answer.is_empty() => default,
+                    _ => answer,
+                });
+            }
+        };
+        let selected = default
+            .and_then(|default| choices.iter().position(|choice| *choice == default))
+            .unwrap_or(0);
+        let choice = interactive::select(&self.prompt, &choices, selected)?;
+        Ok(choices[choice].to_string())
+    }
+
+    fn check(&self, value: String) -> Result<String> {
+        let allowed = match (&self.kind, &self.choices) {
+            (PlaceholderType::Bool, _) => vec!["true".to_string(), "false".to_string()],
+            (PlaceholderType::String, Some(choices)) => choices.clone(),
+            (PlaceholderType::String, None) => return Ok(value),
+        };
+        if allowed.contains(&value) {
+            Ok(value)
+        } else {
+            anyhow::bail!(
+                "{} isn't a value for {}, use one of {}",
+                value,
+                self.name,
+                allowed.join(", ")
+            )
+        }
+    }
+}
+
+/// Fills in the placeholders of a project generated into `dir`: `{{project-name}}`,
+/// `{{crate_name}}`, `{{authors}}`, the ones `template.toml` defines and whatever is given
+/// with `--define`. Only the `files` of `template.toml` are filled in, or the manifests in
+/// `DEFAULT_FILES` when it doesn't list any, so code that uses `{{ }}` itself is left alone.
+/// Placeholders nothing has a value for are left as they are.
+pub fn fill(dir: &Path, project_name: &str, defines: &HashMap<String, String>) -> Result<()> {
+    let config_path = dir.join(TEMPLATE_CONFIG);
+    let config: TemplateConfig = match fs::read_to_string(&config_path) {
+        Ok(contents) => toml::from_str(&contents)
+            .map_err(|e| anyhow!("The template's {} is invalid: {}", TEMPLATE_CONFIG, e))?,
+        Err(_) => TemplateConfig::default(),
+    };
+
+    let mut values = builtin_values(project_name);
+    for placeholder in &config.placeholders {
+        values.insert(placeholder.name.clone(), placeholder.value(defines)?);
+    }
+    values.extend(defines.clone());
+
+    let mut files = OverrideBuilder::new(dir);
+    match &config.files {
+        Some(globs) => {
+            for glob in globs {
+                files.add(glob)?;
+            }
+        }
+        None => {
+            for file in DEFAULT_FILES {
+                files.add(&format!("/{}", file))?;
+            }
+        }
+    }
+    let walker = WalkBuilder::new(dir)
+        .hidden(false)
+        .overrides(files.build()?)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+    for entry in walker {
+        let path = entry?.into_path();
+        if !path.is_file() {
+            continue;
+        }
+        // binary files are left alone
+        if let Ok(contents) = fs::read_to_string(&path) {
+            let filled = substitute(&contents, &values);
+            if filled != contents {
+                fs::write(&path, filled)?;
+            }
+        }
+    }
+
+    if config_path.exists() {
+        fs::remove_file(&config_path)?;
+    }
+    Ok(())
+}
+
+fn builtin_values(project_name: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    values.insert("project-name".to_string(), project_name.to_string());
+    values.insert("crate_name".to_string(), project_name.replace('-', "_"));
+    values.insert("authors".to_string(), authors());
+    values
+}
+
+// The same author cargo would put in a new crate
+fn authors() -> String {
+    let git_config = |key: &str| {
+        Command::new("git")
+            .args(&["config", "--get", key])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    match (git_config("user.name"), git_config("user.email")) {
+        (Some(name), Some(email)) => format!("{} <{}>", name, email),
+        (Some(name), None) => name,
+        _ => std::env::var("USER").unwrap_or_default(),
+    }
+}
+
+fn substitute(contents: &str, values: &HashMap<String, String>) -> String {
+    let placeholder = Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_-]*)\s*\}\}").unwrap();
+    placeholder
+        .replace_all(contents, |captures: &Captures| {
+            match values.get(&captures[1]) {
+                Some(value) => value.clone(),
+                None => captures[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_placeholders_are_filled_in() {
+        let mut values = HashMap::new();
+        values.insert("project-name".to_string(), "my-worker".to_string());
+        values.insert("account_id".to_string(), "abc123".to_string());
+
+        assert_eq!(
+            substitute(
+                "name = \"{{project-name}}\"\naccount_id = \"{{ account_id }}\"\nzone_id = \"{{zone_id}}\"\n",
+                &values
+            ),
+            "name = \"my-worker\"\naccount_id = \"abc123\"\nzone_id = \"{{zone_id}}\"\n"
+        );
+    }
+
+    #[test]
+    fn only_the_files_the_template_lists_are_filled_in() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src")).unwrap();
+        fs::write(
+            dir.path().join("wrangler.toml"),
+            "name = \"{{project-name}}\"",
+        )
+        .unwrap();
+        fs::write(dir.path().join("src/index.js"), "// {{project-name}}").unwrap();
+        fs::write(dir.path().join("src/view.hbs"), "{{project-name}}").unwrap();
+
+        fill(dir.path(), "my-worker", &HashMap::new()).unwrap();
+        let read = |path: &str| fs::read_to_string(dir.path().join(path)).unwrap();
+        assert_eq!(read("wrangler.toml"), "name = \"my-worker\"");
+        assert_eq!(read("src/index.js"), "// {{project-name}}");
+
+        fs::write(dir.path().join(TEMPLATE_CONFIG), "files = [\"src/*.js\"]").unwrap();
+        fill(dir.path(), "my-worker", &HashMap::new()).unwrap();
+        assert_eq!(read("src/index.js"), "// my-worker");
+        assert_eq!(read("src/view.hbs"), "{{project-name}}");
+        assert!(!dir.path().join(TEMPLATE_CONFIG).exists());
+    }
+
+    #[test]
+    fn placeholders_come_from_defines_then_defaults() {
+        let config: TemplateConfig = toml::from_str(
+            r#"
+            [[placeholders]]
+            name = "account_id"
+            prompt = "Your Cloudflare account id"
+
+            [[placeholders]]
+            name = "kv"
+            prompt = "Bind a KV namespace?"
+            type = "bool"
+            default = false
+            "#,
+        )
+        .unwrap();
+        let (account_id, kv) = (&config.placeholders[0], &config.placeholders[1]);
+        interactive::set_non_interactive(true);
+
+        let mut defines = HashMap::new();
+        defines.insert("account_id".to_string(), "abc123".to_string());
+        assert_eq!(account_id.value(&defines).unwrap(), "abc123");
+        assert_eq!(kv.value(&defines).unwrap(), "false");
+
+        defines.insert("kv".to_string(), "maybe".to_string());
+        assert!(kv.value(&defines).is_err());
+
+        // there's no one to ask for the account id, and no default
+        assert!(account_id.value(&HashMap::new()).is_err());
+    }
+}
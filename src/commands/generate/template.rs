@@ -0,0 +1,259 @@
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+use serde_json::json;
+use twox_hash::XxHash64;
+
+use crate::cache;
+use crate::settings::get_wrangler_home_dir;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::table::{Table, TableOptions};
+
+/// A template `wrangler generate` knows by name.
+pub struct RegisteredTemplate {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub url: &'static str,
+}
+
+pub const DEFAULT_TEMPLATE: &str = "default";
+pub const RUST_TEMPLATE: &str = "rust";
+pub const SITES_TEMPLATE: &str = "sites";
+
+pub const REGISTRY: &[RegisteredTemplate] = &[
+    RegisteredTemplate {
+        name: DEFAULT_TEMPLATE,
+        description: "A JavaScript worker that responds to every request",
+        url: "https://github.com/cloudflare/worker-template",
+    },
+    RegisteredTemplate {
+        name: "router",
+        description: "A JavaScript worker that routes requests by method and path",
+        url: "https://github.com/cloudflare/worker-template-router",
+    },
+    RegisteredTemplate {
+        name: "typescript",
+        description: "A TypeScript worker built with webpack",
+        url: "https://github.com/cloudflare/worker-typescript-template",
+    },
+    RegisteredTemplate {
+        name: RUST_TEMPLATE,
+        description: "A Rust worker compiled to WebAssembly",
+        url: "https://github.com/cloudflare/rustwasm-worker-template",
+    },
+    RegisteredTemplate {
+        name: SITES_TEMPLATE,
+        description: "A Workers Sites project serving a static site",
+        url: "https://github.com/cloudflare/worker-sites-template",
+    },
+    RegisteredTemplate {
+        name: "durable-objects",
+        description: "A modules worker with a Durable Object counter",
+        url: "https://github.com/cloudflare/durable-objects-template",
+    },
+];
+
+/// Where a project is generated from.
+#[derive(Debug, PartialEq)]
+pub enum Template {
+    /// The repository of a registered template, copied from `~/.wrangler/templates` so it can
+    /// be generated offline once it's been used.
+    Registered(String),
+    /// Any other git repository, generated by cargo-generate.
+    Git(String),
+    /// A directory on this machine, copied as it is.
+    Local(PathBuf),
+}
+
+impl Template {
+    /// Takes the name of a registered template, a directory or a git URL, in that order.
+    pub fn resolve(template: &str) -> Template {
+        if let Some(registered) = REGISTRY.iter().find(|t| t.name == template) {
+            return Template::Registered(registered.url.to_string());
+        }
+        let path = Path::new(template);
+        if path.is_dir() {
+            return Template::Local(path.to_path_buf());
+        }
+        Template::Git(template.to_string())
+    }
+}
+
+/// Prints the registered templates, and which of them can be generated without network access.
+pub fn list() -> Result<()> {
+    let rows = REGISTRY
+        .iter()
+        .map(|template| {
+            json!({
+                "name": template.name,
+                "description": template.description,
+                "url": template.url,
+                "cached": cached(template.url).is_some(),
+            })
+        })
+        .collect();
+    Table::new(&["name", "description", "url", "cached"], rows).print(&TableOptions::default())
+}
+
+/// Brings the copy of the repository at `url` in `~/.wrangler/templates` up to date and returns
+/// where it is. When the repository can't be reached, the copy from the last time it was used
+/// is returned instead, so templates that were used before also work offline. Returns `None`
+/// when templates aren't kept, without git or with `--no-cache`.
+pub fn fetch(url: &str) -> Result<Option<PathBuf>> {
+    if !cache::enabled() {
+        return Ok(None);
+    }
+    let git = match which::which("git") {
+        Ok(git) => git,
+        Err(_) => {
+            log::info!("git isn't installed, templates aren't kept for offline use");
+            return Ok(None);
+        }
+    };
+
+    let dir = cache_dir(url);
+    let updated = if dir.join(".git").is_dir() {
+        // a reset rather than a pull, in case the template's history was rewritten
+        run(Command::new(&git)
+            .arg("-C")
+            .arg(&dir)
+            .args(&["fetch", "--quiet", "--depth", "1", "origin"]))
+        .and_then(|_| {
+            run(Command::new(&git).arg("-C").arg(&dir).args(&[
+                "reset",
+                "--quiet",
+                "--hard",
+                "FETCH_HEAD",
+            ]))
+        })
+    } else {
+        if let Some(parent) = dir.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let _ = fs::remove_dir_all(&dir);
+        run(Command::new(&git)
+            .args(&["clone", "--quiet", "--depth", "1", url])
+            .arg(&dir))
+    };
+
+    match updated {
+        Ok(()) => Ok(Some(dir)),
+        Err(e) => match cached(url) {
+            Some(dir) => {
+                StdErr::warn(&format!(
+                    "Could not update the template {} ({}), using the copy from the last time it was used",
+                    url, e
+                ));
+                Ok(Some(dir))
+            }
+            None => anyhow::bail!("Could not download the template {}: {}", url, e),
+        },
+    }
+}
+
+/// Copies a template directory to `destination`, leaving out its git history and what it ignores.
+pub fn copy(template: &Path, destination: &Path) -> Result<()> {
+    if destination.exists() {
+        anyhow::bail!("{} already exists", destination.display());
+    }
+    let walker = WalkBuilder::new(template)
+        .hidden(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+    for entry in walker {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(template)?;
+        let target = destination.join(relative);
+        if entry.path().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Starts a git repository in a project generated from a git template, like cargo-generate
+/// does. A project without one is still usable, so failing to is only logged.
+pub fn init_repository(dir: &Path) {
+    if let Err(e) = run(Command::new("git").arg("init").arg("--quiet").arg(dir)) {
+        log::info!(
+            "could not start a git repository in {}: {}",
+            dir.display(),
+            e
+        );
+    }
+}
+
+fn cached(url: &str) -> Option<PathBuf> {
+    let dir = cache_dir(url);
+    if dir.join(".git").is_dir() {
+        Some(dir)
+    } else {
+        None
+    }
+}
+
+fn cache_dir(url: &str) -> PathBuf {
+    let mut hasher = XxHash64::default();
+    hasher.write(url.trim_end_matches('/').as_bytes());
+    get_wrangler_home_dir()
+        .join("templates")
+        .join(format!("{:x}", hasher.finish()))
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let output = command.output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_resolve_by_name_then_directory_then_url() {
+        assert_eq!(
+            Template::resolve("router"),
+            Template::Registered(
+                "https://github.com/cloudflare/worker-template-router".to_string()
+            )
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_str().unwrap();
+        assert_eq!(
+            Template::resolve(path),
+            Template::Local(dir.path().to_path_buf())
+        );
+
+        assert_eq!(
+            Template::resolve("https://github.com/me/my-template"),
+            Template::Git("https://github.com/me/my-template".to_string())
+        );
+    }
+
+    #[test]
+    fn local_templates_are_copied_without_their_history() {
+        let template = tempfile::tempdir().unwrap();
+        fs::create_dir_all(template.path().join("src")).unwrap();
+        fs::create_dir_all(template.path().join(".git")).unwrap();
+        fs::write(template.path().join("src/index.js"), "export default {}").unwrap();
+        fs::write(template.path().join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+
+        let out = tempfile::tempdir().unwrap();
+        let destination = out.path().join("my-worker");
+        copy(template.path(), &destination).unwrap();
+
+        assert!(destination.join("src/index.js").is_file());
+        assert!(!destination.join(".git").exists());
+    }
+}
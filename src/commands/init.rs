@@ -1,10 +1,32 @@
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use crate::commands::validate_worker_name;
+use crate::commands::whoami::fetch_accounts;
+use crate::http;
+use crate::kv::namespace::create;
+use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{Manifest, Site, TargetType};
+use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
+
+const CONFIG_PATH: &str = "./wrangler.toml";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Language {
+    JavaScript,
+    TypeScript,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Modules,
+    ServiceWorker,
+}
+
 pub fn init(name: Option<&str>, target_type: Option<TargetType>, site_flag: bool) -> Result<()> {
     if Path::new("./wrangler.toml").exists() {
         if site_flag {
@@ -57,3 +79,257 @@ fn get_current_dirname() -> Result<String> {
     };
     Ok(dirname)
 }
+
+/// `wrangler init` without any flags asks what kind of project to set up, then writes a
+/// wrangler.toml and the files needed to get it running.
+pub fn wizard() -> Result<()> {
+    if Path::new(CONFIG_PATH).exists() {
+        anyhow::bail!("A wrangler.toml file already exists! Please remove it before running this command again.");
+    }
+
+    let dirname = get_current_dirname()?;
+    let name = interactive::get_user_input(&format!("Name of your worker ({}):", dirname))?;
+    let name = if name.is_empty() { dirname } else { name };
+    validate_worker_name(&name)?;
+
+    let project = interactive::select(
+        "What kind of project is this?",
+        &["JavaScript", "TypeScript", "Rust", "Workers Sites"],
+        0,
+    )?;
+    match project {
+        0 | 1 => {
+            let language = if project == 0 {
+                Language::JavaScript
+            } else {
+                Language::TypeScript
+            };
+            let format = match interactive::select(
+                "Which format should the worker use?",
+                &[
+                    "Modules (export default { fetch })",
+                    "Service worker (addEventListener)",
+                ],
+                0,
+            )? {
+                0 => Format::Modules,
+                _ => Format::ServiceWorker,
+            };
+            Manifest::generate(
+                name.clone(),
+                Some(TargetType::JavaScript),
+                &PathBuf::from("./"),
+                None,
+            )?;
+            scaffold(language, format)?;
+        }
+        2 => {
+            Manifest::generate(
+                name.clone(),
+                Some(TargetType::Rust),
+                &PathBuf::from("./"),
+                None,
+            )?;
+            StdOut::help("Rust workers are built with wasm-pack from a crate in this directory. To start from a template instead, run `wrangler generate --type rust`");
+        }
+        _ => {
+            let site = Site::default();
+            Manifest::generate(
+                name.clone(),
+                Some(TargetType::Webpack),
+                &PathBuf::from("./"),
+                Some(site.clone()),
+            )?;
+            site.scaffold_worker()?;
+        }
+    }
+    StdOut::success("Succesfully created a `wrangler.toml`");
+
+    if !http::is_offline() && interactive::confirm("Create a KV namespace for this worker?")? {
+        if let Err(e) = add_kv_namespace(&name) {
+            StdOut::warn(&format!(
+                "Couldn't create a KV namespace: {}\nYou can create one later with `wrangler kv:namespace create <binding>`",
+                e
+            ));
+        }
+    }
+
+    if !Path::new(".git").exists()
+        && which::which("git").is_ok()
+        && interactive::confirm("Initialize a git repository?")?
+    {
+        init_git()?;
+    }
+
+    Ok(())
+}
+
+fn scaffold(language: Language, format: Format) -> Result<()> {
+    let mut doc = read_config()?;
+    match (language, format) {
+        (Language::JavaScript, Format::Modules) => {
+            doc["build"]["upload"]["format"] = toml_edit::value("modules");
+            doc["build"]["upload"]["dir"] = toml_edit::value("src");
+            doc["build"]["upload"]["main"] = toml_edit::value("./index.mjs");
+            write_new("src/index.mjs", MODULES_WORKER)?;
+        }
+        (Language::JavaScript, Format::ServiceWorker) => {
+            doc["main"] = toml_edit::value("index.js");
+            write_new("index.js", SERVICE_WORKER)?;
+        }
+        (Language::TypeScript, format) => {
+            let (esbuild_format, outfile) = match format {
+                Format::Modules => ("esm", "dist/index.mjs"),
+                Format::ServiceWorker => ("iife", "dist/worker.js"),
+            };
+            doc["build"]["command"] = toml_edit::value("npm install && npm run build");
+            if format == Format::Modules {
+                doc["build"]["upload"]["format"] = toml_edit::value("modules");
+                doc["build"]["upload"]["main"] = toml_edit::value("./index.mjs");
+            } else {
+                doc["build"]["upload"]["format"] = toml_edit::value("service-worker");
+                doc["main"] = toml_edit::value(outfile);
+            }
+            write_new("package.json", &package_json(esbuild_format, outfile))?;
+            write_new("tsconfig.json", TSCONFIG)?;
+            let worker = match format {
+                Format::Modules => MODULES_WORKER_TS,
+                Format::ServiceWorker => SERVICE_WORKER,
+            };
+            write_new("src/index.ts", worker)?;
+        }
+    }
+    write_config(&doc)
+}
+
+fn add_kv_namespace(name: &str) -> Result<()> {
+    let binding = interactive::get_user_input("Binding name for the namespace (KV):")?;
+    let binding = if binding.is_empty() {
+        "KV".to_string()
+    } else {
+        binding
+    };
+
+    let user = GlobalUser::new()?;
+    let accounts = fetch_accounts(&user)?;
+    let account = match accounts.len() {
+        0 => anyhow::bail!("your credentials can't see any accounts"),
+        1 => &accounts[0],
+        _ => {
+            let names = accounts.iter().map(|a| a.name.as_str()).collect::<Vec<_>>();
+            &accounts[interactive::select("Which account should it belong to?", &names, 0)?]
+        }
+    };
+
+    let title = format!("{}-{}", name, binding);
+    StdOut::working(&format!("Creating namespace with title \"{}\"", title));
+    let client = http::cf_v4_client(&user)?;
+    let namespace = create(&client, &account.id, &title)
+        .map_err(|e| anyhow!(http::format_error(e, None)))?
+        .result;
+
+    let mut doc = read_config()?;
+    doc["account_id"] = toml_edit::value(account.id.as_str());
+    let kv_namespaces = format!(
+        "[{{ binding = \"{}\", id = \"{}\" }}]",
+        binding, namespace.id
+    )
+    .parse::<toml_edit::Value>()
+    .map_err(|e| anyhow!("{}", e))?;
+    doc["kv_namespaces"] = toml_edit::Item::Value(kv_namespaces);
+    write_config(&doc)?;
+
+    StdOut::success(&format!(
+        "Created namespace {} and bound it to {}",
+        title, binding
+    ));
+    Ok(())
+}
+
+fn init_git() -> Result<()> {
+    let status = Command::new("git").arg("init").arg("--quiet").status()?;
+    if !status.success() {
+        anyhow::bail!("`git init` failed");
+    }
+    write_new(".gitignore", GITIGNORE)?;
+    StdOut::success("Initialized a git repository");
+    Ok(())
+}
+
+fn read_config() -> Result<toml_edit::Document> {
+    fs::read_to_string(CONFIG_PATH)?
+        .parse::<toml_edit::Document>()
+        .map_err(|e| anyhow!("toml_edit failed to parse wrangler.toml. {}", e))
+}
+
+fn write_config(doc: &toml_edit::Document) -> Result<()> {
+    fs::write(CONFIG_PATH, doc.to_string())?;
+    Ok(())
+}
+
+// Never overwrites what's already there, `wrangler init` is for existing projects too.
+fn write_new(path: &str, contents: &str) -> Result<()> {
+    let path = Path::new(path);
+    if path.exists() {
+        log::info!("{} already exists, leaving it alone", path.display());
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+fn package_json(esbuild_format: &str, outfile: &str) -> String {
+    format!(
+        r#"{{
+  "private": true,
+  "scripts": {{
+    "build": "esbuild src/index.ts --bundle --format={} --outfile={}"
+  }},
+  "devDependencies": {{
+    "@cloudflare/workers-types": "^2.2.2",
+    "esbuild": "^0.12.15",
+    "typescript": "^4.3.5"
+  }}
+}}
+"#,
+        esbuild_format, outfile
+    )
+}
+
+const MODULES_WORKER: &str = r#"export default {
+  async fetch(request, env, ctx) {
+    return new Response("Hello worker!")
+  },
+}
+"#;
+
+const MODULES_WORKER_TS: &str = r#"export default {
+  async fetch(request: Request): Promise<Response> {
+    return new Response("Hello worker!")
+  },
+}
+"#;
+
+const SERVICE_WORKER: &str = r#"addEventListener("fetch", (event) => {
+  event.respondWith(new Response("Hello worker!"))
+})
+"#;
+
+const TSCONFIG: &str = r#"{
+  "compilerOptions": {
+    "target": "es2020",
+    "module": "es2020",
+    "moduleResolution": "node",
+    "lib": ["es2020"],
+    "types": ["@cloudflare/workers-types"],
+    "strict": true,
+    "noEmit": true
+  },
+  "include": ["src"]
+}
+"#;
+
+const GITIGNORE: &str = "node_modules/\ndist/\nworker/generated/\n";
@@ -7,14 +7,13 @@ use std::path::Path;
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
 
 use anyhow::Result;
-use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::kv::bulk::delete;
-use crate::kv::bulk::BATCH_KEY_MAX;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::progress::Progress;
 
 pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Path) -> Result<()> {
     match interactive::confirm(&format!(
@@ -47,19 +46,11 @@ pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Pa
 
     StdOut::working(&format!("deleting {} key value pairs", len));
 
-    let progress_bar = if len > BATCH_KEY_MAX {
-        let pb = ProgressBar::new(len as u64);
-        pb.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-        Some(pb)
-    } else {
-        None
-    };
+    let progress = Progress::new("Deleting", "keys", len as u64, 0);
 
-    delete(target, user, namespace_id, keys, &progress_bar)?;
+    delete(target, user, namespace_id, keys, Some(&progress))?;
 
-    if let Some(pb) = &progress_bar {
-        pb.finish_with_message(&format!("deleted {} key value pairs", len));
-    }
+    progress.finish();
 
     StdOut::success("Success");
     Ok(())
@@ -7,13 +7,13 @@ use std::path::Path;
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
 
 use anyhow::{anyhow, Result};
-use indicatif::{ProgressBar, ProgressStyle};
 
-use crate::kv::bulk::put;
-use crate::kv::bulk::BATCH_KEY_MAX;
+use crate::kv::bulk::{pairs_size, put};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::Progress;
+
 pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Path) -> Result<()> {
     let pairs: Vec<KeyValuePair> = match &metadata(filename) {
         Ok(file_type) if file_type.is_file() => {
@@ -34,19 +34,11 @@ pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, filename: &Pa
     let len = pairs.len();
 
     StdErr::working(&format!("uploading {} key value pairs", len));
-    let progress_bar = if len > BATCH_KEY_MAX {
-        let pb = ProgressBar::new(len as u64);
-        pb.set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-        Some(pb)
-    } else {
-        None
-    };
-
-    put(target, &user, namespace_id, pairs, &progress_bar)?;
-
-    if let Some(pb) = &progress_bar {
-        pb.finish_with_message(&format!("uploaded {} key value pairs", len));
-    }
+    let progress = Progress::new("Uploading", "keys", len as u64, pairs_size(&pairs));
+
+    put(target, &user, namespace_id, pairs, Some(&progress))?;
+
+    progress.finish();
 
     StdErr::success("Success");
     Ok(())
@@ -23,7 +23,7 @@ pub fn get(target: &Target, user: &GlobalUser, id: &str, key: &str) -> Result<()
 
     let client = http::legacy_auth_client(&user);
 
-    let res = client.get(&api_endpoint).send()?;
+    let res = http::trace::send(&client, client.get(&api_endpoint))?;
 
     let response_status = res.status();
     if response_status.is_success() {
@@ -5,41 +5,29 @@ use crate::http;
 use crate::kv::key::KeyList;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::table::{Table, TableOptions};
 
 use anyhow::Result;
 
-// Note: this function only prints keys in json form, given that
-// the number of entries in each json blob is variable (so csv and tsv
-// representation won't make sense)
+// Metadata varies from key to key, so tables only show the name and expiration; the JSON
+// output has everything.
 pub fn list(
     target: &Target,
     user: &GlobalUser,
     namespace_id: &str,
     prefix: Option<&str>,
+    options: &TableOptions,
 ) -> Result<()> {
     let client = http::cf_v4_client(&user)?;
     let key_list = KeyList::new(target, client, namespace_id, prefix)?;
 
-    print!("["); // Open json list bracket
-
-    let mut first_key = true;
-
+    let mut keys = Vec::new();
     for key_result in key_list {
         match key_result {
-            Ok(key) => {
-                if first_key {
-                    first_key = false;
-                } else {
-                    print!(",");
-                }
-
-                print!("{}", serde_json::to_string(&key)?);
-            }
-            Err(e) => print!("{}", kv::format_error(e)),
+            Ok(key) => keys.push(serde_json::to_value(&key)?),
+            Err(e) => anyhow::bail!(kv::format_error(e)),
         }
     }
 
-    print!("]"); // Close json list bracket
-
-    Ok(())
+    Table::new(&["name", "expiration"], keys).print(options)
 }
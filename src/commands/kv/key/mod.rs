@@ -2,8 +2,10 @@ mod delete;
 mod get;
 mod list;
 mod put;
+mod watch;
 
 pub use delete::delete;
 pub use get::get;
 pub use list::list;
 pub use put::{parse_metadata, put, KVMetaData};
+pub use watch::{watch, Watched};
@@ -100,9 +100,9 @@ fn get_response(
             let form = multipart::Form::new()
                 .part("value", value_part)
                 .text("metadata", metadata.to_string());
-            client.put(&url_into_str).multipart(form).send()?
+            http::trace::send(&client, client.put(&url_into_str).multipart(form))?
         }
-        None => client.put(&url_into_str).body(value_body).send()?,
+        None => http::trace::send(&client, client.put(&url_into_str).body(value_body))?,
     };
     Ok(res)
 }
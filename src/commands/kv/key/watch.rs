@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::commands::kv;
+use crate::http;
+use crate::kv::key::KeyList;
+use crate::parallel;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+/// Polling every key under a prefix fetches each value, so it's kept to a handful of keys.
+const MAX_WATCHED_KEYS: usize = 100;
+/// Longer values are cut short when printed.
+const MAX_SHOWN_CHARS: usize = 200;
+
+/// What the namespace holds for a key.
+#[derive(Clone, Debug, PartialEq)]
+struct Entry {
+    value: Vec<u8>,
+    metadata: Option<Value>,
+}
+
+pub enum Watched {
+    Key(String),
+    Prefix(String),
+}
+
+/// Polls a key, or every key under a prefix, every `interval` and prints what changed until
+/// it's interrupted.
+pub fn watch(
+    target: &Target,
+    user: &GlobalUser,
+    namespace_id: &str,
+    watched: &Watched,
+    interval: Duration,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let mut last = poll(target, user, &client, namespace_id, watched)?;
+    match watched {
+        Watched::Key(key) => match last.get(key) {
+            Some(entry) => StdErr::info(&format!("{} is {}", key, show(&entry.value))),
+            None => StdErr::info(&format!("{} doesn't exist yet", key)),
+        },
+        Watched::Prefix(prefix) => {
+            StdErr::info(&format!("{} key(s) start with {:?}", last.len(), prefix))
+        }
+    }
+    StdErr::info(&format!(
+        "Checking for changes every {}s, press Ctrl-C to stop",
+        interval.as_secs()
+    ));
+
+    loop {
+        thread::sleep(interval);
+        let current = match poll(target, user, &client, namespace_id, watched) {
+            Ok(current) => current,
+            Err(e) => {
+                StdErr::warn(&format!("Could not check for changes: {}", e));
+                continue;
+            }
+        };
+        let time = chrono::Local::now().format("%H:%M:%S");
+        for change in changes(&last, &current) {
+            StdOut::info(&format!("[{}] {}", time, change));
+        }
+        last = current;
+    }
+}
+
+fn poll(
+    target: &Target,
+    user: &GlobalUser,
+    client: &Client,
+    namespace_id: &str,
+    watched: &Watched,
+) -> Result<BTreeMap<String, Entry>> {
+    let keys = match watched {
+        Watched::Key(key) => vec![key.clone()],
+        Watched::Prefix(prefix) => {
+            let mut keys = Vec::new();
+            let key_list = KeyList::new(
+                target,
+                http::cf_v4_client(user)?,
+                namespace_id,
+                Some(prefix),
+            )?;
+            for key in key_list {
+                match key {
+                    Ok(key) => keys.push(key.name),
+                    Err(e) => anyhow::bail!(kv::format_error(e)),
+                }
+            }
+            if keys.len() > MAX_WATCHED_KEYS {
+                anyhow::bail!(
+                    "{} keys start with {:?}, use a longer prefix to watch at most {}",
+                    keys.len(),
+                    prefix,
+                    MAX_WATCHED_KEYS
+                );
+            }
+            keys
+        }
+    };
+
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}",
+        target.account_id.load()?,
+        namespace_id
+    );
+    let client = client.clone();
+    let entries = parallel::map(keys, parallel::FILE_READS, move |key: String| {
+        let entry = entry(&client, &addr, &key)?;
+        Ok((key, entry))
+    })?;
+    Ok(entries
+        .into_iter()
+        .filter_map(|(key, entry)| entry.map(|entry| (key, entry)))
+        .collect())
+}
+
+fn entry(client: &Client, addr: &str, key: &str) -> Result<Option<Entry>> {
+    let key = kv::url_encode_key(key);
+    let res = http::trace::send(client, client.get(&format!("{}/values/{}", addr, key)))?;
+    match res.status() {
+        StatusCode::NOT_FOUND => return Ok(None),
+        status if !status.is_success() => anyhow::bail!(crate::format_api_errors(res.text()?)),
+        _ => {}
+    }
+    let value = res.bytes()?.to_vec();
+
+    let res = http::trace::send(client, client.get(&format!("{}/metadata/{}", addr, key)))?;
+    let metadata = if res.status().is_success() {
+        res.json::<Value>()?
+            .get("result")
+            .cloned()
+            .filter(|metadata| !metadata.is_null())
+    } else {
+        None
+    };
+    Ok(Some(Entry { value, metadata }))
+}
+
+fn changes(before: &BTreeMap<String, Entry>, after: &BTreeMap<String, Entry>) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (key, entry) in after {
+        match before.get(key) {
+            None => changes.push(format!("{} was created: {}", key, show(&entry.value))),
+            Some(old) => {
+                if old.value != entry.value {
+                    changes.push(format!(
+                        "{} changed: {} -> {}",
+                        key,
+                        show(&old.value),
+                        show(&entry.value)
+                    ));
+                }
+                if old.metadata != entry.metadata {
+                    changes.push(format!(
+                        "{} metadata changed: {} -> {}",
+                        key,
+                        show_metadata(&old.metadata),
+                        show_metadata(&entry.metadata)
+                    ));
+                }
+            }
+        }
+    }
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        changes.push(format!("{} was deleted", key));
+    }
+    changes
+}
+
+fn show(value: &[u8]) -> String {
+    match std::str::from_utf8(value) {
+        Ok(text) if text.chars().count() > MAX_SHOWN_CHARS => {
+            let shown: String = text.chars().take(MAX_SHOWN_CHARS).collect();
+            format!("{:?}... ({} bytes)", shown, value.len())
+        }
+        Ok(text) => format!("{:?}", text),
+        Err(_) => format!("<{} bytes of binary data>", value.len()),
+    }
+}
+
+fn show_metadata(metadata: &Option<Value>) -> String {
+    match metadata {
+        Some(metadata) => metadata.to_string(),
+        None => "none".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(value: &str, metadata: Option<Value>) -> Entry {
+        Entry {
+            value: value.as_bytes().to_vec(),
+            metadata,
+        }
+    }
+
+    #[test]
+    fn it_reports_created_changed_and_deleted_keys() {
+        let mut before = BTreeMap::new();
+        before.insert("a".to_string(), entry("1", None));
+        before.insert("b".to_string(), entry("2", Some(json!({"v": 1}))));
+        before.insert("c".to_string(), entry("3", None));
+
+        let mut after = BTreeMap::new();
+        after.insert("a".to_string(), entry("1", None));
+        after.insert("b".to_string(), entry("2", Some(json!({"v": 2}))));
+        after.insert("c".to_string(), entry("4", None));
+        after.insert("d".to_string(), entry("5", None));
+
+        assert_eq!(
+            changes(&before, &after),
+            vec![
+                "b metadata changed: {\"v\":1} -> {\"v\":2}",
+                "c changed: \"3\" -> \"4\"",
+                "d was created: \"5\"",
+            ]
+        );
+        assert!(changes(&after, &before).contains(&"d was deleted".to_string()));
+    }
+
+    #[test]
+    fn binary_values_are_summarized() {
+        assert_eq!(show(&[0xff, 0xfe]), "<2 bytes of binary data>");
+    }
+}
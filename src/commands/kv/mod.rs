@@ -80,7 +80,7 @@ pub fn get_namespace_id(target: &Target, binding: &str) -> Result<String> {
 
 const KV_ASCII_SET: &AsciiSet = &CONTROLS.add(b'/');
 
-fn url_encode_key(key: &str) -> String {
+pub(crate) fn url_encode_key(key: &str) -> String {
     utf8_percent_encode(key, KV_ASCII_SET).to_string()
 }
 
@@ -103,11 +103,13 @@ mod tests {
                     binding: "KV".to_string(),
                 },
             ],
+            r2_buckets: Vec::new(),
             durable_objects: None,
             migrations: None,
             name: "test-target".to_string(),
             target_type: TargetType::Webpack,
             webpack_config: None,
+            main: None,
             site: None,
             vars: None,
             text_blobs: None,
@@ -116,6 +118,8 @@ mod tests {
             usage_model: None,
             compatibility_date: None,
             compatibility_flags: Vec::new(),
+            hooks: None,
+            deploy: None,
         };
         assert!(kv::get_namespace_id(&target_with_dup_kv_bindings, "").is_err());
     }
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use anyhow::Result;
 use regex::Regex;
 
@@ -5,26 +7,46 @@ use crate::commands::kv;
 use crate::http;
 use crate::kv::namespace::create;
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::{ConfigKvNamespace, KvNamespace, Manifest};
-use crate::terminal::message::{Message, StdOut};
+use crate::settings::toml::{ConfigEditor, ConfigKvNamespace, KvNamespace, Manifest};
+use crate::terminal::message::{self, Message, Output, StdOut};
+use serde_json::json;
+
+/// Titles namespaces after the worker and binding unless `--title-template` says otherwise.
+pub const DEFAULT_TITLE_TEMPLATE: &str = "{script}-{binding}";
+
 pub fn run(
     manifest: &Manifest,
+    config_path: &Path,
     is_preview: bool,
     env: Option<&str>,
     user: &GlobalUser,
     binding: &str,
+    title_template: Option<&str>,
+    update_config: bool,
 ) -> Result<()> {
     let account_id = manifest.get_account_id(env)?;
     let worker_name = manifest.worker_name(env);
     validate_binding(binding)?;
 
-    let mut title = format!("{}-{}", worker_name, binding);
+    let mut title = title(
+        title_template.unwrap_or(DEFAULT_TITLE_TEMPLATE),
+        &worker_name,
+        &manifest.name,
+        env,
+        binding,
+    )?;
     if is_preview {
         title.push_str("_preview");
     }
     let msg = format!("Creating namespace with title \"{}\"", title);
     StdOut::working(&msg);
 
+    // the namespaces of the environment, since environments don't inherit them
+    let namespaces = match manifest.get_environment(env)? {
+        Some(environment) => environment.kv_namespaces.as_ref(),
+        None => manifest.kv_namespaces.as_ref(),
+    };
+
     let client = http::cf_v4_client(user)?;
     let result = create(&client, &account_id, &title);
 
@@ -32,18 +54,40 @@ pub fn run(
         Ok(success) => {
             let namespace = success.result;
             StdOut::success("Success!");
-            println!(
-                "{}",
-                toml_modification_instructions(
-                    KvNamespace {
-                        binding: binding.to_string(),
-                        id: namespace.id,
-                    },
-                    manifest.kv_namespaces.as_ref(),
-                    env,
-                    is_preview,
-                )
-            );
+            if message::output() == Output::Json {
+                StdOut::as_json(&json!({
+                    "binding": binding,
+                    "id": namespace.id,
+                    "title": namespace.title,
+                }));
+            }
+            let new_namespace = KvNamespace {
+                binding: binding.to_string(),
+                id: namespace.id,
+            };
+            let updated = if update_config {
+                add_to_config(config_path, &new_namespace, env, is_preview)
+            } else {
+                Err(anyhow::anyhow!("--update-config wasn't passed"))
+            };
+            match updated {
+                Ok(()) => StdOut::success(&format!(
+                    "Bound it to {} in {}{}",
+                    binding,
+                    config_path.display(),
+                    env.map(|env| format!(" under [env.{}]", env))
+                        .unwrap_or_default()
+                )),
+                Err(e) => {
+                    log::info!("did not update the configuration file: {}", e);
+                    StdOut::message(&toml_modification_instructions(
+                        new_namespace,
+                        namespaces,
+                        env,
+                        is_preview,
+                    ))
+                }
+            }
         }
         Err(e) => print!("{}", kv::format_error(e)),
     }
@@ -51,6 +95,55 @@ pub fn run(
     Ok(())
 }
 
+// Fills in `{script}` (the worker name of the environment), `{name}` (the top-level name),
+// `{env}` and `{binding}`. Without an environment, separators around `{env}` are dropped.
+fn title(
+    template: &str,
+    script: &str,
+    name: &str,
+    env: Option<&str>,
+    binding: &str,
+) -> Result<String> {
+    let mut title = template
+        .replace("{script}", script)
+        .replace("{name}", name)
+        .replace("{env}", env.unwrap_or(""))
+        .replace("{binding}", binding);
+    if let Some(placeholder) = Regex::new(r"\{[^}]*\}").unwrap().find(&title) {
+        anyhow::bail!(
+            "{} isn't a title placeholder, use {{script}}, {{name}}, {{env}} or {{binding}}",
+            placeholder.as_str()
+        );
+    }
+    while title.contains("--") {
+        title = title.replace("--", "-");
+    }
+    let title = title.trim_matches('-').to_string();
+    if title.is_empty() {
+        anyhow::bail!("The title template {:?} gives an empty title", template);
+    }
+    Ok(title)
+}
+
+// Sets the id, or preview_id, of the binding in the kv_namespaces of the environment, adding
+// the binding if it isn't there. The rest of the file is left as is.
+fn add_to_config(
+    config_path: &Path,
+    new_namespace: &KvNamespace,
+    env: Option<&str>,
+    is_preview: bool,
+) -> Result<()> {
+    let field = if is_preview { "preview_id" } else { "id" };
+    let mut config = ConfigEditor::open(config_path)?;
+    config.upsert_entry(
+        env,
+        "kv_namespaces",
+        ("binding", &new_namespace.binding),
+        &[(field, &new_namespace.id)],
+    )?;
+    config.save()
+}
+
 fn validate_binding(binding: &str) -> Result<()> {
     let re = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9_]*$").unwrap();
     if !re.is_match(binding) {
@@ -127,6 +220,7 @@ fn toml_modification_instructions(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
 
     #[test]
     fn it_messages_about_env() {
@@ -271,6 +365,60 @@ mod tests {
         assert!(!msg.contains("kv_namespaces = ["));
     }
 
+    #[test]
+    fn it_fills_in_title_templates() {
+        let template = "{script}-{env}-{binding}";
+        assert_eq!(
+            title(
+                template,
+                "worker-staging",
+                "worker",
+                Some("staging"),
+                "CACHE"
+            )
+            .unwrap(),
+            "worker-staging-staging-CACHE"
+        );
+        assert_eq!(
+            title("{name}-{env}-{binding}", "worker", "worker", None, "CACHE").unwrap(),
+            "worker-CACHE"
+        );
+        assert_eq!(
+            title(DEFAULT_TITLE_TEMPLATE, "worker", "worker", None, "CACHE").unwrap(),
+            "worker-CACHE"
+        );
+        assert!(title("{worker}-{binding}", "worker", "worker", None, "CACHE").is_err());
+    }
+
+    #[test]
+    fn it_writes_ids_into_the_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("wrangler.toml");
+        let config = "# my worker\nname = \"worker\"\ntype = \"javascript\"\n\n[env.staging]\n# staging only\nworkers_dev = true\n";
+        fs::write(&config_path, config).unwrap();
+
+        let new_namespace = KvNamespace {
+            id: "new_id".to_string(),
+            binding: "CACHE".to_string(),
+        };
+        add_to_config(&config_path, &new_namespace, Some("staging"), false).unwrap();
+
+        let written = fs::read_to_string(&config_path).unwrap();
+        assert!(written.contains("# my worker"));
+        assert!(written.contains("# staging only"));
+        let manifest: Manifest = toml::from_str(&written).unwrap();
+        let staging = manifest.get_environment(Some("staging")).unwrap().unwrap();
+        assert_eq!(
+            staging.kv_namespaces,
+            Some(vec![ConfigKvNamespace {
+                binding: "CACHE".to_string(),
+                id: Some("new_id".to_string()),
+                preview_id: None,
+            }])
+        );
+        assert!(manifest.kv_namespaces.is_none());
+    }
+
     #[test]
     fn it_can_detect_invalid_binding() {
         let invalid_bindings = vec!["hi there", "1234"];
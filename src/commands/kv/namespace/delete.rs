@@ -26,7 +26,7 @@ pub fn run(target: &Target, user: &GlobalUser, id: &str) -> Result<()> {
     let msg = format!("Deleting namespace {}", id);
     StdOut::working(&msg);
 
-    let response = delete(client, target.account_id.load()?, id);
+    let response = delete(&client, target.account_id.load()?, id);
     match response {
         Ok(_) => {
             StdOut::success("Success");
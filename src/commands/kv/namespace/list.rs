@@ -2,17 +2,15 @@ use crate::http;
 use crate::kv::namespace::list;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::table::{Table, TableOptions};
 
 use anyhow::Result;
 
-pub fn run(target: &Target, user: &GlobalUser) -> Result<()> {
+pub fn run(target: &Target, user: &GlobalUser, options: &TableOptions) -> Result<()> {
     let client = http::cf_v4_client(user)?;
     let result = list(&client, target);
     match result {
-        Ok(namespaces) => {
-            println!("{}", serde_json::to_string(&namespaces)?);
-        }
+        Ok(namespaces) => Table::from_serialize(&["id", "title"], &namespaces)?.print(options),
         Err(e) => anyhow::bail!(e),
     }
-    Ok(())
 }
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use indicatif::HumanBytes;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{self, Message, Output, StdOut};
+
+const GRAPHQL_URL: &str = "https://api.cloudflare.com/client/v4/graphql";
+
+// Storage is sampled once a day, so the latest sample of the period is the current size
+const QUERY: &str = r#"
+query ($accountTag: string, $namespaceId: string, $start: Date, $end: Date) {
+  viewer {
+    accounts(filter: { accountTag: $accountTag }) {
+      storage: kvStorageAdaptiveGroups(
+        limit: 1
+        filter: { namespaceId: $namespaceId, date_geq: $start, date_leq: $end }
+        orderBy: [date_DESC]
+      ) {
+        max { keyCount byteCount }
+      }
+      operations: kvOperationsAdaptiveGroups(
+        limit: 100
+        filter: { namespaceId: $namespaceId, date_geq: $start, date_leq: $end }
+      ) {
+        sum { requests }
+        dimensions { actionType }
+      }
+    }
+  }
+}
+"#;
+
+/// What the analytics API knows about a namespace over the last `days` days.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct NamespaceStats {
+    pub namespace_id: String,
+    pub days: u32,
+    /// `None` until the namespace's storage was sampled, up to a day after it was created
+    pub key_count: Option<u64>,
+    pub byte_count: Option<u64>,
+    pub reads: u64,
+    pub writes: u64,
+    pub deletes: u64,
+    pub lists: u64,
+}
+
+pub fn run(target: &Target, user: &GlobalUser, namespace_id: &str, days: u32) -> Result<()> {
+    let end = Utc::today();
+    let start = end - Duration::days(i64::from(days));
+    let body = json!({
+        "query": QUERY,
+        "variables": {
+            "accountTag": target.account_id.load()?,
+            "namespaceId": namespace_id,
+            "start": start.format("%Y-%m-%d").to_string(),
+            "end": end.format("%Y-%m-%d").to_string(),
+        },
+    });
+
+    let client = http::legacy_auth_client(user);
+    let response: Value =
+        http::trace::send(&client, client.post(GRAPHQL_URL).json(&body))?.json()?;
+    let stats = parse(namespace_id, days, &response)?;
+
+    if message::output() == Output::Json {
+        StdOut::as_json(&stats);
+        return Ok(());
+    }
+    let unknown = || "not sampled yet".to_string();
+    StdOut::info(&format!(
+        "Namespace {}, over the last {} days",
+        namespace_id, days
+    ));
+    for (label, value) in &[
+        (
+            "keys",
+            stats.key_count.map_or_else(unknown, |n| n.to_string()),
+        ),
+        (
+            "storage",
+            stats
+                .byte_count
+                .map_or_else(unknown, |n| HumanBytes(n).to_string()),
+        ),
+        ("reads", stats.reads.to_string()),
+        ("writes", stats.writes.to_string()),
+        ("deletes", stats.deletes.to_string()),
+        ("lists", stats.lists.to_string()),
+    ] {
+        StdOut::message(&format!("   {:<8} {}", label, value));
+    }
+    Ok(())
+}
+
+fn parse(namespace_id: &str, days: u32, response: &Value) -> Result<NamespaceStats> {
+    if let Some(errors) = response["errors"].as_array().filter(|e| !e.is_empty()) {
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|error| error["message"].as_str())
+            .collect();
+        anyhow::bail!("The analytics API couldn't answer: {}", messages.join(", "));
+    }
+
+    let account = &response["data"]["viewer"]["accounts"][0];
+    let storage = &account["storage"][0]["max"];
+    let mut stats = NamespaceStats {
+        namespace_id: namespace_id.to_string(),
+        days,
+        key_count: storage["keyCount"].as_u64(),
+        byte_count: storage["byteCount"].as_u64(),
+        ..NamespaceStats::default()
+    };
+    for group in account["operations"].as_array().into_iter().flatten() {
+        let requests = group["sum"]["requests"].as_u64().unwrap_or_default();
+        match group["dimensions"]["actionType"].as_str() {
+            Some("read") => stats.reads += requests,
+            Some("write") => stats.writes += requests,
+            Some("delete") => stats.deletes += requests,
+            Some("list") => stats.lists += requests,
+            _ => {}
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operations_are_added_up_by_type() {
+        let response = json!({
+            "data": { "viewer": { "accounts": [{
+                "storage": [{ "max": { "keyCount": 42, "byteCount": 2048 } }],
+                "operations": [
+                    { "sum": { "requests": 900 }, "dimensions": { "actionType": "read" } },
+                    { "sum": { "requests": 100 }, "dimensions": { "actionType": "read" } },
+                    { "sum": { "requests": 7 }, "dimensions": { "actionType": "write" } },
+                ],
+            }]}},
+            "errors": null,
+        });
+
+        let stats = parse("abc", 30, &response).unwrap();
+        assert_eq!(
+            stats,
+            NamespaceStats {
+                namespace_id: "abc".to_string(),
+                days: 30,
+                key_count: Some(42),
+                byte_count: Some(2048),
+                reads: 1000,
+                writes: 7,
+                ..NamespaceStats::default()
+            }
+        );
+
+        let response = json!({ "data": null, "errors": [{ "message": "not authorized" }] });
+        assert!(parse("abc", 30, &response).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::commands::route;
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::table::{Table, TableOptions};
+
+#[derive(Deserialize)]
+struct Script {
+    id: String,
+    modified_on: Option<String>,
+    usage_model: Option<String>,
+}
+
+/// Lists the workers on the account, only those tagged `tag` when there's one, with what an
+/// audit needs to spot the ones nothing uses anymore.
+pub fn list(
+    user: &GlobalUser,
+    account_id: &str,
+    tag: Option<&str>,
+    options: &TableOptions,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let mut addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+        account_id
+    );
+    if let Some(tag) = tag {
+        addr.push_str(&format!(
+            "?tags={}:yes",
+            utf8_percent_encode(tag, NON_ALPHANUMERIC)
+        ));
+    }
+
+    let mut scripts = deployed::get::<Vec<Script>>(&client, &addr)?.unwrap_or_default();
+    scripts.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut routes = route::patterns_by_script(user, account_id)?;
+
+    let mut rows = Vec::new();
+    for script in &scripts {
+        rows.push(json!({
+            "name": script.id,
+            "size": size(&client, account_id, &script.id)?,
+            "modified_on": script.modified_on,
+            "usage_model": script.usage_model,
+            "routes": routes.remove(&script.id).unwrap_or_default(),
+        }));
+    }
+    Table::new(
+        &["name", "size", "modified_on", "usage_model", "routes"],
+        rows,
+    )
+    .print(options)
+}
+
+// The size in bytes of what was uploaded for `name`. The API doesn't list it, so the script
+// is downloaded.
+fn size(client: &Client, account_id: &str, name: &str) -> Result<Option<u64>> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        account_id, name
+    );
+    let res = http::trace::send(client, client.get(&addr))?;
+    match res.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => Ok(Some(res.bytes()?.len() as u64)),
+        _ => anyhow::bail!(crate::format_api_errors(res.text()?)),
+    }
+}
@@ -0,0 +1,293 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+
+use crate::settings::toml::{Target, TargetType, UploadFormat};
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::styles;
+
+const TODO: &str = "// TODO(wrangler migrate-format):";
+
+/// The result of rewriting a service-worker script into the modules format.
+#[derive(Debug, PartialEq)]
+pub struct Conversion {
+    pub code: String,
+    /// Places the transform could not handle on its own.
+    pub todos: Vec<String>,
+}
+
+/// Rewrites the service-worker script of `target` into a modules worker written to `out`
+/// (by default, next to the original with an `.mjs` extension) and points `config_path`
+/// at the new entrypoint.
+pub fn migrate_format(
+    target: &Target,
+    config_path: &Path,
+    out: Option<PathBuf>,
+    extra_bindings: Vec<String>,
+    dry_run: bool,
+) -> Result<()> {
+    if let Some(build) = &target.build {
+        if let UploadFormat::Modules { .. } = build.upload {
+            anyhow::bail!("This project already uses the modules format.")
+        }
+    }
+    if target.target_type != TargetType::JavaScript {
+        anyhow::bail!(
+            "`wrangler migrate-format` only supports projects of type \"javascript\", this project is of type \"{}\".",
+            target.target_type
+        )
+    }
+
+    let script_path = target.service_worker_entry()?;
+    let source = fs::read_to_string(&script_path)?;
+
+    let mut bindings = binding_names(target);
+    bindings.extend(extra_bindings);
+
+    let conversion = convert(&source, &bindings);
+
+    if dry_run {
+        StdOut::message(&conversion.code);
+    } else {
+        let out = out.unwrap_or_else(|| script_path.with_extension("mjs"));
+        fs::write(&out, &conversion.code)?;
+        update_config(config_path, &out)?;
+        StdErr::success(&format!(
+            "Wrote {} and updated {} to use the modules format.",
+            out.display(),
+            config_path.display()
+        ));
+    }
+
+    for todo in &conversion.todos {
+        StdErr::warn(todo);
+    }
+    if !conversion.todos.is_empty() {
+        StdErr::help(&format!(
+            "Search for {} in the converted script to finish the migration.",
+            styles::highlight("TODO(wrangler migrate-format)")
+        ));
+    }
+
+    Ok(())
+}
+
+fn binding_names(target: &Target) -> Vec<String> {
+    let mut names: Vec<String> = target
+        .kv_namespaces
+        .iter()
+        .map(|kv| kv.binding.clone())
+        .collect();
+
+    if let Some(classes) = target
+        .durable_objects
+        .as_ref()
+        .and_then(|d| d.classes.as_ref())
+    {
+        names.extend(classes.iter().map(|class| class.binding.clone()));
+    }
+    if let Some(vars) = &target.vars {
+        names.extend(vars.keys().cloned());
+    }
+    if let Some(blobs) = &target.text_blobs {
+        names.extend(blobs.keys().cloned());
+    }
+    if let Some(modules) = &target.wasm_modules {
+        names.extend(modules.keys().cloned());
+    }
+
+    names
+}
+
+/// Best-effort conversion of a service-worker script. Event listeners of the common
+/// `event.respondWith(handler(event.request))` shape are turned into handlers on the
+/// default export and global bindings are read off `env`; anything else is left in
+/// place with a TODO marker.
+pub fn convert(source: &str, bindings: &[String]) -> Conversion {
+    let mut todos = Vec::new();
+    let mut handlers = Vec::new();
+
+    let fetch = Regex::new(
+        r#"(?s)addEventListener\(\s*['"]fetch['"]\s*,\s*(?:async\s+)?(?:\(?\s*(\w+)\s*\)?\s*=>|function\s*\w*\s*\(\s*(\w+)\s*\))\s*\{?\s*(\w+)\.respondWith\(\s*(\w+)\(\s*(\w+)\.request\s*\)\s*\)\s*;?\s*\}?\s*\)\s*;?"#,
+    )
+    .unwrap();
+    let scheduled = Regex::new(
+        r#"(?s)addEventListener\(\s*['"]scheduled['"]\s*,\s*(?:async\s+)?(?:\(?\s*(\w+)\s*\)?\s*=>|function\s*\w*\s*\(\s*(\w+)\s*\))\s*\{?\s*(\w+)\.waitUntil\(\s*(\w+)\(\s*(\w+)\s*\)\s*\)\s*;?\s*\}?\s*\)\s*;?"#,
+    )
+    .unwrap();
+
+    let mut code = source.to_string();
+
+    if let Some(caps) = fetch.captures(&code) {
+        let handler = caps[4].to_string();
+        handlers.push(format!(
+            "  async fetch(request, env, ctx) {{\n    return {}(request, env, ctx)\n  }},",
+            handler
+        ));
+        code = fetch.replace(&code, "").into_owned();
+    }
+
+    if let Some(caps) = scheduled.captures(&code) {
+        let handler = caps[4].to_string();
+        handlers.push(format!(
+            "  async scheduled(event, env, ctx) {{\n    ctx.waitUntil({}(event, env, ctx))\n  }},",
+            handler
+        ));
+        code = scheduled.replace(&code, "").into_owned();
+    }
+
+    // Any listener we couldn't match keeps running as-is in the service-worker runtime only.
+    if code.contains("addEventListener(") {
+        code = code.replace(
+            "addEventListener(",
+            &format!(
+                "{} move this listener into the default export\naddEventListener(",
+                TODO
+            ),
+        );
+        todos.push(
+            "Some event listeners could not be converted automatically and were left in place."
+                .to_string(),
+        );
+    }
+
+    let (rewritten, rewritten_bindings) = rewrite_bindings(&code, bindings);
+    code = rewritten;
+    if !rewritten_bindings.is_empty() {
+        todos.push(format!(
+            "The bindings {} are no longer globals; make sure `env` is passed to every function that reads them.",
+            rewritten_bindings.join(", ")
+        ));
+    }
+
+    if handlers.is_empty() {
+        todos.push("No `fetch` or `scheduled` handler could be found.".to_string());
+        handlers.push(format!("  {} add your handlers here", TODO));
+    }
+
+    let code = format!(
+        "{}\n\nexport default {{\n{}\n}}\n",
+        code.trim_end(),
+        handlers.join("\n")
+    );
+
+    Conversion { code, todos }
+}
+
+// Rewrites bare references to each binding as `env.BINDING`, marking every rewritten
+// line since `env` now has to be threaded through by hand.
+fn rewrite_bindings(code: &str, bindings: &[String]) -> (String, Vec<String>) {
+    let mut found = Vec::new();
+    let mut lines = Vec::new();
+
+    for line in code.lines() {
+        let mut line = line.to_string();
+        let mut changed = false;
+        for binding in bindings {
+            let pattern = Regex::new(&format!(r"(^|[^\w.$]){}\b", regex::escape(binding))).unwrap();
+            if pattern.is_match(&line) {
+                line = pattern
+                    .replace_all(&line, format!("${{1}}env.{}", binding).as_str())
+                    .into_owned();
+                changed = true;
+                if !found.contains(binding) {
+                    found.push(binding.clone());
+                }
+            }
+        }
+        if changed {
+            let indent: String = line.chars().take_while(|c| c.is_whitespace()).collect();
+            lines.push(format!("{}{} `env` must be in scope here", indent, TODO));
+        }
+        lines.push(line);
+    }
+
+    (lines.join("\n"), found)
+}
+
+fn update_config(config_path: &Path, entry: &Path) -> Result<()> {
+    let config = fs::read_to_string(config_path)?;
+    let mut doc = config
+        .parse::<toml_edit::Document>()
+        .map_err(|e| anyhow!("could not parse {}: {}", config_path.display(), e))?;
+
+    let current_dir = env::current_dir()?;
+    let entry = entry.strip_prefix(&current_dir).unwrap_or(entry);
+    let dir = entry
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let main = entry
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", entry.display()))?
+        .to_string_lossy();
+
+    // A `main` entrypoint ending in `.mjs` is all the configuration the modules format needs
+    if doc["main"].is_none() {
+        doc["build"]["upload"]["format"] = toml_edit::value("modules");
+        doc["build"]["upload"]["dir"] = toml_edit::value(dir.to_string_lossy().as_ref());
+        doc["build"]["upload"]["main"] = toml_edit::value(format!("./{}", main));
+    } else {
+        doc["main"] = toml_edit::value(entry.to_string_lossy().replace('\\', "/"));
+    }
+
+    fs::write(config_path, doc.to_string_in_original_order())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_converts_fetch_listener() {
+        let source = r#"addEventListener('fetch', event => {
+  event.respondWith(handleRequest(event.request))
+})
+
+async function handleRequest(request) {
+  return new Response('hello')
+}"#;
+        let conversion = convert(source, &[]);
+        assert!(!conversion.code.contains("addEventListener"));
+        assert!(conversion.code.contains("export default {"));
+        assert!(conversion
+            .code
+            .contains("return handleRequest(request, env, ctx)"));
+        assert!(conversion.todos.is_empty());
+    }
+
+    #[test]
+    fn it_converts_scheduled_listener() {
+        let source = r#"addEventListener("scheduled", function (event) {
+  event.waitUntil(handleScheduled(event));
+});"#;
+        let conversion = convert(source, &[]);
+        assert!(conversion
+            .code
+            .contains("ctx.waitUntil(handleScheduled(event, env, ctx))"));
+    }
+
+    #[test]
+    fn it_marks_unknown_listeners() {
+        let source = r#"addEventListener('fetch', event => {
+  const url = new URL(event.request.url)
+  event.respondWith(fetch(url))
+})"#;
+        let conversion = convert(source, &[]);
+        assert!(conversion.code.contains(TODO));
+        assert_eq!(conversion.todos.len(), 2);
+    }
+
+    #[test]
+    fn it_rewrites_bindings() {
+        let source = "const value = await MY_KV.get('key')\nconst other = obj.MY_KV";
+        let conversion = convert(source, &["MY_KV".to_string()]);
+        assert!(conversion.code.contains("await env.MY_KV.get('key')"));
+        assert!(conversion.code.contains("obj.MY_KV"));
+        assert!(!conversion.code.contains("obj.env.MY_KV"));
+    }
+}
@@ -0,0 +1,474 @@
+use std::path::Path;
+
+use anyhow::Result;
+use serde_json::json;
+
+use crate::deploy::{bindings, deployed};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::migrations::{
+    DurableObjectsMigration, Migration, MigrationConfig, Migrations,
+};
+use crate::settings::toml::{ConfigEditor, Target};
+use crate::terminal::interactive;
+use crate::terminal::message::{self, Message, Output, StdErr, StdOut};
+use crate::terminal::styles;
+use crate::terminal::table::{Table, TableOptions};
+
+/// Shows which migrations publishing `target` would send, step by step, and the tag the
+/// script ends up at. Nothing is uploaded.
+pub fn plan(user: &GlobalUser, target: &Target) -> Result<()> {
+    let mut migrations = match &target.migrations {
+        Some(migrations) => migrations.clone(),
+        None => {
+            StdOut::info(
+                "There are no migrations in your configuration file or on the command line",
+            );
+            return Ok(());
+        }
+    };
+
+    let adhoc = migrations.migrations.iter().all(|m| m.tag.is_none());
+    if !adhoc {
+        let client = http::legacy_auth_client(user);
+        migrations.old_tag =
+            deployed::migration_tag(&client, target.account_id.load()?, &target.name)?;
+    }
+
+    let api_migration = migrations.api_migration()?;
+    if message::output() == Output::Json {
+        StdOut::as_json(&json!({
+            "script": target.name,
+            "old_tag": migrations.old_tag,
+            "migration": api_migration,
+        }));
+        return Ok(());
+    }
+
+    let pending: Vec<&MigrationConfig> = if adhoc {
+        migrations.migrations.iter().collect()
+    } else {
+        migrations.pending()?
+    };
+    let api_migration = match api_migration {
+        Some(api_migration) => api_migration,
+        None => {
+            StdOut::success(&format!(
+                "{} is up to date at migration {}",
+                target.name,
+                migrations.old_tag.as_deref().unwrap_or_default()
+            ));
+            return Ok(());
+        }
+    };
+
+    StdOut::info(&format!(
+        "Publishing {} would apply {} migration{}:",
+        target.name,
+        pending.len(),
+        if pending.len() == 1 { "" } else { "s" }
+    ));
+    for migration in &pending {
+        let tag = migration.tag.as_deref().unwrap_or("(untagged)");
+        println!("  {}", styles::highlight(tag));
+        for step in describe(&migration.migration) {
+            println!("    {}", step);
+        }
+    }
+    println!(
+        "Migration tag: {} -> {}",
+        migrations.old_tag.as_deref().unwrap_or("none"),
+        api_migration.new_tag.as_deref().unwrap_or("none")
+    );
+
+    let deleted = deleted_classes(&pending);
+    if !deleted.is_empty() {
+        StdOut::warn(&format!(
+            "Deleting {} destroys every object of the class and its stored data. Publishing will need --accept-data-loss or confirmation",
+            deleted.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Makes sure deleting Durable Object classes is intended before publishing: it destroys every
+/// object of the class for good. `--yes` isn't enough, it takes `--accept-data-loss` or an
+/// answer to the prompt.
+pub fn confirm_data_loss(user: &GlobalUser, target: &Target, accepted: bool) -> Result<()> {
+    let mut migrations = match &target.migrations {
+        Some(migrations) => migrations.clone(),
+        None => return Ok(()),
+    };
+    if !migrations
+        .migrations
+        .iter()
+        .any(|m| !m.migration.durable_objects.deleted_classes.is_empty())
+    {
+        return Ok(());
+    }
+
+    let pending: Vec<&MigrationConfig> = if migrations.migrations.iter().all(|m| m.tag.is_none()) {
+        migrations.migrations.iter().collect()
+    } else {
+        let client = http::legacy_auth_client(user);
+        migrations.old_tag =
+            deployed::migration_tag(&client, target.account_id.load()?, &target.name)?;
+        migrations.pending()?
+    };
+    let deleted = deleted_classes(&pending);
+    if deleted.is_empty() || accepted {
+        return Ok(());
+    }
+
+    StdOut::warn(&format!(
+        "Publishing {} deletes the Durable Object class{} {}, along with every object and all of their stored data. The API doesn't report how many objects that is.",
+        target.name,
+        if deleted.len() == 1 { "" } else { "es" },
+        deleted.join(", ")
+    ));
+    StdOut::help("If you need the data, read it out of the objects and store it elsewhere before publishing, e.g. in Workers KV");
+
+    if !interactive::is_interactive() || interactive::assumes_yes() {
+        anyhow::bail!("Deleting Durable Object classes needs confirmation, pass --accept-data-loss to publish anyway");
+    }
+    if !interactive::confirm("Delete these classes and all of their data?")? {
+        anyhow::bail!("Not publishing, nothing was deleted");
+    }
+    Ok(())
+}
+
+fn deleted_classes(migrations: &[&MigrationConfig]) -> Vec<String> {
+    migrations
+        .iter()
+        .flat_map(|m| m.migration.durable_objects.deleted_classes.iter().cloned())
+        .collect()
+}
+
+/// Durable Object classes of `target` that no migration creates, skipping the ones the deployed
+/// script is already bound to since those were created some other way, e.g. with `--new-class`.
+pub fn missing_new_classes(user: &GlobalUser, target: &Target) -> Vec<String> {
+    let unmigrated = unmigrated_classes(target);
+    if unmigrated.is_empty() {
+        return unmigrated;
+    }
+
+    let deployed = bindings::fetch(user, target).unwrap_or_default();
+    let bound = |class_name: &String| {
+        target
+            .durable_objects
+            .iter()
+            .flat_map(|durable_objects| durable_objects.classes.iter().flatten())
+            .filter(|class| &class.class_name == class_name)
+            .any(|class| {
+                deployed.iter().any(|b| {
+                    b.binding_type == "durable_object_namespace" && b.name == class.binding
+                })
+            })
+    };
+    unmigrated
+        .into_iter()
+        .filter(|class| !bound(class))
+        .collect()
+}
+
+// Classes of this script that are bound but never created, renamed or transferred to
+fn unmigrated_classes(target: &Target) -> Vec<String> {
+    let mut migrated = Vec::new();
+    for migration in target.migrations.iter().flat_map(|m| &m.migrations) {
+        let durable_objects = &migration.migration.durable_objects;
+        migrated.extend(durable_objects.new_classes.iter().cloned());
+        migrated.extend(durable_objects.renamed_classes.iter().map(|r| r.to.clone()));
+        migrated.extend(
+            durable_objects
+                .transferred_classes
+                .iter()
+                .map(|t| t.to.clone()),
+        );
+    }
+
+    let mut missing = Vec::new();
+    for class in target
+        .durable_objects
+        .iter()
+        .flat_map(|durable_objects| durable_objects.classes.iter().flatten())
+    {
+        let own = match &class.script_name {
+            Some(script_name) => script_name == &target.name,
+            None => true,
+        };
+        if own && !migrated.contains(&class.class_name) && !missing.contains(&class.class_name) {
+            missing.push(class.class_name.clone());
+        }
+    }
+    missing
+}
+
+/// Offers to add a `[[migrations]]` entry creating the classes no migration creates yet, the
+/// most common reason publishing a Durable Object fails. With `add`, it's added without asking.
+/// `target` is updated to include the new migration.
+pub fn offer_new_classes(
+    user: &GlobalUser,
+    config_path: &Path,
+    target: &mut Target,
+    add: bool,
+) -> Result<()> {
+    // a migration from the command line replaces the configured ones for this publish
+    if let Some(migrations) = &target.migrations {
+        if migrations.migrations.iter().all(|m| m.tag.is_none()) {
+            return Ok(());
+        }
+    }
+    let missing = missing_new_classes(user, target);
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let count = target
+        .migrations
+        .as_ref()
+        .map(|migrations| migrations.migrations.len())
+        .unwrap_or_default();
+    let migration = MigrationConfig {
+        tag: Some(format!("v{}", count + 1)),
+        migration: Migration {
+            durable_objects: DurableObjectsMigration {
+                new_classes: missing.clone(),
+                ..DurableObjectsMigration::default()
+            },
+        },
+    };
+    let entry = migration_entry(&migration);
+
+    let prompt = format!(
+        "No migration creates the Durable Object class{} {}. Add this to {}?\n{}",
+        if missing.len() == 1 { "" } else { "es" },
+        missing.join(", "),
+        config_path.display(),
+        entry
+    );
+    let add = add || (interactive::is_interactive() && interactive::confirm(&prompt)?);
+    if !add {
+        StdOut::warn(&format!(
+            "No migration creates the Durable Object class{} {}, publishing will likely fail. Add this to {}, or pass --update-config:\n{}",
+            if missing.len() == 1 { "" } else { "es" },
+            missing.join(", "),
+            config_path.display(),
+            entry
+        ));
+        return Ok(());
+    }
+
+    // after the existing migrations, keeping the rest of the file as it is
+    let mut config = ConfigEditor::open(config_path)?;
+    config.append_table("migrations", &migration_body(&migration))?;
+    config.save()?;
+    StdOut::success(&format!(
+        "Added migration {} to {}",
+        migration.tag.as_deref().unwrap_or_default(),
+        config_path.display()
+    ));
+
+    target
+        .migrations
+        .get_or_insert_with(Migrations::default)
+        .migrations
+        .push(migration);
+    Ok(())
+}
+
+fn migration_entry(migration: &MigrationConfig) -> String {
+    format!("[[migrations]]\n{}", migration_body(migration))
+}
+
+fn migration_body(migration: &MigrationConfig) -> String {
+    let classes = migration
+        .migration
+        .durable_objects
+        .new_classes
+        .iter()
+        .map(|class| format!("{:?}", class))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "tag = {:?}\nnew_classes = [{}]\n",
+        migration.tag.as_deref().unwrap_or_default(),
+        classes
+    )
+}
+
+/// Lists the migrations in the configuration file, and whether the deployed script already had
+/// them. The API only reports the tag of the last migration applied, so that's all there is to
+/// go on.
+pub fn list(user: &GlobalUser, target: &Target, options: &TableOptions) -> Result<()> {
+    let configured = target
+        .migrations
+        .as_ref()
+        .map(|migrations| migrations.migrations.as_slice())
+        .unwrap_or_default();
+
+    let client = http::legacy_auth_client(user);
+    let deployed_tag = deployed::migration_tag(&client, target.account_id.load()?, &target.name)?;
+    match &deployed_tag {
+        Some(tag) => StdErr::info(&format!("{} is at migration {}", target.name, tag)),
+        None => StdErr::info(&format!("{} has no migrations applied", target.name)),
+    }
+
+    let rows = statuses(configured, deployed_tag.as_deref())
+        .into_iter()
+        .map(|(migration, status)| {
+            json!({
+                "tag": migration.tag,
+                "status": status,
+                "changes": describe(&migration.migration).join(", "),
+            })
+        })
+        .collect();
+    Table::new(&["tag", "status", "changes"], rows).print(options)
+}
+
+// Everything up to the deployed tag is applied. If the deployed tag isn't configured, there's
+// no telling which ones were.
+fn statuses<'a>(
+    configured: &'a [MigrationConfig],
+    deployed_tag: Option<&str>,
+) -> Vec<(&'a MigrationConfig, &'static str)> {
+    let applied = match deployed_tag {
+        None => Some(0),
+        Some(tag) => configured
+            .iter()
+            .position(|m| m.tag.as_deref() == Some(tag))
+            .map(|index| index + 1),
+    };
+    configured
+        .iter()
+        .enumerate()
+        .map(|(index, migration)| {
+            let status = match applied {
+                Some(applied) if index < applied => "applied",
+                Some(_) => "pending",
+                None => "unknown",
+            };
+            (migration, status)
+        })
+        .collect()
+}
+
+/// One line per change a migration makes, in the order the API applies them.
+pub fn describe(migration: &Migration) -> Vec<String> {
+    let durable_objects = &migration.durable_objects;
+    let mut steps = Vec::new();
+    for class in &durable_objects.new_classes {
+        steps.push(format!("create class {}", class));
+    }
+    for rename in &durable_objects.renamed_classes {
+        steps.push(format!("rename class {} to {}", rename.from, rename.to));
+    }
+    for transfer in &durable_objects.transferred_classes {
+        steps.push(format!(
+            "transfer class {} from script {} to class {}",
+            transfer.from, transfer.from_script, transfer.to
+        ));
+    }
+    for class in &durable_objects.deleted_classes {
+        steps.push(format!("delete class {} and all of its objects", class));
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::migrations::RenameClass;
+    use crate::settings::toml::Manifest;
+    use std::str::FromStr;
+
+    fn tagged(tag: &str) -> MigrationConfig {
+        MigrationConfig {
+            tag: Some(tag.to_string()),
+            migration: Migration::default(),
+        }
+    }
+
+    #[test]
+    fn migrations_up_to_the_deployed_tag_are_applied() {
+        let configured = vec![tagged("v1"), tagged("v2"), tagged("v3")];
+        let status = |deployed_tag| {
+            statuses(&configured, deployed_tag)
+                .into_iter()
+                .map(|(_, status)| status)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(status(Some("v2")), vec!["applied", "applied", "pending"]);
+        assert_eq!(status(None), vec!["pending", "pending", "pending"]);
+        assert_eq!(status(Some("v9")), vec!["unknown", "unknown", "unknown"]);
+    }
+
+    #[test]
+    fn classes_without_a_migration_are_found() {
+        let manifest = Manifest::from_str(
+            r#"
+            name = "worker"
+            type = "javascript"
+
+            [durable_objects]
+            bindings = [
+                { name = "COUNTER", class_name = "Counter" },
+                { name = "ROOM", class_name = "Room" },
+                { name = "OTHER", class_name = "Other", script_name = "other-worker" },
+            ]
+
+            [[migrations]]
+            tag = "v1"
+            new_classes = ["Counter"]
+            "#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+        assert_eq!(unmigrated_classes(&target), vec!["Room"]);
+
+        let entry = migration_entry(&MigrationConfig {
+            tag: Some("v2".to_string()),
+            migration: Migration {
+                durable_objects: DurableObjectsMigration {
+                    new_classes: vec!["Room".to_string()],
+                    ..DurableObjectsMigration::default()
+                },
+            },
+        });
+        assert_eq!(
+            entry,
+            "[[migrations]]\ntag = \"v2\"\nnew_classes = [\"Room\"]\n"
+        );
+        let appended = format!(
+            "{}\n{}",
+            "name = \"worker\"\ntype = \"javascript\"\n", entry
+        );
+        assert_eq!(
+            Manifest::from_str(&appended).unwrap().migrations.unwrap()[0].tag,
+            Some("v2".to_string())
+        );
+    }
+
+    #[test]
+    fn steps_are_described_in_order() {
+        let migration = Migration {
+            durable_objects: DurableObjectsMigration {
+                new_classes: vec!["Counter".to_string()],
+                deleted_classes: vec!["Old".to_string()],
+                renamed_classes: vec![RenameClass {
+                    from: "A".to_string(),
+                    to: "B".to_string(),
+                }],
+                transferred_classes: vec![],
+            },
+        };
+        assert_eq!(
+            describe(&migration),
+            vec![
+                "create class Counter",
+                "rename class A to B",
+                "delete class Old and all of its objects"
+            ]
+        );
+    }
+}
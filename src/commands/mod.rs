@@ -1,19 +1,35 @@
 use std::process::Command;
 
 pub mod config;
+pub mod deployments;
 pub mod dev;
+pub mod doctor;
+pub mod drift;
+pub mod environments;
+pub mod export;
 pub mod generate;
 pub mod init;
 pub mod kv;
+pub mod list;
 pub mod login;
+pub mod migrate_format;
+pub mod migrations;
+pub mod pages;
 mod preview;
+pub mod preview_alias;
 pub mod publish;
+pub mod queue;
+pub mod r2;
+pub mod rename;
 pub mod report;
 pub mod route;
 pub mod secret;
 pub mod subdomain;
+pub mod summary;
 pub mod tail;
+pub mod verify;
 pub mod whoami;
+pub mod zone;
 
 pub use self::config::global_config;
 pub use self::preview::run as preview;
@@ -0,0 +1,316 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::upload::form::{ModuleConfig, ModuleType};
+
+// The entry module of the compiled worker, next to the handlers in the bundle
+const ENTRY_MODULE: &str = "functionsWorker.js";
+const METHODS: &[&str] = &["Get", "Head", "Post", "Put", "Patch", "Delete", "Options"];
+
+/// A handler module of a `functions/` directory, and the requests it's for.
+#[derive(Debug, PartialEq)]
+pub struct FunctionRoute {
+    /// e.g. `/users/:id` for `functions/users/[id].js`
+    pub pattern: String,
+    /// The module's name in the bundle, e.g. `./users/[id].js`
+    pub module: String,
+    /// The `onRequest*` handlers it exports
+    pub handlers: Vec<String>,
+    /// `_middleware.js` runs before everything under its directory
+    pub middleware: bool,
+}
+
+/// A modules-format worker compiled from a `functions/` directory, as the `_worker.bundle`
+/// form part of a Pages deployment.
+pub struct FunctionsBundle {
+    pub routes: Vec<FunctionRoute>,
+    pub bytes: Vec<u8>,
+}
+
+/// Compiles the file-based routes of `directory` into one worker: a generated entry module
+/// that matches requests against the routes and runs their handlers, bundled with the
+/// handler modules as they are. Requests no handler takes are served from the static assets.
+pub fn compile(directory: &Path) -> Result<FunctionsBundle> {
+    let manifest = ModuleConfig::new(ENTRY_MODULE, directory, &None).get_modules()?;
+
+    let mut routes = Vec::new();
+    let mut modules = Vec::new();
+    for (name, module) in manifest.modules {
+        if module.module_type != ModuleType::ESModule && module.module_type != ModuleType::CommonJS
+        {
+            continue;
+        }
+        let source = fs::read_to_string(&module.path)?;
+        if let Some((pattern, middleware)) = route_pattern(&name) {
+            let handlers = exported_handlers(&source);
+            if handlers.is_empty() {
+                log::info!("{} exports no onRequest handlers", name);
+            } else {
+                routes.push(FunctionRoute {
+                    pattern,
+                    module: name.clone(),
+                    handlers,
+                    middleware,
+                });
+            }
+        }
+        modules.push((name, source));
+    }
+    if routes.is_empty() {
+        anyhow::bail!(
+            "{} has no modules exporting onRequest handlers",
+            directory.display()
+        );
+    }
+    routes.sort_by_key(order);
+    modules.sort();
+
+    modules.push((format!("./{}", ENTRY_MODULE), entry_module(&routes)));
+    Ok(FunctionsBundle {
+        bytes: bundle(&modules),
+        routes,
+    })
+}
+
+// `./api/users/[id].js` serves `/api/users/:id`, `[[path]].js` every path below its directory
+// as `:path*`, `index.js` its directory and `_middleware.js` runs before all of those
+fn route_pattern(module: &str) -> Option<(String, bool)> {
+    let path = module.trim_start_matches("./");
+    let path = path
+        .strip_suffix(".js")
+        .or_else(|| path.strip_suffix(".mjs"))?;
+
+    let mut segments: Vec<String> = Vec::new();
+    let mut middleware = false;
+    let parts: Vec<&str> = path.split('/').collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        if last && *part == "index" {
+            break;
+        }
+        if last && *part == "_middleware" {
+            middleware = true;
+            break;
+        }
+        if part.starts_with('_') {
+            // other underscored files are helpers for the handlers
+            return None;
+        }
+        let segment = if let Some(name) = part.strip_prefix("[[").and_then(|p| p.strip_suffix("]]"))
+        {
+            format!(":{}*", name)
+        } else if let Some(name) = part.strip_prefix('[').and_then(|p| p.strip_suffix(']')) {
+            format!(":{}", name)
+        } else {
+            part.to_string()
+        };
+        segments.push(segment);
+    }
+    Some((format!("/{}", segments.join("/")), middleware))
+}
+
+fn exported_handlers(source: &str) -> Vec<String> {
+    let export = Regex::new(
+        r"export\s+(?:async\s+function\s*\*?|function\s*\*?|const|let|var)\s+(onRequest[A-Za-z]*)\b",
+    )
+    .unwrap();
+    let mut handlers: Vec<String> = export
+        .captures_iter(source)
+        .map(|captures| captures[1].to_string())
+        .filter(|name| {
+            name == "onRequest"
+                || METHODS
+                    .iter()
+                    .any(|method| name == &format!("onRequest{}", method))
+        })
+        .collect();
+    handlers.sort();
+    handlers.dedup();
+    handlers
+}
+
+// Middleware first, outermost directory first. Then the handlers, static segments before
+// dynamic ones and longer paths before shorter ones.
+fn order(route: &FunctionRoute) -> (bool, isize, isize) {
+    let segments: Vec<&str> = route.pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let fixed = segments.iter().filter(|s| !s.starts_with(':')).count() as isize;
+    let length = segments.len() as isize;
+    if route.middleware {
+        (false, length, 0)
+    } else {
+        (true, -fixed, -length)
+    }
+}
+
+fn entry_module(routes: &[FunctionRoute]) -> String {
+    let mut imports = String::new();
+    let mut table = String::new();
+    for (i, route) in routes.iter().enumerate() {
+        imports.push_str(&format!(
+            "import * as handlers{} from {:?};\n",
+            i, route.module
+        ));
+        table.push_str(&format!(
+            "  {{ pattern: {:?}, middleware: {}, module: handlers{} }},\n",
+            route.pattern, route.middleware, i
+        ));
+    }
+    format!("{}\nconst routes = [\n{}];\n\n{}", imports, table, ROUTER)
+}
+
+// Matches routes against the request and runs the middleware and handlers in order, each
+// calling `next()` for the rest of the chain. What's left over is served from the assets.
+const ROUTER: &str = r#"function match(pattern, middleware, pathname) {
+  const want = pattern.split("/").filter(Boolean);
+  const have = pathname.split("/").filter(Boolean);
+  const params = {};
+  for (let i = 0; i < want.length; i++) {
+    const segment = want[i];
+    if (segment.startsWith(":") && segment.endsWith("*")) {
+      if (i >= have.length) return null;
+      params[segment.slice(1, -1)] = have.slice(i).map(decodeURIComponent);
+      return params;
+    }
+    if (i >= have.length) return null;
+    if (segment.startsWith(":")) {
+      params[segment.slice(1)] = decodeURIComponent(have[i]);
+    } else if (segment !== have[i]) {
+      return null;
+    }
+  }
+  return middleware || want.length === have.length ? params : null;
+}
+
+function handlersFor(request) {
+  const { pathname } = new URL(request.url);
+  const method = request.method.charAt(0) + request.method.slice(1).toLowerCase();
+  const chain = [];
+  for (const route of routes) {
+    const params = match(route.pattern, route.middleware, pathname);
+    if (params === null) continue;
+    for (const name of ["onRequest" + method, "onRequest"]) {
+      if (typeof route.module[name] === "function") chain.push({ handler: route.module[name], params });
+    }
+  }
+  return chain;
+}
+
+export default {
+  async fetch(request, env, ctx) {
+    const chain = handlersFor(request);
+    const data = {};
+    const next = async (input, init) => {
+      if (input !== undefined) request = new Request(input, init);
+      const step = chain.shift();
+      if (!step) return env.ASSETS.fetch(request);
+      return step.handler({
+        request,
+        env,
+        params: step.params,
+        data,
+        next,
+        waitUntil: ctx.waitUntil.bind(ctx),
+        passThroughOnException: ctx.passThroughOnException.bind(ctx),
+      });
+    };
+    return next();
+  },
+};
+"#;
+
+// The modules as multipart/form-data, the same format a worker upload uses
+fn bundle(modules: &[(String, String)]) -> Vec<u8> {
+    let boundary = format!(
+        "----wranglerPagesFunctions{}",
+        uuid::Uuid::new_v4().to_simple()
+    );
+    let mut body = String::new();
+    let mut part = |name: &str, content_type: &str, contents: &str| {
+        body.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n{}\r\n",
+            boundary, name, name, content_type, contents
+        ));
+    };
+    part(
+        "metadata",
+        "application/json",
+        &serde_json::json!({ "main_module": ENTRY_MODULE }).to_string(),
+    );
+    for (name, source) in modules {
+        part(
+            name.trim_start_matches("./"),
+            ModuleType::ESModule.content_type(),
+            source,
+        );
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_paths_become_route_patterns() {
+        let pattern = |module| route_pattern(module).map(|(pattern, _)| pattern);
+        assert_eq!(pattern("./index.js").unwrap(), "/");
+        assert_eq!(pattern("./api/hello.js").unwrap(), "/api/hello");
+        assert_eq!(pattern("./users/[id]/index.mjs").unwrap(), "/users/:id");
+        assert_eq!(pattern("./docs/[[path]].js").unwrap(), "/docs/:path*");
+        assert_eq!(
+            route_pattern("./api/_middleware.js"),
+            Some(("/api".to_string(), true))
+        );
+        assert_eq!(pattern("./_utils/db.js"), None);
+        assert_eq!(pattern("./data.json"), None);
+    }
+
+    #[test]
+    fn handlers_are_found_in_exports() {
+        let source = r#"
+            import { db } from "../_utils/db.js";
+            export async function onRequestGet(context) {}
+            export const onRequestPost = async () => {};
+            export function onRequestTeapot() {}
+            function onRequestPut() {}
+        "#;
+        assert_eq!(
+            exported_handlers(source),
+            vec!["onRequestGet", "onRequestPost"]
+        );
+    }
+
+    #[test]
+    fn directories_compile_to_a_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("api/users")).unwrap();
+        fs::write(
+            dir.path().join("_middleware.js"),
+            "export const onRequest = ({ next }) => next();",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("api/users/[id].js"),
+            "export function onRequestGet({ params }) { return new Response(params.id); }",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("api/users/me.js"),
+            "export function onRequestGet() { return new Response('me'); }",
+        )
+        .unwrap();
+
+        let bundle = compile(dir.path()).unwrap();
+        let patterns: Vec<&str> = bundle.routes.iter().map(|r| r.pattern.as_str()).collect();
+        assert_eq!(patterns, vec!["/", "/api/users/me", "/api/users/:id"]);
+
+        let bytes = String::from_utf8(bundle.bytes).unwrap();
+        assert!(bytes.contains("name=\"functionsWorker.js\""));
+        assert!(bytes.contains("import * as handlers2 from \"./api/users/[id].js\";"));
+        assert!(bytes.contains("\"main_module\":\"functionsWorker.js\""));
+    }
+}
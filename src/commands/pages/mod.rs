@@ -0,0 +1,164 @@
+mod functions;
+pub(crate) mod upload;
+
+use std::path::Path;
+
+use anyhow::Result;
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::{Table, TableOptions};
+
+#[derive(Deserialize)]
+struct ApiResult<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct Deployment {
+    id: String,
+    url: String,
+    environment: String,
+    created_on: String,
+    #[serde(default)]
+    deployment_trigger: Value,
+}
+
+fn projects_addr(account_id: &str) -> String {
+    format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/pages/projects",
+        account_id
+    )
+}
+
+pub fn create_project(
+    user: &GlobalUser,
+    account_id: &str,
+    name: &str,
+    production_branch: &str,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let project: Value = send(
+        &client,
+        client.post(&projects_addr(account_id)).json(&json!({
+            "name": name,
+            "production_branch": production_branch,
+        })),
+    )?;
+
+    StdOut::success(&format!(
+        "Created Pages project {}, deployments of {} will be served at https://{}",
+        name,
+        production_branch,
+        project["subdomain"].as_str().unwrap_or_default()
+    ));
+    Ok(())
+}
+
+pub fn list_projects(user: &GlobalUser, account_id: &str, options: &TableOptions) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let projects: Vec<Value> =
+        deployed::get(&client, &projects_addr(account_id))?.unwrap_or_default();
+    Table::new(
+        &["name", "subdomain", "production_branch", "created_on"],
+        projects,
+    )
+    .print(options)
+}
+
+pub fn list_deployments(
+    user: &GlobalUser,
+    account_id: &str,
+    project: &str,
+    options: &TableOptions,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let addr = format!("{}/{}/deployments", projects_addr(account_id), project);
+    let deployments: Vec<Deployment> = match deployed::get(&client, &addr)? {
+        Some(deployments) => deployments,
+        None => anyhow::bail!("There's no Pages project named {}", project),
+    };
+
+    let rows = deployments
+        .iter()
+        .map(|deployment| {
+            json!({
+                "id": deployment.id,
+                "environment": deployment.environment,
+                "branch": deployment.deployment_trigger["metadata"]["branch"],
+                "url": deployment.url,
+                "created_on": deployment.created_on,
+            })
+        })
+        .collect();
+    Table::new(&["id", "environment", "branch", "url", "created_on"], rows).print(options)
+}
+
+/// Deploys a directory of static files to a Pages project, uploading only the files the
+/// project doesn't have yet. A `functions` directory is compiled into the worker that serves
+/// them.
+pub fn deploy(
+    user: &GlobalUser,
+    account_id: &str,
+    project: &str,
+    directory: &Path,
+    functions: Option<&Path>,
+    branch: Option<&str>,
+) -> Result<()> {
+    let bundle = match functions {
+        Some(functions) => {
+            let bundle = functions::compile(functions)?;
+            StdOut::info(&format!(
+                "Compiled {} routes from {}",
+                bundle.routes.len(),
+                functions.display()
+            ));
+            Some(bundle)
+        }
+        None => None,
+    };
+    let assets = upload::collect(directory)?;
+    let client = http::legacy_auth_client(user);
+    upload::upload(&client, account_id, project, &assets)?;
+
+    let mut form = Form::new().text(
+        "manifest",
+        serde_json::to_string(&upload::manifest(&assets))?,
+    );
+    if let Some(branch) = branch {
+        form = form.text("branch", branch.to_string());
+    }
+    if let Some(bundle) = bundle {
+        form = form.part(
+            "_worker.bundle",
+            Part::bytes(bundle.bytes).file_name("_worker.bundle"),
+        );
+    }
+
+    let addr = format!("{}/{}/deployments", projects_addr(account_id), project);
+    let deployment: Deployment = send(&client, client.post(&addr).multipart(form))?;
+    StdOut::success(&format!(
+        "Deployed {} files to {} ({})",
+        assets.len(),
+        deployment.url,
+        deployment.environment
+    ));
+    Ok(())
+}
+
+fn send<T: serde::de::DeserializeOwned>(
+    client: &Client,
+    request: reqwest::blocking::RequestBuilder,
+) -> Result<T> {
+    let res = http::trace::send(client, request)?;
+    if !res.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(res.text()?));
+    }
+    Ok(res.json::<ApiResult<T>>()?.result)
+}
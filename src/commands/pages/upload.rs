@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use ignore::WalkBuilder;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use twox_hash::XxHash64;
+
+use crate::http;
+use crate::paths;
+use crate::terminal::message::{Message, StdOut};
+
+const PAGES_API: &str = "https://api.cloudflare.com/client/v4/pages/assets";
+// The limits of a single Pages deployment, and of one upload request
+const MAX_FILE_SIZE: u64 = 25 * 1024 * 1024;
+const MAX_FILES: usize = 20_000;
+const MAX_BUCKET_SIZE: usize = 40 * 1024 * 1024;
+const MAX_BUCKET_FILES: usize = 2000;
+// Never part of a site, whatever the directory holds
+const IGNORED: &[&str] = &[".DS_Store", ".git", "node_modules"];
+
+/// A file of the directory being deployed.
+#[derive(Debug, PartialEq)]
+pub struct Asset {
+    pub path: PathBuf,
+    /// Where the file is served, e.g. `/css/site.css`
+    pub url_path: String,
+    /// The key the file's contents are stored under. Keys only have to be content-addressed,
+    /// so the same file is never uploaded twice.
+    pub hash: String,
+    pub content_type: &'static str,
+}
+
+/// Reads every file under `directory` that's served as part of the site.
+pub fn collect(directory: &Path) -> Result<Vec<Asset>> {
+    if !directory.is_dir() {
+        anyhow::bail!("{} is not a directory", directory.display());
+    }
+
+    let mut assets = Vec::new();
+    let walker = WalkBuilder::new(directory)
+        .standard_filters(false)
+        .follow_links(true)
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !IGNORED.contains(&entry.file_name().to_string_lossy().as_ref())
+        })
+        .build();
+    for entry in walker {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        if entry.metadata()?.len() > MAX_FILE_SIZE {
+            anyhow::bail!(
+                "{} is larger than the 25 MiB Pages allows for a single file",
+                path.display()
+            );
+        }
+
+        let relative = paths::relative_name(path, directory)?;
+        let contents = fs::read(path)?;
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        assets.push(Asset {
+            path: path.to_path_buf(),
+            url_path: format!("/{}", relative),
+            hash: hash(&contents, extension),
+            content_type: content_type(extension),
+        });
+    }
+
+    if assets.is_empty() {
+        anyhow::bail!("There are no files to deploy in {}", directory.display());
+    }
+    if assets.len() > MAX_FILES {
+        anyhow::bail!(
+            "{} has {} files, but a Pages deployment can't have more than {}",
+            directory.display(),
+            assets.len(),
+            MAX_FILES
+        );
+    }
+    assets.sort_by(|a, b| a.url_path.cmp(&b.url_path));
+    Ok(assets)
+}
+
+/// Where each file is served, and the key of its contents.
+pub fn manifest(assets: &[Asset]) -> BTreeMap<&str, &str> {
+    assets
+        .iter()
+        .map(|asset| (asset.url_path.as_str(), asset.hash.as_str()))
+        .collect()
+}
+
+// 128 bits from two differently seeded hashes, as 32 hex characters
+fn hash(contents: &[u8], extension: &str) -> String {
+    let half = |seed| {
+        let mut hasher = XxHash64::with_seed(seed);
+        hasher.write(contents);
+        hasher.write(extension.as_bytes());
+        hasher.finish()
+    };
+    format!("{:016x}{:016x}", half(0), half(1))
+}
+
+pub(crate) fn content_type(extension: &str) -> &'static str {
+    match extension.to_lowercase().as_str() {
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "application/javascript",
+        "json" | "map" => "application/json",
+        "txt" => "text/plain",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+#[derive(Deserialize)]
+struct UploadToken {
+    jwt: String,
+}
+
+#[derive(Deserialize)]
+struct ApiResult<T> {
+    result: T,
+}
+
+/// Uploads the files the project doesn't have yet. The Pages asset API is authorized with a
+/// token for the project rather than the user's credentials.
+pub fn upload(client: &Client, account_id: &str, project: &str, assets: &[Asset]) -> Result<()> {
+    let token_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/pages/projects/{}/upload-token",
+        account_id, project
+    );
+    let jwt = match crate::deploy::deployed::get::<UploadToken>(client, &token_addr)? {
+        Some(token) => token.jwt,
+        None => anyhow::bail!(
+            "There's no Pages project named {}, create it with `wrangler pages project create {}`",
+            project,
+            project
+        ),
+    };
+    let pages_client = http::client();
+    let post = |endpoint: &str, body: Value| -> Result<Value> {
+        let res = http::trace::send(
+            &pages_client,
+            pages_client
+                .post(&format!("{}/{}", PAGES_API, endpoint))
+                .bearer_auth(&jwt)
+                .json(&body),
+        )?;
+        if !res.status().is_success() {
+            anyhow::bail!(crate::format_api_errors(res.text()?));
+        }
+        Ok(res.json::<ApiResult<Value>>()?.result)
+    };
+
+    let hashes: Vec<&str> = assets.iter().map(|asset| asset.hash.as_str()).collect();
+    let missing: Vec<String> =
+        serde_json::from_value(post("check-missing", json!({ "hashes": hashes }))?)?;
+    let mut pending: Vec<&Asset> = Vec::new();
+    for asset in assets {
+        if missing.contains(&asset.hash) && !pending.iter().any(|p| p.hash == asset.hash) {
+            pending.push(asset);
+        }
+    }
+    if pending.len() < assets.len() {
+        log::info!(
+            "{} files are uploaded already",
+            assets.len() - pending.len()
+        );
+    }
+
+    let mut uploaded = 0;
+    for bucket in buckets(&pending)? {
+        let payload = bucket
+            .iter()
+            .map(|asset| {
+                let contents = fs::read(&asset.path)
+                    .map_err(|e| anyhow!("Could not read {}: {}", asset.path.display(), e))?;
+                Ok(json!({
+                    "key": asset.hash,
+                    "value": base64::encode(contents),
+                    "metadata": { "contentType": asset.content_type },
+                    "base64": true,
+                }))
+            })
+            .collect::<Result<Vec<Value>>>()?;
+        post("upload", Value::Array(payload))?;
+        uploaded += bucket.len();
+        StdOut::working(&format!("Uploaded {} of {} files", uploaded, pending.len()));
+    }
+
+    post("upsert-hashes", json!({ "hashes": hashes }))?;
+    Ok(())
+}
+
+// Splits the files into requests of a size the upload endpoint accepts
+fn buckets<'a>(assets: &[&'a Asset]) -> Result<Vec<Vec<&'a Asset>>> {
+    let mut buckets: Vec<Vec<&Asset>> = Vec::new();
+    let mut size = 0;
+    for asset in assets {
+        // base64 grows the contents by a third
+        let encoded = fs::metadata(&asset.path)?.len() as usize * 4 / 3;
+        let full = match buckets.last() {
+            Some(bucket) => bucket.len() >= MAX_BUCKET_FILES || size + encoded > MAX_BUCKET_SIZE,
+            None => true,
+        };
+        if full {
+            buckets.push(Vec::new());
+            size = 0;
+        }
+        buckets.last_mut().unwrap().push(asset);
+        size += encoded;
+    }
+    Ok(buckets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sites_are_collected_with_their_url_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("css")).unwrap();
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("index.html"), "<h1>hi</h1>").unwrap();
+        fs::write(dir.path().join("css/site.css"), "h1 {}").unwrap();
+        fs::write(dir.path().join("copy.html"), "<h1>hi</h1>").unwrap();
+        fs::write(dir.path().join("node_modules/pkg/index.js"), "").unwrap();
+
+        let assets = collect(dir.path()).unwrap();
+        let paths: Vec<&str> = assets.iter().map(|a| a.url_path.as_str()).collect();
+        assert_eq!(paths, vec!["/copy.html", "/css/site.css", "/index.html"]);
+        assert_eq!(assets[1].content_type, "text/css");
+        assert_eq!(assets[0].hash, assets[2].hash);
+        assert_eq!(assets[0].hash.len(), 32);
+        assert_ne!(assets[0].hash, assets[1].hash);
+        assert_eq!(manifest(&assets)["/index.html"], assets[2].hash);
+    }
+
+    #[test]
+    fn same_contents_with_another_extension_hash_differently() {
+        assert_ne!(hash(b"{}", "json"), hash(b"{}", "txt"));
+        assert_eq!(hash(b"{}", "json"), hash(b"{}", "json"));
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use reqwest::StatusCode;
+
+use crate::deploy::{DeployTarget, DeploymentSet, ZonelessTarget};
+use crate::http;
+use crate::kv::namespace;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdOut};
+
+// workers.dev subdomains are DNS labels
+const MAX_SCRIPT_NAME_LEN: usize = 63;
+
+/// The name a branch's preview of `script` is published under, e.g. `my-worker-feature-x` for
+/// `feature/x`.
+pub fn script_name(script: &str, branch: &str) -> Result<String> {
+    let mut slug = String::new();
+    for c in branch.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let room = MAX_SCRIPT_NAME_LEN.saturating_sub(script.len() + 1);
+    slug.truncate(room);
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        anyhow::bail!(
+            "Can't derive a preview alias of {} from {:?}, use a branch name with letters or digits{}",
+            script,
+            branch,
+            if room == 0 { " and a shorter worker name" } else { "" }
+        );
+    }
+
+    Ok(format!("{}-{}", script, slug))
+}
+
+/// A preview alias is only published to workers.dev, so it never takes over production routes
+/// or runs production cron triggers.
+pub fn deployments(target: &Target) -> Result<DeploymentSet> {
+    Ok(vec![DeployTarget::Zoneless(ZonelessTarget {
+        account_id: target.account_id.load()?.to_string(),
+        script_name: target.name.clone(),
+    })])
+}
+
+/// Deletes the script published for `branch`, and the namespace of its site if it has one.
+pub fn delete(user: &GlobalUser, target: &Target, branch: &str) -> Result<()> {
+    let alias = script_name(&target.name, branch)?;
+    if !interactive::confirm(&format!("Are you sure you want to delete {}?", alias))? {
+        StdOut::info(&format!("Not deleting {}", alias));
+        return Ok(());
+    }
+
+    let account_id = target.account_id.load()?;
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        account_id, alias
+    );
+    let client = http::legacy_auth_client(user);
+    let res = http::trace::send(&client, client.delete(&addr))?;
+    match res.status() {
+        StatusCode::NOT_FOUND => anyhow::bail!("{} doesn't exist", alias),
+        status if !status.is_success() => anyhow::bail!(
+            "Could not delete {}: {}\n{}",
+            alias,
+            status,
+            crate::format_api_errors(res.text()?)
+        ),
+        _ => StdOut::success(&format!("Deleted {}", alias)),
+    }
+
+    let site_title = format!("__{}-workers_sites_assets", alias);
+    let v4_client = http::cf_v4_client(user)?;
+    let site_namespace = namespace::list(&v4_client, target)?
+        .into_iter()
+        .find(|ns| ns.title == site_title);
+    if let Some(site_namespace) = site_namespace {
+        match namespace::delete(&v4_client, account_id, &site_namespace.id) {
+            Ok(_) => StdOut::success(&format!("Deleted namespace {}", site_title)),
+            Err(e) => StdOut::warn(&format!(
+                "Could not delete namespace {}: {}",
+                site_title,
+                crate::commands::kv::format_error(e)
+            )),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branch_names_are_turned_into_script_names() {
+        assert_eq!(
+            script_name("my-worker", "feature/Fix_login").unwrap(),
+            "my-worker-feature-fix-login"
+        );
+        assert_eq!(script_name("w", "--pr--42--").unwrap(), "w-pr-42");
+        assert!(script_name("w", "///").is_err());
+
+        let long = script_name("w", &"x".repeat(100)).unwrap();
+        assert_eq!(long.len(), MAX_SCRIPT_NAME_LEN);
+    }
+}
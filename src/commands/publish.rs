@@ -1,54 +1,379 @@
 use std::env;
 use std::path::Path;
+use std::thread;
 
 use anyhow::Result;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::HumanBytes;
+use reqwest::blocking::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
-use crate::build::build_target;
-use crate::deploy::{self, DeploymentSet};
+use crate::build::{build_target, build_target_without_hooks, hooks};
+use crate::commands::{migrations, queue, r2, summary};
+use crate::deploy::bindings::{self, BindingsDiff};
+use crate::deploy::rollback::{self, Snapshot};
+use crate::deploy::{self, lock, DeployTarget, DeploymentSet};
 use crate::http::{self, Feature};
 use crate::kv::bulk;
 use crate::settings::global_user::GlobalUser;
-use crate::settings::toml::Target;
-use crate::sites;
-use crate::terminal::emoji;
+use crate::settings::toml::{lint_patterns, HealthCheck, Target};
+use crate::sites::{self, AssetManifest};
+use crate::terminal::events::{self, Event};
 use crate::terminal::message::{Message, Output, StdErr, StdOut};
-use crate::upload;
+use crate::terminal::progress::Progress;
+use crate::terminal::table::{Format, Table, TableOptions};
+use crate::terminal::{emoji, github_actions, styles};
+use crate::upload::{self, fingerprint};
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct PublishOutput {
     pub success: bool,
     pub name: String,
+    pub environment: Option<String>,
+    /// The version of the script that was deployed, as reported by the API.
+    pub deployment_id: Option<String>,
     pub urls: Vec<String>,
+    pub workers_dev: Option<String>,
+    pub routes: Vec<String>,
     pub schedules: Vec<String>,
+    /// Size in bytes of the uploaded script, when wrangler can tell.
+    pub script_size: Option<u64>,
+    /// Bindings added or removed since the previous deployment, when the API lists them.
+    pub bindings: Option<BindingsDiff>,
+    /// True when `--skip-unchanged` found nothing new to upload.
+    pub unchanged: bool,
+    /// What the success message lists, routes with what happened to them.
+    #[serde(skip)]
+    pub published_to: Vec<String>,
+}
+
+/// What a publish changes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PublishMode {
+    /// Builds and uploads the code with its settings, then deploys it to its routes, workers.dev
+    /// and cron triggers.
+    Full,
+    /// Updates bindings, vars and compatibility settings and deploys routes and cron
+    /// triggers, without building or uploading the code.
+    SettingsOnly,
+    /// Builds and uploads the code with its settings, leaving routes, workers.dev and cron
+    /// triggers as they are.
+    CodeOnly,
+}
+
+/// One environment to publish with `wrangler publish --envs`.
+pub struct EnvironmentTarget {
+    pub environment: String,
+    pub target: Target,
+    pub deployments: DeploymentSet,
 }
 
 pub fn publish(
     user: &GlobalUser,
     target: &mut Target,
+    environment: Option<&str>,
     deployments: DeploymentSet,
     out: Output,
+    skip_unchanged: bool,
+    accept_data_loss: bool,
+    mode: PublishMode,
 ) -> Result<()> {
     validate_target_required_fields_present(target)?;
+    lint_routes(&deployments);
+    summary::print("Publishing", target, environment, &deployments);
+    migrations::confirm_data_loss(user, target, accept_data_loss)?;
+    let _lock = lock::acquire(user, target)?;
+
+    // Build the script before uploading and log build result
+    if mode != PublishMode::SettingsOnly {
+        build(target)?;
+    }
+
+    events::emit(Event::PublishStarted {
+        script: &target.name,
+        environment,
+    });
+    let output = upload_and_deploy(
+        user,
+        target,
+        environment,
+        &deployments,
+        skip_unchanged,
+        mode,
+    );
+    events::emit(Event::PublishFinished {
+        script: &target.name,
+        environment,
+        success: output.is_ok(),
+    });
+    let output = output?;
+    build_output_message(output, out);
+
+    Ok(())
+}
+
+/// Publishes several environments at once. Environments that share a build are built once and
+/// then uploaded and deployed concurrently. Different builds run one after the other, each
+/// followed by its own uploads, since they can write to the same output directory. Everything
+/// is reported together in a table.
+pub fn publish_environments(
+    user: &GlobalUser,
+    environments: Vec<EnvironmentTarget>,
+    out: Output,
+    skip_unchanged: bool,
+    accept_data_loss: bool,
+    mode: PublishMode,
+) -> Result<()> {
+    // held until every environment is published
+    let mut locks = Vec::new();
+    for env in &environments {
+        validate_target_required_fields_present(&env.target)?;
+        lint_routes(&env.deployments);
+        summary::print(
+            "Publishing",
+            &env.target,
+            Some(&env.environment),
+            &env.deployments,
+        );
+        migrations::confirm_data_loss(user, &env.target, accept_data_loss)?;
+        locks.push(lock::acquire(user, &env.target)?);
+    }
 
-    let run_deploy = |target: &Target| match deploy::deploy(&user, &deployments) {
-        Ok(results) => {
-            build_output_message(results, target.name.clone(), out);
-            Ok(())
+    let mut groups: Vec<Vec<EnvironmentTarget>> = Vec::new();
+    for env in environments {
+        match groups
+            .iter_mut()
+            .find(|group| same_build(&group[0].target, &env.target))
+        {
+            Some(group) => group.push(env),
+            None => groups.push(vec![env]),
         }
-        Err(e) => Err(e),
-    };
+    }
 
-    // Build the script before uploading and log build result
-    let build_result = build_target(&target);
-    match build_result {
-        Ok(msg) => {
+    let mut published = Vec::new();
+    for group in groups {
+        if mode != PublishMode::SettingsOnly {
+            let msg = build_target_without_hooks(&group[0].target)?;
             StdErr::success(&msg);
-            Ok(())
         }
-        Err(e) => Err(e),
-    }?;
+
+        let handles = group
+            .into_iter()
+            .map(|env| {
+                let user = user.clone();
+                thread::spawn(move || {
+                    let EnvironmentTarget {
+                        environment,
+                        mut target,
+                        deployments,
+                    } = env;
+                    events::emit(Event::PublishStarted {
+                        script: &target.name,
+                        environment: Some(&environment),
+                    });
+                    let result = upload_and_deploy(
+                        &user,
+                        &mut target,
+                        Some(&environment),
+                        &deployments,
+                        skip_unchanged,
+                        mode,
+                    );
+                    events::emit(Event::PublishFinished {
+                        script: &target.name,
+                        environment: Some(&environment),
+                        success: result.is_ok(),
+                    });
+                    (environment, target, result)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            match handle.join() {
+                Ok(joined) => published.push(joined),
+                Err(_) => anyhow::bail!("publishing an environment panicked"),
+            }
+        }
+    }
+
+    let mut rows = Vec::new();
+    let mut failed = 0;
+    for (environment, target, result) in published {
+        let row = match result {
+            Ok(output) => {
+                StdErr::success(&format!("Published {}", environment));
+                // the shared build skipped them, as each environment uploads under its own name
+                if mode != PublishMode::SettingsOnly {
+                    if let Err(e) = hooks::run_post_build_hooks(&target) {
+                        StdErr::warn(&format!(
+                            "Post-build hooks for {} failed: {}",
+                            environment, e
+                        ));
+                    }
+                }
+                let status = if output.unchanged {
+                    "unchanged"
+                } else {
+                    "published"
+                };
+                json!({
+                    "environment": environment,
+                    "status": status,
+                    "urls": output.urls.join(" "),
+                    "deployment_id": output.deployment_id,
+                    "error": null,
+                })
+            }
+            Err(e) => {
+                failed += 1;
+                StdErr::user_error(&format!("Failed to publish {}: {}", environment, e));
+                json!({
+                    "environment": environment,
+                    "status": "failed",
+                    "urls": "",
+                    "deployment_id": null,
+                    "error": e.to_string(),
+                })
+            }
+        };
+        rows.push(row);
+    }
+
+    let total = rows.len();
+    let columns = ["environment", "status", "urls", "deployment_id", "error"];
+    let options = TableOptions {
+        format: if out == Output::Json {
+            Format::Json
+        } else {
+            Format::Auto
+        },
+        ..TableOptions::default()
+    };
+    Table::new(&columns, rows).print(&options)?;
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} environments failed to publish", failed, total);
+    }
+    Ok(())
+}
+
+fn build(target: &Target) -> Result<()> {
+    let msg = build_target(target)?;
+    StdErr::success(&msg);
+    Ok(())
+}
+
+// Environments that only differ in bindings, routes or vars share a build
+fn same_build(a: &Target, b: &Target) -> bool {
+    a.target_type == b.target_type
+        && a.build == b.build
+        && a.main == b.main
+        && a.webpack_config == b.webpack_config
+        && a.site == b.site
+}
+
+// Warns about route patterns that won't match what they're meant to before they're attached
+fn lint_routes(deployments: &[DeployTarget]) {
+    for deployment in deployments {
+        if let DeployTarget::Zoned(zoned) = deployment {
+            let patterns: Vec<String> = zoned.routes.iter().map(|r| r.pattern.clone()).collect();
+            for warning in lint_patterns(&patterns) {
+                StdOut::warn(&warning);
+            }
+        }
+    }
+}
+
+// Uploads a built target, including its site's files, and deploys it to its routes and
+// workers.dev, or whichever part of that `mode` asks for. Returns what was published for the
+// caller to report.
+fn upload_and_deploy(
+    user: &GlobalUser,
+    target: &mut Target,
+    environment: Option<&str>,
+    deployments: &DeploymentSet,
+    skip_unchanged: bool,
+    mode: PublishMode,
+) -> Result<PublishOutput> {
+    let previous_bindings = bindings::fetch(user, target);
+
+    let health_check = target
+        .deploy
+        .as_ref()
+        .and_then(|deploy| deploy.health_check.clone());
+    let snapshot = match &health_check {
+        Some(_) => rollback::snapshot(user, target)?,
+        None => None,
+    };
+
+    let run_deploy = |target: &Target, uploaded: upload::UploadedScript| -> Result<PublishOutput> {
+        let results = if mode == PublishMode::CodeOnly {
+            StdErr::info("Leaving routes, workers.dev and cron triggers as they are");
+            code_only_results(deployments)
+        } else {
+            let results = deploy::deploy(&user, deployments)?;
+            r2::notification::apply(user, target)?;
+            queue::apply(user, target)?;
+            results
+        };
+
+        let bindings = match (&previous_bindings, bindings::fetch(user, target)) {
+            (Some(before), Some(after)) => Some(bindings::diff(before, &after)),
+            _ => None,
+        };
+        let script_size = hooks::BuildArtifacts::collect(target)
+            .ok()
+            .map(|artifacts| artifacts.script_size());
+
+        let deploy::DeployResults {
+            urls,
+            workers_dev,
+            routes,
+            schedules,
+            published_to,
+        } = results;
+
+        if let Some(health_check) = &health_check {
+            check_health(
+                user,
+                target,
+                health_check,
+                &urls,
+                snapshot,
+                uploaded.unchanged,
+            )?;
+        }
+
+        Ok(PublishOutput {
+            success: true,
+            name: target.name.clone(),
+            environment: environment.map(|env| env.to_string()),
+            deployment_id: uploaded.etag,
+            urls,
+            workers_dev,
+            routes,
+            schedules,
+            script_size,
+            bindings,
+            unchanged: uploaded.unchanged,
+            published_to,
+        })
+    };
+
+    if mode == PublishMode::SettingsOnly {
+        // secrets and the bindings that come with the code are kept by listing them too
+        let deployed = previous_bindings.clone().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not fetch the bindings of {}, so --settings-only could drop its secrets",
+                target.name
+            )
+        })?;
+        let client = http::legacy_auth_client(user);
+        upload::settings(&client, target, &deployed)?;
+        upload::tags(&client, target)?;
+        return run_deploy(target, upload::UploadedScript::default());
+    }
 
     // We verify early here, so we don't perform pre-upload tasks if the upload will fail
     if let Some(build_config) = &target.build {
@@ -66,90 +391,241 @@ pub fn publish(
 
         // First, upload all existing files in bucket directory
         StdErr::working("Uploading site files");
-        let upload_progress_bar = if to_upload.len() > bulk::BATCH_KEY_MAX {
-            let upload_progress_bar = ProgressBar::new(to_upload.len() as u64);
-            upload_progress_bar
-                .set_style(ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"));
-            Some(upload_progress_bar)
-        } else {
-            None
-        };
+        let upload_progress = Progress::new(
+            "Uploading site files",
+            "files",
+            to_upload.len() as u64,
+            bulk::pairs_size(&to_upload),
+        );
 
-        bulk::put(
+        let uploaded = bulk::put(
             target,
             user,
             &site_namespace.id,
             to_upload,
-            &upload_progress_bar,
-        )?;
-
-        if let Some(pb) = upload_progress_bar {
-            pb.finish_with_message("Done Uploading");
+            Some(&upload_progress),
+        );
+        if uploaded.is_err() {
+            // files are stored under their content hash, and `sites::sync` skips the ones
+            // already there
+            StdErr::info("The site files that were uploaded are kept, publishing again only uploads the rest");
         }
+        uploaded?;
+
+        upload_progress.finish();
 
         let upload_client = http::featured_legacy_auth_client(user, Feature::Sites);
 
         // Next, upload and deploy the worker with the updated asset_manifest
-        upload::script(&upload_client, &target, Some(asset_manifest))?;
+        let uploaded = upload_script(
+            &upload_client,
+            user,
+            target,
+            Some(asset_manifest),
+            skip_unchanged,
+        )?;
 
-        run_deploy(target)?;
+        let output = run_deploy(target, uploaded)?;
 
         // Finally, remove any stale files
         if !to_delete.is_empty() {
             StdErr::info("Deleting stale files...");
 
-            let delete_progress_bar = if to_delete.len() > bulk::BATCH_KEY_MAX {
-                let delete_progress_bar = ProgressBar::new(to_delete.len() as u64);
-                delete_progress_bar.set_style(
-                    ProgressStyle::default_bar().template("{wide_bar} {pos}/{len}\n{msg}"),
-                );
-                Some(delete_progress_bar)
-            } else {
-                None
-            };
+            let delete_progress =
+                Progress::new("Deleting stale files", "files", to_delete.len() as u64, 0);
 
             bulk::delete(
                 target,
                 user,
                 &site_namespace.id,
                 to_delete,
-                &delete_progress_bar,
+                Some(&delete_progress),
             )?;
 
-            if let Some(pb) = delete_progress_bar {
-                pb.finish_with_message("Done deleting");
-            }
+            delete_progress.finish();
         }
+        Ok(output)
     } else {
         let upload_client = http::legacy_auth_client(user);
 
-        upload::script(&upload_client, &target, None)?;
-        run_deploy(target)?;
+        let uploaded = upload_script(&upload_client, user, target, None, skip_unchanged)?;
+        run_deploy(target, uploaded)
     }
+}
 
-    Ok(())
+// What `--code-only` leaves deployed: the routes can still be health checked, workers.dev
+// would need the API to look up the subdomain
+fn code_only_results(deployments: &DeploymentSet) -> deploy::DeployResults {
+    let mut results = deploy::DeployResults::default();
+    for deployment in deployments {
+        if let DeployTarget::Zoned(zoned) = deployment {
+            for route in &zoned.routes {
+                results.add_route(route.pattern.clone());
+            }
+        }
+    }
+    results
 }
 
-fn build_output_message(deploy_results: deploy::DeployResults, target_name: String, out: Output) {
-    let deploy::DeployResults { urls, schedules } = deploy_results;
+// Probes the health check of a fresh deployment. If it fails, the script from before the publish
+// is uploaded again and the publish fails, whether or not the rollback worked.
+fn check_health(
+    user: &GlobalUser,
+    target: &Target,
+    health_check: &HealthCheck,
+    urls: &[String],
+    snapshot: Option<Snapshot>,
+    unchanged: bool,
+) -> Result<()> {
+    let error = match deploy::health::check(health_check, urls) {
+        Ok(()) => return Ok(()),
+        Err(e) => e,
+    };
+
+    match snapshot {
+        _ if unchanged => {
+            StdErr::warn("The script wasn't uploaded, so there's nothing to roll back")
+        }
+        Some(snapshot) => {
+            if let Err(e) = rollback::restore(user, target, snapshot) {
+                StdErr::user_error(&e.to_string());
+            }
+        }
+        None => StdErr::warn(&format!(
+            "{} wasn't deployed before, so there's nothing to roll back to",
+            target.name
+        )),
+    }
+    Err(error)
+}
+
+// Uploads the script unless `skip_unchanged` is set and it's identical to the last upload
+// from this machine, which is still the deployed version. Routes and schedules are deployed
+// either way since they're cheap to re-apply.
+fn upload_script(
+    client: &Client,
+    user: &GlobalUser,
+    target: &Target,
+    asset_manifest: Option<AssetManifest>,
+    skip_unchanged: bool,
+) -> Result<upload::UploadedScript> {
+    let fingerprint = fingerprint::compute(target, asset_manifest.as_ref());
+    if let Err(e) = &fingerprint {
+        log::debug!("could not fingerprint the upload: {}", e);
+    }
+
+    if let (true, Ok(fingerprint)) = (skip_unchanged, &fingerprint) {
+        match fingerprint::last_upload(target, user) {
+            Some(last) if &last.fingerprint == fingerprint => {
+                StdErr::info(&format!(
+                    "{} is unchanged since it was last published, skipping the upload",
+                    target.name
+                ));
+                upload::tags(client, target)?;
+                return Ok(upload::UploadedScript {
+                    etag: last.etag,
+                    modified_on: None,
+                    unchanged: true,
+                });
+            }
+            _ => log::info!("script changed since the last publish, uploading"),
+        }
+    }
+
+    let uploaded = upload::script(client, target, asset_manifest)?;
+    upload::tags(client, target)?;
+    if let Ok(fingerprint) = fingerprint {
+        let last = fingerprint::Upload {
+            fingerprint,
+            etag: uploaded.etag.clone(),
+        };
+        if let Err(e) = fingerprint::save(target, last) {
+            log::debug!("could not record the upload: {}", e);
+        }
+    }
+    Ok(uploaded)
+}
+
+fn build_output_message(output: PublishOutput, out: Output) {
+    // steps after this one want something they can request, not a route pattern
+    let urls: Vec<String> = output.urls.iter().map(|url| deploy::url_of(url)).collect();
+    let outputs = [
+        (
+            "deployment-id",
+            output.deployment_id.clone().unwrap_or_default(),
+        ),
+        ("url", urls.first().cloned().unwrap_or_default()),
+        ("urls", urls.join("\n")),
+    ];
+    let outputs: Vec<(&str, &str)> = outputs
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+    if let Err(e) = github_actions::set_outputs(&outputs) {
+        log::debug!("could not write the step outputs: {}", e);
+    }
 
     let mut msg = "Successfully published your script ".to_owned();
-    if !urls.is_empty() {
-        msg.push_str(&format!("to\n {}\n", urls.join("\n ")));
+    if !output.published_to.is_empty() {
+        msg.push_str(&format!("to\n {}\n", output.published_to.join("\n ")));
     }
-    if !schedules.is_empty() {
-        msg.push_str(&format!("with this schedule\n {}\n", schedules.join("\n ")));
+    if !output.schedules.is_empty() {
+        msg.push_str(&format!(
+            "with this schedule\n {}\n",
+            output.schedules.join("\n ")
+        ));
     }
 
     StdErr::success(&msg);
     if out == Output::Json {
-        StdOut::as_json(&PublishOutput {
-            success: true,
-            name: target_name,
-            urls,
-            schedules,
-        });
+        StdOut::as_json(&output);
+    } else {
+        StdErr::message(&summary(&output));
+    }
+}
+
+fn summary(output: &PublishOutput) -> String {
+    let mut lines = vec![("Script", output.name.clone())];
+    if let Some(environment) = &output.environment {
+        lines.push(("Environment", environment.clone()));
+    }
+    if let Some(workers_dev) = &output.workers_dev {
+        lines.push(("workers.dev", styles::url(workers_dev).to_string()));
+    }
+    if !output.routes.is_empty() {
+        lines.push(("Routes", output.routes.join(", ")));
     }
+    if let Some(size) = output.script_size {
+        lines.push(("Size", HumanBytes(size).to_string()));
+    }
+    if let Some(bindings) = &output.bindings {
+        let changes = if bindings.is_empty() {
+            "unchanged".to_string()
+        } else {
+            let added = bindings
+                .added
+                .iter()
+                .map(|b| format!("+ {} ({})", b.name, b.binding_type));
+            let removed = bindings
+                .removed
+                .iter()
+                .map(|b| format!("- {} ({})", b.name, b.binding_type));
+            added.chain(removed).collect::<Vec<_>>().join(", ")
+        };
+        lines.push(("Bindings", changes));
+    }
+    if output.unchanged {
+        lines.push(("Upload", "skipped, script unchanged".to_string()));
+    }
+    if let Some(deployment_id) = &output.deployment_id {
+        lines.push(("Deployment", deployment_id.clone()));
+    }
+
+    lines
+        .into_iter()
+        .map(|(label, value)| format!(" {:<12} {}", format!("{}:", label), value))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 // We don't want folks setting their bucket to the top level directory,
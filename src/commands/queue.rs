@@ -0,0 +1,157 @@
+use anyhow::Result;
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{QueueConsumer, Target};
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+#[derive(Deserialize)]
+struct Queue {
+    queue_id: String,
+    queue_name: String,
+}
+
+#[derive(Deserialize)]
+struct Consumer {
+    consumer_id: String,
+    script_name: Option<String>,
+}
+
+/// The id of the queue named `name`, which the Queues API addresses queues by.
+pub(crate) fn id(client: &Client, account_id: &str, name: &str) -> Result<Option<String>> {
+    let queues = deployed::get::<Vec<Queue>>(client, &queues_addr(account_id))?;
+    Ok(queues
+        .unwrap_or_default()
+        .into_iter()
+        .find(|queue| queue.queue_name == name)
+        .map(|queue| queue.queue_id))
+}
+
+/// Sets up the `[[queues.consumers]]` of the configuration file when publishing, making sure
+/// their dead letter queues exist first.
+pub fn apply(user: &GlobalUser, target: &Target) -> Result<()> {
+    if target.queue_consumers.is_empty() {
+        return Ok(());
+    }
+    for consumer in &target.queue_consumers {
+        consumer.validate()?;
+    }
+
+    let client = http::legacy_auth_client(user);
+    let account_id = target.account_id.load()?;
+    for consumer in &target.queue_consumers {
+        if let Some(dead_letter_queue) = &consumer.dead_letter_queue {
+            ensure_exists(&client, account_id, dead_letter_queue)?;
+        }
+        let queue_id = id(&client, account_id, &consumer.queue)?.ok_or_else(|| {
+            anyhow::anyhow!("There's no queue named {} on the account", consumer.queue)
+        })?;
+
+        StdErr::working(&format!("Setting up the consumer of {}", consumer.queue));
+        let addr = consumers_addr(account_id, &queue_id);
+        let body = consumer_body(&target.name, consumer);
+        let existing = deployed::get::<Vec<Consumer>>(&client, &addr)?
+            .unwrap_or_default()
+            .into_iter()
+            .find(|existing| existing.script_name.as_deref() == Some(target.name.as_str()));
+        let request = match existing {
+            Some(existing) => client
+                .put(&format!("{}/{}", addr, existing.consumer_id))
+                .json(&body),
+            None => client.post(&addr).json(&body),
+        };
+        let res = http::trace::send(&client, request)?;
+        if !res.status().is_success() {
+            anyhow::bail!(crate::format_api_errors(res.text()?));
+        }
+        StdOut::success(&format!("{} consumes {}", target.name, consumer.queue));
+    }
+    Ok(())
+}
+
+// Messages sent to a dead letter queue that doesn't exist are lost, so it has to exist before
+// the consumer is pointed at it
+fn ensure_exists(client: &Client, account_id: &str, name: &str) -> Result<()> {
+    if id(client, account_id, name)?.is_some() {
+        return Ok(());
+    }
+    if !interactive::confirm(&format!(
+        "The dead letter queue {} doesn't exist. Create it?",
+        name
+    ))? {
+        anyhow::bail!(
+            "The dead letter queue {} doesn't exist, create it or remove dead_letter_queue",
+            name
+        );
+    }
+    let res = http::trace::send(
+        client,
+        client
+            .post(&queues_addr(account_id))
+            .json(&json!({ "queue_name": name })),
+    )?;
+    if !res.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(res.text()?));
+    }
+    StdOut::success(&format!("Created the queue {}", name));
+    Ok(())
+}
+
+fn consumer_body(script_name: &str, consumer: &QueueConsumer) -> serde_json::Value {
+    let mut settings = serde_json::Map::new();
+    if let Some(max_retries) = consumer.max_retries {
+        settings.insert("max_retries".to_string(), json!(max_retries));
+    }
+    if let Some(retry_delay) = consumer.retry_delay {
+        settings.insert("retry_delay".to_string(), json!(retry_delay));
+    }
+    let mut body = json!({
+        "type": "worker",
+        "script_name": script_name,
+        "settings": settings,
+    });
+    if let Some(dead_letter_queue) = &consumer.dead_letter_queue {
+        body["dead_letter_queue"] = json!(dead_letter_queue);
+    }
+    body
+}
+
+fn queues_addr(account_id: &str) -> String {
+    format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/queues",
+        account_id
+    )
+}
+
+fn consumers_addr(account_id: &str, queue_id: &str) -> String {
+    format!("{}/{}/consumers", queues_addr(account_id), queue_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_configured_settings_are_sent() {
+        let consumer = QueueConsumer {
+            queue: "jobs".to_string(),
+            dead_letter_queue: Some("failed-jobs".to_string()),
+            max_retries: Some(5),
+            retry_delay: None,
+        };
+        assert_eq!(
+            consumer_body("worker", &consumer),
+            json!({
+                "type": "worker",
+                "script_name": "worker",
+                "dead_letter_queue": "failed-jobs",
+                "settings": { "max_retries": 5 },
+            })
+        );
+    }
+}
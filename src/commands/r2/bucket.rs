@@ -0,0 +1,177 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::terminal::interactive;
+use crate::terminal::message::{self, Message, Output, StdOut};
+use crate::terminal::table::{Table, TableOptions};
+
+/// Where a bucket should keep its data. It's a hint, R2 picks the closest location it has.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocationHint {
+    Wnam,
+    Enam,
+    Weur,
+    Eeur,
+    Apac,
+    Oc,
+}
+
+impl LocationHint {
+    pub const VARIANTS: &'static [&'static str] = &["wnam", "enam", "weur", "eeur", "apac", "oc"];
+}
+
+impl fmt::Display for LocationHint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hint = match self {
+            LocationHint::Wnam => "wnam",
+            LocationHint::Enam => "enam",
+            LocationHint::Weur => "weur",
+            LocationHint::Eeur => "eeur",
+            LocationHint::Apac => "apac",
+            LocationHint::Oc => "oc",
+        };
+        f.write_str(hint)
+    }
+}
+
+impl FromStr for LocationHint {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "wnam" => Ok(LocationHint::Wnam),
+            "enam" => Ok(LocationHint::Enam),
+            "weur" => Ok(LocationHint::Weur),
+            "eeur" => Ok(LocationHint::Eeur),
+            "apac" => Ok(LocationHint::Apac),
+            "oc" => Ok(LocationHint::Oc),
+            _ => anyhow::bail!(
+                "{} isn't a location hint, use one of {}",
+                s,
+                LocationHint::VARIANTS.join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Bucket {
+    name: String,
+    creation_date: Option<String>,
+    location: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Buckets {
+    #[serde(default)]
+    buckets: Vec<Bucket>,
+}
+
+pub fn create(
+    user: &GlobalUser,
+    account_id: &str,
+    name: &str,
+    location: Option<LocationHint>,
+) -> Result<()> {
+    validate_name(name)?;
+    StdOut::working(&format!("Creating bucket {}", name));
+    let client = http::legacy_auth_client(user);
+    let mut body = json!({ "name": name });
+    if let Some(location) = location {
+        body["locationHint"] = json!(location);
+    }
+    let res = http::trace::send(&client, client.post(&buckets_addr(account_id)).json(&body))?;
+    if !res.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(res.text()?));
+    }
+
+    if message::output() == Output::Json {
+        StdOut::as_json(&json!({ "name": name, "location_hint": location }));
+        return Ok(());
+    }
+    StdOut::success(&format!("Created bucket {}", name));
+    StdOut::message(&format!(
+        "Bind it to your worker in wrangler.toml with\nr2_buckets = [{{ binding = \"{}\", bucket_name = \"{}\" }}]",
+        binding_suggestion(name),
+        name
+    ));
+    Ok(())
+}
+
+pub fn list(user: &GlobalUser, account_id: &str, options: &TableOptions) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let buckets = deployed::get::<Buckets>(&client, &buckets_addr(account_id))?
+        .map(|buckets| buckets.buckets)
+        .unwrap_or_default();
+    Table::from_serialize(&["name", "creation_date", "location"], &buckets)?.print(options)
+}
+
+pub fn delete(user: &GlobalUser, account_id: &str, name: &str) -> Result<()> {
+    if !interactive::confirm(&format!("Are you sure you want to delete bucket {}?", name))? {
+        StdOut::info(&format!("Not deleting bucket {}", name));
+        return Ok(());
+    }
+
+    StdOut::working(&format!("Deleting bucket {}", name));
+    let client = http::legacy_auth_client(user);
+    let addr = format!("{}/{}", buckets_addr(account_id), name);
+    let res = http::trace::send(&client, client.delete(&addr))?;
+    if !res.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(res.text()?));
+    }
+    StdOut::success(&format!("Deleted bucket {}", name));
+    StdOut::warn("Make sure to remove its r2_buckets entry from your configuration file!");
+    Ok(())
+}
+
+fn buckets_addr(account_id: &str) -> String {
+    format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/r2/buckets",
+        account_id
+    )
+}
+
+// R2 turns these down with an error that doesn't say why
+fn validate_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if !(3..=63).contains(&name.len())
+        || !valid_chars
+        || name.starts_with('-')
+        || name.ends_with('-')
+    {
+        anyhow::bail!(
+            "{} isn't a valid bucket name: use 3 to 63 lowercase letters, digits and dashes, starting and ending with a letter or digit",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn binding_suggestion(name: &str) -> String {
+    name.replace('-', "_").to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_names_are_checked() {
+        assert!(validate_name("user-uploads").is_ok());
+        assert!(validate_name("ab").is_err());
+        assert!(validate_name("User-Uploads").is_err());
+        assert!(validate_name("uploads-").is_err());
+        assert!(validate_name("user_uploads").is_err());
+        assert_eq!(binding_suggestion("user-uploads"), "USER_UPLOADS");
+    }
+}
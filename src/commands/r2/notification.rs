@@ -0,0 +1,242 @@
+use anyhow::Result;
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::commands::queue;
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{R2Notification, Target};
+use crate::terminal::message::{Message, StdErr, StdOut};
+use crate::terminal::table::{Table, TableOptions};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BucketNotifications {
+    #[serde(default)]
+    queues: Vec<QueueNotifications>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct QueueNotifications {
+    queue_name: String,
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Rule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    suffix: Option<String>,
+    actions: Vec<String>,
+}
+
+/// Sends the events of a bucket to a queue, replacing whatever the bucket sent to it before.
+pub fn create(user: &GlobalUser, account_id: &str, notification: &R2Notification) -> Result<()> {
+    put_rules(
+        user,
+        account_id,
+        &notification.bucket,
+        &notification.queue,
+        vec![rule(notification)],
+    )
+}
+
+// The rules of a bucket and queue are replaced all at once, so every rule for the pair has to
+// be sent together
+fn put_rules(
+    user: &GlobalUser,
+    account_id: &str,
+    bucket: &str,
+    queue: &str,
+    rules: Vec<Rule>,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let queue_id = queue_id(&client, account_id, queue)?;
+    send(
+        &client,
+        client
+            .put(&queue_addr(account_id, bucket, &queue_id))
+            .json(&json!({ "rules": rules })),
+    )?;
+    StdOut::success(&format!(
+        "Events of {} now go to the queue {}",
+        bucket, queue
+    ));
+    Ok(())
+}
+
+pub fn list(
+    user: &GlobalUser,
+    account_id: &str,
+    bucket: &str,
+    options: &TableOptions,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/event_notifications/r2/{}/configuration",
+        account_id, bucket
+    );
+    let notifications = deployed::get::<BucketNotifications>(&client, &addr)?
+        .map(|notifications| notifications.queues)
+        .unwrap_or_default();
+
+    let mut rows = Vec::new();
+    for queue in &notifications {
+        for rule in &queue.rules {
+            rows.push(json!({
+                "queue": queue.queue_name,
+                "actions": rule.actions,
+                "prefix": rule.prefix,
+                "suffix": rule.suffix,
+            }));
+        }
+    }
+    Table::new(&["queue", "actions", "prefix", "suffix"], rows).print(options)
+}
+
+/// Stops sending the events of a bucket to a queue.
+pub fn delete(user: &GlobalUser, account_id: &str, bucket: &str, queue: &str) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let queue_id = queue_id(&client, account_id, queue)?;
+    send(
+        &client,
+        client.delete(&queue_addr(account_id, bucket, &queue_id)),
+    )?;
+    StdOut::success(&format!(
+        "Events of {} don't go to the queue {} anymore",
+        bucket, queue
+    ));
+    Ok(())
+}
+
+/// Sets up the `[[r2_notifications]]` of the configuration file when publishing.
+pub fn apply(user: &GlobalUser, target: &Target) -> Result<()> {
+    for notification in &target.r2_notifications {
+        StdErr::working(&format!(
+            "Sending the {} events of {} to {}",
+            notification
+                .event_types
+                .iter()
+                .map(|event| event.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            notification.bucket,
+            notification.queue
+        ));
+    }
+    for ((bucket, queue), rules) in by_bucket_and_queue(&target.r2_notifications) {
+        put_rules(user, target.account_id.load()?, bucket, queue, rules)?;
+    }
+    Ok(())
+}
+
+// The rules of each bucket and queue, in the order they're first configured
+fn by_bucket_and_queue(notifications: &[R2Notification]) -> Vec<((&str, &str), Vec<Rule>)> {
+    let mut grouped: Vec<((&str, &str), Vec<Rule>)> = Vec::new();
+    for notification in notifications {
+        let pair = (notification.bucket.as_str(), notification.queue.as_str());
+        match grouped.iter_mut().find(|(p, _)| *p == pair) {
+            Some((_, rules)) => rules.push(rule(notification)),
+            None => grouped.push((pair, vec![rule(notification)])),
+        }
+    }
+    grouped
+}
+
+fn rule(notification: &R2Notification) -> Rule {
+    let mut actions: Vec<String> = Vec::new();
+    for event in &notification.event_types {
+        for action in event.actions() {
+            if !actions.iter().any(|a| a == action) {
+                actions.push(action.to_string());
+            }
+        }
+    }
+    Rule {
+        prefix: notification.prefix.clone(),
+        suffix: notification.suffix.clone(),
+        actions,
+    }
+}
+
+// Notifications are addressed by queue id, which users rarely know
+fn queue_id(client: &Client, account_id: &str, name: &str) -> Result<String> {
+    queue::id(client, account_id, name)?
+        .ok_or_else(|| anyhow::anyhow!("There's no queue named {} on the account", name))
+}
+
+fn queue_addr(account_id: &str, bucket: &str, queue_id: &str) -> String {
+    format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/event_notifications/r2/{}/configuration/queues/{}",
+        account_id, bucket, queue_id
+    )
+}
+
+fn send(client: &Client, request: RequestBuilder) -> Result<()> {
+    let res = http::trace::send(client, request)?;
+    if !res.status().is_success() {
+        anyhow::bail!(crate::format_api_errors(res.text()?));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::R2EventType;
+
+    fn notification(bucket: &str, queue: &str, prefix: &str) -> R2Notification {
+        R2Notification {
+            bucket: bucket.to_string(),
+            queue: queue.to_string(),
+            event_types: vec![R2EventType::ObjectDelete],
+            prefix: Some(prefix.to_string()),
+            suffix: None,
+        }
+    }
+
+    #[test]
+    fn rules_for_the_same_bucket_and_queue_are_sent_together() {
+        let grouped = by_bucket_and_queue(&[
+            notification("uploads", "thumbnails", "images/"),
+            notification("uploads", "audit", "images/"),
+            notification("uploads", "thumbnails", "videos/"),
+        ]);
+
+        let pairs: Vec<(&str, &str)> = grouped.iter().map(|(pair, _)| *pair).collect();
+        assert_eq!(pairs, vec![("uploads", "thumbnails"), ("uploads", "audit")]);
+        let prefixes: Vec<Option<&str>> = grouped[0]
+            .1
+            .iter()
+            .map(|rule| rule.prefix.as_deref())
+            .collect();
+        assert_eq!(prefixes, vec![Some("images/"), Some("videos/")]);
+    }
+
+    #[test]
+    fn event_types_become_actions_once() {
+        let notification = R2Notification {
+            bucket: "uploads".to_string(),
+            queue: "thumbnails".to_string(),
+            event_types: vec![R2EventType::ObjectCreate, R2EventType::ObjectCreate],
+            prefix: Some("images/".to_string()),
+            suffix: None,
+        };
+        assert_eq!(
+            rule(&notification),
+            Rule {
+                prefix: Some("images/".to_string()),
+                suffix: None,
+                actions: vec![
+                    "PutObject".to_string(),
+                    "CopyObject".to_string(),
+                    "CompleteMultipartUpload".to_string()
+                ],
+            }
+        );
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use indicatif::HumanBytes;
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use reqwest::blocking::{Body, Client};
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash64;
+
+use crate::cache;
+use crate::commands::pages::upload::content_type;
+use crate::http;
+use crate::parallel;
+use crate::paths;
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::global_user::GlobalUser;
+use crate::sites::hash_cache::FileStamp;
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+// Object keys keep their slashes in the URL
+const KEY_ASCII_SET: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'?')
+    .add(b'+')
+    .add(b'&');
+
+/// The largest object the API takes in a single PUT.
+const MAX_PUT_SIZE: u64 = 300 * 1024 * 1024;
+
+pub struct SyncOptions {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub delete: bool,
+    pub concurrency: usize,
+}
+
+#[derive(Deserialize)]
+struct ObjectsPage {
+    result: Vec<Object>,
+    result_info: Option<Cursor>,
+}
+
+#[derive(Deserialize)]
+struct Object {
+    key: String,
+    etag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Cursor {
+    cursor: Option<String>,
+    #[serde(default)]
+    is_truncated: bool,
+}
+
+/// A file to sync, the key it's uploaded to and the MD5 of its contents.
+#[derive(Clone, Debug, PartialEq)]
+struct LocalFile {
+    path: PathBuf,
+    key: String,
+    md5: String,
+    size: u64,
+}
+
+/// The MD5 of every file synced before, kept in `~/.wrangler/cache` so files that didn't
+/// change since don't have to be read and hashed again. Only an optimization: whether a file
+/// is uploaded is decided by the etags in the bucket.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct SyncState {
+    files: HashMap<String, Hashed>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Hashed {
+    stamp: FileStamp,
+    md5: String,
+}
+
+/// Uploads the files of `dir` that changed to `destination`, a bucket optionally followed by a
+/// `/prefix`, and with `delete` removes the objects under the prefix that aren't in `dir`.
+pub fn sync(
+    user: &GlobalUser,
+    account_id: &str,
+    dir: &Path,
+    destination: &str,
+    options: &SyncOptions,
+) -> Result<()> {
+    let (bucket, prefix) = split_destination(destination)?;
+    let filter = Filter::new(&options.include, &options.exclude)?;
+    let mut state = SyncState::load(&bucket, &prefix);
+    let local = collect(dir, &prefix, &filter, &mut state)?;
+    if let Err(e) = state.save(&bucket, &prefix) {
+        log::debug!("could not save the sync state: {}", e);
+    }
+
+    let client = http::legacy_auth_client(user);
+    let remote = list_objects(&client, account_id, &bucket, &prefix)?;
+
+    let uploads: Vec<LocalFile> = local
+        .iter()
+        .filter(|file| !unchanged(file, &remote))
+        .cloned()
+        .collect();
+    if let Some(file) = uploads.iter().find(|file| file.size > MAX_PUT_SIZE) {
+        anyhow::bail!(
+            "{} is {}, over the {} limit for uploading an object in one request. Exclude it from the sync, multipart uploads aren't supported",
+            file.path.display(),
+            HumanBytes(file.size),
+            HumanBytes(MAX_PUT_SIZE)
+        );
+    }
+    let skipped = local.len() - uploads.len();
+    let local_keys: HashSet<&str> = local.iter().map(|file| file.key.as_str()).collect();
+    let deletions: Vec<String> = if options.delete {
+        remote
+            .keys()
+            .filter(|key| !local_keys.contains(key.as_str()))
+            .filter(|key| filter.matches(&key[prefix.len()..]))
+            .cloned()
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let uploaded = uploads.len();
+    let addr = objects_addr(account_id, &bucket);
+    let upload_client = client.clone();
+    let upload_addr = addr.clone();
+    parallel::run(
+        uploads,
+        options.concurrency,
+        move |file: LocalFile| {
+            put(&upload_client, &upload_addr, &file)?;
+            Ok(file)
+        },
+        |file| StdErr::info(&format!("Uploaded {}", file.key)),
+    )?;
+
+    let deleted = deletions.len();
+    parallel::run(
+        deletions,
+        options.concurrency,
+        move |key: String| {
+            let res = http::trace::send(
+                &client,
+                client.delete(&format!("{}/{}", addr, encode(&key))),
+            )?;
+            if !res.status().is_success() {
+                anyhow::bail!(
+                    "Could not delete {}: {}",
+                    key,
+                    crate::format_api_errors(res.text()?)
+                );
+            }
+            Ok(key)
+        },
+        |key| StdErr::info(&format!("Deleted {}", key)),
+    )?;
+
+    StdOut::success(&format!(
+        "Synced {} to {}: {} uploaded, {} unchanged, {} deleted",
+        dir.display(),
+        destination,
+        uploaded,
+        skipped,
+        deleted
+    ));
+    Ok(())
+}
+
+fn split_destination(destination: &str) -> Result<(String, String)> {
+    let (bucket, prefix) = match destination.split_once('/') {
+        Some((bucket, prefix)) => (bucket, prefix.trim_matches('/')),
+        None => (destination, ""),
+    };
+    if bucket.is_empty() {
+        anyhow::bail!("{} doesn't start with a bucket name", destination);
+    }
+    let prefix = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", prefix)
+    };
+    Ok((bucket.to_string(), prefix))
+}
+
+/// Decides which paths, relative to the synced directory, are synced: those matching an
+/// `--include` glob if there are any, and no `--exclude` glob.
+struct Filter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl Filter {
+    fn new(include: &[String], exclude: &[String]) -> Result<Filter> {
+        let set = |globs: &[String]| -> Result<GlobSet> {
+            let mut builder = GlobSetBuilder::new();
+            for glob in globs {
+                builder.add(Glob::new(glob)?);
+            }
+            Ok(builder.build()?)
+        };
+        Ok(Filter {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(set(include)?)
+            },
+            exclude: set(exclude)?,
+        })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        self.include
+            .as_ref()
+            .map(|include| include.is_match(path))
+            .unwrap_or(true)
+            && !self.exclude.is_match(path)
+    }
+}
+
+// The files to sync, hashing those the state has no hash for. The state is left with only
+// these files.
+fn collect(
+    dir: &Path,
+    prefix: &str,
+    filter: &Filter,
+    state: &mut SyncState,
+) -> Result<Vec<LocalFile>> {
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+
+    let mut files = Vec::new();
+    let mut hashed = HashMap::new();
+    for entry in WalkBuilder::new(dir).standard_filters(false).build() {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let relative = paths::relative_name(path, dir)?;
+        if !filter.matches(&relative) {
+            continue;
+        }
+        let stamp = FileStamp::of(path)?;
+        let name = path.display().to_string();
+        let md5 = match state.files.get(&name).filter(|known| known.stamp == stamp) {
+            Some(known) => known.md5.clone(),
+            None => file_md5(path)?,
+        };
+        files.push(LocalFile {
+            path: path.to_path_buf(),
+            key: format!("{}{}", prefix, relative),
+            md5: md5.clone(),
+            size: stamp.size(),
+        });
+        hashed.insert(name, Hashed { stamp, md5 });
+    }
+    state.files = hashed;
+    Ok(files)
+}
+
+// Hashed a buffer at a time, the files can be backups far bigger than memory
+fn file_md5(path: &Path) -> Result<String> {
+    let mut context = md5::Context::new();
+    io::copy(&mut File::open(path)?, &mut context)?;
+    Ok(format!("{:x}", context.compute()))
+}
+
+// R2 gives an object uploaded in a single PUT the MD5 of its contents as its etag. Objects
+// uploaded in parts have etags that aren't, so they're always uploaded again.
+fn unchanged(file: &LocalFile, remote: &HashMap<String, Option<String>>) -> bool {
+    match remote.get(&file.key) {
+        Some(Some(etag)) => etag.trim_matches('"') == file.md5,
+        _ => false,
+    }
+}
+
+// Every object under `prefix`, a page at a time
+fn list_objects(
+    client: &Client,
+    account_id: &str,
+    bucket: &str,
+    prefix: &str,
+) -> Result<HashMap<String, Option<String>>> {
+    let mut objects = HashMap::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut addr = format!(
+            "{}?per_page=1000&prefix={}",
+            objects_addr(account_id, bucket),
+            utf8_percent_encode(prefix, KEY_ASCII_SET)
+        );
+        if let Some(cursor) = &cursor {
+            addr.push_str(&format!("&cursor={}", cursor));
+        }
+        let res = http::trace::send(client, client.get(&addr))?;
+        if !res.status().is_success() {
+            anyhow::bail!(crate::format_api_errors(res.text()?));
+        }
+        let page: ObjectsPage = res.json()?;
+        objects.extend(
+            page.result
+                .into_iter()
+                .map(|object| (object.key, object.etag)),
+        );
+        cursor = match page.result_info {
+            Some(Cursor {
+                cursor: Some(cursor),
+                is_truncated: true,
+            }) => Some(cursor),
+            _ => break,
+        };
+    }
+    Ok(objects)
+}
+
+// The file is streamed from disk rather than read into memory first
+fn put(client: &Client, addr: &str, file: &LocalFile) -> Result<()> {
+    let extension = file.path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let body = Body::sized(File::open(&file.path)?, file.size);
+    let res = http::trace::send(
+        client,
+        client
+            .put(&format!("{}/{}", addr, encode(&file.key)))
+            .header("Content-Type", content_type(extension))
+            .body(body),
+    )?;
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "Could not upload {}: {}",
+            file.key,
+            crate::format_api_errors(res.text()?)
+        );
+    }
+    Ok(())
+}
+
+fn objects_addr(account_id: &str, bucket: &str) -> String {
+    format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/r2/buckets/{}/objects",
+        account_id, bucket
+    )
+}
+
+fn encode(key: &str) -> String {
+    utf8_percent_encode(key, KEY_ASCII_SET).to_string()
+}
+
+impl SyncState {
+    fn load(bucket: &str, prefix: &str) -> SyncState {
+        if !cache::enabled() {
+            return SyncState::default();
+        }
+        fs::read(state_path(bucket, prefix))
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, bucket: &str, prefix: &str) -> Result<()> {
+        if !cache::enabled() {
+            return Ok(());
+        }
+        let path = state_path(bucket, prefix);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+}
+
+fn state_path(bucket: &str, prefix: &str) -> PathBuf {
+    let mut hasher = XxHash64::default();
+    hasher.write(bucket.as_bytes());
+    hasher.write(prefix.as_bytes());
+    get_wrangler_home_dir()
+        .join("cache")
+        .join(format!("r2-sync-{:x}.json", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(key: &str, md5: &str) -> LocalFile {
+        LocalFile {
+            path: PathBuf::from(key),
+            key: key.to_string(),
+            md5: md5.to_string(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn destinations_split_into_bucket_and_prefix() {
+        assert_eq!(
+            split_destination("assets").unwrap(),
+            ("assets".to_string(), String::new())
+        );
+        assert_eq!(
+            split_destination("assets/static/v2/").unwrap(),
+            ("assets".to_string(), "static/v2/".to_string())
+        );
+        assert!(split_destination("/static").is_err());
+    }
+
+    #[test]
+    fn excludes_win_over_includes() {
+        let filter = Filter::new(
+            &["**/*.js".to_string(), "*.css".to_string()],
+            &["vendor/**".to_string()],
+        )
+        .unwrap();
+        assert!(filter.matches("app/index.js"));
+        assert!(filter.matches("main.css"));
+        assert!(!filter.matches("vendor/lib.js"));
+        assert!(!filter.matches("index.html"));
+
+        let everything = Filter::new(&[], &[]).unwrap();
+        assert!(everything.matches("index.html"));
+    }
+
+    #[test]
+    fn files_are_unchanged_when_the_etag_is_their_md5() {
+        let mut remote = HashMap::new();
+        remote.insert(
+            "a.js".to_string(),
+            Some("\"9e107d9d372bb6826bd81d3542a419d6\"".to_string()),
+        );
+        remote.insert("b.js".to_string(), None);
+
+        assert!(unchanged(
+            &file("a.js", "9e107d9d372bb6826bd81d3542a419d6"),
+            &remote
+        ));
+        assert!(!unchanged(
+            &file("a.js", "e4d909c290d0fb1ca068ffaddf22cbd0"),
+            &remote
+        ));
+        assert!(!unchanged(
+            &file("b.js", "9e107d9d372bb6826bd81d3542a419d6"),
+            &remote
+        ));
+        assert!(!unchanged(
+            &file("c.js", "9e107d9d372bb6826bd81d3542a419d6"),
+            &remote
+        ));
+    }
+
+    #[test]
+    fn files_are_hashed_as_r2_does() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fox.txt");
+        fs::write(&path, "The quick brown fox jumps over the lazy dog").unwrap();
+        assert_eq!(file_md5(&path).unwrap(), "9e107d9d372bb6826bd81d3542a419d6");
+    }
+}
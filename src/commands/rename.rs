@@ -0,0 +1,269 @@
+use std::path::Path;
+
+use anyhow::Result;
+use reqwest::StatusCode;
+use serde_json::json;
+
+use cloudflare::endpoints::workers::ListRoutes;
+use cloudflare::framework::apiclient::ApiClient;
+
+use crate::commands::publish::PublishMode;
+use crate::commands::{self, validate_worker_name};
+use crate::deploy::{DeployTarget, ZonedTarget};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{ConfigEditor, Manifest, Route};
+use crate::terminal::interactive;
+use crate::terminal::message::{Message, Output, StdOut};
+use crate::terminal::styles;
+
+/// Publishes the worker of `env` as `new_name`, moves its routes, workers.dev subdomain and
+/// cron triggers over to it, and writes the new name into the configuration file. The old
+/// script is deleted if the user confirms it.
+pub fn rename(
+    user: &GlobalUser,
+    manifest: &Manifest,
+    config_path: &Path,
+    env: Option<&str>,
+    new_name: &str,
+) -> Result<()> {
+    validate_worker_name(new_name)?;
+    let old = manifest.get_target(env, false)?;
+    if old.name == new_name {
+        anyhow::bail!("The worker is already called {}", new_name);
+    }
+    check_durable_objects(manifest, env)?;
+
+    let renamed = with_name(manifest, env, new_name)?;
+    let mut target = renamed.get_target(env, false)?;
+    let deployments = renamed.get_deployments(env)?;
+    let account_id = target.account_id.load()?.to_string();
+    let secrets = commands::secret::secret_names(user, &old).unwrap_or_default();
+
+    // routes still point at the old script, so they're moved once the new one exists instead
+    // of being published
+    let (zoned, deployments): (Vec<_>, Vec<_>) = deployments
+        .into_iter()
+        .partition(|deployment| matches!(deployment, DeployTarget::Zoned(_)));
+    commands::publish(
+        user,
+        &mut target,
+        env,
+        deployments,
+        Output::PlainText,
+        false,
+        false,
+        PublishMode::Full,
+    )?;
+    for deployment in &zoned {
+        if let DeployTarget::Zoned(zoned) = deployment {
+            move_routes(user, zoned, &old.name, new_name)?;
+        }
+    }
+
+    let mut config = ConfigEditor::open(config_path)?;
+    config.set(env, "name", &format!("{:?}", new_name))?;
+    config.save()?;
+    StdOut::success(&format!(
+        "Renamed {} to {} in {}",
+        old.name,
+        new_name,
+        config_path.display()
+    ));
+    if env.is_none() && manifest.env.is_some() {
+        StdOut::warn(&format!(
+            "Environments without a name of their own are now published as {}-<env>, rename their workers with `wrangler rename --env <env>` before publishing them",
+            new_name
+        ));
+    }
+
+    if !secrets.is_empty() {
+        StdOut::warn(&format!(
+            "Secrets can't be copied, set {} on {} again with `wrangler secret put`",
+            secrets.join(", "),
+            new_name
+        ));
+    }
+
+    let delete = interactive::is_interactive() || interactive::assumes_yes();
+    if delete && interactive::confirm(&format!("Delete the old worker {}?", old.name))? {
+        delete_script(user, &account_id, &old.name)?;
+        StdOut::success(&format!("Deleted {}", old.name));
+    } else {
+        retire(user, &account_id, &old.name)?;
+        StdOut::info(&format!(
+            "{} is still deployed, without routes, workers.dev or cron triggers. Delete it from {} once you don't need it anymore",
+            old.name,
+            styles::url("https://dash.cloudflare.com/?to=/:account/workers/overview")
+        ));
+    }
+    Ok(())
+}
+
+// The manifest as it would be with the worker of `env` called `new_name`
+fn with_name(manifest: &Manifest, env: Option<&str>, new_name: &str) -> Result<Manifest> {
+    let mut renamed = manifest.clone();
+    match (env, renamed.env.as_mut()) {
+        (Some(env), Some(environments)) if environments.contains_key(env) => {
+            environments.get_mut(env).unwrap().name = Some(new_name.to_string());
+        }
+        (Some(env), _) => anyhow::bail!("There's no environment {} to rename", env),
+        (None, _) => renamed.name = new_name.to_string(),
+    }
+    Ok(renamed)
+}
+
+// Durable Objects belong to the script that implements their class, a new script would start
+// without them
+fn check_durable_objects(manifest: &Manifest, env: Option<&str>) -> Result<()> {
+    let target = manifest.get_target(env, false)?;
+    let classes: Vec<_> = target
+        .durable_objects
+        .iter()
+        .flat_map(|durable_objects| durable_objects.classes.iter().flatten())
+        .filter(|class| class.script_name.is_none())
+        .map(|class| class.class_name.as_str())
+        .collect();
+    if !classes.is_empty() {
+        anyhow::bail!(
+            "{} implements the Durable Object class(es) {}, whose objects would stay with the old script. Publish the new one with a transferred_classes migration instead",
+            target.name,
+            classes.join(", ")
+        );
+    }
+    Ok(())
+}
+
+// Points the routes of the old script on the zone at the new one
+fn move_routes(user: &GlobalUser, zoned: &ZonedTarget, old: &str, new: &str) -> Result<()> {
+    let client = http::cf_v4_client(user)?;
+    let existing: Vec<Route> = client
+        .request(&ListRoutes {
+            zone_identifier: &zoned.zone_id,
+        })
+        .map_err(|e| http::api_error(e, None))?
+        .result
+        .iter()
+        .map(Route::from)
+        .collect();
+
+    let legacy = http::legacy_auth_client(user);
+    for route in &zoned.routes {
+        let id = existing
+            .iter()
+            .find(|e| e.pattern == route.pattern && e.script.as_deref() == Some(old))
+            .and_then(|e| e.id.clone());
+        let res = match id {
+            Some(id) => {
+                let addr = format!(
+                    "https://api.cloudflare.com/client/v4/zones/{}/workers/routes/{}",
+                    zoned.zone_id, id
+                );
+                let body = json!({ "pattern": route.pattern, "script": new });
+                http::trace::send(&legacy, legacy.put(&addr).json(&body))?
+            }
+            None => {
+                let addr = format!(
+                    "https://api.cloudflare.com/client/v4/zones/{}/workers/routes",
+                    zoned.zone_id
+                );
+                let body = json!({ "pattern": route.pattern, "script": new });
+                http::trace::send(&legacy, legacy.post(&addr).json(&body))?
+            }
+        };
+        if !res.status().is_success() {
+            anyhow::bail!(
+                "Could not point {} at {}: {}",
+                route.pattern,
+                new,
+                crate::format_api_errors(res.text()?)
+            );
+        }
+        StdOut::success(&format!("{} => {}", route.pattern, new));
+    }
+    Ok(())
+}
+
+// Takes the old script off workers.dev and stops its cron triggers, so it doesn't keep running
+// next to the new one
+fn retire(user: &GlobalUser, account_id: &str, script: &str) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let script_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        account_id, script
+    );
+
+    let subdomain = client
+        .post(&format!("{}/subdomain", script_addr))
+        .json(&json!({ "enabled": false }));
+    let schedules = client
+        .put(&format!("{}/schedules", script_addr))
+        .json(&json!([]));
+    for request in [subdomain, schedules] {
+        let res = http::trace::send(&client, request)?;
+        if !res.status().is_success() {
+            StdOut::warn(&format!(
+                "Could not retire {}: {}",
+                script,
+                crate::format_api_errors(res.text()?)
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn delete_script(user: &GlobalUser, account_id: &str, script: &str) -> Result<()> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        account_id, script
+    );
+    let client = http::legacy_auth_client(user);
+    let res = http::trace::send(&client, client.delete(&addr))?;
+    match res.status() {
+        StatusCode::NOT_FOUND => Ok(()),
+        status if !status.is_success() => anyhow::bail!(
+            "Could not delete {}: {}",
+            script,
+            crate::format_api_errors(res.text()?)
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn manifest() -> Manifest {
+        Manifest::from_str(
+            r#"
+            name = "shop"
+            type = "javascript"
+            account_id = "abc"
+            workers_dev = true
+
+            [env.staging]
+
+            [env.production]
+            name = "shop-live"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn the_name_of_the_right_worker_is_changed() {
+        let manifest = manifest();
+
+        let renamed = with_name(&manifest, None, "store").unwrap();
+        assert_eq!(renamed.worker_name(None), "store");
+        assert_eq!(renamed.worker_name(Some("production")), "shop-live");
+
+        let renamed = with_name(&manifest, Some("staging"), "store-staging").unwrap();
+        assert_eq!(renamed.worker_name(None), "shop");
+        assert_eq!(renamed.worker_name(Some("staging")), "store-staging");
+
+        assert!(with_name(&manifest, Some("qa"), "store-qa").is_err());
+    }
+}
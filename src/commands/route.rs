@@ -1,12 +1,25 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
 use anyhow::Result;
 use cloudflare::endpoints::workers::{DeleteRoute, ListRoutes};
 use cloudflare::framework::apiclient::ApiClient;
 
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::commands::subdomain::Subdomain;
+use crate::deploy::deployed;
+use crate::deploy::{publish_routes, DeployTarget, RouteUploadResult, ZonedTarget};
 use crate::http;
 use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Route, RoutePattern};
+use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::{Table, TableOptions};
 
-pub fn list(zone_identifier: &str, user: &GlobalUser) -> Result<()> {
+pub fn list(zone_identifier: &str, user: &GlobalUser, options: &TableOptions) -> Result<()> {
     let client = http::cf_v4_client(user)?;
 
     let result = client.request(&ListRoutes { zone_identifier });
@@ -14,15 +27,137 @@ pub fn list(zone_identifier: &str, user: &GlobalUser) -> Result<()> {
     match result {
         Ok(success) => {
             let routes = success.result;
-            println!("{}", serde_json::to_string(&routes)?);
+            Table::from_serialize(&["id", "pattern", "script"], &routes)?.print(options)
         }
 
-        Err(e) => anyhow::bail!("{}", http::format_error(e, None)),
+        Err(e) => Err(http::api_error(e, None)),
     }
-    Ok(())
+}
+
+#[derive(Deserialize)]
+struct Zone {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct CustomDomain {
+    hostname: String,
+    zone_name: String,
+    service: String,
+}
+
+#[derive(Deserialize)]
+struct ScriptSubdomain {
+    enabled: bool,
+}
+
+/// Lists everything that sends requests to `script`: its routes on every zone of the account,
+/// its custom domains and its workers.dev URL.
+pub fn list_for_script(
+    user: &GlobalUser,
+    account_id: &str,
+    script: &str,
+    options: &TableOptions,
+) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let mut rows = Vec::new();
+
+    for zone in zones(&client, account_id)? {
+        for route in fetch(user, &zone.id)? {
+            if route.script.as_deref() == Some(script) {
+                rows.push(json!({"type": "route", "zone": zone.name, "pattern": route.pattern}));
+            }
+        }
+    }
+
+    let domains_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/domains",
+        account_id
+    );
+    let domains: Vec<CustomDomain> = deployed::get(&client, &domains_addr)?.unwrap_or_default();
+    for domain in domains
+        .into_iter()
+        .filter(|domain| domain.service == script)
+    {
+        rows.push(
+            json!({"type": "custom domain", "zone": domain.zone_name, "pattern": domain.hostname}),
+        );
+    }
+
+    let subdomain_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/subdomain",
+        account_id, script
+    );
+    let enabled = deployed::get::<ScriptSubdomain>(&client, &subdomain_addr)?
+        .map(|subdomain| subdomain.enabled)
+        .unwrap_or_default();
+    if enabled {
+        if let Some(subdomain) = Subdomain::get(account_id, user)? {
+            rows.push(json!({
+                "type": "workers.dev",
+                "zone": "",
+                "pattern": format!("{}.{}.workers.dev", script, subdomain)
+            }));
+        }
+    }
+
+    if rows.is_empty() {
+        StdOut::info(&format!("Nothing routes requests to {}", script));
+        return Ok(());
+    }
+    Table::new(&["type", "zone", "pattern"], rows).print(options)
+}
+
+/// The route patterns on every zone of the account, keyed by the script they point at.
+pub fn patterns_by_script(
+    user: &GlobalUser,
+    account_id: &str,
+) -> Result<HashMap<String, Vec<String>>> {
+    let client = http::legacy_auth_client(user);
+    let mut patterns: HashMap<String, Vec<String>> = HashMap::new();
+    for zone in zones(&client, account_id)? {
+        for route in fetch(user, &zone.id)? {
+            if let Some(script) = route.script {
+                patterns.entry(script).or_default().push(route.pattern);
+            }
+        }
+    }
+    Ok(patterns)
+}
+
+// Every zone of the account, a page at a time
+fn zones(client: &reqwest::blocking::Client, account_id: &str) -> Result<Vec<Zone>> {
+    const PER_PAGE: usize = 50;
+    let mut zones = Vec::new();
+    for page in 1.. {
+        let addr = format!(
+            "https://api.cloudflare.com/client/v4/zones?account.id={}&per_page={}&page={}",
+            account_id, PER_PAGE, page
+        );
+        let page: Vec<Zone> = deployed::get(client, &addr)?.unwrap_or_default();
+        let last = page.len() < PER_PAGE;
+        zones.extend(page);
+        if last {
+            break;
+        }
+    }
+    Ok(zones)
 }
 
 pub fn delete(zone_identifier: &str, user: &GlobalUser, route_id: &str) -> Result<()> {
+    // `route delete` never asked before --yes existed, so scripts that run it without a
+    // terminal keep working without one
+    if interactive::is_interactive()
+        && !interactive::confirm(&format!(
+            "Are you sure you want to delete the route with id {}?",
+            route_id
+        ))?
+    {
+        StdOut::info(&format!("Not deleting route {}.", route_id));
+        return Ok(());
+    }
+
     let client = http::cf_v4_client(user)?;
 
     let result = client.request(&DeleteRoute {
@@ -36,14 +171,270 @@ pub fn delete(zone_identifier: &str, user: &GlobalUser, route_id: &str) -> Resul
             StdOut::success(&msg);
         }
 
-        Err(e) => anyhow::bail!("{}", http::format_error(e, Some(&error_suggestions))),
+        Err(e) => return Err(http::api_error(e, Some(&error_suggestions))),
     }
     Ok(())
 }
 
+/// The routes of a zone as `wrangler route export` prints them. Route IDs are left out, they
+/// only mean something on the zone they came from.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct RouteSnapshot {
+    zone_id: String,
+    routes: Vec<SnapshotRoute>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct SnapshotRoute {
+    pattern: String,
+    /// `None` for routes that turn workers off for the pattern
+    script: Option<String>,
+}
+
+pub fn export(zone_identifier: &str, user: &GlobalUser) -> Result<()> {
+    let snapshot = RouteSnapshot {
+        zone_id: zone_identifier.to_string(),
+        routes: fetch(user, zone_identifier)?
+            .into_iter()
+            .map(|route| SnapshotRoute {
+                pattern: route.pattern,
+                script: route.script,
+            })
+            .collect(),
+    };
+    println!("{}", serde_json::to_string_pretty(&snapshot)?);
+    Ok(())
+}
+
+/// Creates the routes of the snapshot that the zone doesn't have. Routes of the zone that
+/// point at another worker than in the snapshot are reported rather than changed.
+pub fn import(zone_identifier: &str, user: &GlobalUser, path: &Path) -> Result<()> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {}", path.display(), e))?;
+    let snapshot: RouteSnapshot = serde_json::from_str(&contents).map_err(|e| {
+        anyhow::anyhow!(
+            "{} isn't a file written by `wrangler route export`: {}",
+            path.display(),
+            e
+        )
+    })?;
+    if snapshot.zone_id != zone_identifier {
+        StdOut::info(&format!(
+            "Importing the routes of zone {} into zone {}",
+            snapshot.zone_id, zone_identifier
+        ));
+    }
+
+    let target = ZonedTarget {
+        zone_id: zone_identifier.to_string(),
+        routes: snapshot
+            .routes
+            .into_iter()
+            .map(|route| Route {
+                id: None,
+                script: route.script,
+                pattern: route.pattern,
+            })
+            .collect(),
+    };
+    let mut failed = 0;
+    for result in publish_routes(user, &target)? {
+        match result {
+            RouteUploadResult::New(_) => StdOut::success(&result.to_string()),
+            RouteUploadResult::Same(_) => StdOut::info(&result.to_string()),
+            RouteUploadResult::Conflict(_) => StdOut::warn(&result.to_string()),
+            RouteUploadResult::Error(_) => {
+                failed += 1;
+                StdOut::warn(&result.to_string());
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} route(s) could not be created", failed);
+    }
+    Ok(())
+}
+
+/// Warns about routes about to be attached that overlap a route on the zone belonging to
+/// another worker, since the more specific pattern would take that worker's traffic or lose
+/// its own. With `strict` that's an error instead.
+pub fn check_conflicts(
+    user: &GlobalUser,
+    deployments: &[DeployTarget],
+    strict: bool,
+) -> Result<()> {
+    let mut conflicts = Vec::new();
+    for deployment in deployments {
+        if let DeployTarget::Zoned(zoned) = deployment {
+            let existing = fetch(user, &zoned.zone_id)?;
+            conflicts.extend(overlapping(zoned, &existing));
+        }
+    }
+
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    if strict {
+        anyhow::bail!(
+            "Routes overlap routes of other workers, remove them or drop --strict to attach them anyway:\n{}",
+            conflicts.join("\n")
+        );
+    }
+    for conflict in conflicts {
+        StdOut::warn(&conflict);
+    }
+    Ok(())
+}
+
+/// Deletes the routes on each zone that point at the worker but aren't in the configuration
+/// anymore, e.g. after a pattern was renamed.
+pub fn prune(user: &GlobalUser, deployments: &[DeployTarget]) -> Result<()> {
+    for deployment in deployments {
+        if let DeployTarget::Zoned(zoned) = deployment {
+            let client = http::cf_v4_client(user)?;
+            for route in stale(zoned, &fetch(user, &zoned.zone_id)?) {
+                let id = match &route.id {
+                    Some(id) => id,
+                    None => continue,
+                };
+                client
+                    .request(&DeleteRoute {
+                        zone_identifier: &zoned.zone_id,
+                        identifier: id,
+                    })
+                    .map_err(|e| http::api_error(e, Some(&error_suggestions)))?;
+                StdOut::success(&format!("Removed stale route {}", route.pattern));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The routes on the zone.
+pub fn fetch(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
+    let client = http::cf_v4_client(user)?;
+    match client.request(&ListRoutes { zone_identifier }) {
+        Ok(success) => Ok(success.result.iter().map(Route::from).collect()),
+        Err(e) => Err(http::api_error(e, None)),
+    }
+}
+
+// The routes of the worker `zoned` deploys that its configuration doesn't list
+fn stale<'a>(zoned: &ZonedTarget, existing: &'a [Route]) -> Vec<&'a Route> {
+    let script = match zoned.routes.first() {
+        Some(route) => &route.script,
+        None => return Vec::new(),
+    };
+    existing
+        .iter()
+        .filter(|route| {
+            &route.script == script
+                && !zoned
+                    .routes
+                    .iter()
+                    .any(|configured| configured.pattern == route.pattern)
+        })
+        .collect()
+}
+
+// Describes each route of `zoned` that overlaps one of `existing` belonging to another worker
+fn overlapping(zoned: &ZonedTarget, existing: &[Route]) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    for route in &zoned.routes {
+        let pattern = RoutePattern::parse(&route.pattern);
+        for other in existing {
+            if other.script == route.script
+                || !pattern.overlaps(&RoutePattern::parse(&other.pattern))
+            {
+                continue;
+            }
+            let owner = match &other.script {
+                Some(script) => script.as_str(),
+                None => "no worker",
+            };
+            conflicts.push(format!(
+                "{} overlaps {}, which routes to {}",
+                route.pattern, other.pattern, owner
+            ));
+        }
+    }
+    conflicts
+}
+
 fn error_suggestions(code: u16) -> &'static str {
     match code {
         10005 => "Confirm the route id by running `wrangler route list`",
         _ => "",
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(pattern: &str, script: Option<&str>) -> Route {
+        Route {
+            id: None,
+            script: script.map(|s| s.to_string()),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn routes_of_other_workers_conflict() {
+        let zoned = ZonedTarget {
+            zone_id: "zone".to_string(),
+            routes: vec![route("example.com/api/*", Some("api"))],
+        };
+        let existing = vec![
+            route("example.com/api/*", Some("api")),
+            route("*example.com/*", Some("site")),
+            route("example.com/api/health", None),
+            route("example.com/blog/*", Some("blog")),
+        ];
+
+        assert_eq!(
+            overlapping(&zoned, &existing),
+            vec![
+                "example.com/api/* overlaps *example.com/*, which routes to site",
+                "example.com/api/* overlaps example.com/api/health, which routes to no worker",
+            ]
+        );
+    }
+
+    #[test]
+    fn unconfigured_routes_of_the_worker_are_stale() {
+        let zoned = ZonedTarget {
+            zone_id: "zone".to_string(),
+            routes: vec![route("example.com/v2/*", Some("api"))],
+        };
+        let existing = vec![
+            route("example.com/v2/*", Some("api")),
+            route("example.com/v1/*", Some("api")),
+            route("example.com/*", Some("site")),
+        ];
+
+        assert_eq!(stale(&zoned, &existing), vec![&existing[1]]);
+    }
+
+    #[test]
+    fn snapshots_keep_disabled_routes() {
+        let snapshot: RouteSnapshot = serde_json::from_str(
+            r#"{
+                "zone_id": "zone",
+                "routes": [
+                    { "pattern": "example.com/*", "script": "worker" },
+                    { "pattern": "example.com/static/*", "script": null }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            snapshot.routes[1],
+            SnapshotRoute {
+                pattern: "example.com/static/*".to_string(),
+                script: None,
+            }
+        );
+    }
+}
@@ -2,17 +2,23 @@ use cloudflare::endpoints::workers::{CreateSecret, CreateSecretParams, DeleteSec
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
-use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
 
 use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
 use crate::terminal::interactive;
 use crate::terminal::message::{Message, StdOut};
+use crate::terminal::table::{Table, TableOptions};
 use crate::upload;
 
-fn format_error(e: ApiFailure) -> String {
-    http::format_error(e, Some(&secret_errors))
+fn api_error(e: ApiFailure) -> anyhow::Error {
+    http::api_error(e, Some(&secret_errors))
 }
 
 // secret_errors() provides more detailed explanations of API error codes.
@@ -43,7 +49,7 @@ pub fn upload_draft_worker(
             if error.code == 10007 {
                 StdOut::working(&format!("Worker {} doesn't exist in the API yet. Creating a draft Worker so we can create new secret.", target.name));
                 let upload_client = http::legacy_auth_client(user);
-                Some(upload::script(&upload_client, target, None))
+                Some(upload::script(&upload_client, target, None).map(|_| ()))
             } else {
                 None
             }
@@ -52,26 +58,107 @@ pub fn upload_draft_worker(
     }
 }
 
-pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<()> {
-    let secret_value = interactive::get_user_input_multi_line(&format!(
+/// How `wrangler secret put` reads the value.
+pub struct SecretInput {
+    /// Ask for the value a second time when prompting
+    pub confirm: bool,
+    /// The value is base64-encoded
+    pub base64: bool,
+}
+
+/// Asks for the value of `name` once and uploads it to every target.
+pub fn create_secret(
+    name: &str,
+    user: &GlobalUser,
+    targets: &[Target],
+    input: SecretInput,
+) -> Result<()> {
+    let prompt = format!(
         "Enter the secret text you'd like assigned to the variable {} on the script named {}:",
-        name, target.name
-    ));
+        name,
+        script_names(targets)
+    );
+    // the trailing whitespace is dropped from the value, but it's only seen before that
+    let raw_value = interactive::get_raw_user_input_multi_line(&prompt)?;
+    let mut secret_value = raw_value.trim_end().to_string();
+    if input.confirm && interactive::is_interactive() {
+        let again = interactive::get_user_input_multi_line("Enter the secret text again:")?;
+        if again != secret_value {
+            anyhow::bail!("The two values don't match, the secret wasn't uploaded.");
+        }
+    }
+    if input.base64 {
+        secret_value = decode_base64(&secret_value)?;
+    } else if let Some(warning) = whitespace_warning(name, &raw_value) {
+        StdOut::warn(&warning);
+    }
 
     if secret_value.is_empty() {
         anyhow::bail!("Your secret cannot be empty.")
     }
 
-    StdOut::working(&format!(
-        "Creating the secret for script name {}",
-        target.name
-    ));
+    for target in targets {
+        StdOut::working(&format!(
+            "Creating the secret for script name {}",
+            target.name
+        ));
 
+        put_secret(name, secret_value.clone(), user, target)?;
+        StdOut::success(&format!(
+            "Success! Uploaded secret {} to {}.",
+            name, target.name
+        ));
+    }
+    Ok(())
+}
+
+// Secrets are stored as text, so the decoded value has to be text too
+fn decode_base64(value: &str) -> Result<String> {
+    let bytes = base64::decode(value.trim())
+        .map_err(|e| anyhow!("The secret isn't valid base64: {}", e))?;
+    String::from_utf8(bytes).map_err(|_| {
+        anyhow!("The decoded secret isn't UTF-8 text, which is all secrets can hold. Upload it base64-encoded without --base64 and decode it in your worker with atob() instead.")
+    })
+}
+
+// Whitespace around a value is nearly always a copy-paste or `echo` accident. `value` is what
+// was read: its trailing whitespace is left out of the secret, its leading whitespace is kept.
+fn whitespace_warning(name: &str, value: &str) -> Option<String> {
+    if value.ends_with('\n') {
+        Some(format!(
+            "The value of {} ends with a newline, which was left out of the secret. Use `echo -n` or `printf` when piping it.",
+            name
+        ))
+    } else if value.trim_end() != value {
+        Some(format!(
+            "The value of {} ends with whitespace, which was left out of the secret.",
+            name
+        ))
+    } else if value.trim_start() != value {
+        Some(format!(
+            "The value of {} starts with whitespace, which will be part of the secret.",
+            name
+        ))
+    } else {
+        None
+    }
+}
+
+fn script_names(targets: &[Target]) -> String {
+    targets
+        .iter()
+        .map(|target| target.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Creates or updates a secret, creating a draft of the worker first if it doesn't exist yet
+fn put_secret(name: &str, value: String, user: &GlobalUser, target: &Target) -> Result<()> {
     let client = http::cf_v4_client(user)?;
 
     let params = CreateSecretParams {
         name: name.to_string(),
-        text: secret_value,
+        text: value,
         secret_type: "secret_text".to_string(),
     };
 
@@ -82,9 +169,9 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
     });
 
     match response {
-        Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
+        Ok(_) => Ok(()),
         Err(e) => match upload_draft_worker(&e, user, target) {
-            None => anyhow::bail!(format_error(e)),
+            None => Err(api_error(e)),
             Some(draft_upload_response) => match draft_upload_response {
                 Ok(_) => {
                     let retry_response = client.request(&CreateSecret {
@@ -94,22 +181,21 @@ pub fn create_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
                     });
 
                     match retry_response {
-                        Ok(_) => StdOut::success(&format!("Success! Uploaded secret {}.", name)),
-                        Err(e) => anyhow::bail!(format_error(e)),
+                        Ok(_) => Ok(()),
+                        Err(e) => Err(api_error(e)),
                     }
                 }
                 Err(e) => anyhow::bail!(e),
             },
         },
     }
-
-    Ok(())
 }
 
-pub fn delete_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<()> {
+pub fn delete_secret(name: &str, user: &GlobalUser, targets: &[Target]) -> Result<()> {
     match interactive::confirm(&format!(
         "Are you sure you want to permanently delete the variable {} on the script named {}?",
-        name, target.name
+        name,
+        script_names(targets)
     )) {
         Ok(true) => (),
         Ok(false) => {
@@ -119,11 +205,22 @@ pub fn delete_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
         Err(e) => anyhow::bail!(e),
     }
 
-    StdOut::working(&format!(
-        "Deleting the secret {} on script {}.",
-        name, target.name
-    ));
+    for target in targets {
+        StdOut::working(&format!(
+            "Deleting the secret {} on script {}.",
+            name, target.name
+        ));
+
+        remove_secret(name, user, target)?;
+        StdOut::success(&format!(
+            "Success! Deleted secret {} from {}.",
+            name, target.name
+        ));
+    }
+    Ok(())
+}
 
+fn remove_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<()> {
     let client = http::cf_v4_client(user)?;
 
     let response = client.request(&DeleteSecret {
@@ -133,14 +230,13 @@ pub fn delete_secret(name: &str, user: &GlobalUser, target: &Target) -> Result<(
     });
 
     match response {
-        Ok(_) => StdOut::success(&format!("Success! Deleted secret {}.", name)),
-        Err(e) => anyhow::bail!(format_error(e)),
+        Ok(_) => Ok(()),
+        Err(e) => Err(api_error(e)),
     }
-
-    Ok(())
 }
 
-pub fn list_secrets(user: &GlobalUser, target: &Target) -> Result<()> {
+// The names of the secrets the worker has
+pub(crate) fn secret_names(user: &GlobalUser, target: &Target) -> Result<Vec<String>> {
     let client = http::cf_v4_client(user)?;
 
     let response = client.request(&ListSecrets {
@@ -149,12 +245,166 @@ pub fn list_secrets(user: &GlobalUser, target: &Target) -> Result<()> {
     });
 
     match response {
-        Ok(success) => {
-            let secrets = success.result;
-            println!("{}", serde_json::to_string(&secrets)?);
+        Ok(success) => Ok(success.result.into_iter().map(|s| s.name).collect()),
+        Err(e) => Err(api_error(e)),
+    }
+}
+
+/// How the secrets of a worker have to change to match a secrets file.
+#[derive(Debug, Default, PartialEq)]
+struct SyncPlan {
+    create: Vec<String>,
+    /// Secrets that exist already. Their values can't be read back, so they're always put again.
+    update: Vec<String>,
+    extraneous: Vec<String>,
+}
+
+impl SyncPlan {
+    fn new(desired: &BTreeMap<String, String>, existing: &[String]) -> SyncPlan {
+        let mut plan = SyncPlan::default();
+        for name in desired.keys() {
+            if existing.contains(name) {
+                plan.update.push(name.clone());
+            } else {
+                plan.create.push(name.clone());
+            }
         }
-        Err(e) => anyhow::bail!(format_error(e)),
+        plan.extraneous = existing
+            .iter()
+            .filter(|name| !desired.contains_key(*name))
+            .cloned()
+            .collect();
+        plan.extraneous.sort();
+        plan
+    }
+}
+
+/// Makes the worker's secrets match a JSON file of names and values: secrets missing from the
+/// worker are created and the others are updated. Secrets the file doesn't list are only
+/// deleted with `delete`, after confirming.
+pub fn sync_secrets(from: &Path, delete: bool, user: &GlobalUser, target: &Target) -> Result<()> {
+    let contents = fs::read_to_string(from)
+        .map_err(|e| anyhow!("Could not read {}: {}", from.display(), e))?;
+    let desired: BTreeMap<String, String> = serde_json::from_str(&contents).map_err(|e| {
+        anyhow!(
+            "{} should be a JSON object of secret names and values: {}",
+            from.display(),
+            e
+        )
+    })?;
+    if let Some((name, _)) = desired.iter().find(|(_, value)| value.is_empty()) {
+        anyhow::bail!("The value of {} in {} is empty", name, from.display());
+    }
+
+    let plan = SyncPlan::new(&desired, &secret_names(user, target)?);
+
+    for name in &plan.create {
+        put_secret(name, desired[name].clone(), user, target)?;
+        StdOut::success(&format!("Created secret {}", name));
+    }
+    for name in &plan.update {
+        put_secret(name, desired[name].clone(), user, target)?;
+        StdOut::success(&format!("Updated secret {}", name));
+    }
+
+    if plan.extraneous.is_empty() {
+        return Ok(());
+    }
+    let extraneous = plan.extraneous.join(", ");
+    if !delete {
+        StdOut::info(&format!(
+            "{} has secrets that {} doesn't list: {}. Run with --delete to remove them.",
+            target.name,
+            from.display(),
+            extraneous
+        ));
+        return Ok(());
+    }
+    if !interactive::confirm(&format!(
+        "Are you sure you want to permanently delete {} from the script named {}?",
+        extraneous, target.name
+    ))? {
+        StdOut::info("Not deleting any secrets.");
+        return Ok(());
+    }
+    for name in &plan.extraneous {
+        remove_secret(name, user, target)?;
+        StdOut::success(&format!("Deleted secret {}", name));
     }
 
     Ok(())
 }
+
+/// Lists the secrets of one target, or of several with a column for the script they're on.
+pub fn list_secrets(user: &GlobalUser, targets: &[Target], options: &TableOptions) -> Result<()> {
+    let client = http::cf_v4_client(user)?;
+
+    let mut rows = Vec::new();
+    for target in targets {
+        let response = client.request(&ListSecrets {
+            account_identifier: target.account_id.load()?,
+            script_name: &target.name,
+        });
+
+        match response {
+            Ok(success) => {
+                for secret in success.result {
+                    let mut row = serde_json::to_value(&secret)?;
+                    row["script"] = json!(target.name);
+                    rows.push(row);
+                }
+            }
+            Err(e) => return Err(api_error(e)),
+        }
+    }
+
+    if targets.len() == 1 {
+        Table::new(&["name", "type"], rows).print(options)
+    } else {
+        Table::new(&["script", "name", "type"], rows).print(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sync_plans_every_secret() {
+        let desired: BTreeMap<String, String> = [("API_KEY", "abc"), ("DB_URL", "postgres://")]
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let existing = vec!["OLD_TOKEN".to_string(), "DB_URL".to_string()];
+
+        assert_eq!(
+            SyncPlan::new(&desired, &existing),
+            SyncPlan {
+                create: vec!["API_KEY".to_string()],
+                update: vec!["DB_URL".to_string()],
+                extraneous: vec!["OLD_TOKEN".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn surrounding_whitespace_is_reported() {
+        assert!(whitespace_warning("TOKEN", "abc").is_none());
+        assert!(whitespace_warning("TOKEN", "abc\n")
+            .unwrap()
+            .contains("newline"));
+        assert!(whitespace_warning("TOKEN", "abc  ")
+            .unwrap()
+            .contains("left out"));
+        assert!(whitespace_warning("TOKEN", " abc")
+            .unwrap()
+            .contains("part of the secret"));
+    }
+
+    #[test]
+    fn base64_values_are_decoded_to_text() {
+        assert_eq!(decode_base64("aGVsbG8=\n").unwrap(), "hello");
+        assert!(decode_base64("not base64!").is_err());
+        assert!(decode_base64("/w==").is_err());
+    }
+}
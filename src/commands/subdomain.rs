@@ -18,7 +18,7 @@ impl Subdomain {
 
         let client = http::legacy_auth_client(user);
 
-        let response = client.get(&addr).send()?;
+        let response = http::trace::send(&client, client.get(&addr))?;
 
         if !response.status().is_success() {
             anyhow::bail!(
@@ -41,11 +41,13 @@ impl Subdomain {
 
         let client = http::legacy_auth_client(user);
 
-        let response = client
-            .put(&addr)
-            .header("allow-rename", "1")
-            .body(subdomain_request)
-            .send()?;
+        let response = http::trace::send(
+            &client,
+            client
+                .put(&addr)
+                .header("allow-rename", "1")
+                .body(subdomain_request),
+        )?;
 
         let response_status = response.status();
         if !response_status.is_success() {
@@ -180,10 +182,12 @@ fn get_subdomain_scripts(account_id: &str, user: &GlobalUser) -> Result<Vec<Stri
 
     let client = http::legacy_auth_client(user);
 
-    let response = client
-        .get(&addr)
-        .query(&[("include_subdomain_availability", "1")])
-        .send()?;
+    let response = http::trace::send(
+        &client,
+        client
+            .get(&addr)
+            .query(&[("include_subdomain_availability", "1")]),
+    )?;
 
     if !response.status().is_success() {
         anyhow::bail!(
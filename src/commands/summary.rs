@@ -0,0 +1,152 @@
+use crate::deploy::{DeployTarget, DeploymentSet};
+use crate::settings::toml::{Target, UploadFormat};
+use crate::terminal::message::{is_quiet, Message, StdErr};
+
+/// Prints what `verb` is about to use, so a wrong account or environment stands out before
+/// anything is uploaded.
+pub fn print(verb: &str, target: &Target, environment: Option<&str>, deployments: &DeploymentSet) {
+    if is_quiet() {
+        return;
+    }
+    let account = target.account_id.maybe_load();
+    StdErr::info(&format!("{} {}", verb, target.name));
+    for (label, value) in lines(target, environment, account, deployments) {
+        StdErr::message(&format!("   {:<12} {}", label, value));
+    }
+}
+
+fn lines(
+    target: &Target,
+    environment: Option<&str>,
+    account: Option<String>,
+    deployments: &DeploymentSet,
+) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "environment",
+            environment.unwrap_or("(top level)").to_string(),
+        ),
+        (
+            "account",
+            account.unwrap_or_else(|| "(not set)".to_string()),
+        ),
+        ("routes", routes(deployments)),
+        ("bindings", bindings(target)),
+        (
+            "compat date",
+            target
+                .compatibility_date
+                .clone()
+                .unwrap_or_else(|| "(not set)".to_string()),
+        ),
+        ("upload", upload_format(target).to_string()),
+    ]
+}
+
+fn routes(deployments: &DeploymentSet) -> String {
+    let routes: Vec<String> = deployments
+        .iter()
+        .flat_map(|deployment| match deployment {
+            DeployTarget::Zoned(zoned) => zoned
+                .routes
+                .iter()
+                .map(|route| route.pattern.clone())
+                .collect(),
+            DeployTarget::Zoneless(_) => vec!["workers.dev".to_string()],
+            DeployTarget::Schedule(schedule) => schedule
+                .crons
+                .iter()
+                .map(|cron| format!("cron {}", cron))
+                .collect(),
+        })
+        .collect();
+    if routes.is_empty() {
+        "(none)".to_string()
+    } else {
+        routes.join(", ")
+    }
+}
+
+fn bindings(target: &Target) -> String {
+    let classes = target
+        .durable_objects
+        .iter()
+        .flat_map(|durable_objects| durable_objects.classes.iter().flatten())
+        .count();
+    let counts = [
+        (target.kv_namespaces.len(), "KV namespace"),
+        (target.r2_buckets.len(), "R2 bucket"),
+        (classes, "Durable Object"),
+        (target.vars.as_ref().map_or(0, |vars| vars.len()), "var"),
+        (
+            target.text_blobs.as_ref().map_or(0, |blobs| blobs.len()),
+            "text blob",
+        ),
+        (
+            target
+                .wasm_modules
+                .as_ref()
+                .map_or(0, |modules| modules.len()),
+            "wasm module",
+        ),
+    ];
+    let total: usize = counts.iter().map(|(count, _)| count).sum();
+    let kinds: Vec<String> = counts
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, kind)| format!("{} {}{}", count, kind, if *count == 1 { "" } else { "s" }))
+        .collect();
+    if kinds.is_empty() {
+        "0".to_string()
+    } else {
+        format!("{} ({})", total, kinds.join(", "))
+    }
+}
+
+fn upload_format(target: &Target) -> &'static str {
+    match target.build.as_ref().map(|build| &build.upload) {
+        Some(UploadFormat::Modules { .. }) => "modules",
+        _ => "service-worker",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::Manifest;
+    use std::str::FromStr;
+
+    #[test]
+    fn it_summarizes_the_target() {
+        let manifest = Manifest::from_str(
+            r#"
+            name = "worker"
+            type = "javascript"
+            account_id = "account"
+            workers_dev = true
+            compatibility_date = "2021-11-01"
+            vars = { MODE = "production", REGION = "eu" }
+            kv_namespaces = [{ binding = "CACHE", id = "abc" }]
+
+            [triggers]
+            crons = ["0 * * * *"]
+            "#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+        let deployments = manifest.get_deployments(None).unwrap();
+
+        let lines = lines(&target, None, Some("account".to_string()), &deployments);
+        assert_eq!(
+            lines,
+            vec![
+                ("environment", "(top level)".to_string()),
+                ("account", "account".to_string()),
+                ("routes", "workers.dev, cron 0 * * * *".to_string()),
+                ("bindings", "3 (1 KV namespace, 2 vars)".to_string()),
+                ("compat date", "2021-11-01".to_string()),
+                ("upload", "service-worker".to_string()),
+            ]
+        );
+    }
+}
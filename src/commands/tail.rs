@@ -1,35 +1,77 @@
 use std::net::{SocketAddr, TcpListener};
 
 use anyhow::Result;
+use serde::Deserialize;
 
+use crate::deploy::deployed;
+use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
-use crate::tail::Tail;
+use crate::tail::{Tail, TailOptions};
 
 const DEFAULT_TUNNEL_PORT: u16 = 8080;
 const DEFAULT_METRICS_PORT: u16 = 8081;
 
+/// Which scripts a tail should follow.
+pub enum TailScripts {
+    /// The script described by the configuration file.
+    Target,
+    /// Scripts given by name, in the configuration file's account.
+    Named(Vec<String>),
+    /// Every script in the account.
+    All,
+}
+
 pub fn start(
     target: &Target,
     user: &GlobalUser,
-    format: String,
+    scripts: TailScripts,
+    options: TailOptions,
     tunnel_port: Option<u16>,
     metrics_port: Option<u16>,
     verbose: bool,
 ) -> Result<()> {
+    let scripts = match scripts {
+        TailScripts::Target => vec![target.name.clone()],
+        TailScripts::Named(names) => names,
+        TailScripts::All => list_scripts(user, target.account_id.load()?)?,
+    };
+    if scripts.is_empty() {
+        anyhow::bail!("There are no Worker scripts on this account to tail.")
+    }
+
     let tunnel_port = find_open_port(tunnel_port, DEFAULT_TUNNEL_PORT)?;
     let metrics_port = find_open_port(metrics_port, DEFAULT_METRICS_PORT)?;
 
     Tail::run(
         target.clone(),
+        scripts,
         user.clone(),
-        format,
+        options,
         tunnel_port,
         metrics_port,
         verbose,
     )
 }
 
+#[derive(Debug, Deserialize)]
+struct ScriptSummary {
+    id: String,
+}
+
+fn list_scripts(user: &GlobalUser, account_id: &str) -> Result<Vec<String>> {
+    let client = http::legacy_auth_client(user);
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+        account_id
+    );
+    Ok(deployed::get::<Vec<ScriptSummary>>(&client, &addr)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|script| script.id)
+        .collect())
+}
+
 /// Find open port takes two arguments: an Optional requested port, and a default port.
 fn find_open_port(requested: Option<u16>, default: u16) -> Result<u16> {
     if let Some(port) = requested {
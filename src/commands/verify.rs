@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use anyhow::Result;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use ring::signature::Ed25519KeyPair;
+
+use crate::build::signing::{self, Signature, SignedBundles};
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdOut};
+use crate::terminal::styles;
+
+/// Signs the bundles just published for `target`, writing the signature to `<script>.sig`.
+pub fn sign(target: &Target, key: &Ed25519KeyPair) -> Result<()> {
+    let signature = Signature::sign(SignedBundles::collect(target)?, key)?;
+    let path = signing::default_path(&target.name);
+    signature.write(&path)?;
+    StdOut::success(&format!(
+        "Signed {} bundle(s) of {}, the signature is in {}",
+        signature.signed.bundles.len(),
+        target.name,
+        path.display()
+    ));
+    Ok(())
+}
+
+/// Writes a new signing key to `path` and prints the public key to verify with.
+pub fn keygen(path: &Path) -> Result<()> {
+    if path.exists() {
+        anyhow::bail!("{} already exists", path.display());
+    }
+    let encoded = signing::generate_key()?;
+    let key = signing::parse_key(&encoded)?;
+    std::fs::write(path, &encoded)?;
+    StdOut::success(&format!("Wrote a new signing key to {}", path.display()));
+    StdOut::info(&format!(
+        "Verify deployments with {}",
+        styles::highlight(format!(
+            "wrangler verify --public-key {}",
+            signing::public_key(&key)
+        ))
+    ));
+    Ok(())
+}
+
+/// Checks that the script deployed for `target` is exactly what the signature at `path` signed.
+pub fn verify(
+    user: &GlobalUser,
+    target: &Target,
+    path: Option<&Path>,
+    public_key: Option<&str>,
+) -> Result<()> {
+    let default = signing::default_path(&target.name);
+    let path = path.unwrap_or(&default);
+    let signature = Signature::read(path)?;
+    if signature.signed.script != target.name {
+        anyhow::bail!(
+            "{} is a signature of {}, not {}",
+            path.display(),
+            signature.signed.script,
+            target.name
+        );
+    }
+    signature.verify(public_key)?;
+    if public_key.is_none() {
+        StdOut::warn("Without --public-key this only checks the signature file wasn't altered, not who signed it");
+    }
+
+    let deployed = deployed_parts(user, target)?;
+    let mismatches = mismatches(&signature.signed, &deployed);
+    if !mismatches.is_empty() {
+        anyhow::bail!(
+            "The deployed {} doesn't match the signed bundles:\n{}",
+            target.name,
+            mismatches.join("\n")
+        );
+    }
+
+    StdOut::success(&format!(
+        "The deployed {} matches the {} signed bundle(s) in {}",
+        target.name,
+        signature.signed.bundles.len(),
+        path.display()
+    ));
+    Ok(())
+}
+
+// The SHA-256 of every part of the deployed script. A service worker comes back as the bare
+// script, which is named `None`; modules come back as a multipart form.
+fn deployed_parts(user: &GlobalUser, target: &Target) -> Result<Vec<(Option<String>, String)>> {
+    let client = http::legacy_auth_client(user);
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        target.account_id.load()?,
+        target.name
+    );
+    let res = http::trace::send(&client, client.get(&addr))?;
+    match res.status() {
+        StatusCode::NOT_FOUND => anyhow::bail!("{} hasn't been published yet", target.name),
+        status if !status.is_success() => {
+            anyhow::bail!(crate::format_api_errors(res.text()?))
+        }
+        _ => {}
+    }
+
+    let boundary = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(boundary);
+    let body = res.bytes()?;
+    Ok(match boundary {
+        Some(boundary) => multipart(&body, &boundary)
+            .into_iter()
+            .map(|(name, contents)| (Some(name), signing::sha256(contents)))
+            .collect(),
+        None => vec![(None, signing::sha256(&body))],
+    })
+}
+
+fn mismatches(signed: &SignedBundles, deployed: &[(Option<String>, String)]) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    for (name, hash) in &signed.bundles {
+        let deployed_hash = match deployed {
+            [(None, hash)] if signed.bundles.len() == 1 => Some(hash),
+            _ => deployed
+                .iter()
+                .find(|(part, _)| part.as_deref() == Some(name.as_str()))
+                .map(|(_, hash)| hash),
+        };
+        match deployed_hash {
+            Some(deployed_hash) if deployed_hash == hash => {}
+            Some(_) => mismatches.push(format!("{} was changed", name)),
+            None => mismatches.push(format!("{} isn't deployed", name)),
+        }
+    }
+    mismatches
+}
+
+fn boundary(content_type: &str) -> Option<String> {
+    if !content_type.starts_with("multipart/") {
+        return None;
+    }
+    content_type.split(';').find_map(|param| {
+        param
+            .trim()
+            .strip_prefix("boundary=")
+            .map(|boundary| boundary.trim_matches('"').to_string())
+    })
+}
+
+// Splits a multipart body into its named parts
+fn multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<(String, &'a [u8])> {
+    let delimiter = format!("--{}", boundary);
+    let delimiter = delimiter.as_bytes();
+
+    let mut parts = Vec::new();
+    let mut rest = body;
+    while let Some(start) = find(rest, delimiter) {
+        rest = &rest[start + delimiter.len()..];
+        if rest.starts_with(b"--") {
+            break;
+        }
+        let end = find(rest, delimiter).unwrap_or(rest.len());
+        let part = &rest[..end];
+        if let Some(headers_end) = find(part, b"\r\n\r\n") {
+            let headers = String::from_utf8_lossy(&part[..headers_end]);
+            let contents = &part[headers_end + 4..];
+            let contents = contents.strip_suffix(b"\r\n").unwrap_or(contents);
+            if let Some(name) = part_name(&headers) {
+                parts.push((name, contents));
+            }
+        }
+        rest = &rest[end..];
+    }
+    parts
+}
+
+fn part_name(headers: &str) -> Option<String> {
+    headers
+        .lines()
+        .find(|line| line.to_lowercase().starts_with("content-disposition:"))?
+        .split(';')
+        .find_map(|param| param.trim().strip_prefix("name="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn it_splits_multipart_bodies() {
+        let body = concat!(
+            "--abc\r\n",
+            "Content-Disposition: form-data; name=\"index.mjs\"; filename=\"index.mjs\"\r\n",
+            "Content-Type: application/javascript+module\r\n\r\n",
+            "export default {}\r\n",
+            "--abc\r\n",
+            "Content-Disposition: form-data; name=\"lib/util.mjs\"\r\n\r\n",
+            "export const a = 1;\r\n",
+            "--abc--\r\n"
+        );
+        assert_eq!(
+            boundary("multipart/form-data; boundary=\"abc\""),
+            Some("abc".to_string())
+        );
+        assert_eq!(
+            multipart(body.as_bytes(), "abc"),
+            vec![
+                ("index.mjs".to_string(), &b"export default {}"[..]),
+                ("lib/util.mjs".to_string(), &b"export const a = 1;"[..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_finds_changed_and_missing_bundles() {
+        let mut bundles = BTreeMap::new();
+        bundles.insert("index.mjs".to_string(), "aaa".to_string());
+        bundles.insert("util.mjs".to_string(), "bbb".to_string());
+        let signed = SignedBundles {
+            script: "worker".to_string(),
+            bundles,
+        };
+
+        let deployed = vec![(Some("index.mjs".to_string()), "aaa".to_string())];
+        assert_eq!(
+            mismatches(&signed, &deployed),
+            vec!["util.mjs isn't deployed"]
+        );
+
+        let deployed = vec![
+            (Some("index.mjs".to_string()), "aaa".to_string()),
+            (Some("util.mjs".to_string()), "ccc".to_string()),
+        ];
+        assert_eq!(mismatches(&signed, &deployed), vec!["util.mjs was changed"]);
+    }
+}
@@ -1,38 +1,70 @@
 use crate::http;
 use crate::settings::global_user::GlobalUser;
-use crate::terminal::message::{Message, StdOut};
+use crate::terminal::message::{self, Message, Output, StdOut};
 use crate::terminal::{emoji, styles};
-use cloudflare::endpoints::account::{self, Account};
+use cloudflare::endpoints::account;
 use cloudflare::endpoints::user::GetUserDetails;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
 
 use anyhow::Result;
 use prettytable::{Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::cache;
+
+/// `wrangler whoami --json`
+#[derive(Serialize)]
+struct WhoamiOutput {
+    auth_type: &'static str,
+    email: Option<String>,
+    accounts: Vec<Account>,
+    missing_permissions: Vec<String>,
+}
+
+/// An account the user's credentials can see.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct Account {
+    pub name: String,
+    pub id: String,
+}
 
 /// Tells the user who they are
 pub fn whoami(user: &GlobalUser) -> Result<()> {
     let mut missing_permissions: Vec<String> = Vec::with_capacity(2);
     // Attempt to print email for both GlobalKeyAuth and TokenAuth users
-    let auth: String = match user {
-        GlobalUser::GlobalKeyAuth { email, .. } => {
-            format!("a Global API Key, associated with the email '{}'", email,)
-        }
-        GlobalUser::TokenAuth { .. } => {
-            let token_auth_email = fetch_api_token_email(user, &mut missing_permissions)?;
-
-            if let Some(token_auth_email) = token_auth_email {
-                format!(
-                    "an API Token, associated with the email '{}'",
-                    token_auth_email,
-                )
-            } else {
-                "an API Token".to_string()
-            }
+    let (auth_type, email) = match user {
+        GlobalUser::GlobalKeyAuth { email, .. } => ("global_api_key", Some(email.clone())),
+        GlobalUser::TokenAuth { .. } => (
+            "api_token",
+            fetch_api_token_email(user, &mut missing_permissions)?,
+        ),
+    };
+    let auth = match (user, &email) {
+        (GlobalUser::GlobalKeyAuth { .. }, Some(email)) => {
+            format!("a Global API Key, associated with the email '{}'", email)
         }
+        (_, Some(email)) => format!("an API Token, associated with the email '{}'", email),
+        _ => "an API Token".to_string(),
     };
 
     let accounts = fetch_accounts(user)?;
+
+    if message::output() == Output::Json {
+        if let GlobalUser::TokenAuth { .. } = user {
+            if accounts.is_empty() {
+                missing_permissions.push("Account Settings: Read".to_string());
+            }
+        }
+        StdOut::as_json(&WhoamiOutput {
+            auth_type,
+            email,
+            accounts,
+            missing_permissions,
+        });
+        return Ok(());
+    }
+
     let table = format_accounts(user, accounts, &mut missing_permissions);
     let mut msg = format!("{} You are logged in with {}!\n", emoji::WAVING, auth);
     let num_permissions_missing = missing_permissions.len();
@@ -89,7 +121,7 @@ pub fn display_account_id_maybe() {
     }
 }
 
-fn fetch_api_token_email(
+pub(crate) fn fetch_api_token_email(
     user: &GlobalUser,
     missing_permissions: &mut Vec<String>,
 ) -> Result<Option<String>> {
@@ -105,19 +137,29 @@ fn fetch_api_token_email(
                 }
                 Ok(None)
             }
-            ApiFailure::Invalid(_) => anyhow::bail!(http::format_error(e, None)),
+            ApiFailure::Invalid(_) => Err(http::api_error(e, None)),
         },
     }
 }
 
-/// Fetch the accounts associated with a user
+/// Fetch the accounts associated with a user, cached for a few minutes
 pub(crate) fn fetch_accounts(user: &GlobalUser) -> Result<Vec<Account>> {
-    let client = http::cf_v4_client(user)?;
-    let response = client.request(&account::ListAccounts { params: None });
-    match response {
-        Ok(res) => Ok(res.result),
-        Err(e) => anyhow::bail!(http::format_error(e, None)),
-    }
+    let key = cache::user_key("accounts", user);
+    cache::get_or_fetch(&key, cache::LOOKUP_TTL, || {
+        let client = http::cf_v4_client(user)?;
+        let response = client.request(&account::ListAccounts { params: None });
+        match response {
+            Ok(res) => Ok(res
+                .result
+                .into_iter()
+                .map(|account| Account {
+                    name: account.name,
+                    id: account.id,
+                })
+                .collect()),
+            Err(e) => Err(http::api_error(e, None)),
+        }
+    })
 }
 
 /// Format a user's accounts into a nice table
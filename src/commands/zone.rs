@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::commands::route;
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Route;
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::table::{Table, TableOptions};
+
+#[derive(Deserialize)]
+struct Script {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct ZoneInfo {
+    id: String,
+    account: Account,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    id: String,
+}
+
+/// Lists the routes of `zone`, a name or an ID, with the worker each sends requests to. Routes
+/// to workers that were deleted since still match requests, which then fail. The workers are
+/// looked up on the account that owns the zone.
+pub fn inspect(user: &GlobalUser, zone: &str, options: &TableOptions) -> Result<()> {
+    let client = http::legacy_auth_client(user);
+    let info = resolve(&client, zone)?;
+    let scripts_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+        info.account.id
+    );
+    let scripts: HashSet<String> = deployed::get::<Vec<Script>>(&client, &scripts_addr)?
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not list the workers on account {} to check the routes against",
+                info.account.id
+            )
+        })?
+        .into_iter()
+        .map(|script| script.id)
+        .collect();
+
+    let mut routes = route::fetch(user, &info.id)?;
+    if routes.is_empty() {
+        StdErr::info(&format!("There are no routes on {}", zone));
+        return Ok(());
+    }
+    routes.sort_by(|a, b| a.pattern.cmp(&b.pattern));
+    let rows = rows(&routes, &scripts);
+    let dangling = rows
+        .iter()
+        .filter(|row| row["status"] == "missing worker")
+        .count();
+    Table::new(&["id", "pattern", "script", "status"], rows).print(options)?;
+    if dangling > 0 {
+        StdErr::warn(&format!(
+            "{} route(s) send requests to workers that don't exist, delete them with `wrangler route delete <id>`",
+            dangling
+        ));
+    }
+    Ok(())
+}
+
+// Zone IDs are 32 hex digits, anything else is looked up as a name
+fn resolve(client: &reqwest::blocking::Client, zone: &str) -> Result<ZoneInfo> {
+    if zone.len() == 32 && zone.chars().all(|c| c.is_ascii_hexdigit()) {
+        let addr = format!("https://api.cloudflare.com/client/v4/zones/{}", zone);
+        return deployed::get::<ZoneInfo>(client, &addr)?
+            .ok_or_else(|| anyhow::anyhow!("There's no zone with the ID {}", zone));
+    }
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/zones?name={}",
+        utf8_percent_encode(zone, NON_ALPHANUMERIC)
+    );
+    deployed::get::<Vec<ZoneInfo>>(client, &addr)?
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("There's no zone named {}", zone))
+}
+
+fn rows(routes: &[Route], scripts: &HashSet<String>) -> Vec<Value> {
+    routes
+        .iter()
+        .map(|route| {
+            let status = match &route.script {
+                None => "workers off",
+                Some(script) if scripts.contains(script) => "ok",
+                Some(_) => "missing worker",
+            };
+            json!({
+                "id": route.id,
+                "pattern": route.pattern,
+                "script": route.script,
+                "status": status,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_to_deleted_workers_stand_out() {
+        let route = |pattern: &str, script: Option<&str>| Route {
+            id: Some(pattern.to_string()),
+            script: script.map(str::to_string),
+            pattern: pattern.to_string(),
+        };
+        let routes = vec![
+            route("example.com/*", Some("site")),
+            route("example.com/api/*", Some("old-api")),
+            route("example.com/static/*", None),
+        ];
+        let scripts = vec!["site".to_string()].into_iter().collect();
+
+        let statuses: Vec<Value> = rows(&routes, &scripts)
+            .into_iter()
+            .map(|row| row["status"].clone())
+            .collect();
+        assert_eq!(statuses, vec!["ok", "missing worker", "workers off"]);
+    }
+}
@@ -0,0 +1,112 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+
+/// A binding as the API lists it for a deployed script.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ScriptBinding {
+    #[serde(rename = "type")]
+    pub binding_type: String,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct BindingsDiff {
+    pub added: Vec<ScriptBinding>,
+    pub removed: Vec<ScriptBinding>,
+}
+
+impl BindingsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct BindingsResponse {
+    result: Vec<ScriptBinding>,
+}
+
+/// Fetches the bindings of the deployed script. A script that doesn't exist yet has none; any
+/// other failure gives `None`, since the bindings only feed the publish summary.
+pub fn fetch(user: &GlobalUser, target: &Target) -> Option<Vec<ScriptBinding>> {
+    let account_id = target.account_id.load().ok()?;
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/bindings",
+        account_id, target.name,
+    );
+
+    let client = http::legacy_auth_client(user);
+    let res = http::trace::send(&client, client.get(&addr)).ok()?;
+    match res.status() {
+        StatusCode::NOT_FOUND => Some(Vec::new()),
+        status if status.is_success() => res
+            .json::<BindingsResponse>()
+            .ok()
+            .map(|response| response.result),
+        _ => None,
+    }
+}
+
+/// What changed between two sets of bindings. Secrets are left out: they aren't part of the
+/// configuration, and survive a publish.
+pub fn diff(before: &[ScriptBinding], after: &[ScriptBinding]) -> BindingsDiff {
+    let missing_from = |bindings: &[ScriptBinding], other: &[ScriptBinding]| {
+        let mut missing = bindings
+            .iter()
+            .filter(|b| b.binding_type != "secret_text" && !other.contains(b))
+            .cloned()
+            .collect::<Vec<_>>();
+        missing.sort();
+        missing
+    };
+
+    BindingsDiff {
+        added: missing_from(after, before),
+        removed: missing_from(before, after),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(binding_type: &str, name: &str) -> ScriptBinding {
+        ScriptBinding {
+            binding_type: binding_type.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn it_diffs_bindings() {
+        let before = vec![
+            binding("kv_namespace", "CACHE"),
+            binding("plain_text", "OLD"),
+            binding("secret_text", "TOKEN"),
+        ];
+        let after = vec![
+            binding("kv_namespace", "CACHE"),
+            binding("plain_text", "NEW"),
+        ];
+        assert_eq!(
+            diff(&before, &after),
+            BindingsDiff {
+                added: vec![binding("plain_text", "NEW")],
+                removed: vec![binding("plain_text", "OLD")],
+            }
+        );
+    }
+
+    #[test]
+    fn a_changed_type_is_a_removal_and_an_addition() {
+        let before = vec![binding("plain_text", "STORE")];
+        let after = vec![binding("kv_namespace", "STORE")];
+        let diff = diff(&before, &after);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.removed.len(), 1);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use cloudflare::endpoints::workers::ListRoutes;
+use cloudflare::framework::apiclient::ApiClient;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{DurableObjectsClass, KvNamespace, R2Bucket};
+
+/// The settings of a script as it's deployed, as far as the API reports them.
+#[derive(Debug, Default, PartialEq)]
+pub struct DeployedScript {
+    pub name: String,
+    pub account_id: String,
+    pub workers_dev: Option<bool>,
+    pub usage_model: Option<String>,
+    pub compatibility_date: Option<String>,
+    pub kv_namespaces: Vec<KvNamespace>,
+    pub r2_buckets: Vec<R2Bucket>,
+    pub vars: BTreeMap<String, String>,
+    /// Only the names, the API never returns the values
+    pub secrets: Vec<String>,
+    pub durable_objects: Vec<DurableObjectsClass>,
+    /// Bindings that can't be described without local files, e.g. wasm modules, as
+    /// `name (type)`
+    pub other_bindings: Vec<String>,
+    /// `None` when there was no zone to list routes for
+    pub routes: Option<Vec<String>>,
+    pub crons: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiResult<T> {
+    result: T,
+}
+
+#[derive(Deserialize)]
+struct Schedules {
+    schedules: Vec<Schedule>,
+}
+
+#[derive(Deserialize)]
+struct Schedule {
+    cron: String,
+}
+
+#[derive(Deserialize)]
+struct UsageModel {
+    usage_model: String,
+}
+
+#[derive(Deserialize)]
+struct Subdomain {
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct Script {
+    id: String,
+    tag: Option<String>,
+    compatibility_date: Option<String>,
+    migration_tag: Option<String>,
+}
+
+/// Reads everything wrangler.toml could configure for `name` from the API. Routes are only
+/// listed when there's a zone to list them for.
+pub fn fetch(
+    user: &GlobalUser,
+    account_id: &str,
+    name: &str,
+    zone_id: Option<&str>,
+) -> Result<DeployedScript> {
+    let client = http::legacy_auth_client(user);
+    let script_addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        account_id, name
+    );
+
+    let bindings: Vec<Value> = match get(&client, &format!("{}/bindings", script_addr))? {
+        Some(bindings) => bindings,
+        None => anyhow::bail!("There's no worker named {} on this account", name),
+    };
+
+    let mut deployed = DeployedScript {
+        name: name.to_string(),
+        account_id: account_id.to_string(),
+        ..DeployedScript::default()
+    };
+    for binding in &bindings {
+        deployed.add_binding(binding);
+    }
+
+    deployed.crons = get::<Schedules>(&client, &format!("{}/schedules", script_addr))?
+        .map(|schedules| schedules.schedules.into_iter().map(|s| s.cron).collect())
+        .unwrap_or_default();
+    deployed.usage_model = get::<UsageModel>(&client, &format!("{}/usage-model", script_addr))?
+        .map(|usage| usage.usage_model);
+    deployed.workers_dev = get::<Subdomain>(&client, &format!("{}/subdomain", script_addr))?
+        .map(|subdomain| subdomain.enabled);
+    deployed.compatibility_date = get::<Vec<Script>>(
+        &client,
+        &format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+            account_id
+        ),
+    )?
+    .and_then(|scripts| scripts.into_iter().find(|script| script.id == name))
+    .and_then(|script| script.compatibility_date);
+
+    if let Some(zone_identifier) = zone_id {
+        let routes = http::cf_v4_client(user)?
+            .request(&ListRoutes { zone_identifier })
+            .map_err(|e| http::api_error(e, None))?
+            .result;
+        let mut patterns: Vec<String> = routes
+            .into_iter()
+            .filter(|route| route.script.as_deref() == Some(name))
+            .map(|route| route.pattern)
+            .collect();
+        patterns.sort();
+        deployed.routes = Some(patterns);
+    }
+
+    Ok(deployed)
+}
+
+/// The tag of the last Durable Objects migration applied to `name`, if it has any.
+pub fn migration_tag(client: &Client, account_id: &str, name: &str) -> Result<Option<String>> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+        account_id
+    );
+    Ok(get::<Vec<Script>>(client, &addr)?
+        .and_then(|scripts| scripts.into_iter().find(|script| script.id == name))
+        .and_then(|script| script.migration_tag))
+}
+
+/// The id the API gives `name` internally, which some endpoints use instead of the name.
+pub fn script_tag(client: &Client, account_id: &str, name: &str) -> Result<Option<String>> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+        account_id
+    );
+    Ok(get::<Vec<Script>>(client, &addr)?
+        .and_then(|scripts| scripts.into_iter().find(|script| script.id == name))
+        .and_then(|script| script.tag))
+}
+
+impl DeployedScript {
+    fn add_binding(&mut self, binding: &Value) {
+        let field = |key: &str| binding[key].as_str().unwrap_or_default().to_string();
+        let name = field("name");
+        match binding["type"].as_str().unwrap_or_default() {
+            "kv_namespace" => self.kv_namespaces.push(KvNamespace {
+                binding: name,
+                id: field("namespace_id"),
+            }),
+            "r2_bucket" => self.r2_buckets.push(R2Bucket {
+                binding: name,
+                bucket_name: field("bucket_name"),
+            }),
+            "plain_text" => {
+                self.vars.insert(name, field("text"));
+            }
+            "secret_text" => self.secrets.push(name),
+            "durable_object_namespace" => self.durable_objects.push(DurableObjectsClass {
+                binding: name,
+                class_name: field("class_name"),
+                script_name: binding["script_name"].as_str().map(|s| s.to_string()),
+                preview_script_name: None,
+                jurisdiction: serde_json::from_value(binding["jurisdiction"].clone()).ok(),
+                location_hint: serde_json::from_value(binding["location_hint"].clone()).ok(),
+            }),
+            other => self.other_bindings.push(format!("{} ({})", name, other)),
+        }
+    }
+}
+
+/// GETs an API result, or `None` if there's nothing at `addr`.
+pub(crate) fn get<T: DeserializeOwned>(client: &Client, addr: &str) -> Result<Option<T>> {
+    let res = http::trace::send(client, client.get(addr))?;
+    match res.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => Ok(Some(res.json::<ApiResult<T>>()?.result)),
+        _ => anyhow::bail!(crate::format_api_errors(res.text()?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bindings_are_sorted_by_type() {
+        let mut deployed = DeployedScript::default();
+        for binding in &[
+            json!({"type": "kv_namespace", "name": "CACHE", "namespace_id": "abc"}),
+            json!({"type": "r2_bucket", "name": "UPLOADS", "bucket_name": "uploads"}),
+            json!({"type": "plain_text", "name": "MODE", "text": "production"}),
+            json!({"type": "secret_text", "name": "TOKEN"}),
+            json!({"type": "durable_object_namespace", "name": "ROOMS", "class_name": "Room"}),
+            json!({"type": "wasm_module", "name": "WASM", "part": "WASM"}),
+        ] {
+            deployed.add_binding(binding);
+        }
+
+        assert_eq!(
+            deployed.kv_namespaces,
+            vec![KvNamespace {
+                binding: "CACHE".to_string(),
+                id: "abc".to_string()
+            }]
+        );
+        assert_eq!(deployed.r2_buckets[0].bucket_name, "uploads");
+        assert_eq!(deployed.vars["MODE"], "production");
+        assert_eq!(deployed.secrets, vec!["TOKEN"]);
+        assert_eq!(deployed.durable_objects[0].class_name, "Room");
+        assert_eq!(deployed.durable_objects[0].script_name, None);
+        assert_eq!(deployed.other_bindings, vec!["WASM (wasm_module)"]);
+    }
+}
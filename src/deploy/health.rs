@@ -0,0 +1,113 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use url::Url;
+
+use crate::deploy;
+use crate::http;
+use crate::settings::toml::HealthCheck;
+use crate::terminal::message::{Message, StdErr};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Requests the health check URL until it responds with the expected status, or fails once the
+/// timeout is up. Routes and new scripts can take a few seconds to propagate, so a failed
+/// request is only final at the end.
+pub fn check(health_check: &HealthCheck, urls: &[String]) -> Result<()> {
+    let url = resolve(&health_check.url, urls)?;
+    let timeout = health_check.timeout()?;
+    let client = http::client();
+
+    StdErr::working(&format!("Checking {}", url));
+    let started = Instant::now();
+    loop {
+        let last = match client.get(url.clone()).timeout(timeout).send() {
+            Ok(res) if res.status().as_u16() == health_check.expected_status => {
+                StdErr::success(&format!("{} is healthy", url));
+                return Ok(());
+            }
+            Ok(res) => format!("it responded with {}", res.status()),
+            Err(e) => e.to_string(),
+        };
+        log::info!("health check failed: {}", last);
+
+        if started.elapsed() + POLL_INTERVAL > timeout {
+            anyhow::bail!(
+                "Health check failed: expected {} from {} within {}, but {}",
+                health_check.expected_status,
+                url,
+                health_check.timeout,
+                last
+            );
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// A path is checked on the first URL the script was deployed to, preferring workers.dev since
+// routes can be patterns.
+fn resolve(url: &str, deployed: &[String]) -> Result<Url> {
+    if let Ok(url) = Url::parse(url) {
+        return Ok(url);
+    }
+
+    let base = deployed
+        .iter()
+        .find(|url| url.ends_with(".workers.dev"))
+        .or_else(|| deployed.first())
+        .ok_or_else(|| {
+            anyhow!(
+                "The health check URL {:?} is a path, but the worker isn't deployed to any URLs",
+                url
+            )
+        })?;
+    Ok(Url::parse(&format!(
+        "{}/{}",
+        deploy::url_of(base),
+        url.trim_start_matches('/')
+    ))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deploy::{DeployResults, RouteUploadResult};
+    use crate::settings::toml::Route;
+
+    fn route(pattern: &str, script: &str) -> Route {
+        Route {
+            id: None,
+            script: Some(script.to_string()),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn paths_are_resolved_against_the_deployed_urls() {
+        let mut results = DeployResults::default();
+        results.add_routes(vec![
+            RouteUploadResult::New(route("example.com/api/*", "worker")),
+            RouteUploadResult::Conflict(route("example.com/*", "other")),
+        ]);
+        results.add_workers_dev("https://worker.me.workers.dev".to_string());
+        let deployed = results.urls;
+        assert_eq!(deployed.len(), 2);
+        assert_eq!(
+            resolve("/healthz", &deployed).unwrap().as_str(),
+            "https://worker.me.workers.dev/healthz"
+        );
+        assert_eq!(
+            resolve("healthz", &deployed[..1]).unwrap().as_str(),
+            "https://example.com/api/healthz"
+        );
+        assert_eq!(
+            resolve("https://status.example.com/", &deployed)
+                .unwrap()
+                .as_str(),
+            "https://status.example.com/"
+        );
+        assert!(resolve("/healthz", &[]).is_err());
+        assert_eq!(deploy::url_of(&deployed[0]), "https://example.com/api");
+    }
+}
@@ -0,0 +1,292 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use chrono::{TimeZone, Utc};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::kv;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{DeployLock, Target};
+use crate::terminal::message::{Message, StdErr};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// KV writes can take a moment to be read back, even from the same location
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+const PACE: Pace = Pace {
+    poll: POLL_INTERVAL,
+    settle: SETTLE_TIME,
+};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Holder {
+    owner: String,
+    host: String,
+    /// Unix seconds
+    since: u64,
+    expires: u64,
+}
+
+impl Holder {
+    fn describe(&self) -> String {
+        format!(
+            "{} (since {})",
+            self.host,
+            Utc.timestamp(self.since as i64, 0).to_rfc3339()
+        )
+    }
+}
+
+// How long to wait between looks at the lock
+struct Pace {
+    poll: Duration,
+    settle: Duration,
+}
+
+// Where the lock is kept, a KV value outside of tests
+trait Store: Send {
+    fn get(&self) -> Result<Option<Holder>>;
+    fn put(&self, holder: &Holder, ttl: Duration) -> Result<()>;
+    fn delete(&self) -> Result<()>;
+}
+
+struct KvStore {
+    client: Client,
+    url: String,
+}
+
+/// The lock of a publish, released when it's dropped.
+pub struct Lock {
+    store: Box<dyn Store>,
+    owner: String,
+}
+
+/// Takes the publish lock of the target when `[deploy] lock` is set, waiting for the publish
+/// that holds it to finish. KV isn't strongly consistent, so this only keeps out publishes
+/// that start more than a few seconds apart, which is what concurrent CI jobs look like.
+pub fn acquire(user: &GlobalUser, target: &Target) -> Result<Option<Lock>> {
+    let config = match target
+        .deploy
+        .as_ref()
+        .and_then(|deploy| deploy.lock.as_ref())
+    {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    let store = KvStore {
+        client: http::legacy_auth_client(user),
+        url: url(target, config)?,
+    };
+    take(Box::new(store), &target.name, config, &PACE).map(Some)
+}
+
+fn take(store: Box<dyn Store>, name: &str, config: &DeployLock, pace: &Pace) -> Result<Lock> {
+    let wait = config.wait()?;
+    let ttl = config.ttl()?;
+    let lock = Lock {
+        store,
+        owner: uuid::Uuid::new_v4().to_string(),
+    };
+
+    let started = Instant::now();
+    loop {
+        match lock.store.get()? {
+            Some(holder) if holder.owner != lock.owner && holder.expires > now() => {
+                if started.elapsed() + pace.poll > wait {
+                    anyhow::bail!(
+                        "Another publish of {} holds the lock: {}. It's released when that publish ends, or after {}",
+                        name,
+                        holder.describe(),
+                        config.ttl
+                    );
+                }
+                StdErr::working(&format!(
+                    "Waiting for another publish of {} to finish: {}",
+                    name,
+                    holder.describe()
+                ));
+                thread::sleep(pace.poll);
+            }
+            Some(holder) if holder.owner == lock.owner => return Ok(lock),
+            _ => {
+                let since = now();
+                let holder = Holder {
+                    owner: lock.owner.clone(),
+                    host: hostname(),
+                    since,
+                    expires: since + ttl.as_secs(),
+                };
+                lock.store.put(&holder, ttl)?;
+                // whoever wrote last wins, the others see it on the next round
+                thread::sleep(pace.settle);
+            }
+        }
+    }
+}
+
+impl Lock {
+    fn release(&self) -> Result<()> {
+        // a lock that expired may have been taken by someone else since
+        if let Some(holder) = self.store.get()? {
+            if holder.owner == self.owner {
+                self.store.delete()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Store for KvStore {
+    fn get(&self) -> Result<Option<Holder>> {
+        let res = http::trace::send(&self.client, self.client.get(&self.url))?;
+        match res.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(res.json().ok()),
+            status => anyhow::bail!("Could not read the publish lock: {}", status),
+        }
+    }
+
+    fn put(&self, holder: &Holder, ttl: Duration) -> Result<()> {
+        let request = self
+            .client
+            .put(&self.url)
+            .query(&[("expiration_ttl", ttl.as_secs())])
+            .body(serde_json::to_string(holder)?);
+        let res = http::trace::send(&self.client, request)?;
+        if !res.status().is_success() {
+            anyhow::bail!("Could not take the publish lock: {}", res.status());
+        }
+        Ok(())
+    }
+
+    fn delete(&self) -> Result<()> {
+        http::trace::send(&self.client, self.client.delete(&self.url))?;
+        Ok(())
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Err(e) = self.release() {
+            StdErr::warn(&format!(
+                "Could not release the publish lock, it expires on its own: {}",
+                e
+            ));
+        }
+    }
+}
+
+fn url(target: &Target, config: &DeployLock) -> Result<String> {
+    Ok(format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/values/{}",
+        target.account_id.load()?,
+        config.namespace_id,
+        kv::url_encode_key(&format!("wrangler-publish-lock/{}", target.name))
+    ))
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| "unknown host".to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const FAST: Pace = Pace {
+        poll: Duration::from_millis(1),
+        settle: Duration::from_millis(0),
+    };
+
+    #[derive(Clone, Default)]
+    struct MemoryStore(Arc<Mutex<Option<Holder>>>);
+
+    impl Store for MemoryStore {
+        fn get(&self) -> Result<Option<Holder>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn put(&self, holder: &Holder, _ttl: Duration) -> Result<()> {
+            *self.0.lock().unwrap() = Some(holder.clone());
+            Ok(())
+        }
+
+        fn delete(&self) -> Result<()> {
+            *self.0.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    fn config(wait: &str) -> DeployLock {
+        toml::from_str(&format!("namespace_id = \"abc\"\nwait = \"{}\"", wait)).unwrap()
+    }
+
+    fn held_by(owner: &str, expires: u64) -> Holder {
+        Holder {
+            owner: owner.to_string(),
+            host: "ci-runner".to_string(),
+            since: now(),
+            expires,
+        }
+    }
+
+    #[test]
+    fn a_free_lock_is_taken_and_released_when_dropped() {
+        let store = MemoryStore::default();
+        let lock = take(Box::new(store.clone()), "worker", &config("0s"), &FAST).unwrap();
+        assert_eq!(store.get().unwrap().unwrap().owner, lock.owner);
+
+        drop(lock);
+        assert!(store.get().unwrap().is_none());
+    }
+
+    #[test]
+    fn a_held_lock_is_waited_for_until_wait_runs_out() {
+        let store = MemoryStore::default();
+        store
+            .put(&held_by("other", now() + 600), Duration::from_secs(600))
+            .unwrap();
+
+        let error = take(Box::new(store.clone()), "worker", &config("0s"), &FAST)
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("ci-runner"));
+        assert_eq!(store.get().unwrap().unwrap().owner, "other");
+    }
+
+    #[test]
+    fn an_expired_lock_is_taken_over() {
+        let store = MemoryStore::default();
+        store
+            .put(&held_by("other", now() - 1), Duration::from_secs(600))
+            .unwrap();
+
+        let lock = take(Box::new(store.clone()), "worker", &config("0s"), &FAST).unwrap();
+        assert_eq!(store.get().unwrap().unwrap().owner, lock.owner);
+    }
+
+    #[test]
+    fn a_lock_taken_over_by_someone_else_is_left_alone() {
+        let store = MemoryStore::default();
+        let lock = take(Box::new(store.clone()), "worker", &config("0s"), &FAST).unwrap();
+        store
+            .put(&held_by("other", now() + 600), Duration::from_secs(600))
+            .unwrap();
+
+        drop(lock);
+        assert_eq!(store.get().unwrap().unwrap().owner, "other");
+    }
+}
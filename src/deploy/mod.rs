@@ -1,3 +1,8 @@
+pub mod bindings;
+pub mod deployed;
+pub mod health;
+pub mod lock;
+pub mod rollback;
 mod schedule;
 mod zoned;
 mod zoneless;
@@ -5,7 +10,7 @@ mod zoneless;
 use anyhow::Result;
 use indicatif::{ProgressBar, ProgressStyle};
 pub use schedule::ScheduleTarget;
-pub use zoned::ZonedTarget;
+pub use zoned::{publish_routes, RouteUploadResult, ZonedTarget};
 pub use zoneless::ZonelessTarget;
 
 use crate::settings::global_user::GlobalUser;
@@ -29,13 +34,13 @@ pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<Depl
         match target {
             DeployTarget::Zoned(zoned) => {
                 spinner.set_message("Configuring routes...");
-                let route_urls = zoned.deploy(user)?;
-                results.urls.extend(route_urls);
+                let published = zoned.deploy(user)?;
+                results.add_routes(published);
             }
             DeployTarget::Zoneless(zoneless) => {
                 spinner.set_message("Configuring workers.dev...");
                 let worker_dev = zoneless.deploy(user)?;
-                results.urls.push(worker_dev);
+                results.add_workers_dev(worker_dev);
             }
             DeployTarget::Schedule(schedule) => {
                 spinner.set_message("Configuring schedules...");
@@ -50,8 +55,54 @@ pub fn deploy(user: &GlobalUser, deploy_targets: &[DeployTarget]) -> Result<Depl
     Ok(results)
 }
 
+/// The URL a deployed URL or route pattern is reached at, e.g. `https://example.com/api` for
+/// `example.com/api/*`.
+pub fn url_of(deployed: &str) -> String {
+    let url = deployed.trim_end_matches('*').trim_end_matches('/');
+    if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
+
 #[derive(Default)]
 pub struct DeployResults {
+    /// Every URL the script is reachable at: the workers.dev URL and the route patterns.
     pub urls: Vec<String>,
+    pub workers_dev: Option<String>,
+    /// The patterns of the routes that point at the script.
+    pub routes: Vec<String>,
     pub schedules: Vec<String>,
+    /// What to tell the user the script was published to: the workers.dev URL, and each
+    /// route with what happened to it.
+    pub published_to: Vec<String>,
+}
+
+impl DeployResults {
+    /// Records a route the script is reachable at.
+    pub fn add_route(&mut self, pattern: String) {
+        self.urls.push(pattern.clone());
+        self.routes.push(pattern);
+    }
+
+    pub(crate) fn add_workers_dev(&mut self, url: String) {
+        self.urls.push(url.clone());
+        self.published_to.push(url.clone());
+        self.workers_dev = Some(url);
+    }
+
+    // A route that another script already had, or that couldn't be created, doesn't reach
+    // this one, so it's only reported
+    pub(crate) fn add_routes(&mut self, published: Vec<RouteUploadResult>) {
+        for result in published {
+            self.published_to.push(result.to_string());
+            match result {
+                RouteUploadResult::New(route) | RouteUploadResult::Same(route) => {
+                    self.add_route(route.pattern)
+                }
+                RouteUploadResult::Conflict(_) | RouteUploadResult::Error(_) => {}
+            }
+        }
+    }
 }
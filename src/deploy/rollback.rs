@@ -0,0 +1,352 @@
+use anyhow::Result;
+use reqwest::blocking::multipart::{Form, Part};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::deploy::deployed;
+use crate::http;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::Target;
+use crate::terminal::message::{Message, StdErr};
+
+/// The deployed upload as it was before a publish, code and metadata, so it can be put back if
+/// the publish turns out to be broken.
+#[derive(Debug)]
+pub struct Snapshot {
+    parts: Vec<ScriptPart>,
+    /// Whether the parts are modules, the first one being the main module
+    modules: bool,
+    bindings: Vec<Value>,
+    usage_model: Option<String>,
+    compatibility_date: Option<String>,
+    compatibility_flags: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+struct ScriptPart {
+    name: String,
+    file_name: Option<String>,
+    content_type: String,
+    content: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct UsageModel {
+    usage_model: String,
+}
+
+#[derive(Deserialize)]
+struct Script {
+    id: String,
+    compatibility_date: Option<String>,
+    #[serde(default)]
+    compatibility_flags: Vec<String>,
+}
+
+fn script_addr(target: &Target) -> Result<String> {
+    Ok(format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
+        target.account_id.load()?,
+        target.name,
+    ))
+}
+
+/// Downloads the deployed script and its settings, or `None` if it hasn't been published yet.
+pub fn snapshot(user: &GlobalUser, target: &Target) -> Result<Option<Snapshot>> {
+    let client = http::legacy_auth_client(user);
+    let addr = script_addr(target)?;
+    let res = http::trace::send(&client, client.get(&addr))?;
+
+    let (parts, modules) = match res.status() {
+        StatusCode::NOT_FOUND => return Ok(None),
+        status if status.is_success() => {
+            let content_type = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("application/javascript")
+                .to_string();
+            let content = res.bytes()?.to_vec();
+            match boundary(&content_type) {
+                Some(boundary) => (parse_multipart(&content, &boundary)?, true),
+                None => (
+                    vec![ScriptPart {
+                        name: "script".to_string(),
+                        file_name: Some("script".to_string()),
+                        content_type,
+                        content,
+                    }],
+                    false,
+                ),
+            }
+        }
+        status => anyhow::bail!(
+            "Could not save the deployed script to roll back to: {}\n{}",
+            status,
+            crate::format_api_errors(res.text()?)
+        ),
+    };
+
+    let bindings = deployed::get::<Vec<Value>>(&client, &format!("{}/bindings", addr))?
+        .map(restorable)
+        .unwrap_or_default();
+    let usage_model = deployed::get::<UsageModel>(&client, &format!("{}/usage-model", addr))?
+        .map(|usage| usage.usage_model);
+    let listed = deployed::get::<Vec<Script>>(
+        &client,
+        &format!(
+            "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+            target.account_id.load()?
+        ),
+    )?
+    .and_then(|scripts| scripts.into_iter().find(|script| script.id == target.name));
+    let (compatibility_date, compatibility_flags) = match listed {
+        Some(script) => (script.compatibility_date, script.compatibility_flags),
+        None => (None, Vec::new()),
+    };
+
+    Ok(Some(Snapshot {
+        parts,
+        modules,
+        bindings,
+        usage_model,
+        compatibility_date,
+        compatibility_flags,
+    }))
+}
+
+/// Uploads the script from before the publish again, with the bindings and settings it had.
+/// Routes are left as they are, they point at the script by name.
+pub fn restore(user: &GlobalUser, target: &Target, snapshot: Snapshot) -> Result<()> {
+    StdErr::working(&format!(
+        "Rolling {} back to the previous deployment",
+        target.name
+    ));
+    let client = http::legacy_auth_client(user);
+    let request = client
+        .put(&script_addr(target)?)
+        .multipart(snapshot.form()?);
+    let res = http::trace::send(&client, request)?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "Rolling back failed: {}\n{}",
+            res.status(),
+            crate::format_api_errors(res.text()?)
+        );
+    }
+    StdErr::success("Rolled back to the previous deployment");
+    Ok(())
+}
+
+impl Snapshot {
+    fn metadata(&self) -> Value {
+        let mut metadata = json!({
+            "bindings": self.bindings,
+            "usage_model": self.usage_model,
+        });
+        let entry = if self.modules {
+            "main_module"
+        } else {
+            "body_part"
+        };
+        if let Some(first) = self.parts.first() {
+            metadata[entry] = json!(first.name);
+        }
+        if let Some(date) = &self.compatibility_date {
+            metadata["compatibility_date"] = json!(date);
+        }
+        if !self.compatibility_flags.is_empty() {
+            metadata["compatibility_flags"] = json!(self.compatibility_flags);
+        }
+        metadata
+    }
+
+    /// The same form `upload::form::build` would have sent for the deployed script, metadata
+    /// part first.
+    fn form(self) -> Result<Form> {
+        let metadata = Part::text(self.metadata().to_string())
+            .file_name("metadata.json")
+            .mime_str("application/json")?;
+        let mut form = Form::new().part("metadata", metadata);
+        for part in self.parts {
+            let ScriptPart {
+                name,
+                file_name,
+                content_type,
+                content,
+            } = part;
+            let upload = Part::bytes(content)
+                .file_name(file_name.unwrap_or_else(|| name.clone()))
+                .mime_str(&content_type)?;
+            form = form.part(name, upload);
+        }
+        Ok(form)
+    }
+}
+
+/// The deployed bindings that can be sent back as they are. Secrets are left out, the API
+/// never returns their values and keeps them across uploads anyway.
+fn restorable(bindings: Vec<Value>) -> Vec<Value> {
+    bindings
+        .into_iter()
+        .filter(|binding| binding["type"] != "secret_text")
+        .collect()
+}
+
+fn boundary(content_type: &str) -> Option<String> {
+    if !content_type.starts_with("multipart/") {
+        return None;
+    }
+    content_type.split(';').find_map(|param| {
+        let (key, value) = param.trim().split_once('=')?;
+        (key.eq_ignore_ascii_case("boundary")).then(|| value.trim_matches('"').to_string())
+    })
+}
+
+/// Splits a `multipart/form-data` body, as the API returns it for modules workers, into its
+/// parts.
+fn parse_multipart(body: &[u8], boundary: &str) -> Result<Vec<ScriptPart>> {
+    let delimiter = format!("--{}", boundary).into_bytes();
+    let mut parts = Vec::new();
+
+    let mut sections = split(body, &delimiter).into_iter().skip(1);
+    while let Some(section) = sections.next() {
+        if section.starts_with(b"--") {
+            break;
+        }
+        let section = section.strip_prefix(b"\r\n").unwrap_or(section);
+        let header_end = find(section, b"\r\n\r\n")
+            .ok_or_else(|| anyhow::anyhow!("The deployed script has a malformed part"))?;
+        let headers = String::from_utf8_lossy(&section[..header_end]);
+        let content = &section[header_end + 4..];
+        let content = content.strip_suffix(b"\r\n").unwrap_or(content);
+
+        let mut part = ScriptPart {
+            name: String::new(),
+            file_name: None,
+            content_type: "application/octet-stream".to_string(),
+            content: content.to_vec(),
+        };
+        for header in headers.lines() {
+            let (key, value) = match header.split_once(':') {
+                Some(header) => header,
+                None => continue,
+            };
+            if key.eq_ignore_ascii_case("content-type") {
+                part.content_type = value.trim().to_string();
+            } else if key.eq_ignore_ascii_case("content-disposition") {
+                for param in value.split(';').skip(1) {
+                    if let Some((key, value)) = param.trim().split_once('=') {
+                        let value = value.trim_matches('"').to_string();
+                        match key {
+                            "name" => part.name = value,
+                            "filename" => part.file_name = Some(value),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        parts.push(part);
+    }
+
+    if parts.is_empty() {
+        anyhow::bail!("The deployed script has no parts to roll back to");
+    }
+    Ok(parts)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn split<'a>(mut body: &'a [u8], delimiter: &[u8]) -> Vec<&'a [u8]> {
+    let mut sections = Vec::new();
+    while let Some(at) = find(body, delimiter) {
+        sections.push(&body[..at]);
+        body = &body[at + delimiter.len()..];
+    }
+    sections.push(body);
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(modules: bool, parts: Vec<ScriptPart>) -> Snapshot {
+        Snapshot {
+            parts,
+            modules,
+            bindings: restorable(vec![
+                json!({"type": "kv_namespace", "name": "CACHE", "namespace_id": "abc"}),
+                json!({"type": "secret_text", "name": "TOKEN"}),
+            ]),
+            usage_model: Some("bundled".to_string()),
+            compatibility_date: Some("2021-11-01".to_string()),
+            compatibility_flags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn restored_metadata_keeps_the_bindings() {
+        let snapshot = script(
+            false,
+            vec![ScriptPart {
+                name: "script".to_string(),
+                file_name: Some("script".to_string()),
+                content_type: "application/javascript".to_string(),
+                content: b"addEventListener('fetch', () => {})".to_vec(),
+            }],
+        );
+        let metadata = snapshot.metadata();
+
+        assert_eq!(metadata["body_part"], "script");
+        assert_eq!(
+            metadata["bindings"],
+            json!([{"type": "kv_namespace", "name": "CACHE", "namespace_id": "abc"}])
+        );
+        assert_eq!(metadata["usage_model"], "bundled");
+        assert_eq!(metadata["compatibility_date"], "2021-11-01");
+        assert!(snapshot.form().is_ok());
+    }
+
+    #[test]
+    fn modules_are_restored_with_their_main_module() {
+        let body = b"--xyz\r\n\
+Content-Disposition: form-data; name=\"index.mjs\"; filename=\"index.mjs\"\r\n\
+Content-Type: application/javascript+module\r\n\r\n\
+export default {}\r\n\
+--xyz\r\n\
+Content-Disposition: form-data; name=\"util.mjs\"\r\n\r\n\
+export const a = 1\r\n\
+--xyz--\r\n";
+        let content_type = "multipart/form-data; boundary=xyz";
+        let parts = parse_multipart(body, &boundary(content_type).unwrap()).unwrap();
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].name, "index.mjs");
+        assert_eq!(parts[0].content_type, "application/javascript+module");
+        assert_eq!(parts[0].content, b"export default {}");
+        assert_eq!(parts[1].file_name, None);
+        assert_eq!(parts[1].content, b"export const a = 1");
+
+        let metadata = script(true, parts).metadata();
+        assert_eq!(metadata["main_module"], "index.mjs");
+        assert_eq!(metadata["bindings"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn plain_scripts_have_no_boundary() {
+        assert_eq!(boundary("application/javascript"), None);
+        assert_eq!(
+            boundary("multipart/form-data; boundary=\"abc\""),
+            Some("abc".to_string())
+        );
+    }
+}
@@ -35,11 +35,13 @@ impl ScheduleTarget {
         let client = http::legacy_auth_client(user);
 
         log::info!("Pushing {} schedule(s)...", self.crons.len());
-        let res = client
-            .put(&schedule_worker_addr)
-            .header("Content-Type", "application/json")
-            .body(build_schedules_request(&self.crons))
-            .send()?;
+        let res = http::trace::send(
+            &client,
+            client
+                .put(&schedule_worker_addr)
+                .header("Content-Type", "application/json")
+                .body(build_schedules_request(&self.crons)),
+        )?;
 
         let status = res.status();
         let text = res.text()?;
@@ -53,14 +53,10 @@ impl ZonedTarget {
         }
     }
 
-    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<String>> {
+    pub fn deploy(&self, user: &GlobalUser) -> Result<Vec<RouteUploadResult>> {
         log::info!("publishing to zone {}", self.zone_id);
 
-        let published_routes = publish_routes(&user, self)?;
-
-        let display_results: Vec<String> = published_routes.iter().map(|r| r.to_string()).collect();
-
-        Ok(display_results)
+        publish_routes(&user, self)
     }
 }
 
@@ -87,7 +83,7 @@ fn fetch_all(user: &GlobalUser, zone_identifier: &str) -> Result<Vec<Route>> {
 
     let routes: Vec<Route> = match client.request(&ListRoutes { zone_identifier }) {
         Ok(success) => success.result.iter().map(Route::from).collect(),
-        Err(e) => anyhow::bail!("{}", http::format_error(e, None)), // TODO: add suggestion fn
+        Err(e) => return Err(http::api_error(e, None)), // TODO: add suggestion fn
     };
 
     Ok(routes)
@@ -109,7 +105,7 @@ fn create(user: &GlobalUser, zone_identifier: &str, route: &Route) -> Result<Rou
             pattern: route.pattern.clone(),
             script: route.script.clone(),
         }),
-        Err(e) => anyhow::bail!("{}", http::format_error(e, Some(&routes_error_help))),
+        Err(e) => Err(http::api_error(e, Some(&routes_error_help))),
     }
 }
 
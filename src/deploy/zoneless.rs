@@ -36,11 +36,13 @@ impl ZonelessTarget {
         let client = http::legacy_auth_client(user);
 
         log::info!("Making public on subdomain...");
-        let res = client
-            .post(&sd_worker_addr)
-            .header("Content-type", "application/json")
-            .body(build_subdomain_request())
-            .send()?;
+        let res = http::trace::send(
+            &client,
+            client
+                .post(&sd_worker_addr)
+                .header("Content-type", "application/json")
+                .body(build_subdomain_request()),
+        )?;
 
         let status = res.status();
         let text = res.text()?;
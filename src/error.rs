@@ -0,0 +1,172 @@
+use std::error::Error;
+use std::fmt;
+
+use serde::Serialize;
+
+/// Broad classes of failure, each with its own process exit code so scripts can tell them
+/// apart without parsing messages.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorCategory {
+    /// Missing or rejected credentials.
+    Auth,
+    /// A missing or invalid configuration file.
+    Config,
+    /// The project failed to build.
+    Build,
+    /// The Cloudflare API rejected a request.
+    Api,
+    /// The Cloudflare API couldn't be reached.
+    Network,
+    /// A deployed worker doesn't match its configuration.
+    Drift,
+    /// Anything else.
+    Internal,
+}
+
+impl ErrorCategory {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::Internal => 1,
+            ErrorCategory::Config => 2,
+            ErrorCategory::Auth => 3,
+            ErrorCategory::Build => 4,
+            ErrorCategory::Api => 5,
+            ErrorCategory::Network => 6,
+            ErrorCategory::Drift => 7,
+        }
+    }
+}
+
+/// Tags an error with a category and a stable code such as `config.not_found`. The message
+/// and causes of the wrapped error are left untouched.
+#[derive(Debug)]
+pub struct WranglerError {
+    pub category: ErrorCategory,
+    pub code: String,
+    inner: anyhow::Error,
+}
+
+impl WranglerError {
+    pub fn new<E>(category: ErrorCategory, code: &str, inner: E) -> anyhow::Error
+    where
+        E: Into<anyhow::Error>,
+    {
+        let inner = inner.into();
+        // Keep the innermost tag, it's the most specific one
+        if inner.chain().any(|e| e.is::<WranglerError>()) {
+            return inner;
+        }
+
+        anyhow::Error::new(WranglerError {
+            category,
+            code: code.to_string(),
+            inner,
+        })
+    }
+}
+
+impl fmt::Display for WranglerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl Error for WranglerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.inner.chain().nth(1)
+    }
+}
+
+pub trait Categorize<T> {
+    fn categorize(self, category: ErrorCategory, code: &str) -> anyhow::Result<T>;
+}
+
+impl<T, E> Categorize<T> for Result<T, E>
+where
+    E: Into<anyhow::Error>,
+{
+    fn categorize(self, category: ErrorCategory, code: &str) -> anyhow::Result<T> {
+        self.map_err(|e| WranglerError::new(category, code, e))
+    }
+}
+
+/// The error reported by `--json` when a command fails.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ErrorOutput {
+    pub category: ErrorCategory,
+    pub code: String,
+    pub message: String,
+}
+
+impl ErrorOutput {
+    pub fn new(e: &anyhow::Error) -> ErrorOutput {
+        let (category, code) = classify(e);
+        ErrorOutput {
+            category,
+            code,
+            message: console::strip_ansi_codes(&e.to_string()).into_owned(),
+        }
+    }
+}
+
+/// Finds the category and code of an error, falling back to what its causes say about it
+/// for errors nobody tagged.
+pub fn classify(e: &anyhow::Error) -> (ErrorCategory, String) {
+    for cause in e.chain() {
+        if let Some(tagged) = cause.downcast_ref::<WranglerError>() {
+            return (tagged.category, tagged.code.clone());
+        }
+    }
+
+    for cause in e.chain() {
+        if cause.is::<reqwest::Error>() {
+            return (ErrorCategory::Network, "network.request_failed".to_string());
+        }
+        if cause.is::<toml::de::Error>() || cause.is::<config::ConfigError>() {
+            return (ErrorCategory::Config, "config.invalid".to_string());
+        }
+    }
+
+    (ErrorCategory::Internal, "internal".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn it_keeps_the_message() {
+        let e = WranglerError::new(ErrorCategory::Config, "config.invalid", anyhow!("bad toml"));
+        assert_eq!(e.to_string(), "bad toml");
+        assert_eq!(
+            ErrorOutput::new(&e),
+            ErrorOutput {
+                category: ErrorCategory::Config,
+                code: "config.invalid".to_string(),
+                message: "bad toml".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn the_innermost_tag_wins() {
+        let e: anyhow::Result<()> = Err(anyhow!("401"));
+        let e = e
+            .categorize(ErrorCategory::Auth, "auth.invalid")
+            .categorize(ErrorCategory::Api, "api.error")
+            .unwrap_err();
+        assert_eq!(
+            classify(&e),
+            (ErrorCategory::Auth, "auth.invalid".to_string())
+        );
+    }
+
+    #[test]
+    fn untagged_errors_are_internal() {
+        let e = anyhow!("oops").context("while doing something");
+        assert_eq!(classify(&e).0, ErrorCategory::Internal);
+        assert_eq!(ErrorCategory::Internal.exit_code(), 1);
+    }
+}
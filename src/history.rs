@@ -0,0 +1,274 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::get_wrangler_home_dir;
+
+// The last word of the commands that change something on Cloudflare's side
+const MUTATING: &[&str] = &[
+    "publish",
+    "rename",
+    "subdomain",
+    "put",
+    "delete",
+    "create",
+    "sync",
+    "deploy",
+    "import",
+];
+
+// Flags whose values are credentials, e.g. --api-token or --signing-key
+const SECRET_FLAGS: &[&str] = &["key", "token", "secret", "password"];
+// The arguments of each command that carry data rather than say what to change, e.g. the value
+// of `kv:key put`, which can be a credential too
+const PAYLOADS: &[(&str, &[&str])] = &[
+    ("kv:key put", &["value"]),
+    ("kv:bulk put", &["path"]),
+    ("kv:bulk delete", &["path"]),
+    ("secret sync", &["from"]),
+];
+const REDACTED: &str = "<redacted>";
+
+/// A command that was run, one JSON object per line of `~/.wrangler/history.ndjson`. The file
+/// is only ever appended to.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Entry {
+    /// RFC 3339
+    pub time: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub duration_ms: u128,
+}
+
+pub fn is_mutating(command_name: &str) -> bool {
+    command_name
+        .rsplit(' ')
+        .next()
+        .map_or(false, |verb| MUTATING.contains(&verb))
+}
+
+/// The values of the arguments in `PAYLOADS` the command was given, to pass to `record`.
+pub fn payloads(matches: &ArgMatches) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let (name, Some(subcommand)) = matches.subcommand() {
+        names.push(name);
+        matches = subcommand;
+    }
+    let command = names.join(" ");
+    PAYLOADS
+        .iter()
+        .filter(|(payload_command, _)| *payload_command == command)
+        .flat_map(|(_, args)| args.iter())
+        .filter_map(|arg| matches.values_of_lossy(arg))
+        .flatten()
+        .collect()
+}
+
+/// Appends the command to the history, leaving out credentials and the `payloads` it was
+/// given. The history never gets in the way of the command, so failing to write it is only
+/// logged.
+pub fn record(
+    command_name: &str,
+    args: &[String],
+    payloads: &[String],
+    duration: Duration,
+    result: &Result<()>,
+) {
+    let entry = Entry {
+        time: Utc::now().to_rfc3339(),
+        command: command_name.to_string(),
+        args: redact(args, payloads),
+        cwd: std::env::current_dir().ok(),
+        success: result.is_ok(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+        duration_ms: duration.as_millis(),
+    };
+    if let Err(e) = append(&entry) {
+        log::warn!("could not write the history: {}", e);
+    }
+}
+
+fn append(entry: &Entry) -> Result<()> {
+    let path = history_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
+/// The entries of the history, oldest first. Lines that don't parse, e.g. one cut short by a
+/// crash, are skipped.
+pub fn read() -> Result<Vec<Entry>> {
+    let contents = match fs::read_to_string(history_path()) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Which entries `wrangler history` shows.
+#[derive(Debug, Default)]
+pub struct Query {
+    /// Commands starting with this, e.g. "secret" or "kv:key put"
+    pub command: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub failed_only: bool,
+}
+
+impl Query {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let command = self
+            .command
+            .as_ref()
+            .map_or(true, |command| entry.command.starts_with(command.as_str()));
+        let since = self.since.map_or(true, |since| {
+            DateTime::parse_from_rfc3339(&entry.time)
+                .map_or(false, |time| time.with_timezone(&Utc) >= since)
+        });
+        command && since && (!self.failed_only || !entry.success)
+    }
+}
+
+fn redact(args: &[String], payloads: &[String]) -> Vec<String> {
+    let is_secret =
+        |flag: &str| flag.starts_with("--") && SECRET_FLAGS.iter().any(|name| flag.contains(name));
+    let is_payload = |value: &str| payloads.iter().any(|payload| payload == value);
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut value_is_secret = false;
+    for arg in args {
+        if is_payload(arg) || (value_is_secret && !arg.starts_with('-')) {
+            redacted.push(REDACTED.to_string());
+            value_is_secret = false;
+            continue;
+        }
+        match arg.split_once('=') {
+            Some((flag, value)) if is_secret(flag) || is_payload(value) => {
+                redacted.push(format!("{}={}", flag, REDACTED));
+                value_is_secret = false;
+            }
+            _ => {
+                value_is_secret = is_secret(arg);
+                redacted.push(arg.clone());
+            }
+        }
+    }
+    redacted
+}
+
+fn history_path() -> PathBuf {
+    get_wrangler_home_dir().join("history.ndjson")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{App, Arg, SubCommand};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn only_mutating_commands_are_recorded() {
+        assert!(is_mutating("publish"));
+        assert!(is_mutating("secret put"));
+        assert!(is_mutating("kv:bulk delete"));
+        assert!(!is_mutating("kv:key get"));
+        assert!(!is_mutating("whoami"));
+    }
+
+    #[test]
+    fn credentials_are_redacted() {
+        assert_eq!(
+            redact(
+                &args(&[
+                    "publish",
+                    "--signing-key",
+                    "key.pem",
+                    "--api-token=abc",
+                    "--env",
+                    "production"
+                ]),
+                &[]
+            ),
+            args(&[
+                "publish",
+                "--signing-key",
+                "<redacted>",
+                "--api-token=<redacted>",
+                "--env",
+                "production"
+            ])
+        );
+    }
+
+    #[test]
+    fn payloads_are_redacted() {
+        let app = App::new("wrangler").subcommand(
+            SubCommand::with_name("kv:key").subcommand(
+                SubCommand::with_name("put")
+                    .arg(Arg::with_name("binding").long("binding").takes_value(true))
+                    .arg(Arg::with_name("key").index(1))
+                    .arg(Arg::with_name("value").index(2)),
+            ),
+        );
+        let argv = args(&["kv:key", "put", "--binding", "KV", "API_KEY", "hunter2"]);
+        let matches = app
+            .get_matches_from(std::iter::once("wrangler".to_string()).chain(argv.iter().cloned()));
+
+        let payloads = payloads(&matches);
+        assert_eq!(payloads, args(&["hunter2"]));
+        assert_eq!(
+            redact(&argv, &payloads),
+            args(&["kv:key", "put", "--binding", "KV", "API_KEY", "<redacted>"])
+        );
+        assert_eq!(
+            redact(
+                &args(&["secret", "sync", "--from=secrets.json"]),
+                &args(&["secrets.json"])
+            ),
+            args(&["secret", "sync", "--from=<redacted>"])
+        );
+    }
+
+    #[test]
+    fn queries_filter_entries() {
+        let entry = Entry {
+            time: "2021-11-01T12:00:00+00:00".to_string(),
+            command: "secret put".to_string(),
+            args: args(&["secret", "put", "API_KEY"]),
+            cwd: None,
+            success: false,
+            error: Some("Authentication error".to_string()),
+            duration_ms: 300,
+        };
+
+        let query = Query {
+            command: Some("secret".to_string()),
+            since: Some(Utc::now()),
+            failed_only: true,
+        };
+        assert!(!query.matches(&entry));
+        let query = Query {
+            since: None,
+            ..query
+        };
+        assert!(query.matches(&entry));
+    }
+}
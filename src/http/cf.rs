@@ -1,28 +1,87 @@
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::async_api;
 use cloudflare::framework::auth::Credentials;
-use cloudflare::framework::response::ApiFailure;
+use cloudflare::framework::endpoint::{Endpoint, Method};
+use cloudflare::framework::response::{ApiFailure, ApiResponse, ApiResult};
 use cloudflare::framework::{Environment, HttpApiClient, HttpApiClientConfig};
 use http::StatusCode;
+use serde::Serialize;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
 
-use crate::http::{feature::headers, DEFAULT_HTTP_TIMEOUT_SECONDS};
+use crate::error::{ErrorCategory, WranglerError};
+use crate::http::{feature::headers, guidance, retry, timeout, trace};
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::emoji;
 use crate::terminal::message::{Message, StdOut};
-pub fn cf_v4_client(user: &GlobalUser) -> Result<HttpApiClient> {
+
+// Like the reqwest clients, cloudflare-rs clients are built once per process, credentials and
+// timeout, and shared, so every call through them reuses one connection pool.
+static CLIENTS: Lazy<Mutex<Vec<(GlobalUser, Duration, Arc<CfClient>)>>> =
+    Lazy::new(|| Mutex::new(Vec::new()));
+
+/// The cloudflare-rs client every API call goes through, so calls are traced like the requests
+/// of `trace::send` and idempotent ones are retried as described in `http::retry` wherever
+/// they're made.
+pub struct CfClient {
+    inner: HttpApiClient,
+}
+
+impl ApiClient for CfClient {
+    fn request<ResultType, QueryType, BodyType>(
+        &self,
+        endpoint: &dyn Endpoint<ResultType, QueryType, BodyType>,
+    ) -> ApiResponse<ResultType>
+    where
+        ResultType: ApiResult,
+        QueryType: Serialize,
+        BodyType: Serialize,
+    {
+        let idempotent = matches!(
+            endpoint.method(),
+            Method::Get | Method::Put | Method::Delete
+        );
+        retry::call(idempotent, || {
+            trace::call(endpoint, || self.inner.request(endpoint))
+        })
+    }
+}
+
+pub fn cf_v4_client(user: &GlobalUser) -> Result<Arc<CfClient>> {
+    cf_v4_client_with_timeout(user, timeout())
+}
+
+/// A shared client for calls that can take longer than `timeout()` allows, e.g. bulk KV
+/// operations.
+pub fn cf_v4_client_with_timeout(
+    user: &GlobalUser,
+    http_timeout: Duration,
+) -> Result<Arc<CfClient>> {
+    let mut clients = CLIENTS.lock().unwrap();
+    let shared = clients
+        .iter()
+        .find(|(u, t, _)| u == user && *t == http_timeout);
+    if let Some((_, _, client)) = shared {
+        return Ok(Arc::clone(client));
+    }
+
     let config = HttpApiClientConfig {
-        http_timeout: Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS),
+        http_timeout,
         default_headers: headers(None),
     };
-
-    HttpApiClient::new(
-        Credentials::from(user.to_owned()),
-        config,
-        Environment::Production,
-    )
+    let client = Arc::new(CfClient {
+        inner: HttpApiClient::new(
+            Credentials::from(user.to_owned()),
+            config,
+            Environment::Production,
+        )?,
+    });
+    clients.push((user.to_owned(), http_timeout, Arc::clone(&client)));
+    Ok(client)
 }
 
 pub fn cf_v4_api_client_async(
@@ -39,6 +98,7 @@ pub fn cf_v4_api_client_async(
 // Format errors from the cloudflare-rs cli for printing.
 // Optionally takes an argument for providing a function that maps error code numbers to
 // helpful additional information about why someone is getting an error message and how to fix it.
+// Codes the helper doesn't know about fall back to the general guidance in `http::guidance`.
 pub fn format_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static str>) -> String {
     match e {
         ApiFailure::Error(status, api_errors) => {
@@ -46,13 +106,15 @@ pub fn format_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static s
             let mut complete_err = "".to_string();
             for error in api_errors.errors {
                 let error_msg = format!("{} Code {}: {}\n", emoji::WARN, error.code, error.message);
+                complete_err.push_str(&error_msg);
 
-                if let Some(annotate_help) = err_helper {
-                    let suggestion_text = annotate_help(error.code);
-                    let help_msg = format!("{} {}\n", emoji::SLEUTH, suggestion_text);
-                    complete_err.push_str(&format!("{}{}", error_msg, help_msg));
-                } else {
-                    complete_err.push_str(&error_msg)
+                let suggestion_text = err_helper
+                    .map(|annotate_help| annotate_help(error.code))
+                    .unwrap_or_default();
+                if !suggestion_text.is_empty() {
+                    complete_err.push_str(&format!("{} {}\n", emoji::SLEUTH, suggestion_text));
+                } else if let Some(guidance) = guidance(error.code) {
+                    complete_err.push_str(&format!("{}\n", guidance));
                 }
             }
             complete_err.trim_end().to_string() // Trimming strings in place for String is apparently not a thing...
@@ -61,6 +123,24 @@ pub fn format_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static s
     }
 }
 
+/// Like `format_error`, but tagged with the API error code (e.g. `api.10007`), or as a
+/// network error when the API couldn't be reached.
+pub fn api_error(e: ApiFailure, err_helper: Option<&dyn Fn(u16) -> &'static str>) -> anyhow::Error {
+    let (category, code) = match &e {
+        ApiFailure::Error(_, api_errors) => (
+            ErrorCategory::Api,
+            api_errors
+                .errors
+                .first()
+                .map(|error| format!("api.{}", error.code))
+                .unwrap_or_else(|| "api.error".to_string()),
+        ),
+        ApiFailure::Invalid(_) => (ErrorCategory::Network, "network.request_failed".to_string()),
+    };
+
+    WranglerError::new(category, &code, anyhow!(format_error(e, err_helper)))
+}
+
 // For handling cases where the API gateway returns errors via HTTP status codes
 // (no API-specific, more granular error code is given).
 fn print_status_code_context(status_code: StatusCode) {
@@ -0,0 +1,109 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use http::Response as HttpResponse;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::CONTENT_ENCODING;
+use reqwest::StatusCode;
+
+use super::trace;
+
+// Below this, compressing costs more than it saves
+const MIN_SIZE: usize = 1024;
+
+// What the error says when a 400 is about the body not being decoded
+const DECODING_ERRORS: &[&str] = &["decod", "content-encoding", "gzip", "parse", "malformed"];
+
+// Set once the API turns down a compressed body, so the rest of the run doesn't retry it
+static UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Sends a JSON `body` gzipped when it's large enough to be worth it. If the API rejects the
+/// compressed body, it's sent again as is and nothing else is compressed for the rest of the
+/// run. Only use this for bodies the API parses: a raw value would be stored compressed by
+/// an endpoint that ignores `Content-Encoding` instead of failing.
+pub fn send_json<F>(client: &Client, request: F, body: Vec<u8>) -> Result<Response>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let request = || request().header("Content-Type", "application/json");
+
+    if body.len() >= MIN_SIZE && !UNSUPPORTED.load(Ordering::Relaxed) {
+        let compressed = gzip(&body)?;
+        log::info!(
+            "compressed request body from {} to {} bytes",
+            body.len(),
+            compressed.len()
+        );
+        let response = trace::send(
+            client,
+            request().header(CONTENT_ENCODING, "gzip").body(compressed),
+        )?;
+        let status = response.status();
+        if status != StatusCode::UNSUPPORTED_MEDIA_TYPE && status != StatusCode::BAD_REQUEST {
+            return Ok(response);
+        }
+        // the error has to be read to tell, so the caller gets a response rebuilt from it
+        let text = response.text()?;
+        if !rejected(status, &text) {
+            return Ok(HttpResponse::builder().status(status).body(text)?.into());
+        }
+        log::info!("the API rejected a compressed body, sending it uncompressed");
+        UNSUPPORTED.store(true, Ordering::Relaxed);
+    }
+
+    trace::send(client, request().body(body))
+}
+
+pub fn gzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?)
+}
+
+// An API that doesn't decode the body answers 415, or 400 as it fails to parse it. Other 400s
+// are about what's in the body, e.g. a key that's too long, which sending it uncompressed
+// wouldn't change.
+fn rejected(status: StatusCode, error: &str) -> bool {
+    if status == StatusCode::UNSUPPORTED_MEDIA_TYPE {
+        return true;
+    }
+    let error = error.to_lowercase();
+    status == StatusCode::BAD_REQUEST && DECODING_ERRORS.iter().any(|about| error.contains(about))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn gzip_round_trips() {
+        let body = "{\"key\":\"value\"}".repeat(100);
+        let compressed = gzip(body.as_bytes()).unwrap();
+        assert!(compressed.len() < body.len());
+
+        let mut decoded = String::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+    }
+
+    #[test]
+    fn only_errors_about_decoding_turn_compression_off() {
+        assert!(rejected(StatusCode::UNSUPPORTED_MEDIA_TYPE, ""));
+        assert!(rejected(
+            StatusCode::BAD_REQUEST,
+            r#"{"success":false,"errors":[{"code":10026,"message":"could not parse request body"}]}"#
+        ));
+        assert!(!rejected(
+            StatusCode::BAD_REQUEST,
+            r#"{"success":false,"errors":[{"code":10019,"message":"key too long"}]}"#
+        ));
+        assert!(!rejected(StatusCode::PAYLOAD_TOO_LARGE, "malformed"));
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+
+use crate::terminal::{emoji, styles};
+
+/// What an API error code means in plain language, and what to do about it.
+#[derive(Debug, PartialEq)]
+pub struct Guidance {
+    pub explanation: &'static str,
+    pub fix: &'static str,
+}
+
+impl fmt::Display for Guidance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}\n   {} {}",
+            emoji::SLEUTH,
+            self.explanation,
+            styles::bold("Fix:"),
+            self.fix
+        )
+    }
+}
+
+/// Looks up guidance for the API error codes people run into most. Command-specific helpers
+/// (e.g. for KV or secrets) take precedence over these.
+pub fn guidance(code: u16) -> Option<Guidance> {
+    let (explanation, fix) = match code {
+        7000 | 7003 => (
+            "The API couldn't find the account or zone in the request URL, so an ID in your configuration is probably wrong.",
+            "Check `account_id` and `zone_id` in wrangler.toml against the output of `wrangler whoami`.",
+        ),
+        9103 | 9106 => (
+            "Cloudflare didn't accept your credentials.",
+            "Run `wrangler login`, or `wrangler config` with a valid API token.",
+        ),
+        9109 => (
+            "Your API token is valid, but isn't allowed to do this.",
+            "Create a token with the \"Edit Cloudflare Workers\" template and run `wrangler config`. `wrangler whoami` shows which permissions are missing.",
+        ),
+        10000 => (
+            "Authentication with the Workers API failed.",
+            "Run `wrangler whoami` to check your credentials, then `wrangler login` or `wrangler config` to replace them.",
+        ),
+        10007 => (
+            "The worker doesn't exist on this account yet.",
+            "Check `name` and `account_id` in wrangler.toml, or run `wrangler publish` to create it.",
+        ),
+        10014 => (
+            "A KV namespace with this title already exists.",
+            "Run `wrangler kv:namespace list` to find its id and add it to `kv_namespaces` in wrangler.toml instead of creating a new one.",
+        ),
+        10021 => (
+            "The script is larger than the Workers size limit of 1MB after compression.",
+            "Remove unused dependencies or enable minification in your build. Large static files belong in Workers Sites or KV.",
+        ),
+        10026 => (
+            "workers.dev isn't enabled for this account.",
+            "Register a subdomain with `wrangler subdomain <name>`, or set `workers_dev = false` and add a `route` instead.",
+        ),
+        10034 => (
+            "Your account's email address hasn't been verified.",
+            "Follow the link in the verification email, or log in to https://dash.cloudflare.com to resend it.",
+        ),
+        _ => return None,
+    };
+
+    Some(Guidance { explanation, fix })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_has_guidance_for_common_codes() {
+        for code in &[9109, 10014, 10021, 10026] {
+            assert!(guidance(*code).is_some(), "no guidance for {}", code);
+        }
+        assert_eq!(guidance(1), None);
+    }
+}
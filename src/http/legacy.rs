@@ -1,17 +1,33 @@
 use reqwest::blocking::{Client, ClientBuilder};
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::redirect::Policy;
+use std::sync::Mutex;
 use std::time::Duration;
 
-use crate::http::{feature::headers, Feature, DEFAULT_HTTP_TIMEOUT_SECONDS};
+use once_cell::sync::Lazy;
+
+use crate::http::{feature::headers, timeout, Feature};
 use crate::settings::global_user::GlobalUser;
 
+// How long an unused connection is kept around for the next request to the same host
+const POOL_IDLE_TIMEOUT_SECONDS: u64 = 90;
+
+// Building a client is expensive and each one has its own connection pool, so every client
+// is built once per process and handed out as a cheap clone that shares that pool.
+static CLIENTS: Lazy<Mutex<Vec<(ClientKey, Client)>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+#[derive(PartialEq)]
+struct ClientKey {
+    user: Option<GlobalUser>,
+    feature: Option<Feature>,
+}
+
 // TODO: remove this and replace it entirely with cloudflare-rs
 pub fn client() -> Client {
-    builder()
-        .default_headers(headers(None))
-        .build()
-        .expect("could not create http client")
+    shared(ClientKey {
+        user: None,
+        feature: None,
+    })
 }
 
 pub fn legacy_auth_client(user: &GlobalUser) -> Client {
@@ -23,21 +39,47 @@ pub fn featured_legacy_auth_client(user: &GlobalUser, feature: Feature) -> Clien
 }
 
 fn get_client(user: &GlobalUser, feature: Option<Feature>) -> Client {
-    let mut headers = headers(feature);
-    add_auth_headers(&mut headers, user);
+    shared(ClientKey {
+        user: Some(user.to_owned()),
+        feature,
+    })
+}
 
-    builder()
-        .default_headers(headers)
-        .redirect(Policy::none())
-        .build()
-        .expect("could not create authenticated http client")
+fn shared(key: ClientKey) -> Client {
+    let mut clients = CLIENTS.lock().unwrap();
+    if let Some((_, client)) = clients.iter().find(|(k, _)| *k == key) {
+        return client.clone();
+    }
+
+    let client = build(&key);
+    clients.push((key, client.clone()));
+    client
+}
+
+fn build(key: &ClientKey) -> Client {
+    let mut headers = headers(key.feature);
+    match &key.user {
+        Some(user) => {
+            add_auth_headers(&mut headers, user);
+            builder()
+                .default_headers(headers)
+                .redirect(Policy::none())
+                .build()
+                .expect("could not create authenticated http client")
+        }
+        None => builder()
+            .default_headers(headers)
+            .build()
+            .expect("could not create http client"),
+    }
 }
 
 fn builder() -> ClientBuilder {
     let builder = reqwest::blocking::Client::builder();
     builder
         .connect_timeout(Duration::from_secs(10))
-        .timeout(Duration::from_secs(DEFAULT_HTTP_TIMEOUT_SECONDS))
+        .timeout(timeout())
+        .pool_idle_timeout(Duration::from_secs(POOL_IDLE_TIMEOUT_SECONDS))
 }
 
 fn add_auth_headers(headers: &mut HeaderMap, user: &GlobalUser) {
@@ -1,8 +1,56 @@
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::error::{ErrorCategory, WranglerError};
+
 pub(self) mod cf;
+pub mod compress;
 pub(crate) mod feature;
+pub mod guidance;
 pub(self) mod legacy;
+pub mod retry;
+pub mod trace;
 
 pub const DEFAULT_HTTP_TIMEOUT_SECONDS: u64 = 60;
-pub use cf::{cf_v4_api_client_async, cf_v4_client, format_error};
+pub use cf::{
+    api_error, cf_v4_api_client_async, cf_v4_client, cf_v4_client_with_timeout, format_error,
+    CfClient,
+};
 pub use feature::Feature;
+pub use guidance::guidance;
 pub use legacy::{client, featured_legacy_auth_client, legacy_auth_client};
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// With `--offline`, nothing is sent over the network: commands that need it fail before they
+/// start, and local ones fail on the first request they'd make instead of waiting on it.
+pub fn set_offline() {
+    OFFLINE.store(true, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// The error of everything that needs the network with `--offline`. `what` is what needed it.
+pub fn offline_error(what: &str) -> anyhow::Error {
+    WranglerError::new(
+        ErrorCategory::Network,
+        "network.offline",
+        anyhow::anyhow!(
+            "{} needs the network, but wrangler is running with --offline",
+            what
+        ),
+    )
+}
+
+/// How long a request to the API may take, `WRANGLER_HTTP_TIMEOUT` seconds if it's set.
+pub fn timeout() -> Duration {
+    let seconds = env::var("WRANGLER_HTTP_TIMEOUT")
+        .ok()
+        .and_then(|seconds| seconds.parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .unwrap_or(DEFAULT_HTTP_TIMEOUT_SECONDS);
+    Duration::from_secs(seconds)
+}
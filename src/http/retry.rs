@@ -0,0 +1,259 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use cloudflare::framework::response::{ApiFailure, ApiResponse};
+use once_cell::sync::OnceCell;
+use rand::Rng;
+use reqwest::blocking::{Request, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+
+use crate::terminal::message::{Message, StdErr};
+
+const MAX_ATTEMPTS: u32 = 4;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+// Also caps how long we'll honor a Retry-After header for
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+// Set by `--wait-for-api`, requests are retried until then instead of MAX_ATTEMPTS times
+static WAIT_UNTIL: OnceCell<Instant> = OnceCell::new();
+
+/// Keeps retrying requests for up to `duration` while the API is unavailable, e.g. during
+/// maintenance, instead of giving up after a few attempts. Every 5xx counts as unavailable.
+pub fn wait_for_api(duration: Duration) {
+    let _ = WAIT_UNTIL.set(Instant::now() + duration);
+}
+
+/// Whether a request can be sent again without changing the outcome. Only these are retried.
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::PUT | Method::DELETE
+    )
+}
+
+/// Rate limiting and the errors the API returns while it's briefly unavailable.
+pub fn is_retryable(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE
+    )
+}
+
+// How much longer `--wait-for-api` keeps retrying, `None` without it or once it's over
+fn waiting_left() -> Option<Duration> {
+    WAIT_UNTIL
+        .get()
+        .and_then(|until| until.checked_duration_since(Instant::now()))
+        .filter(|left| !left.is_zero())
+}
+
+fn should_retry(status: StatusCode) -> bool {
+    is_retryable(status) || (WAIT_UNTIL.get().is_some() && status.is_server_error())
+}
+
+fn gives_up(attempt: u32, waiting_left: Option<Duration>) -> bool {
+    attempt >= MAX_ATTEMPTS && waiting_left.is_none()
+}
+
+/// Sends `request` with `send`, trying idempotent requests again on rate limiting, 502s, 503s
+/// and network errors. Retries wait as long as Retry-After asks, or back off exponentially
+/// with jitter. With `--wait-for-api` they're retried on any 5xx until it's up.
+pub fn send<F>(request: Request, mut send: F) -> Result<Response>
+where
+    F: FnMut(Request) -> reqwest::Result<Response>,
+{
+    // Requests with streamed bodies can't be copied, so they only get one attempt
+    let retryable = is_idempotent(request.method()) && request.try_clone().is_some();
+    if !retryable {
+        return Ok(send(request)?);
+    }
+
+    let mut attempt = 1;
+    loop {
+        let next = request.try_clone().expect("request was cloned before");
+        let wait = match send(next) {
+            Ok(response) if !should_retry(response.status()) => return Ok(response),
+            Ok(response) => {
+                let reason = response.status().to_string();
+                if gives_up(attempt, waiting_left()) {
+                    anyhow::bail!(gave_up(attempt, &request, &reason));
+                }
+                (retry_after(response.headers()), reason)
+            }
+            Err(e) if is_transient(&e) => {
+                if gives_up(attempt, waiting_left()) {
+                    return Err(anyhow::Error::new(e).context(gave_up(attempt, &request, "")));
+                }
+                (None, e.to_string())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let (retry_after, reason) = wait;
+        pause(&reason, retry_after, attempt);
+        attempt += 1;
+    }
+}
+
+/// Requests with streamed bodies, like script uploads, aren't retried by `send` as they have
+/// to be built again. With `--wait-for-api` this waits out a 5xx before the next attempt, and
+/// returns whether to make one.
+pub fn wait_for_next_attempt(status: StatusCode, attempt: u32) -> bool {
+    if waiting_left().is_none() || !should_retry(status) {
+        return false;
+    }
+    pause(&status.to_string(), None, attempt);
+    true
+}
+
+/// Retries a cloudflare-rs call the same way `send` retries requests. Pass `idempotent` as
+/// false for calls that create something, they're only sent again when the API can't have
+/// acted on them, so `--wait-for-api` waits out maintenance for them too.
+pub fn call<T, F>(idempotent: bool, mut call: F) -> ApiResponse<T>
+where
+    T: cloudflare::framework::response::ApiResult,
+    F: FnMut() -> ApiResponse<T>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = call();
+        let reason = match &result {
+            Err(ApiFailure::Error(status, _)) if should_retry(*status) => status.to_string(),
+            Err(ApiFailure::Invalid(e)) if is_transient(e) => e.to_string(),
+            _ => return result,
+        };
+        if !idempotent && !matches!(&result, Err(failure) if unsent(failure)) {
+            return result;
+        }
+        if gives_up(attempt, waiting_left()) {
+            StdErr::warn(&format!("Gave up after {} attempts: {}", attempt, reason));
+            return result;
+        }
+
+        pause(&reason, None, attempt);
+        attempt += 1;
+    }
+}
+
+// The connection failed before the call was sent, or with `--wait-for-api`, the API answered
+// that it's unavailable instead of handling it
+fn unsent(failure: &ApiFailure) -> bool {
+    match failure {
+        ApiFailure::Invalid(e) => e.is_connect(),
+        ApiFailure::Error(status, _) => {
+            *status == StatusCode::SERVICE_UNAVAILABLE && WAIT_UNTIL.get().is_some()
+        }
+    }
+}
+
+// Errors building a request would happen again on every attempt
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout()
+}
+
+fn gave_up(attempts: u32, request: &Request, reason: &str) -> String {
+    let mut msg = format!(
+        "Gave up on {} {} after {} attempts",
+        request.method(),
+        request.url().path(),
+        attempts
+    );
+    if !reason.is_empty() {
+        msg.push_str(&format!(", the API kept returning {}", reason));
+    }
+    msg
+}
+
+// Sleeps before attempt `attempt + 1`, saying why
+fn pause(reason: &str, retry_after: Option<Duration>, attempt: u32) {
+    let delay = retry_after
+        .unwrap_or_else(|| backoff(attempt))
+        .min(MAX_DELAY);
+    let (delay, message) = match waiting_left() {
+        Some(left) => {
+            let delay = delay.min(left);
+            let message = format!(
+                "The Cloudflare API is unavailable ({}), trying again in {:.1}s. Waiting up to {}s more for it (attempt {})",
+                reason,
+                delay.as_secs_f32(),
+                left.as_secs(),
+                attempt + 1
+            );
+            (delay, message)
+        }
+        None => {
+            let message = format!(
+                "Request failed ({}), retrying in {:.1}s (attempt {} of {})",
+                reason,
+                delay.as_secs_f32(),
+                attempt + 1,
+                MAX_ATTEMPTS
+            );
+            (delay, message)
+        }
+    };
+    StdErr::warn(&message);
+    thread::sleep(delay);
+}
+
+// Doubles with every attempt, and picks a random point in the upper half of that so many
+// clients that were rate limited together don't all come back at once.
+fn backoff(attempt: u32) -> Duration {
+    // --wait-for-api can go on for many attempts, the ceiling is hit long before this
+    let ceiling = (BASE_DELAY * 2u32.pow((attempt - 1).min(10))).min(MAX_DELAY);
+    let half = ceiling.as_millis() as u64 / 2;
+    Duration::from_millis(half + rand::thread_rng().gen_range(0..=half))
+}
+
+// Only the delay-seconds form; the API doesn't send HTTP dates here
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn backoff_grows_and_stays_capped() {
+        for attempt in 1..10 {
+            let ceiling = (BASE_DELAY * 2u32.pow(attempt - 1)).min(MAX_DELAY);
+            let delay = backoff(attempt);
+            assert!(delay >= ceiling / 2 && delay <= ceiling);
+        }
+        assert!(backoff(100) <= MAX_DELAY);
+    }
+
+    #[test]
+    fn waiting_for_the_api_outlasts_max_attempts() {
+        assert!(!gives_up(1, None));
+        assert!(gives_up(MAX_ATTEMPTS, None));
+        assert!(!gives_up(MAX_ATTEMPTS + 20, Some(Duration::from_secs(60))));
+    }
+
+    #[test]
+    fn it_reads_retry_after_seconds() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn only_idempotent_requests_are_retried() {
+        assert!(is_idempotent(&Method::PUT));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::Result;
+use cloudflare::framework::endpoint::{Endpoint, Method};
+use cloudflare::framework::response::{ApiFailure, ApiResponse, ApiResult};
+use reqwest::blocking::{Client, Request, RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::retry;
+
+/// The log target HTTP traces are written to, enabled with `WRANGLER_LOG=http`.
+pub const TARGET: &str = "wrangler::http::trace";
+
+const REDACTED: &str = "[redacted]";
+
+// Header names containing any of these carry credentials
+const SECRET_HEADERS: &[&str] = &["auth", "token", "key", "cookie", "email"];
+// JSON fields that hold secret or user data, e.g. the text of `wrangler secret put`
+const SECRET_FIELDS: &[&str] = &[
+    "text",
+    "value",
+    "token",
+    "api_token",
+    "api_key",
+    "secret",
+    "password",
+];
+
+/// Sends `request` with `client`, logging the request and the response it gets with any
+/// credentials and secret values redacted. Idempotent requests are retried as described in
+/// `http::retry`.
+pub fn send(client: &Client, request: RequestBuilder) -> Result<Response> {
+    let request = request.build()?;
+    if super::is_offline() {
+        return Err(super::offline_error(&format!(
+            "{} {}",
+            request.method(),
+            request.url()
+        )));
+    }
+    retry::send(request, |request| execute(client, request))
+}
+
+fn execute(client: &Client, request: Request) -> reqwest::Result<Response> {
+    if log::log_enabled!(target: TARGET, log::Level::Trace) {
+        let body = match request.body() {
+            Some(body) => match body.as_bytes() {
+                Some(bytes) => redact_body(bytes),
+                None => "<streamed body>".to_string(),
+            },
+            None => String::new(),
+        };
+        log::trace!(
+            target: TARGET,
+            "--> {} {}\n{}{}",
+            request.method(),
+            request.url(),
+            format_headers(request.headers()),
+            body
+        );
+    }
+
+    let response = client.execute(request)?;
+
+    log::trace!(
+        target: TARGET,
+        "<-- {} {}\n{}",
+        response.status(),
+        response.url(),
+        format_headers(response.headers())
+    );
+
+    Ok(response)
+}
+
+/// Makes a cloudflare-rs call with `call`, logging it and its outcome like `send` logs
+/// requests. cloudflare-rs doesn't hand out the headers, so only the body of the call is
+/// logged, redacted, and what it returned.
+pub fn call<R, Q, B, F>(endpoint: &dyn Endpoint<R, Q, B>, call: F) -> ApiResponse<R>
+where
+    R: ApiResult,
+    Q: Serialize,
+    B: Serialize,
+    F: FnOnce() -> ApiResponse<R>,
+{
+    if !log::log_enabled!(target: TARGET, log::Level::Trace) {
+        return call();
+    }
+
+    let body = endpoint
+        .body()
+        .and_then(|body| serde_json::to_vec(&body).ok())
+        .map(|bytes| redact_body(&bytes))
+        .unwrap_or_default();
+    let method = match endpoint.method() {
+        Method::Get => "GET",
+        Method::Post => "POST",
+        Method::Put => "PUT",
+        Method::Delete => "DELETE",
+        Method::Patch => "PATCH",
+    };
+    let path = endpoint.path();
+    log::trace!(target: TARGET, "--> {} /client/v4/{}\n{}", method, path, body);
+
+    let response = call();
+    match &response {
+        Ok(success) => log::trace!(target: TARGET, "<-- {}\n{:?}", path, success.result),
+        Err(ApiFailure::Error(status, errors)) => {
+            log::trace!(target: TARGET, "<-- {} {}\n{:?}", status, path, errors.errors)
+        }
+        Err(ApiFailure::Invalid(e)) => log::trace!(target: TARGET, "<-- {} failed: {}", path, e),
+    }
+    response
+}
+
+fn format_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let lowercase = name.as_str().to_lowercase();
+            let value = if SECRET_HEADERS.iter().any(|s| lowercase.contains(s)) {
+                REDACTED
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{}: {}\n", name, value)
+        })
+        .collect()
+}
+
+fn redact_body(bytes: &[u8]) -> String {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => format!("<{} bytes>", bytes.len()),
+    }
+}
+
+fn redact_json(json: &mut Value) {
+    match json {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.to_lowercase().as_str()) {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn it_redacts_secret_fields() {
+        let body = json!({ "name": "API_KEY", "text": "hunter2", "bindings": [{ "value": "x" }] });
+        let redacted = redact_body(body.to_string().as_bytes());
+        assert!(!redacted.contains("hunter2"));
+        assert!(!redacted.contains("\"x\""));
+        assert!(redacted.contains("API_KEY"));
+    }
+
+    #[test]
+    fn it_redacts_credential_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Auth-Key", HeaderValue::from_static("abc123"));
+        headers.insert("Content-Type", HeaderValue::from_static("application/json"));
+        let formatted = format_headers(&headers);
+        assert!(!formatted.contains("abc123"));
+        assert!(formatted.contains("application/json"));
+    }
+}
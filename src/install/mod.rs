@@ -1,7 +1,9 @@
 pub mod dependencies;
 pub mod target;
 
+use crate::http;
 use crate::terminal::emoji;
+use crate::terminal::message::{Message, StdOut};
 
 use anyhow::{anyhow, Result};
 use binary_install::{Cache, Download};
@@ -50,7 +52,18 @@ pub fn install(
 ) -> Result<Download> {
     let download = match tool_needs_update(tool_name, version)? {
         ToolDownload::NeedsInstall(version) => {
-            println!("{}  Installing {} v{}...", emoji::DOWN, tool_name, version);
+            if http::is_offline() {
+                return Err(http::offline_error(&format!(
+                    "Installing {} v{}",
+                    tool_name, version
+                )));
+            }
+            StdOut::message(&format!(
+                "{}  Installing {} v{}...",
+                emoji::DOWN,
+                tool_name,
+                version
+            ));
             let binaries: Vec<&str> = if is_binary { vec![tool_name] } else { vec![] };
             let download =
                 download_prebuilt(tool_name, owner, &version.to_string(), binaries.as_ref());
@@ -1,39 +1,37 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
-use indicatif::ProgressBar;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
 
 use cloudflare::endpoints::workerskv::delete_bulk::DeleteBulk;
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
-use cloudflare::endpoints::workerskv::write_bulk::WriteBulk;
 use cloudflare::framework::apiclient::ApiClient;
-use cloudflare::framework::auth::Credentials;
-use cloudflare::framework::{Environment, HttpApiClient, HttpApiClientConfig};
 
 use crate::commands::kv::format_error;
-use crate::http::feature::headers;
+use crate::http::{self, compress, CfClient};
+use crate::parallel;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::progress::Progress;
 
 const API_MAX_PAIRS: usize = 10000;
 // The consts below are halved from the API's true capacity to help avoid
 // hammering it with large requests.
 pub const BATCH_KEY_MAX: usize = API_MAX_PAIRS / 2;
 const UPLOAD_MAX_SIZE: usize = 50 * 1024 * 1024;
+// How many batches are sent at once
+const CONCURRENT_BATCHES: usize = 4;
+// Failed batches are sent again in halves down to about this size
+const MIN_SPLIT_SIZE: u64 = 1024 * 1024;
 
-// Create a special API client that has a longer timeout than usual, given that KV operations
-// can be lengthy if payloads are large.
-fn bulk_api_client(user: &GlobalUser) -> Result<HttpApiClient> {
-    let config = HttpApiClientConfig {
-        http_timeout: Duration::from_secs(5 * 60),
-        default_headers: headers(None),
-    };
+// KV operations can be lengthy if payloads are large, so they get a longer timeout than usual
+const BULK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
 
-    HttpApiClient::new(
-        Credentials::from(user.to_owned()),
-        config,
-        Environment::Production,
-    )
+// A special API client with the longer timeout
+fn bulk_api_client(user: &GlobalUser) -> Result<Arc<CfClient>> {
+    http::cf_v4_client_with_timeout(user, BULK_TIMEOUT)
 }
 
 pub fn put(
@@ -41,26 +39,73 @@ pub fn put(
     user: &GlobalUser,
     namespace_id: &str,
     pairs: Vec<KeyValuePair>,
-    progress_bar: &Option<ProgressBar>,
+    progress: Option<&Progress>,
 ) -> Result<()> {
-    let client = bulk_api_client(user)?;
-
-    for b in batch_keys_values(pairs) {
-        match client.request(&WriteBulk {
-            account_identifier: target.account_id.load()?,
-            namespace_identifier: namespace_id,
-            bulk_key_value_pairs: b.to_owned(),
-        }) {
-            Ok(_) => {}
-            Err(e) => anyhow::bail!("{}", format_error(e)),
-        }
+    // Written without cloudflare-rs so the body can be compressed, these are mostly text
+    let client = http::legacy_auth_client(user);
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/storage/kv/namespaces/{}/bulk",
+        target.account_id.load()?,
+        namespace_id
+    );
+
+    parallel::run(
+        batch_keys_values(pairs),
+        CONCURRENT_BATCHES,
+        move |b| {
+            let (count, size) = (b.len() as u64, pairs_size(&b));
+            put_batch(&client, &addr, b)?;
+            Ok((count, size))
+        },
+        |(count, size)| {
+            if let Some(progress) = progress {
+                progress.inc(count, size);
+            }
+        },
+    )
+}
 
-        if let Some(pb) = &progress_bar {
-            pb.inc(b.len() as u64);
+// Sends a batch, and when a big one doesn't make it in one piece, e.g. over a flaky
+// connection, sends it again in halves. A half that made it isn't sent again when the other
+// one fails.
+fn put_batch(client: &Client, addr: &str, mut batch: Vec<KeyValuePair>) -> Result<()> {
+    let body = serde_json::to_vec(&batch)?;
+    let request = || client.put(addr).timeout(BULK_TIMEOUT);
+    let failure = match compress::send_json(client, request, body) {
+        Ok(response) if response.status().is_success() => return Ok(()),
+        Ok(response) if !worth_splitting(response.status()) => {
+            anyhow::bail!(crate::format_api_errors(response.text()?))
         }
+        Ok(response) => anyhow::anyhow!(crate::format_api_errors(response.text()?)),
+        Err(e) => e,
+    };
+    if batch.len() < 2 || pairs_size(&batch) < 2 * MIN_SPLIT_SIZE {
+        return Err(failure);
     }
 
-    Ok(())
+    log::info!(
+        "a batch of {} keys failed ({}), sending it in halves",
+        batch.len(),
+        failure
+    );
+    let second = batch.split_off(batch.len() / 2);
+    put_batch(client, addr, batch)?;
+    put_batch(client, addr, second)
+}
+
+// Failures a smaller body might get past
+fn worth_splitting(status: StatusCode) -> bool {
+    status == StatusCode::PAYLOAD_TOO_LARGE
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// The number of bytes a batch of key-value pairs takes up.
+pub fn pairs_size(pairs: &[KeyValuePair]) -> u64 {
+    pairs
+        .iter()
+        .map(|pair| (pair.key.len() + pair.value.len()) as u64)
+        .sum()
 }
 
 pub fn delete(
@@ -68,26 +113,33 @@ pub fn delete(
     user: &GlobalUser,
     namespace_id: &str,
     keys: Vec<String>,
-    progress_bar: &Option<ProgressBar>,
+    progress: Option<&Progress>,
 ) -> Result<()> {
     let client = bulk_api_client(user)?;
-
-    for b in batch_keys(keys) {
-        match client.request(&DeleteBulk {
-            account_identifier: target.account_id.load()?,
-            namespace_identifier: namespace_id,
-            bulk_keys: b.to_owned(),
-        }) {
-            Ok(_) => {}
-            Err(e) => anyhow::bail!("{}", format_error(e)),
-        }
-
-        if let Some(pb) = &progress_bar {
-            pb.inc(b.len() as u64);
-        }
-    }
-
-    Ok(())
+    let account_id = target.account_id.load()?.to_string();
+    let namespace_id = namespace_id.to_string();
+
+    parallel::run(
+        batch_keys(keys),
+        CONCURRENT_BATCHES,
+        move |b| {
+            let count = b.len() as u64;
+            let delete = DeleteBulk {
+                account_identifier: &account_id,
+                namespace_identifier: &namespace_id,
+                bulk_keys: b,
+            };
+            match client.request(&delete) {
+                Ok(_) => Ok(count),
+                Err(e) => anyhow::bail!("{}", format_error(e)),
+            }
+        },
+        |count| {
+            if let Some(progress) = progress {
+                progress.inc(count, 0);
+            }
+        },
+    )
 }
 
 fn batch_keys_values(mut pairs: Vec<KeyValuePair>) -> Vec<Vec<KeyValuePair>> {
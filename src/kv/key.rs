@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use serde_json::value::Value as JsonValue;
 
@@ -6,14 +8,14 @@ use cloudflare::endpoints::workerskv::list_namespace_keys::ListNamespaceKeysPara
 use cloudflare::endpoints::workerskv::Key;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::ApiFailure;
-use cloudflare::framework::HttpApiClient;
 
+use crate::http::CfClient;
 use crate::settings::toml::Target;
 
 pub struct KeyList {
     keys_result: Option<Vec<Key>>,
     prefix: Option<String>,
-    client: HttpApiClient,
+    client: Arc<CfClient>,
     account_id: String,
     namespace_id: String,
     cursor: Option<String>,
@@ -23,7 +25,7 @@ pub struct KeyList {
 impl KeyList {
     pub fn new(
         target: &Target,
-        client: HttpApiClient,
+        client: Arc<CfClient>,
         namespace_id: &str,
         prefix: Option<&str>,
     ) -> Result<KeyList> {
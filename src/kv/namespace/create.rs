@@ -4,11 +4,16 @@ use cloudflare::endpoints::workerskv::WorkersKvNamespace;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::{ApiFailure, ApiSuccess};
 
+use super::list::cache_key;
+use crate::cache;
+use crate::http::CfClient;
+
 pub fn create(
-    client: &impl ApiClient,
+    client: &CfClient,
     account_id: &str,
     title: &str,
 ) -> Result<ApiSuccess<WorkersKvNamespace>, ApiFailure> {
+    cache::invalidate(&cache_key(account_id));
     client.request(&CreateNamespace {
         account_identifier: account_id,
         params: CreateNamespaceParams {
@@ -1,13 +1,13 @@
 use cloudflare::endpoints::workerskv::remove_namespace::RemoveNamespace;
 use cloudflare::framework::apiclient::ApiClient;
 use cloudflare::framework::response::{ApiFailure, ApiSuccess};
-use cloudflare::framework::HttpApiClient;
 
-pub fn delete(
-    client: HttpApiClient,
-    account_id: &str,
-    id: &str,
-) -> Result<ApiSuccess<()>, ApiFailure> {
+use super::list::cache_key;
+use crate::cache;
+use crate::http::CfClient;
+
+pub fn delete(client: &CfClient, account_id: &str, id: &str) -> Result<ApiSuccess<()>, ApiFailure> {
+    cache::invalidate(&cache_key(account_id));
     client.request(&RemoveNamespace {
         account_identifier: account_id,
         namespace_identifier: id,
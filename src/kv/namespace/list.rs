@@ -7,12 +7,25 @@ use cloudflare::framework::response::ApiSuccess;
 use anyhow::Result;
 use serde::Deserialize;
 
+use crate::cache;
 use crate::commands::kv;
+use crate::http::CfClient;
 use crate::settings::toml::Target;
 
 const MAX_NAMESPACES_PER_PAGE: u32 = 1000;
 
-pub fn list(client: &impl ApiClient, target: &Target) -> Result<Vec<WorkersKvNamespace>> {
+/// Lists the namespaces in the target's account, cached for a few minutes.
+pub fn list(client: &CfClient, target: &Target) -> Result<Vec<WorkersKvNamespace>> {
+    let key = cache_key(target.account_id.load()?);
+    cache::get_or_fetch(&key, cache::LOOKUP_TTL, || fetch_all(client, target))
+}
+
+/// The cache key for the namespaces in an account, to invalidate it when they change.
+pub fn cache_key(account_id: &str) -> String {
+    format!("kv-namespaces-{}", account_id)
+}
+
+fn fetch_all(client: &CfClient, target: &Target) -> Result<Vec<WorkersKvNamespace>> {
     let mut namespaces: Vec<WorkersKvNamespace> = Vec::new();
     let mut all_namespaces_added = false;
     let mut page_number = 1;
@@ -33,10 +33,10 @@ pub fn upsert(target: &Target, user: &GlobalUser, title: String) -> Result<Upser
                         None => anyhow::bail!("namespace already exists, but could not be found in the API's listed namespaces"),
                     }
                 } else {
-                    anyhow::bail!("{}", http::format_error(e, Some(&error_suggestions)))
+                    Err(http::api_error(e, Some(&error_suggestions)))
                 }
             }
-            _ => anyhow::bail!("{}", http::format_error(e, Some(&error_suggestions))),
+            _ => Err(http::api_error(e, Some(&error_suggestions))),
         },
     }
 }
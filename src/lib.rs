@@ -7,20 +7,28 @@ extern crate text_io;
 use cloudflare::framework::response::ApiErrors;
 
 mod build;
+pub mod cache;
 pub mod cli;
 pub mod preview;
 pub use build::build_target;
 pub mod commands;
 pub mod deploy;
+pub mod error;
+pub mod history;
 pub mod http;
 pub mod install;
 pub mod installer;
 pub mod kv;
+pub mod logger;
 pub mod login;
+pub mod parallel;
+pub mod paths;
 pub mod reporter;
 pub mod settings;
 pub mod sites;
+pub mod sourcemap;
 pub mod tail;
+pub mod telemetry;
 pub mod terminal;
 pub mod upload;
 pub mod version;
@@ -48,7 +56,10 @@ pub fn format_api_errors(raw: String) -> String {
         let formatted_errors: Vec<String> = api_errors
             .errors
             .iter()
-            .map(|e| format!("{} [API code: {}]", e.message.clone(), e.code))
+            .map(|e| match crate::http::guidance(e.code) {
+                Some(guidance) => format!("{} [API code: {}]\n{}", e.message, e.code, guidance),
+                None => format!("{} [API code: {}]", e.message.clone(), e.code),
+            })
             .collect();
         msg.push_str(&formatted_errors.join("\n"));
 
@@ -0,0 +1,55 @@
+use std::env;
+
+use env_logger::WriteStyle;
+
+use crate::http::trace;
+use crate::terminal::color;
+
+/// Sets up logging. `WRANGLER_LOG` takes the same filters as `RUST_LOG` (e.g. `debug` or
+/// `wrangler::upload=trace`), plus `http` to trace every HTTP request wrangler makes with
+/// credentials redacted. Without either, `--verbose` and `--quiet` pick the level.
+pub fn init(verbose: bool, quiet: bool) {
+    let filters = match (env::var("WRANGLER_LOG"), env::var("RUST_LOG")) {
+        (Ok(filters), _) => expand(&filters),
+        (Err(_), Ok(filters)) => filters,
+        _ if quiet => "off".to_string(),
+        _ if verbose => "info".to_string(),
+        _ => "error".to_string(),
+    };
+
+    let style = if color::enabled() {
+        WriteStyle::Auto
+    } else {
+        WriteStyle::Never
+    };
+
+    env_logger::Builder::new()
+        .parse_filters(&filters)
+        .write_style(style)
+        .init();
+}
+
+fn expand(filters: &str) -> String {
+    filters
+        .split(',')
+        .map(|filter| match filter.trim() {
+            "http" => format!("{}=trace", trace::TARGET),
+            filter => filter.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_expands_http() {
+        assert_eq!(
+            expand("info, http"),
+            "info,wrangler::http::trace=trace".to_string()
+        );
+        assert_eq!(expand("wrangler=debug"), "wrangler=debug".to_string());
+    }
+}
@@ -9,6 +9,7 @@ use std::collections::HashMap;
 use std::str;
 
 use crate::commands::config::global_config;
+use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::terminal::{interactive, open_browser};
 
@@ -27,6 +28,10 @@ pub fn run() -> Result<()> {
         });
     let pubkey_encoded = percent_encode(pubkey_filtered.as_bytes(), NON_ALPHANUMERIC).to_string();
 
+    if !interactive::is_interactive() {
+        anyhow::bail!("`wrangler login` needs a browser and can't run non-interactively. Set the CF_API_TOKEN environment variable or pipe a token to `wrangler config` instead.");
+    }
+
     let browser_permission =
         interactive::confirm("Allow Wrangler to open a page in your browser?")?;
     if !browser_permission {
@@ -63,7 +68,7 @@ fn poll_token(token_id: String) -> Result<String> {
     let mut request_params = HashMap::new();
     request_params.insert("token-id", token_id);
 
-    let client = reqwest::blocking::Client::builder().build()?;
+    let client = http::client();
     let timer = Timer::new().interval_ms(1000).iter();
 
     let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
@@ -4,23 +4,34 @@ extern crate text_io;
 extern crate tokio;
 
 use std::env;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
 
-use wrangler::cli::{exec, Cli, Command};
+use wrangler::cache;
+use wrangler::cli::publish::PublishOptions;
+use wrangler::cli::{alias, exec, Cli, Command};
 use wrangler::commands;
+use wrangler::error::ErrorOutput;
+use wrangler::history;
+use wrangler::http;
 use wrangler::installer;
+use wrangler::logger;
 use wrangler::reporter;
-use wrangler::terminal::message::{Message, StdOut};
-use wrangler::terminal::styles;
+use wrangler::settings;
+use wrangler::telemetry;
+use wrangler::terminal::events::{self, Event};
+use wrangler::terminal::message::{self, Message, Output, StdErr, StdOut};
+use wrangler::terminal::{color, github_actions, interactive, styles, theme};
 use wrangler::version::background_check_for_updates;
 
 use anyhow::Result;
+use serde_json::json;
 use structopt::StructOpt;
 
 fn main() -> Result<()> {
     reporter::init();
-    env_logger::init();
 
-    let latest_version_receiver = background_check_for_updates();
     if let Ok(me) = env::current_exe() {
         // If we're actually running as the installer then execute our
         // self-installation, otherwise just continue as usual.
@@ -33,8 +44,20 @@ fn main() -> Result<()> {
             installer::install()?;
         }
     }
-    run()?;
-    if let Ok(latest_version) = latest_version_receiver.try_recv() {
+
+    // Parsing comes first so `--help` and typos return straight away, and config is only
+    // read by the commands that need it
+    let (cli, command_name, payloads) = parse_args();
+    let latest_version_receiver = if cli.command.uses_network() && !cli.offline {
+        Some(background_check_for_updates())
+    } else {
+        None
+    };
+    let config_path = cli.config.clone();
+    if let Err(e) = run(cli, &command_name, &payloads) {
+        exit_with_error(e, &config_path);
+    }
+    if let Some(latest_version) = latest_version_receiver.and_then(|r| r.try_recv().ok()) {
         let latest_version = styles::highlight(latest_version.to_string());
         let new_version_available = format!(
             "A new version of Wrangler ({}) is available!",
@@ -53,18 +76,108 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn run() -> Result<()> {
-    let cli = Cli::from_args();
+// Errors are printed like returning them from `main` would, or as JSON on stdout with
+// `--json`. Either way, the exit code tells scripts what kind of failure it was. In GitHub
+// Actions, configuration and build errors are annotated too.
+fn exit_with_error(e: anyhow::Error, config_path: &Path) -> ! {
+    let output = ErrorOutput::new(&e);
+    if message::output() == Output::Json {
+        StdOut::as_json(&json!({ "error": output }));
+    } else {
+        eprintln!("Error: {:?}", e);
+        if github_actions::enabled() {
+            if let Some(annotation) = github_actions::error_annotation(&e, config_path) {
+                println!("{}", annotation);
+            }
+        }
+    }
+    process::exit(output.category.exit_code())
+}
+
+// Built-in commands come first; only arguments that don't parse are tried as an alias.
+// Also returns the name of the command, for telemetry, and the data it was given, which the
+// history leaves out.
+fn parse_args() -> (Cli, String, Vec<String>) {
+    let args = env::args_os().collect::<Vec<_>>();
+    let aliases = alias::load(&args);
+    let alias_help = alias::help(&aliases);
+    let mut app = Cli::clap();
+    if !alias_help.is_empty() {
+        app = app.after_help(alias_help.as_str());
+    }
+
+    let matches = match app.clone().get_matches_from_safe(&args) {
+        Ok(matches) => matches,
+        Err(e) => match alias::expand(&args, &aliases) {
+            Some(expanded) => app.get_matches_from(expanded),
+            None => e.exit(),
+        },
+    };
+    (
+        Cli::from_clap(&matches),
+        telemetry::command_name(&matches),
+        history::payloads(&matches),
+    )
+}
+
+fn run(cli: Cli, command_name: &str, payloads: &[String]) -> Result<()> {
     let cli_params = cli.clone();
 
-    match cli.command {
-        Command::Config { api_key, no_verify } => exec::configure(api_key, no_verify),
+    if cli.no_color || color::disabled_by_env() {
+        color::disable();
+    }
+    logger::init(cli.verbose, cli.quiet);
+    match settings::output_style() {
+        Ok(Some(style)) => theme::set_style(style),
+        Ok(None) => {}
+        Err(e) => StdErr::warn(&e.to_string()),
+    }
+    if cli.quiet {
+        message::set_quiet(true);
+    }
+    if cli.non_interactive {
+        interactive::set_non_interactive(true);
+    }
+    if cli.no_cache {
+        cache::disable();
+    }
+    if cli.yes {
+        interactive::set_assume_yes(true);
+    }
+    if cli.json {
+        message::set_output(Output::Json);
+    }
+
+    if cli.offline {
+        http::set_offline();
+    }
+    if let Some(fd) = cli.progress_fd {
+        events::open_fd(fd, command_name)?;
+    }
+
+    let uses_network = cli.command.uses_network();
+    if uses_network && cli.offline {
+        return Err(http::offline_error(&format!("wrangler {}", command_name)));
+    }
+    if uses_network {
+        telemetry::ask_for_consent();
+    }
+
+    let started = Instant::now();
+    let result = match cli.command {
+        Command::Config {
+            api_key,
+            no_verify,
+            subcommand,
+        } => exec::configure(api_key, no_verify, subcommand),
         Command::Generate {
             name,
             site,
             template,
             target_type,
-        } => exec::generate(name, site, template, target_type),
+            list,
+            define,
+        } => exec::generate(name, site, template, target_type, list, define),
         Command::Init {
             name,
             site,
@@ -77,41 +190,139 @@ fn run() -> Result<()> {
             body,
             watch,
             headless,
-        } => exec::preview(method, url, body, watch, headless, &cli_params),
+            checks,
+            share,
+        } => exec::preview(
+            method,
+            url,
+            body,
+            watch,
+            headless,
+            checks,
+            share,
+            &cli_params,
+        ),
         Command::Dev {
             host,
             ip,
             port,
             local_protocol,
             upstream_protocol,
+            cf_json,
+            fake_time,
+            share,
         } => exec::dev(
             host,
             ip,
             port,
             local_protocol,
             upstream_protocol,
+            cf_json,
+            fake_time,
+            share,
             &cli_params,
         ),
         Command::Whoami => exec::whoami(),
+        Command::Doctor => exec::doctor(&cli_params),
         Command::Publish {
             release,
             output,
             migration,
-        } => exec::publish(release, output, migration, &cli_params),
+            skip_unchanged,
+            envs,
+            all_envs,
+            preview_alias,
+            accept_data_loss,
+            add_migrations,
+            strict,
+            prune_routes,
+            sign,
+            signing_key,
+            wait_for_api,
+            settings_only,
+            code_only,
+            confirm_protected,
+        } => exec::publish(
+            release,
+            output,
+            migration,
+            PublishOptions {
+                skip_unchanged,
+                envs,
+                all_envs,
+                preview_alias,
+                accept_data_loss,
+                add_migrations,
+                strict,
+                prune_routes,
+                sign,
+                signing_key,
+                wait_for_api,
+                settings_only,
+                code_only,
+                confirm_protected,
+            },
+            &cli_params,
+        ),
+        Command::Verify {
+            signature,
+            public_key,
+        } => exec::verify(signature, public_key, &cli_params),
+        Command::Keygen { path } => exec::keygen(&path),
+        Command::MigrateFormat {
+            out,
+            binding,
+            dry_run,
+        } => exec::migrate_format(out, binding, dry_run, &cli_params),
         Command::Subdomain { name } => exec::subdomain(name, &cli_params),
         Command::Route(route) => exec::route(route, &cli_params),
+        Command::Env(environments) => exec::environments(environments, &cli_params),
+        Command::Deployments(deployments) => exec::deployments(deployments, &cli_params),
+        Command::Migrations(migrations) => exec::migrations(migrations, &cli_params),
+        Command::List { tag, options } => exec::list(tag, options, &cli_params),
+        Command::Drift { secrets, options } => exec::drift(secrets, options, &cli_params),
+        Command::Export {
+            name,
+            zone_id,
+            output,
+            format,
+        } => exec::export(name, zone_id, output, format, &cli_params),
+        Command::Pages(pages) => exec::pages(pages, &cli_params),
+        Command::R2(r2) => exec::r2(r2, &cli_params),
+        Command::Zone(zone) => exec::zone(zone, &cli_params),
+        Command::Rename { new_name } => exec::rename(&new_name, &cli_params),
+        Command::PreviewAlias(preview_alias) => exec::preview_alias(preview_alias, &cli_params),
         Command::Secret(secret) => exec::secret(secret, &cli_params),
         Command::KvNamespace(namespace) => exec::kv_namespace(namespace, &cli_params),
         Command::KvKey(key) => exec::kv_key(key, &cli_params),
         Command::KvBulk(bulk) => exec::kv_bulk(bulk, &cli_params),
-        Command::Tail {
-            format,
-            tunnel_port,
-            metrics_port,
-        } => exec::tail(format, tunnel_port, metrics_port, &cli_params),
+        Command::Tail(tail) => exec::tail(tail, &cli_params),
         Command::Login => commands::login::run(),
+        Command::Telemetry(telemetry) => exec::telemetry(telemetry),
+        Command::History {
+            command,
+            since,
+            failed,
+            options,
+        } => exec::history(command, since, failed, options, &cli_params),
         Command::Report { log } => commands::report::run(log.as_deref()).map(|_| {
             eprintln!("Report submission sucessful. Thank you!");
         }),
+    };
+
+    events::emit(Event::CommandFinished {
+        command: command_name,
+        success: result.is_ok(),
+    });
+    if uses_network {
+        telemetry::record(command_name, started.elapsed(), result.is_ok());
+    }
+    if history::is_mutating(command_name) {
+        let args: Vec<String> = env::args_os()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        history::record(command_name, &args, payloads, started.elapsed(), &result);
     }
+    result
 }
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use anyhow::Result;
+
+/// How many files are read at once when preparing an upload.
+pub const FILE_READS: usize = 8;
+
+/// Runs `work` on every item using up to `concurrency` threads. `done` gets each result on
+/// the calling thread as soon as it's ready, so it can report progress; results come in the
+/// order they finish. After the first error no new work is started, and that error is
+/// returned once the running work is finished.
+pub fn run<T, R, F, D>(items: Vec<T>, concurrency: usize, work: F, mut done: D) -> Result<()>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Result<R> + Send + Sync + 'static,
+    D: FnMut(R),
+{
+    let threads = concurrency.max(1).min(items.len());
+    let queue = Arc::new(Mutex::new(items.into_iter().collect::<VecDeque<_>>()));
+    let work = Arc::new(work);
+    let failed = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+
+    let handles = (0..threads)
+        .map(|_| {
+            let queue = Arc::clone(&queue);
+            let work = Arc::clone(&work);
+            let failed = Arc::clone(&failed);
+            let sender = sender.clone();
+            thread::spawn(move || {
+                while !failed.load(Ordering::Relaxed) {
+                    let item = match queue.lock().unwrap().pop_front() {
+                        Some(item) => item,
+                        None => break,
+                    };
+                    let result = work(item);
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    if sender.send(result).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    // Only the workers hold senders now, so the loop below ends when they're all done
+    drop(sender);
+
+    let mut first_error = None;
+    for result in receiver {
+        match result {
+            Ok(result) => done(result),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    for handle in handles {
+        if handle.join().is_err() {
+            anyhow::bail!("a worker thread panicked");
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Like `run`, but collects the results in the order of `items`.
+pub fn map<T, R, F>(items: Vec<T>, concurrency: usize, work: F) -> Result<Vec<R>>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Result<R> + Send + Sync + 'static,
+{
+    let mut results = Vec::with_capacity(items.len());
+    results.resize_with(items.len(), || None);
+
+    let items = items.into_iter().enumerate().collect();
+    run(
+        items,
+        concurrency,
+        move |(i, item)| work(item).map(|result| (i, result)),
+        |(i, result)| results[i] = Some(result),
+    )?;
+
+    Ok(results.into_iter().map(Option::unwrap).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_keeps_the_order() {
+        let squares = map((0..100u64).collect(), 8, |n| Ok(n * n)).unwrap();
+        assert_eq!(squares, (0..100u64).map(|n| n * n).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_returns_the_first_error() {
+        let result = map((0..10).collect(), 4, |n: i32| {
+            if n == 3 {
+                anyhow::bail!("three")
+            } else {
+                Ok(n)
+            }
+        });
+        assert_eq!(result.unwrap_err().to_string(), "three");
+    }
+
+    #[test]
+    fn it_handles_no_items() {
+        let results: Vec<i32> = map(Vec::new(), 4, Ok).unwrap();
+        assert!(results.is_empty());
+    }
+}
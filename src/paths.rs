@@ -0,0 +1,121 @@
+//! Turns file paths into the names they're uploaded under: module names, Sites keys and
+//! object keys. These use `/` whatever the OS, so an upload from Windows matches one from
+//! anywhere else.
+
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::Result;
+
+/// The name of `path` under `base`, e.g. `static/app.js`. Both may be verbatim Windows paths
+/// (`\\?\C:\...`), which `canonicalize` returns, while the other isn't.
+pub fn relative_name(path: &Path, base: &Path) -> Result<String> {
+    let path = strip_verbatim(path);
+    let base = strip_verbatim(base);
+    let relative = path
+        .strip_prefix(&base)
+        .map_err(|_| anyhow::anyhow!("{} isn't inside {}", path.display(), base.display()))?;
+    Ok(slash_name(relative))
+}
+
+/// `path` with `/` between its components, and without a drive, root or `.` components.
+pub fn slash_name(path: &Path) -> String {
+    let mut names: Vec<Cow<str>> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => names.push(Cow::Borrowed("..")),
+            Component::Normal(name) => names.push(name.to_string_lossy()),
+        }
+    }
+    names.join("/")
+}
+
+/// `path` without the `\\?\` prefix of verbatim paths, which don't compare equal to the
+/// same path written normally. Only Windows has them.
+pub fn strip_verbatim(path: &Path) -> PathBuf {
+    if !cfg!(windows) {
+        return path.to_path_buf();
+    }
+    match path.to_str() {
+        Some(path) => PathBuf::from(strip_verbatim_str(path).as_ref()),
+        None => path.to_path_buf(),
+    }
+}
+
+fn strip_verbatim_str(path: &str) -> Cow<str> {
+    if let Some(unc) = path.strip_prefix(r"\\?\UNC\") {
+        Cow::Owned(format!(r"\\{}", unc))
+    } else if let Some(disk) = path.strip_prefix(r"\\?\") {
+        // other verbatim paths, e.g. to volumes by GUID, have no short form
+        if disk.as_bytes().get(1) == Some(&b':') {
+            Cow::Borrowed(disk)
+        } else {
+            Cow::Borrowed(path)
+        }
+    } else {
+        Cow::Borrowed(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_use_forward_slashes() {
+        assert_eq!(
+            slash_name(Path::new("static/css/app.css")),
+            "static/css/app.css"
+        );
+        assert_eq!(slash_name(Path::new("./index.mjs")), "index.mjs");
+        assert_eq!(
+            relative_name(Path::new("dist/lib/util.js"), Path::new("dist")).unwrap(),
+            "lib/util.js"
+        );
+        assert!(relative_name(Path::new("src/index.js"), Path::new("dist")).is_err());
+    }
+
+    #[test]
+    fn verbatim_prefixes_are_stripped() {
+        assert_eq!(strip_verbatim_str(r"\\?\C:\site\dist"), r"C:\site\dist");
+        assert_eq!(
+            strip_verbatim_str(r"\\?\UNC\server\share\dist"),
+            r"\\server\share\dist"
+        );
+        assert_eq!(
+            strip_verbatim_str(r"\\?\Volume{1234}\dist"),
+            r"\\?\Volume{1234}\dist"
+        );
+        assert_eq!(strip_verbatim_str(r"C:\site\dist"), r"C:\site\dist");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_paths_become_the_same_names() {
+        assert_eq!(
+            slash_name(Path::new(r"static\css\app.css")),
+            "static/css/app.css"
+        );
+        assert_eq!(
+            relative_name(
+                Path::new(r"\\?\C:\site\dist\lib\util.js"),
+                Path::new(r"C:\site\dist")
+            )
+            .unwrap(),
+            "lib/util.js"
+        );
+        assert_eq!(
+            relative_name(
+                Path::new(r"C:\site\public\index.html"),
+                Path::new(r"\\?\C:\site\public")
+            )
+            .unwrap(),
+            "index.html"
+        );
+        assert_eq!(
+            slash_name(Path::new(r"C:\site\index.html")),
+            "site/index.html"
+        );
+    }
+}
@@ -7,6 +7,9 @@ pub use http_method::HttpMethod;
 mod request_payload;
 pub use request_payload::RequestPayload;
 
+pub mod smoke;
+use smoke::SmokeTest;
+
 mod upload;
 pub use upload::upload;
 
@@ -23,7 +26,7 @@ use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{Target, UploadFormat};
 use crate::terminal::message::{Message, StdOut};
-use crate::terminal::open_browser;
+use crate::terminal::{open_browser, share_url};
 use crate::watch::watch_and_build;
 
 pub fn preview(
@@ -48,11 +51,25 @@ pub fn preview(
 
     let script_id = upload(&mut target, user.as_ref(), sites_preview, verbose)?;
 
+    if !options.checks.is_empty() {
+        return smoke::run(&options.checks, &options.url, &script_id);
+    }
+
     let request_payload = RequestPayload::create(options.method, options.url, options.body);
 
     let session = &request_payload.session;
     let browser_url = &request_payload.browser_url;
 
+    // the live reload session only works on this machine, so other devices get a plain preview
+    share_url(
+        &format!(
+            "https://cloudflareworkers.com/?hide_editor#{0}:{1}",
+            script_id, browser_url
+        ),
+        options.qr,
+        options.copy,
+    );
+
     if options.livereload {
         // explicitly use 127.0.0.1, since localhost can resolve to 2 addresses
         let server = WebSocket::new(|out| FiddleMessageServer { out })?.bind("127.0.0.1:0")?;
@@ -102,6 +119,12 @@ pub struct PreviewOpt {
     pub body: Option<String>,
     pub livereload: bool,
     pub headless: bool,
+    /// Requests to check the preview with instead of opening it
+    pub checks: Vec<SmokeTest>,
+    /// Print the preview's URL as a QR code
+    pub qr: bool,
+    /// Copy the preview's URL to the clipboard
+    pub copy: bool,
 }
 
 fn client_request(payload: &RequestPayload, script_id: &str, sites_preview: bool) {
@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::Method;
+use serde::Deserialize;
+use url::Url;
+
+use super::{HttpMethod, RequestPayload};
+use crate::http;
+use crate::terminal::message::{Message, StdOut};
+
+/// A request to send to the preview, and what the response has to look like.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SmokeTest {
+    pub name: Option<String>,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default = "default_path")]
+    pub path: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<String>,
+    /// Any status below 400 passes when this isn't set
+    pub expect_status: Option<u16>,
+    pub expect_body: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestFile {
+    #[serde(rename = "test", default)]
+    tests: Vec<SmokeTest>,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_path() -> String {
+    "/".to_string()
+}
+
+impl SmokeTest {
+    fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{} {}", self.method.to_uppercase(), self.path),
+        }
+    }
+
+    /// What's wrong with a response, empty if it's what the test expected.
+    pub fn check(&self, status: u16, body: &str) -> Vec<String> {
+        let mut failures = Vec::new();
+        match self.expect_status {
+            Some(expected) if expected != status => {
+                failures.push(format!("expected status {}, got {}", expected, status))
+            }
+            None if status >= 400 => failures.push(format!("got status {}", status)),
+            _ => {}
+        }
+        if let Some(expected) = &self.expect_body {
+            if !body.contains(expected.as_str()) {
+                failures.push(format!("expected the body to contain {:?}", expected));
+            }
+        }
+        failures
+    }
+}
+
+/// Reads the `[[test]]` tables from a smoke test file.
+pub fn load(path: &Path) -> Result<Vec<SmokeTest>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Could not read {}: {}", path.display(), e))?;
+    parse(&contents).map_err(|e| anyhow!("Could not parse {}: {}", path.display(), e))
+}
+
+fn parse(contents: &str) -> Result<Vec<SmokeTest>> {
+    let file: TestFile = toml::from_str(contents)?;
+    anyhow::ensure!(!file.tests.is_empty(), "there are no [[test]] tables");
+    Ok(file.tests)
+}
+
+/// Sends every test's request to the uploaded preview and reports which ones didn't get the
+/// response they expected. Fails if any of them didn't, so CI can stop there.
+pub fn run(tests: &[SmokeTest], url: &Url, script_id: &str) -> Result<()> {
+    let client = http::client();
+    let mut failed = 0;
+
+    for test in tests {
+        let failures = match send(&client, test, url, script_id) {
+            Ok((status, body)) => test.check(status, &body),
+            Err(e) => vec![e.to_string()],
+        };
+
+        if failures.is_empty() {
+            StdOut::success(&format!("{} passed", test.label()));
+        } else {
+            failed += 1;
+            StdOut::user_error(&format!("{} failed: {}", test.label(), failures.join(", ")));
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} preview checks failed", failed, tests.len());
+    }
+    Ok(())
+}
+
+fn send(
+    client: &reqwest::blocking::Client,
+    test: &SmokeTest,
+    url: &Url,
+    script_id: &str,
+) -> Result<(u16, String)> {
+    let method = Method::from_bytes(test.method.to_uppercase().as_bytes())
+        .map_err(|_| anyhow!("{:?} is not an HTTP method", test.method))?;
+    let url = url.join(&test.path)?;
+    let payload = RequestPayload::create(HttpMethod::default(), url, None);
+
+    let mut request = client
+        .request(method, &payload.service_url)
+        .header("Cookie", payload.cookie(script_id));
+    for (name, value) in &test.headers {
+        request = request.header(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+    if let Some(body) = &test.body {
+        request = request.body(body.clone());
+    }
+
+    let response = request.send()?;
+    let status = response.status().as_u16();
+    Ok((status, response.text()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_are_read_with_defaults() {
+        let tests = parse(
+            r#"
+            [[test]]
+            expect_body = "Hello"
+
+            [[test]]
+            name = "create"
+            method = "post"
+            path = "/items?dry=1"
+            headers = { "content-type" = "application/json" }
+            body = "{}"
+            expect_status = 201
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(tests.len(), 2);
+        assert_eq!(tests[0].method, "GET");
+        assert_eq!(tests[0].path, "/");
+        assert_eq!(tests[1].label(), "create");
+        assert_eq!(tests[1].headers["content-type"], "application/json");
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn responses_are_checked_against_expectations() {
+        let mut test = parse("[[test]]").unwrap().remove(0);
+        assert!(test.check(302, "").is_empty());
+        assert_eq!(test.check(500, ""), vec!["got status 500"]);
+
+        test.expect_status = Some(404);
+        test.expect_body = Some("not found".to_string());
+        assert!(test.check(404, "page not found").is_empty());
+        assert_eq!(test.check(200, "ok").len(), 2);
+    }
+}
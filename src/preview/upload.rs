@@ -71,7 +71,7 @@ pub fn upload(
                         StdOut::info("Uploading updated files...");
                     }
 
-                    bulk::put(target, user, &site_namespace.id, to_upload, &None)?;
+                    bulk::put(target, user, &site_namespace.id, to_upload, None)?;
 
                     let preview = authenticated_upload(&client, &target, Some(asset_manifest))?;
                     if !to_delete.is_empty() {
@@ -79,7 +79,7 @@ pub fn upload(
                             StdOut::info("Deleting stale files...");
                         }
 
-                        bulk::delete(target, user, &site_namespace.id, to_delete, &None)?;
+                        bulk::delete(target, user, &site_namespace.id, to_delete, None)?;
                     }
 
                     preview
@@ -155,7 +155,7 @@ fn authenticated_upload(
     );
     log::info!("address: {}", create_address);
 
-    let script_upload_form = upload::form::build(target, asset_manifest, None)?;
+    let script_upload_form = upload::form::build(target, asset_manifest, None, None)?;
 
     let res = client
         .post(&create_address)
@@ -198,7 +198,7 @@ fn unauthenticated_upload(target: &Target) -> Result<Preview> {
         target.site = None;
     }
 
-    let script_upload_form = upload::form::build(&target, None, None)?;
+    let script_upload_form = upload::form::build(&target, None, None, None)?;
     let client = http::client();
     let res = client
         .post(create_address)
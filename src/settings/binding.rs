@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+use crate::settings::toml::{Jurisdiction, LocationHint};
+
 #[derive(Serialize, Debug)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
@@ -12,11 +14,19 @@ pub enum Binding {
         name: String,
         namespace_id: String,
     },
+    R2Bucket {
+        name: String,
+        bucket_name: String,
+    },
     #[serde(rename = "durable_object_namespace")]
     DurableObjectsClass {
         name: String,
         class_name: String,
         script_name: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        jurisdiction: Option<Jurisdiction>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        location_hint: Option<LocationHint>,
     },
     TextBlob {
         name: String,
@@ -26,6 +36,10 @@ pub enum Binding {
         name: String,
         text: String,
     },
+    /// Keeps the binding the deployed script already has, e.g. a secret.
+    Inherit {
+        name: String,
+    },
 }
 
 impl Binding {
@@ -37,15 +51,23 @@ impl Binding {
         Binding::KvNamespace { name, namespace_id }
     }
 
+    pub fn new_r2_bucket(name: String, bucket_name: String) -> Binding {
+        Binding::R2Bucket { name, bucket_name }
+    }
+
     pub fn new_durable_object_namespace(
         name: String,
         class_name: String,
         script_name: Option<String>,
+        jurisdiction: Option<Jurisdiction>,
+        location_hint: Option<LocationHint>,
     ) -> Binding {
         Binding::DurableObjectsClass {
             name,
             class_name,
             script_name,
+            jurisdiction,
+            location_hint,
         }
     }
 
@@ -56,4 +78,8 @@ impl Binding {
     pub fn new_plain_text(name: String, text: String) -> Binding {
         Binding::PlainText { name, text }
     }
+
+    pub fn new_inherit(name: String) -> Binding {
+        Binding::Inherit { name }
+    }
 }
@@ -1,6 +1,12 @@
 use std::env;
+use std::fs;
 use std::path::{Path, PathBuf};
 
+use anyhow::Result;
+use serde::Deserialize;
+
+use crate::terminal::theme::Style;
+
 pub const DEFAULT_CONFIG_FILE_NAME: &str = "default.toml";
 
 pub fn get_wrangler_home_dir() -> PathBuf {
@@ -21,3 +27,32 @@ pub fn get_global_config_path() -> PathBuf {
     log::info!("Using global config file: {}", global_config_file.display());
     global_config_file
 }
+
+#[derive(Default, Deserialize)]
+struct GlobalConfig {
+    #[serde(default)]
+    output: OutputConfig,
+}
+
+#[derive(Default, Deserialize)]
+struct OutputConfig {
+    style: Option<Style>,
+}
+
+/// `[output] style` from the global config, if it's set.
+pub fn output_style() -> Result<Option<Style>> {
+    let path = get_global_config_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let config: GlobalConfig = toml::from_str(&contents).map_err(|e| {
+        anyhow::anyhow!(
+            "{} has an invalid [output] section, style can be \"emoji\", \"plain\" or \"minimal\": {}",
+            path.display(),
+            e
+        )
+    })?;
+    Ok(config.output.style)
+}
@@ -5,6 +5,7 @@ use anyhow::Result;
 use cloudflare::framework::auth::Credentials;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Categorize, ErrorCategory};
 use crate::settings::{get_global_config_path, Environment, QueryEnvironment};
 use crate::terminal::{emoji, styles};
 
@@ -30,6 +31,7 @@ impl GlobalUser {
 
         let config_path = get_global_config_path();
         GlobalUser::build(environment, config_path)
+            .categorize(ErrorCategory::Auth, "auth.not_configured")
     }
 
     fn build<T: 'static + QueryEnvironment>(environment: T, config_path: PathBuf) -> Result<Self>
@@ -86,7 +88,17 @@ impl GlobalUser {
     }
 
     pub fn to_file(&self, config_path: &Path) -> Result<()> {
-        let toml = toml::to_string(self)?;
+        let mut config = toml::value::Table::new();
+        // sections that aren't credentials, e.g. [output], survive logging in again
+        if let Ok(existing) = fs::read_to_string(config_path) {
+            if let Ok(toml::Value::Table(existing)) = existing.parse::<toml::Value>() {
+                config.extend(existing.into_iter().filter(|(_, value)| value.is_table()));
+            }
+        }
+        if let toml::Value::Table(credentials) = toml::Value::try_from(self)? {
+            config.extend(credentials);
+        }
+        let toml = toml::to_string(&config)?;
 
         fs::create_dir_all(&config_path.parent().unwrap())?;
         fs::write(&config_path, toml)?;
@@ -243,6 +255,28 @@ mod tests {
         assert!(new_user.is_ok());
     }
 
+    #[test]
+    fn other_settings_are_kept_when_credentials_are_saved() {
+        let tmp_dir = tempdir().unwrap();
+        let config_path = tmp_dir.path().join(DEFAULT_CONFIG_FILE_NAME);
+        fs::write(
+            &config_path,
+            "email = \"old@example.com\"\napi_key = \"old\"\n\n[output]\nstyle = \"plain\"\n",
+        )
+        .unwrap();
+
+        let user = GlobalUser::TokenAuth {
+            api_token: "thisisanapitoken".to_string(),
+        };
+        user.to_file(&config_path).unwrap();
+
+        let contents = fs::read_to_string(&config_path).unwrap();
+        assert!(contents.contains("style = \"plain\""));
+        assert!(!contents.contains("old"));
+        let new_user = GlobalUser::from_file(config_path).unwrap();
+        assert_eq!(new_user, user);
+    }
+
     fn test_config_dir(tmp_dir: &tempfile::TempDir, user: Option<GlobalUser>) -> Result<PathBuf> {
         let tmp_config_path = tmp_dir.path().join(DEFAULT_CONFIG_FILE_NAME);
         if let Some(user_config) = user {
@@ -1,16 +1,18 @@
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::upload::form::ModuleType;
+use crate::upload::Package;
 
 const WATCH_DIR: &str = "src";
 const UPLOAD_DIR: &str = "dist";
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Builder {
     pub command: Option<String>,
@@ -21,7 +23,7 @@ pub struct Builder {
     pub upload: UploadFormat,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(tag = "format")]
 #[serde(deny_unknown_fields)]
 pub enum UploadFormat {
@@ -36,7 +38,7 @@ pub enum UploadFormat {
     },
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct ModuleRule {
     pub globs: Vec<String>,
@@ -59,6 +61,51 @@ fn upload_dir() -> PathBuf {
 }
 
 impl Builder {
+    /// Infers the build configuration for a top-level `main` entrypoint.
+    ///
+    /// An entrypoint is treated as an ES module when it ends in `.mjs` or when the
+    /// project's `package.json` declares `"type": "module"`; in that case the modules
+    /// format is used with the entrypoint's directory as the upload dir. Service-worker
+    /// entrypoints don't need a `[build]` section, so `None` is returned for them.
+    pub fn from_main(main: &Path) -> Option<Builder> {
+        let root = project_root();
+        let is_mjs = main.extension().map_or(false, |ext| ext == "mjs");
+        let is_package_module = root.join("package.json").is_file()
+            && Package::new(&root).map_or(false, |package| package.is_module());
+
+        if !is_mjs && !is_package_module {
+            return None;
+        }
+
+        let dir = match main.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => root.join(parent),
+            _ => root.clone(),
+        };
+        let file_name = main.file_name()?.to_string_lossy();
+
+        // `.js` files are CommonJS by default, which is wrong for a `"type": "module"` package
+        let rules = if is_mjs {
+            None
+        } else {
+            Some(vec![ModuleRule {
+                globs: vec!["**/*.js".to_string()],
+                module_type: ModuleType::ESModule,
+                fallthrough: false,
+            }])
+        };
+
+        Some(Builder {
+            command: None,
+            cwd: root,
+            watch_dir: dir.clone(),
+            upload: UploadFormat::Modules {
+                main: format!("./{}", file_name),
+                dir,
+                rules,
+            },
+        })
+    }
+
     pub fn verify_watch_dir(&self) -> Result<()> {
         let watch_canonical = match self.watch_dir.canonicalize() {
             Ok(path) => path,
@@ -139,3 +186,26 @@ impl Builder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_infers_modules_from_mjs_main() {
+        let builder = Builder::from_main(Path::new("src/index.mjs")).unwrap();
+        match builder.upload {
+            UploadFormat::Modules { main, dir, rules } => {
+                assert_eq!(main, "./index.mjs");
+                assert_eq!(dir, project_root().join("src"));
+                assert!(rules.is_none());
+            }
+            UploadFormat::ServiceWorker {} => panic!("expected the modules format"),
+        }
+    }
+
+    #[test]
+    fn it_leaves_service_workers_alone() {
+        assert!(Builder::from_main(Path::new("src/index.js")).is_none());
+    }
+}
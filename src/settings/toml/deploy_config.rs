@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// What has to hold after a publish for it to be kept.
+///
+/// ```toml
+/// [deploy]
+/// health_check = { url = "/healthz", expected_status = 200, timeout = "30s" }
+/// lock = { namespace_id = "0f2ac74b498b48028cb68387c421e279" }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeployConfig {
+    pub health_check: Option<HealthCheck>,
+    pub lock: Option<DeployLock>,
+}
+
+/// A key in a KV namespace that only one publish of the worker holds at a time, so publishes
+/// from concurrent CI jobs can't interleave.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeployLock {
+    pub namespace_id: String,
+    /// How long to wait for another publish to finish before failing
+    #[serde(default = "default_lock_wait")]
+    pub wait: String,
+    /// When a lock that was never released, e.g. by a cancelled job, is given up. KV expires
+    /// keys after a minute at the earliest
+    #[serde(default = "default_lock_ttl")]
+    pub ttl: String,
+}
+
+fn default_lock_wait() -> String {
+    "5m".to_string()
+}
+
+fn default_lock_ttl() -> String {
+    "15m".to_string()
+}
+
+impl DeployLock {
+    pub fn wait(&self) -> Result<Duration> {
+        parse_duration(&self.wait)
+            .map_err(|_| anyhow!("Invalid lock wait {:?}, use e.g. \"5m\"", self.wait))
+    }
+
+    pub fn ttl(&self) -> Result<Duration> {
+        let ttl = parse_duration(&self.ttl)
+            .map_err(|_| anyhow!("Invalid lock ttl {:?}, use e.g. \"15m\"", self.ttl))?;
+        if ttl < Duration::from_secs(60) {
+            anyhow::bail!("The lock ttl is {}, but it has to be at least 1m", self.ttl);
+        }
+        Ok(ttl)
+    }
+}
+
+/// A URL that has to respond with `expected_status` within `timeout` of publishing, or the
+/// previous deployment is restored.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthCheck {
+    /// Either a full URL or a path on the first URL the worker was deployed to.
+    pub url: String,
+    #[serde(default = "default_expected_status")]
+    pub expected_status: u16,
+    #[serde(default = "default_timeout")]
+    pub timeout: String,
+}
+
+fn default_expected_status() -> u16 {
+    200
+}
+
+fn default_timeout() -> String {
+    "30s".to_string()
+}
+
+impl HealthCheck {
+    pub fn timeout(&self) -> Result<Duration> {
+        parse_duration(&self.timeout).map_err(|_| {
+            anyhow!(
+                "Invalid health check timeout {:?}, use e.g. \"30s\" or \"2m\"",
+                self.timeout
+            )
+        })
+    }
+}
+
+/// Accepts a number followed by ms, s, m or h, e.g. "500ms" or "2m". A bare number is seconds.
+pub fn parse_duration(duration: &str) -> Result<Duration> {
+    let duration = duration.trim();
+    let invalid = || {
+        anyhow!(
+            "Invalid duration {:?}, use e.g. \"30s\" or \"2m\"",
+            duration
+        )
+    };
+
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or_else(|| duration.len());
+    let (amount, unit) = duration.split_at(split);
+    let amount: u64 = amount.parse().map_err(|_| invalid())?;
+    let seconds_per_unit = match unit {
+        "ms" => return Ok(Duration::from_millis(amount)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(invalid()),
+    };
+    amount
+        .checked_mul(seconds_per_unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| anyhow!("The duration {:?} is too long", duration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_are_parsed() {
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse_duration("10").unwrap(), Duration::from_secs(10));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("5d").is_err());
+        assert!(parse_duration(&format!("{}m", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn locks_outlive_a_minute() {
+        let lock: DeployLock = toml::from_str(r#"namespace_id = "abc""#).unwrap();
+        assert_eq!(lock.wait().unwrap(), Duration::from_secs(300));
+        assert_eq!(lock.ttl().unwrap(), Duration::from_secs(900));
+
+        let lock: DeployLock = toml::from_str(
+            r#"
+            namespace_id = "abc"
+            ttl = "30s"
+            "#,
+        )
+        .unwrap();
+        assert!(lock.ttl().is_err());
+    }
+}
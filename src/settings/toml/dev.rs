@@ -1,12 +1,76 @@
 use crate::commands::dev::Protocol;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use std::net::IpAddr;
+use std::path::PathBuf;
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Dev {
     pub ip: Option<IpAddr>,
     pub port: Option<u16>,
     pub local_protocol: Option<Protocol>,
     pub upstream_protocol: Option<Protocol>,
+    /// `request.cf` fields the worker sees in a dev session, e.g. `country = "DE"`
+    pub cf: Option<Map<String, Value>>,
+    /// The `CF-Connecting-IP` header the worker sees in a dev session
+    pub connecting_ip: Option<String>,
+    /// How the worker's `caches` behave in a dev session. Without it, the edge's cache is used
+    pub cache: Option<DevCache>,
+    /// What the worker's outbound `fetch` calls get in a dev session instead of the response of
+    /// the URL, by URL pattern, e.g. `"https://api.example.com/users/*" = "fixtures/users.json"`
+    pub mocks: Option<BTreeMap<String, Mock>>,
+}
+
+impl Dev {
+    /// These settings, with the ones they leave out taken from `fallback`.
+    pub fn or(&self, fallback: &Dev) -> Dev {
+        Dev {
+            ip: self.ip.or(fallback.ip),
+            port: self.port.or(fallback.port),
+            local_protocol: self.local_protocol.or(fallback.local_protocol),
+            upstream_protocol: self.upstream_protocol.or(fallback.upstream_protocol),
+            cf: self.cf.clone().or_else(|| fallback.cf.clone()),
+            connecting_ip: self
+                .connecting_ip
+                .clone()
+                .or_else(|| fallback.connecting_ip.clone()),
+            cache: self.cache.or(fallback.cache),
+            mocks: self.mocks.clone().or_else(|| fallback.mocks.clone()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DevCache {
+    pub mode: CacheEmulation,
+    /// How much the in-memory cache holds before it evicts the oldest entries, 50MB by default
+    pub max_size_mb: Option<u32>,
+}
+
+impl DevCache {
+    pub fn max_size(&self) -> u64 {
+        u64::from(self.max_size_mb.unwrap_or(50)) * 1024 * 1024
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheEmulation {
+    /// An in-memory cache of the session's isolate
+    Memory,
+    /// `cache.match` always misses
+    Disabled,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Mock {
+    /// An empty response with this status
+    Status(u16),
+    /// A `200 OK` with the contents of this file
+    Fixture(PathBuf),
 }
@@ -1,19 +1,84 @@
+use std::collections::HashSet;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::settings::binding::Binding;
+use crate::settings::toml::Manifest;
+use crate::terminal::message::{Message, StdOut};
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct DurableObjects {
     #[serde(alias = "bindings")]
     pub classes: Option<Vec<DurableObjectsClass>>,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct DurableObjectsClass {
     #[serde(alias = "name")]
     pub binding: String,
     pub class_name: String,
     pub script_name: Option<String>,
+    /// The worker implementing the class in `wrangler dev` and `wrangler preview`, so sessions
+    /// don't reach the objects of `script_name` in production
+    pub preview_script_name: Option<String>,
+    /// Keeps the objects created through this binding, and their data, inside a jurisdiction
+    pub jurisdiction: Option<Jurisdiction>,
+    /// Where new objects created through this binding should preferably be placed
+    pub location_hint: Option<LocationHint>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Jurisdiction {
+    Eu,
+    Fedramp,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LocationHint {
+    Wnam,
+    Enam,
+    Sam,
+    Weur,
+    Eeur,
+    Apac,
+    Oc,
+    Afr,
+    Me,
+}
+
+impl DurableObjects {
+    /// The bindings as a preview session or a publish uses them: previews bind classes of
+    /// other workers to their `preview_script_name`.
+    pub fn for_session(&self, preview: bool) -> DurableObjects {
+        if !preview {
+            return self.clone();
+        }
+        let classes = self.classes.as_ref().map(|classes| {
+            classes
+                .iter()
+                .map(|class| match (&class.script_name, &class.preview_script_name) {
+                    (Some(script_name), Some(preview_script_name)) => {
+                        if script_name == preview_script_name {
+                            StdOut::warn(&format!("{} uses {} for both preview and production sessions, previews will change the objects of your production worker! Proceed with caution.", class.binding, script_name));
+                        }
+                        DurableObjectsClass {
+                            script_name: Some(preview_script_name.clone()),
+                            ..class.clone()
+                        }
+                    }
+                    (Some(script_name), None) => {
+                        StdOut::warn(&format!("{} has no preview_script_name, so previews use the objects of {}", class.binding, script_name));
+                        class.clone()
+                    }
+                    (None, _) => class.clone(),
+                })
+                .collect()
+        });
+        DurableObjects { classes }
+    }
 }
 
 impl DurableObjectsClass {
@@ -22,6 +87,207 @@ impl DurableObjectsClass {
             self.binding.clone(),
             self.class_name.clone(),
             self.script_name.clone(),
+            self.jurisdiction,
+            self.location_hint,
         )
     }
 }
+
+/// Problems with how Durable Objects are set up across environments. Errors mean publishing
+/// wouldn't do what the configuration file says; warnings are likely mistakes.
+#[derive(Debug, Default, PartialEq)]
+pub struct DurableObjectsReport {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Checks the Durable Object bindings of every environment against the migrations, which only
+/// exist at the top level, and against each other.
+pub fn check_environments(manifest: &Manifest) -> DurableObjectsReport {
+    let mut report = DurableObjectsReport::default();
+
+    let mut created = HashSet::new();
+    for migration in manifest.migrations.iter().flatten() {
+        let durable_objects = &migration.migration.durable_objects;
+        created.extend(durable_objects.new_classes.iter());
+        created.extend(durable_objects.renamed_classes.iter().map(|r| &r.to));
+        created.extend(durable_objects.transferred_classes.iter().map(|t| &t.to));
+    }
+    let has_migrations = manifest.migrations.iter().flatten().next().is_some();
+
+    let top_level = classes(&manifest.durable_objects);
+    let has_top_level = !top_level.is_empty();
+    let mut scopes = vec![(
+        "the top level".to_string(),
+        manifest.name.clone(),
+        top_level,
+    )];
+    let mut env_names: Vec<&String> = manifest.env.iter().flat_map(|envs| envs.keys()).collect();
+    env_names.sort();
+    for env_name in env_names {
+        let environment = &manifest.env.as_ref().unwrap()[env_name];
+        let scope = format!("[env.{}]", env_name);
+        if environment.migrations.is_some() {
+            report.errors.push(format!(
+                "{} has migrations, but they're only read from the top level of the configuration file. Move them there, every environment gets them",
+                scope
+            ));
+        }
+        let env_classes = classes(&environment.durable_objects);
+        if env_classes.is_empty() && has_top_level {
+            report.warnings.push(format!(
+                "{} has no Durable Object bindings. They aren't inherited from the top level, so its worker can't reach any objects",
+                scope
+            ));
+        }
+        scopes.push((
+            scope,
+            manifest.worker_name(Some(env_name.as_str())),
+            env_classes,
+        ));
+    }
+
+    for (scope, worker_name, bindings) in &scopes {
+        for class in bindings {
+            let own_class = match &class.script_name {
+                None => true,
+                Some(script_name) => script_name == worker_name,
+            };
+            if own_class && has_migrations && !created.contains(&class.class_name) {
+                report.warnings.push(format!(
+                    "{} binds {} to class {}, which no migration creates",
+                    scope, class.binding, class.class_name
+                ));
+            }
+            if worker_name != &manifest.name && class.script_name.as_ref() == Some(&manifest.name) {
+                report.warnings.push(format!(
+                    "{} binds {} to class {} of {}, the top level worker, rather than of {}",
+                    scope, class.binding, class.class_name, manifest.name, worker_name
+                ));
+            }
+        }
+    }
+
+    let (_, _, top_level) = &scopes[0];
+    for (scope, _, bindings) in &scopes[1..] {
+        for class in bindings {
+            if let Some(other) = top_level.iter().find(|other| {
+                other.binding == class.binding && other.class_name != class.class_name
+            }) {
+                report.warnings.push(format!(
+                    "{} is bound to class {} at the top level but to class {} in {}",
+                    class.binding, other.class_name, class.class_name, scope
+                ));
+            }
+        }
+    }
+
+    report
+}
+
+fn classes(durable_objects: &Option<DurableObjects>) -> Vec<DurableObjectsClass> {
+    durable_objects
+        .as_ref()
+        .and_then(|durable_objects| durable_objects.classes.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placement_is_part_of_the_binding() {
+        let class: DurableObjectsClass = toml::from_str(
+            r#"
+            name = "ROOMS"
+            class_name = "Room"
+            jurisdiction = "eu"
+            location_hint = "weur"
+            "#,
+        )
+        .unwrap();
+        let binding = serde_json::to_value(class.binding()).unwrap();
+        assert_eq!(binding["jurisdiction"], "eu");
+        assert_eq!(binding["location_hint"], "weur");
+
+        let unknown = toml::from_str::<DurableObjectsClass>(
+            r#"
+            name = "ROOMS"
+            class_name = "Room"
+            jurisdiction = "mars"
+            "#,
+        );
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn previews_bind_to_the_preview_script() {
+        let durable_objects: DurableObjects = toml::from_str(
+            r#"
+            bindings = [
+                { name = "ROOMS", class_name = "Room" },
+                { name = "USERS", class_name = "User", script_name = "accounts", preview_script_name = "accounts-preview" },
+            ]
+            "#,
+        )
+        .unwrap();
+        let script_names = |durable_objects: DurableObjects| -> Vec<Option<String>> {
+            durable_objects
+                .classes
+                .unwrap()
+                .into_iter()
+                .map(|class| class.script_name)
+                .collect()
+        };
+
+        assert_eq!(
+            script_names(durable_objects.for_session(true)),
+            vec![None, Some("accounts-preview".to_string())]
+        );
+        assert_eq!(
+            script_names(durable_objects.for_session(false)),
+            vec![None, Some("accounts".to_string())]
+        );
+    }
+
+    #[test]
+    fn environments_are_checked_together() {
+        let manifest: Manifest = toml::from_str(
+            r#"
+            name = "chat"
+            type = "javascript"
+
+            [durable_objects]
+            bindings = [{ name = "ROOMS", class_name = "Room" }]
+
+            [[migrations]]
+            tag = "v1"
+            new_classes = ["Room"]
+
+            [env.staging]
+            durable_objects = { bindings = [
+                { name = "ROOMS", class_name = "Lobby" },
+                { name = "PROD", class_name = "Room", script_name = "chat" },
+            ] }
+            migrations = [{ tag = "v1", new_classes = ["Lobby"] }]
+
+            [env.dev]
+            "#,
+        )
+        .unwrap();
+
+        let report = check_environments(&manifest);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("[env.staging] has migrations"));
+        assert_eq!(
+            report.warnings,
+            vec![
+                "[env.dev] has no Durable Object bindings. They aren't inherited from the top level, so its worker can't reach any objects",
+                "[env.staging] binds ROOMS to class Lobby, which no migration creates",
+                "[env.staging] binds PROD to class Room of chat, the top level worker, rather than of chat-staging",
+                "ROOMS is bound to class Room at the top level but to class Lobby in [env.staging]",
+            ]
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use super::Manifest;
+
+/// Edits the configuration file in place, keeping its comments and formatting, so commands
+/// can write what they'd otherwise ask users to paste in.
+pub struct ConfigEditor {
+    path: PathBuf,
+    text: String,
+}
+
+impl ConfigEditor {
+    pub fn open(path: &Path) -> Result<ConfigEditor> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| anyhow!("could not read {}: {}", path.display(), e))?;
+        Ok(ConfigEditor {
+            path: path.to_path_buf(),
+            text,
+        })
+    }
+
+    /// Sets `key` in the table of the environment, or at the top level, to `value`, written
+    /// as TOML, e.g. `[{ binding = "CACHE", id = "..." }]`.
+    pub fn set(&mut self, env: Option<&str>, key: &str, value: &str) -> Result<()> {
+        let mut doc = self.parse()?;
+        let value = value
+            .parse::<toml_edit::Value>()
+            .map_err(|e| anyhow!("{}", e))?;
+        match env {
+            Some(env) => doc["env"][env][key] = toml_edit::Item::Value(value),
+            None => doc[key] = toml_edit::Item::Value(value),
+        }
+        self.text = doc.to_string_in_original_order();
+        Ok(())
+    }
+
+    /// Adds an entry to an array of tables, e.g. `[[migrations]]`, right after its last entry
+    /// so entries stay together. `body` is the entry without its header.
+    pub fn append_table(&mut self, key: &str, body: &str) -> Result<()> {
+        let mut doc = self.parse()?;
+        let entry = body
+            .parse::<toml_edit::Document>()
+            .map_err(|e| anyhow!("{}", e))?;
+        let mut table = toml_edit::Table::new();
+        for (key, item) in entry.iter() {
+            *table.entry(key) = item.clone();
+        }
+
+        let array = &mut doc[key];
+        if array.is_none() {
+            *array = toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new());
+        }
+        array
+            .as_array_of_tables_mut()
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} in {} isn't an array of tables",
+                    key,
+                    self.path.display()
+                )
+            })?
+            .append(table);
+        // tables without a position of their own are written after the one before them
+        self.text = doc.to_string_in_original_order();
+        Ok(())
+    }
+
+    /// Sets `fields` on the entry of an array of tables in the table of the environment, e.g.
+    /// `kv_namespaces`, whose `id_key` is `id`, adding the entry after the others if there's
+    /// none. `[[...]]` tables and inline arrays are both kept as they are, and an array that
+    /// isn't there yet is added as `[[...]]` tables.
+    pub fn upsert_entry(
+        &mut self,
+        env: Option<&str>,
+        key: &str,
+        (id_key, id): (&str, &str),
+        fields: &[(&str, &str)],
+    ) -> Result<()> {
+        let mut doc = self.parse()?;
+        let array = match env {
+            Some(env) => &mut doc["env"][env][key],
+            None => &mut doc[key],
+        };
+        if array.is_none() {
+            *array = toml_edit::Item::ArrayOfTables(toml_edit::ArrayOfTables::new());
+        }
+
+        let len = match (
+            array.as_array_of_tables(),
+            array.as_value().and_then(|value| value.as_array()),
+        ) {
+            (Some(tables), _) => tables.len(),
+            (None, Some(values)) => values.len(),
+            (None, None) => anyhow::bail!("{} in {} isn't an array", key, self.path.display()),
+        };
+        let index = match (0..len).find(|i| array[*i][id_key].as_str() == Some(id)) {
+            Some(index) => index,
+            None => {
+                let mut entry = toml_edit::Table::new();
+                *entry.entry(id_key) = toml_edit::value(id);
+                match array.as_array_of_tables_mut() {
+                    Some(tables) => {
+                        tables.append(entry);
+                    }
+                    None => {
+                        let entry = format!("{{ {} = {:?} }}", id_key, id)
+                            .parse::<toml_edit::Value>()
+                            .map_err(|e| anyhow!("{}", e))?;
+                        let values = array
+                            .as_value_mut()
+                            .and_then(|value| value.as_array_mut())
+                            .expect("checked above");
+                        let _ = values.push(entry);
+                        if values.len() == len {
+                            anyhow::bail!(
+                                "{} in {} holds something other than tables",
+                                key,
+                                self.path.display()
+                            );
+                        }
+                    }
+                }
+                len
+            }
+        };
+        for (field, value) in fields {
+            array[index][*field] = toml_edit::value(*value);
+        }
+
+        self.text = doc.to_string_in_original_order();
+        Ok(())
+    }
+
+    fn parse(&self) -> Result<toml_edit::Document> {
+        self.text
+            .parse::<toml_edit::Document>()
+            .map_err(|e| anyhow!("toml_edit failed to parse {}. {}", self.path.display(), e))
+    }
+
+    /// Writes the file back, unless the edits made it something wrangler can't read.
+    pub fn save(&self) -> Result<()> {
+        Manifest::from_str(&self.text).map_err(|e| {
+            anyhow!(
+                "Could not update {}, it wouldn't be valid anymore: {}",
+                self.path.display(),
+                e
+            )
+        })?;
+        fs::write(&self.path, &self.text)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> ConfigEditor {
+        ConfigEditor {
+            path: PathBuf::from("wrangler.toml"),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn entries_go_after_the_last_entry_of_their_array() {
+        let mut config = editor(
+            "name = \"chat\"\n\n[[migrations]]\ntag = \"v1\"\nnew_classes = [\"Room\"]\n\n# staging\n[env.staging]\nworkers_dev = true\n",
+        );
+        config
+            .append_table("migrations", "tag = \"v2\"\nnew_classes = [\"Lobby\"]")
+            .unwrap();
+        assert_eq!(
+            config.text,
+            "name = \"chat\"\n\n[[migrations]]\ntag = \"v1\"\nnew_classes = [\"Room\"]\n\n[[migrations]]\ntag = \"v2\"\nnew_classes = [\"Lobby\"]\n\n# staging\n[env.staging]\nworkers_dev = true\n"
+        );
+    }
+
+    #[test]
+    fn entries_go_after_the_sub_tables_of_the_last_entry() {
+        let mut config = editor(
+            "name = \"chat\"\n\n[[migrations]]\ntag = \"v1\"\n\n[[migrations.renamed_classes]]\nfrom = \"Room\"\nto = \"Chat\"\n",
+        );
+        config
+            .append_table("migrations", "tag = \"v2\"\nnew_classes = [\"Lobby\"]")
+            .unwrap();
+        assert_eq!(
+            config.text,
+            "name = \"chat\"\n\n[[migrations]]\ntag = \"v1\"\n\n[[migrations.renamed_classes]]\nfrom = \"Room\"\nto = \"Chat\"\n\n[[migrations]]\ntag = \"v2\"\nnew_classes = [\"Lobby\"]\n"
+        );
+
+        let manifest = Manifest::from_str(&config.text).unwrap();
+        let migrations = manifest.migrations.unwrap();
+        assert_eq!(migrations.len(), 2);
+    }
+
+    #[test]
+    fn the_first_entry_goes_at_the_end() {
+        let mut config = editor("name = \"chat\"\n");
+        config
+            .append_table("migrations", "tag = \"v1\"\nnew_classes = [\"Room\"]\n")
+            .unwrap();
+        assert_eq!(
+            config.text,
+            "name = \"chat\"\n\n[[migrations]]\ntag = \"v1\"\nnew_classes = [\"Room\"]\n"
+        );
+    }
+
+    #[test]
+    fn entries_are_added_to_arrays_of_tables_as_tables() {
+        let mut config = editor(
+            "name = \"worker\"\n\n# caches\n[[kv_namespaces]]\nbinding = \"CACHE\"\nid = \"a\"\n",
+        );
+        config
+            .upsert_entry(None, "kv_namespaces", ("binding", "USERS"), &[("id", "b")])
+            .unwrap();
+        config
+            .upsert_entry(
+                None,
+                "kv_namespaces",
+                ("binding", "CACHE"),
+                &[("preview_id", "c")],
+            )
+            .unwrap();
+        assert!(config.text.contains("# caches\n[[kv_namespaces]]"));
+        assert!(!config.text.contains("kv_namespaces = ["));
+
+        let manifest = Manifest::from_str(&config.text).unwrap();
+        let namespaces = manifest.kv_namespaces.unwrap();
+        assert_eq!(namespaces.len(), 2);
+        assert_eq!(namespaces[0].preview_id.as_deref(), Some("c"));
+        assert_eq!(namespaces[1].binding, "USERS");
+        assert_eq!(namespaces[1].id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn entries_are_added_to_inline_arrays() {
+        let mut config = editor(
+            "name = \"worker\"\n# bindings\nkv_namespaces = [\n    { binding = \"CACHE\", id = \"a\" }, # the cache\n]\n",
+        );
+        config
+            .upsert_entry(None, "kv_namespaces", ("binding", "USERS"), &[("id", "b")])
+            .unwrap();
+        assert!(config.text.contains("# bindings"));
+        assert!(config.text.contains("# the cache"));
+
+        let manifest = Manifest::from_str(&config.text).unwrap();
+        let namespaces = manifest.kv_namespaces.unwrap();
+        assert_eq!(namespaces.len(), 2);
+        assert_eq!(namespaces[1].id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn values_are_set_without_losing_comments() {
+        let mut config = editor(
+            "# my worker\nname = \"chat\"\ntype = \"javascript\"\n\n[env.staging]\n# staging only\nworkers_dev = true\n",
+        );
+        config
+            .set(Some("staging"), "route", "\"example.com/*\"")
+            .unwrap();
+        assert!(config.text.contains("# my worker"));
+        assert!(config.text.contains("# staging only"));
+
+        let manifest = Manifest::from_str(&config.text).unwrap();
+        let staging = manifest.get_environment(Some("staging")).unwrap().unwrap();
+        assert_eq!(staging.route.as_deref(), Some("example.com/*"));
+        assert!(manifest.route.is_none());
+    }
+}
@@ -1,26 +1,59 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::rust::string_empty_as_none;
 
 use crate::settings::toml::builder::Builder;
+use crate::settings::toml::deploy_config::DeployConfig;
+use crate::settings::toml::dev::Dev;
 use crate::settings::toml::durable_objects::DurableObjects;
+use crate::settings::toml::hooks::Hooks;
 use crate::settings::toml::kv_namespace::ConfigKvNamespace;
+use crate::settings::toml::migrations::MigrationConfig;
+use crate::settings::toml::queues::Queues;
+use crate::settings::toml::r2_bucket::R2Bucket;
+use crate::settings::toml::r2_notification::R2Notification;
 use crate::settings::toml::route::RouteConfig;
 use crate::settings::toml::site::Site;
 use crate::settings::toml::triggers::Triggers;
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+/// The top-level keys an environment uses when it doesn't set them itself. `vars`,
+/// `kv_namespaces`, `r2_buckets`, `durable_objects`, `r2_notifications` and `queues` are never
+/// inherited.
+pub const INHERITABLE: &[&str] = &[
+    "account_id",
+    "build",
+    "webpack_config",
+    "workers_dev",
+    "route",
+    "routes",
+    "zone_id",
+    "triggers",
+    "site",
+    "hooks",
+    "deploy",
+    "tags",
+    "dev",
+];
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct Environment {
     pub name: Option<String>,
+    /// `false` to inherit nothing from the top level, or the keys to inherit, e.g.
+    /// `["account_id", "build"]`. Everything inheritable is inherited without it.
+    pub inherit: Option<Inherit>,
     #[serde(default, with = "string_empty_as_none")]
+    #[schemars(with = "Option<String>")]
     pub account_id: Option<String>,
     pub workers_dev: Option<bool>,
     #[serde(default, with = "string_empty_as_none")]
+    #[schemars(with = "Option<String>")]
     pub route: Option<String>,
     pub routes: Option<Vec<String>>,
     #[serde(default, with = "string_empty_as_none")]
+    #[schemars(with = "Option<String>")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
     pub build: Option<Builder>,
@@ -28,20 +61,72 @@ pub struct Environment {
     pub site: Option<Site>,
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
+    pub r2_buckets: Option<Vec<R2Bucket>>,
     pub vars: Option<HashMap<String, String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
     pub triggers: Option<Triggers>,
     pub durable_objects: Option<DurableObjects>,
+    pub hooks: Option<Hooks>,
+    pub deploy: Option<DeployConfig>,
+    /// Migrations are only read from the top level, this is here to report them as misplaced
+    pub migrations: Option<Vec<MigrationConfig>>,
+    pub tags: Option<Vec<String>>,
+    pub r2_notifications: Option<Vec<R2Notification>>,
+    pub queues: Option<Queues>,
+    pub dev: Option<Dev>,
+    /// Publishing to the environment has to be confirmed by typing the script name, or with
+    /// `--confirm-protected` when there's no one to type it
+    pub protected: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Inherit {
+    All(bool),
+    Only(Vec<String>),
 }
 
 impl Environment {
+    /// Whether the environment takes the top-level value of `key` when it doesn't set it.
+    pub fn inherits(&self, key: &str) -> bool {
+        match &self.inherit {
+            None | Some(Inherit::All(true)) => true,
+            Some(Inherit::All(false)) => false,
+            Some(Inherit::Only(keys)) => keys.iter().any(|k| k == key),
+        }
+    }
+
+    /// Fails on keys in `inherit` that can't be inherited, which are most likely typos.
+    pub fn check_inherit(&self, name: &str) -> anyhow::Result<()> {
+        if let Some(Inherit::Only(keys)) = &self.inherit {
+            for key in keys {
+                if !INHERITABLE.contains(&key.as_str()) {
+                    anyhow::bail!(
+                        "[env.{}] inherit lists {}, which isn't inherited. Environments can inherit {}",
+                        name,
+                        key,
+                        INHERITABLE.join(", ")
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn route_config(
         &self,
         top_level_account_id: Option<String>,
         top_level_zone_id: Option<String>,
     ) -> Option<RouteConfig> {
-        let account_id = self.account_id.clone().or(top_level_account_id).into();
-        let zone_id = self.zone_id.clone().or(top_level_zone_id);
+        let account_id = self
+            .account_id
+            .clone()
+            .or(top_level_account_id.filter(|_| self.inherits("account_id")))
+            .into();
+        let zone_id = self
+            .zone_id
+            .clone()
+            .or(top_level_zone_id.filter(|_| self.inherits("zone_id")));
 
         if self.workers_dev.is_none() && self.route.is_none() && self.routes.is_none() {
             None
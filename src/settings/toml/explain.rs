@@ -0,0 +1,257 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::environment::Environment;
+use super::Manifest;
+
+/// Where the value an environment ends up with comes from.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// Only set in the environment.
+    Environment,
+    /// Set in the environment, replacing the top-level value.
+    Overridden,
+    /// Not set in the environment, so the top-level value is used.
+    Inherited,
+    /// Can only be set at the top level.
+    TopLevel,
+    /// Worked out from other values, e.g. the worker name from the top-level name.
+    Derived,
+    /// Set at the top level, but environments don't inherit it.
+    NotInherited,
+    /// Set in the environment, but only the top-level value is used.
+    Ignored,
+}
+
+/// A value of the configuration an environment is published with.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Resolved {
+    pub key: &'static str,
+    /// What the environment gets, `null` when it gets nothing.
+    pub value: Value,
+    pub source: Source,
+}
+
+impl Manifest {
+    /// Describes the configuration of environment `name` the way `get_target` and
+    /// `get_deployments` put it together, and where each value comes from.
+    pub fn explain(&self, name: &str) -> Result<Vec<Resolved>> {
+        let env = self
+            .get_environment(Some(name))?
+            .expect("an environment name was given");
+        let mut resolved = Resolution {
+            env,
+            resolved: Vec::new(),
+        };
+
+        match &env.name {
+            Some(worker) => resolved.push("name", worker, Source::Overridden),
+            None => resolved.push("name", &self.worker_name(Some(name)), Source::Derived),
+        }
+        resolved.top_level("type", Some(&self.target_type), None::<&()>);
+        resolved.inherited(
+            "account_id",
+            env.account_id.as_ref(),
+            self.account_id.if_present(),
+        );
+        resolved.top_level("main", self.main.as_ref(), None::<&()>);
+        resolved.inherited("build", env.build.as_ref(), self.build.as_ref());
+        resolved.inherited(
+            "webpack_config",
+            env.webpack_config.as_ref(),
+            self.webpack_config.as_ref(),
+        );
+        resolved.top_level("usage_model", self.usage_model.as_ref(), None::<&()>);
+        resolved.top_level(
+            "compatibility_date",
+            self.compatibility_date.as_ref(),
+            None::<&()>,
+        );
+        let flags = Some(&self.compatibility_flags).filter(|flags| !flags.is_empty());
+        resolved.top_level("compatibility_flags", flags, None::<&()>);
+
+        // workers_dev, route and routes go together: setting any of them in the environment
+        // replaces all three
+        let routing = env.workers_dev.is_some() || env.route.is_some() || env.routes.is_some();
+        if routing {
+            resolved.not_inherited(
+                "workers_dev",
+                env.workers_dev.as_ref(),
+                self.workers_dev.as_ref(),
+            );
+            resolved.not_inherited("route", env.route.as_ref(), self.route.as_ref());
+            resolved.not_inherited("routes", env.routes.as_ref(), self.routes.as_ref());
+        } else {
+            resolved.inherited("workers_dev", None, self.workers_dev.as_ref());
+            resolved.inherited("route", None, self.route.as_ref());
+            resolved.inherited("routes", None, self.routes.as_ref());
+        }
+        resolved.inherited("zone_id", env.zone_id.as_ref(), self.zone_id.as_ref());
+        resolved.inherited("triggers", env.triggers.as_ref(), self.triggers.as_ref());
+
+        resolved.not_inherited(
+            "kv_namespaces",
+            env.kv_namespaces.as_ref(),
+            self.kv_namespaces.as_ref(),
+        );
+        resolved.not_inherited(
+            "r2_buckets",
+            env.r2_buckets.as_ref(),
+            self.r2_buckets.as_ref(),
+        );
+        resolved.not_inherited(
+            "durable_objects",
+            env.durable_objects.as_ref(),
+            self.durable_objects.as_ref(),
+        );
+        resolved.not_inherited("vars", env.vars.as_ref(), self.vars.as_ref());
+        resolved.not_inherited(
+            "r2_notifications",
+            env.r2_notifications.as_ref(),
+            self.r2_notifications.as_ref(),
+        );
+        resolved.not_inherited("queues", env.queues.as_ref(), self.queues.as_ref());
+        resolved.top_level(
+            "text_blobs",
+            self.text_blobs.as_ref(),
+            env.text_blobs.as_ref(),
+        );
+        resolved.top_level("wasm_modules", self.wasm_modules.as_ref(), None::<&()>);
+        resolved.top_level(
+            "migrations",
+            self.migrations.as_ref(),
+            env.migrations.as_ref(),
+        );
+        resolved.inherited("site", env.site.as_ref(), self.site.as_ref());
+        resolved.inherited("hooks", env.hooks.as_ref(), self.hooks.as_ref());
+        resolved.inherited("deploy", env.deploy.as_ref(), self.deploy.as_ref());
+        resolved.inherited("tags", env.tags.as_ref(), self.tags.as_ref());
+        resolved.inherited("dev", env.dev.as_ref(), self.dev.as_ref());
+
+        Ok(resolved.resolved)
+    }
+}
+
+struct Resolution<'a> {
+    env: &'a Environment,
+    resolved: Vec<Resolved>,
+}
+
+impl Resolution<'_> {
+    fn push<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T, source: Source) {
+        self.resolved.push(Resolved {
+            key,
+            value: serde_json::to_value(value).unwrap_or(Value::Null),
+            source,
+        });
+    }
+
+    fn inherited<T: Serialize>(&mut self, key: &'static str, env: Option<&T>, top: Option<&T>) {
+        match (env, top) {
+            (Some(env), Some(_)) => self.push(key, env, Source::Overridden),
+            (Some(env), None) => self.push(key, env, Source::Environment),
+            // `inherit` can leave it out
+            (None, Some(_)) if !self.env.inherits(key) => {
+                self.push(key, &Value::Null, Source::NotInherited)
+            }
+            (None, Some(top)) => self.push(key, top, Source::Inherited),
+            (None, None) => {}
+        }
+    }
+
+    fn not_inherited<T: Serialize>(&mut self, key: &'static str, env: Option<&T>, top: Option<&T>) {
+        match (env, top) {
+            (Some(env), Some(_)) => self.push(key, env, Source::Overridden),
+            (Some(env), None) => self.push(key, env, Source::Environment),
+            (None, Some(_)) => self.push(key, &Value::Null, Source::NotInherited),
+            (None, None) => {}
+        }
+    }
+
+    fn top_level<T: Serialize, E: Serialize>(
+        &mut self,
+        key: &'static str,
+        top: Option<&T>,
+        env: Option<&E>,
+    ) {
+        if let Some(env) = env {
+            self.push(key, env, Source::Ignored);
+        }
+        if let Some(top) = top {
+            self.push(key, top, Source::TopLevel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn source(resolved: &[Resolved], key: &str) -> Vec<Source> {
+        resolved
+            .iter()
+            .filter(|r| r.key == key)
+            .map(|r| r.source)
+            .collect()
+    }
+
+    #[test]
+    fn it_explains_where_values_come_from() {
+        let manifest = Manifest::from_str(
+            r#"
+            name = "shop"
+            type = "javascript"
+            account_id = "abc"
+            route = "shop.example.com/*"
+            zone_id = "zone"
+            vars = { MODE = "production" }
+            tags = ["shop"]
+
+            [env.staging]
+            workers_dev = true
+            account_id = "def"
+            tags = ["shop", "staging"]
+            text_blobs = { TEMPLATE = "template.html" }
+            "#,
+        )
+        .unwrap();
+        let resolved = manifest.explain("staging").unwrap();
+
+        assert_eq!(source(&resolved, "name"), vec![Source::Derived]);
+        assert_eq!(resolved[0].value, "shop-staging");
+        assert_eq!(source(&resolved, "type"), vec![Source::TopLevel]);
+        assert_eq!(source(&resolved, "account_id"), vec![Source::Overridden]);
+        assert_eq!(source(&resolved, "workers_dev"), vec![Source::Environment]);
+        assert_eq!(source(&resolved, "route"), vec![Source::NotInherited]);
+        assert_eq!(source(&resolved, "zone_id"), vec![Source::Inherited]);
+        assert_eq!(source(&resolved, "vars"), vec![Source::NotInherited]);
+        assert_eq!(source(&resolved, "tags"), vec![Source::Overridden]);
+        assert_eq!(source(&resolved, "text_blobs"), vec![Source::Ignored]);
+        assert!(source(&resolved, "kv_namespaces").is_empty());
+    }
+
+    #[test]
+    fn it_explains_what_inherit_leaves_out() {
+        let manifest = Manifest::from_str(
+            r#"
+            name = "shop"
+            type = "javascript"
+            account_id = "abc"
+            workers_dev = true
+            tags = ["shop"]
+
+            [env.staging]
+            inherit = ["account_id"]
+            "#,
+        )
+        .unwrap();
+        let resolved = manifest.explain("staging").unwrap();
+
+        assert_eq!(source(&resolved, "account_id"), vec![Source::Inherited]);
+        assert_eq!(source(&resolved, "workers_dev"), vec![Source::NotInherited]);
+        assert_eq!(source(&resolved, "tags"), vec![Source::NotInherited]);
+    }
+}
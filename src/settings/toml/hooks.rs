@@ -0,0 +1,52 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const SENTRY_URL: &str = "https://sentry.io";
+const SENTRY_URL_PREFIX: &str = "~/";
+
+/// Integrations that run every time a worker has been built, by `build`, `dev`, `preview` or
+/// `publish`.
+///
+/// ```toml
+/// [hooks]
+/// post_build = ["node ./scripts/upload-sourcemaps.js"]
+///
+/// [hooks.sentry]
+/// org = "my-org"
+/// project = "my-worker"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hooks {
+    /// Commands that receive the build artifacts as JSON on stdin.
+    #[serde(default)]
+    pub post_build: Vec<String>,
+    pub sentry: Option<SentryConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SentryConfig {
+    pub org: String,
+    pub project: String,
+    /// Overrides the release name, which defaults to the script version.
+    pub release: Option<String>,
+    #[serde(default = "sentry_url")]
+    pub url: String,
+    #[serde(default = "sentry_url_prefix")]
+    pub url_prefix: String,
+}
+
+fn sentry_url() -> String {
+    SENTRY_URL.to_string()
+}
+
+fn sentry_url_prefix() -> String {
+    SENTRY_URL_PREFIX.to_string()
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.post_build.is_empty() && self.sentry.is_none()
+    }
+}
@@ -8,6 +8,7 @@ use config::{Config, File};
 
 use anyhow::{anyhow, Result};
 use once_cell::sync::OnceCell;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::rust::string_empty_as_none;
 
@@ -15,12 +16,19 @@ use super::UsageModel;
 use crate::commands::whoami::fetch_accounts;
 use crate::commands::{validate_worker_name, whoami, DEFAULT_CONFIG_PATH};
 use crate::deploy::{self, DeployTarget, DeploymentSet};
+use crate::error::{Categorize, ErrorCategory, WranglerError};
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::builder::Builder;
+use crate::settings::toml::deploy_config::DeployConfig;
 use crate::settings::toml::dev::Dev;
 use crate::settings::toml::durable_objects::DurableObjects;
 use crate::settings::toml::environment::Environment;
+use crate::settings::toml::hooks::Hooks;
 use crate::settings::toml::kv_namespace::{ConfigKvNamespace, KvNamespace};
+use crate::settings::toml::migrations::{MigrationConfig, Migrations};
+use crate::settings::toml::queues::{QueueConsumer, Queues};
+use crate::settings::toml::r2_bucket::R2Bucket;
+use crate::settings::toml::r2_notification::R2Notification;
 use crate::settings::toml::route::RouteConfig;
 use crate::settings::toml::site::Site;
 use crate::settings::toml::target_type::TargetType;
@@ -32,21 +40,25 @@ use crate::terminal::{
     styles,
 };
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct Manifest {
     #[serde(default)]
     pub name: String,
     #[serde(rename = "type")]
     pub target_type: TargetType,
     #[serde(default)]
+    #[schemars(with = "String")]
     pub account_id: LazyAccountId,
     pub workers_dev: Option<bool>,
     #[serde(default, with = "string_empty_as_none")]
+    #[schemars(with = "Option<String>")]
     pub route: Option<String>,
     pub routes: Option<Vec<String>>,
     #[serde(default, with = "string_empty_as_none")]
+    #[schemars(with = "Option<String>")]
     pub zone_id: Option<String>,
     pub webpack_config: Option<String>,
+    pub main: Option<PathBuf>,
     pub build: Option<Builder>,
     pub private: Option<bool>,
     // TODO: maybe one day, serde toml support will allow us to serialize sites
@@ -55,6 +67,7 @@ pub struct Manifest {
     pub dev: Option<Dev>,
     #[serde(alias = "kv-namespaces")]
     pub kv_namespaces: Option<Vec<ConfigKvNamespace>>,
+    pub r2_buckets: Option<Vec<R2Bucket>>,
     pub env: Option<HashMap<String, Environment>>,
     pub vars: Option<HashMap<String, String>>,
     pub text_blobs: Option<HashMap<String, PathBuf>>,
@@ -62,10 +75,17 @@ pub struct Manifest {
     pub triggers: Option<Triggers>,
     pub durable_objects: Option<DurableObjects>,
     #[serde(default, with = "string_empty_as_none")]
+    #[schemars(with = "Option<UsageModel>")]
     pub usage_model: Option<UsageModel>,
     pub compatibility_date: Option<String>,
     #[serde(default)]
     pub compatibility_flags: Vec<String>,
+    pub hooks: Option<Hooks>,
+    pub deploy: Option<DeployConfig>,
+    pub migrations: Option<Vec<MigrationConfig>>,
+    pub tags: Option<Vec<String>>,
+    pub r2_notifications: Option<Vec<R2Notification>>,
+    pub queues: Option<Queues>,
 }
 
 impl Manifest {
@@ -75,7 +95,18 @@ impl Manifest {
         if config_path.to_str().unwrap() == DEFAULT_CONFIG_PATH {
             message.push_str("; run `wrangler init` to create one.");
         }
-        anyhow::ensure!(config_path.exists(), message);
+        if !config_path.exists() {
+            return Err(WranglerError::new(
+                ErrorCategory::Config,
+                "config.not_found",
+                anyhow!(message),
+            ));
+        }
+
+        Self::parse(config_path).categorize(ErrorCategory::Config, "config.invalid")
+    }
+
+    fn parse(config_path: &Path) -> Result<Self> {
         let config = read_config(config_path)?;
 
         let manifest: Manifest = match config.try_into() {
@@ -181,6 +212,32 @@ impl Manifest {
         Ok(manifest)
     }
 
+    /// The `wrangler dev` settings of an environment: its own `[env.<name>.dev]`, falling back
+    /// to the top-level `[dev]` setting by setting.
+    /// Whether publishing to `env` needs to be confirmed. The top level is never protected.
+    pub fn is_protected(&self, env: Option<&str>) -> Result<bool> {
+        Ok(self
+            .get_environment(env)?
+            .and_then(|environment| environment.protected)
+            .unwrap_or(false))
+    }
+
+    pub fn dev_config(&self, env: Option<&str>) -> Result<Option<Dev>> {
+        let top_level = self.dev.as_ref();
+        let environment = self.get_environment(env)?;
+        Ok(match (environment, top_level) {
+            (Some(environment), top_level) => {
+                let top_level = top_level.filter(|_| environment.inherits("dev"));
+                match (&environment.dev, top_level) {
+                    (Some(dev), Some(top_level)) => Some(dev.or(top_level)),
+                    (Some(dev), None) => Some(dev.clone()),
+                    (None, top_level) => top_level.cloned(),
+                }
+            }
+            (None, top_level) => top_level.cloned(),
+        })
+    }
+
     pub fn worker_name(&self, env_arg: Option<&str>) -> String {
         if let Some(environment) = self.get_environment(env_arg).unwrap_or_default() {
             if let Some(name) = &environment.name {
@@ -204,6 +261,23 @@ impl Manifest {
         }
     }
 
+    // The top-level routing of an environment that doesn't have its own, without what its
+    // `inherit` leaves out
+    fn inherited_route_config(&self, env: &Environment) -> RouteConfig {
+        let config = self.route_config();
+        RouteConfig {
+            account_id: if env.inherits("account_id") {
+                config.account_id
+            } else {
+                LazyAccountId::default()
+            },
+            workers_dev: config.workers_dev.filter(|_| env.inherits("workers_dev")),
+            route: config.route.filter(|_| env.inherits("route")),
+            routes: config.routes.filter(|_| env.inherits("routes")),
+            zone_id: config.zone_id.filter(|_| env.inherits("zone_id")),
+        }
+    }
+
     pub fn get_deployments(&self, env: Option<&str>) -> Result<DeploymentSet> {
         let script = self.worker_name(env);
         validate_worker_name(&script)?;
@@ -249,7 +323,7 @@ impl Manifest {
             {
                 add_routed_deployments(&env_route_cfg)
             } else {
-                let config = self.route_config();
+                let config = self.inherited_route_config(env);
                 if config.is_zoned() {
                     anyhow::bail!("you must specify route(s) per environment for zoned deploys.");
                 } else {
@@ -262,13 +336,14 @@ impl Manifest {
 
         let crons = match env {
             Some(e) => {
-                let account_id = e
-                    .account_id
-                    .as_ref()
-                    .or_else(|| self.account_id.if_present());
+                let account_id = e.account_id.as_ref().or_else(|| {
+                    self.account_id
+                        .if_present()
+                        .filter(|_| e.inherits("account_id"))
+                });
                 e.triggers
                     .as_ref()
-                    .or_else(|| self.triggers.as_ref())
+                    .or_else(|| self.triggers.as_ref().filter(|_| e.inherits("triggers")))
                     .map(|t| (t.crons.as_slice(), account_id))
             }
             None => self
@@ -301,6 +376,9 @@ impl Manifest {
             if let Some(account_id) = &environment.account_id {
                 return Ok(account_id.to_string());
             }
+            if !environment.inherits("account_id") {
+                return LazyAccountId::default().load().map(String::from);
+            }
         }
         self.account_id.load().map(String::from)
     }
@@ -338,30 +416,73 @@ impl Manifest {
         Inherited: Can be configured at the top level and/or environment. If the property is defined only at the top level, the environment will use the property value from the top level. If the property is defined in the environment, the environment value will override the top level value.
 
         Not inherited: Must be defined for every environment individually.
+
+        `Manifest::explain` describes these rules for `wrangler env show`, keep the two in sync.
         */
         let mut target = Target {
             target_type: self.target_type.clone(),       // Top level
             account_id: self.account_id.clone(),         // Inherited
             webpack_config: self.webpack_config.clone(), // Inherited
             build: self.build.clone(),                   // Inherited
+            main: self.main.clone(),                     // Top level
             // importantly, the top level name will be modified
             // to include the name of the environment
             name: self.name.clone(), // Inherited
-            kv_namespaces: get_namespaces(self.kv_namespaces.clone(), preview)?, // Not inherited
-            durable_objects: self.durable_objects.clone(), // Not inherited
-            migrations: None,        // TODO(soon) Allow migrations in wrangler.toml
-            site: self.site.clone(), // Inherited
-            vars: self.vars.clone(), // Not inherited
-            text_blobs: self.text_blobs.clone(), // Inherited
-            usage_model: self.usage_model, // Top level
+            // an environment's namespaces are looked up below, the top-level ones don't matter
+            kv_namespaces: match environment_name {
+                Some(_) => Vec::new(),
+                None => get_namespaces(self.kv_namespaces.clone(), None, preview)?,
+            }, // Not inherited
+            r2_buckets: self.r2_buckets.clone().unwrap_or_default(), // Not inherited
+            durable_objects: self
+                .durable_objects
+                .as_ref()
+                .map(|durable_objects| durable_objects.for_session(preview)), // Not inherited
+            migrations: self.migrations.clone().map(|migrations| Migrations {
+                migrations,
+                old_tag: None,
+            }), // Top level
+            site: self.site.clone(),                                 // Inherited
+            vars: self.vars.clone(),                                 // Not inherited
+            text_blobs: self.text_blobs.clone(),                     // Inherited
+            usage_model: self.usage_model,                           // Top level
             wasm_modules: self.wasm_modules.clone(),
             compatibility_date: self.compatibility_date.clone(),
             compatibility_flags: self.compatibility_flags.clone(),
+            hooks: self.hooks.clone(),   // Inherited
+            deploy: self.deploy.clone(), // Inherited
+            tags: self.tags.clone(),     // Inherited
+            r2_notifications: self.r2_notifications.clone().unwrap_or_default(), // Not inherited
+            queue_consumers: consumers(self.queues.as_ref()), // Not inherited
         };
 
         let environment = self.get_environment(environment_name)?;
 
         if let Some(environment) = environment {
+            environment.check_inherit(environment_name.unwrap_or_default())?;
+            // with `inherit`, an environment only gets some of the top-level values, or none
+            if !environment.inherits("account_id") {
+                target.account_id = LazyAccountId::default();
+            }
+            if !environment.inherits("webpack_config") {
+                target.webpack_config = None;
+            }
+            if !environment.inherits("build") {
+                target.build = None;
+            }
+            if !environment.inherits("site") {
+                target.site = None;
+            }
+            if !environment.inherits("hooks") {
+                target.hooks = None;
+            }
+            if !environment.inherits("deploy") {
+                target.deploy = None;
+            }
+            if !environment.inherits("tags") {
+                target.tags = None;
+            }
+
             target.name = self.worker_name(environment_name);
             if let Some(account_id) = &environment.account_id {
                 target.account_id = Some(account_id.clone()).into();
@@ -374,10 +495,17 @@ impl Manifest {
             }
 
             // don't inherit kv namespaces because it is an anti-pattern to use the same namespaces across multiple environments
-            target.kv_namespaces = get_namespaces(environment.kv_namespaces.clone(), preview)?;
+            target.kv_namespaces =
+                get_namespaces(environment.kv_namespaces.clone(), environment_name, preview)?;
+
+            // don't inherit R2 buckets either, for the same reason
+            target.r2_buckets = environment.r2_buckets.clone().unwrap_or_default();
 
             // don't inherit durable object configuration
-            target.durable_objects = environment.durable_objects.clone();
+            target.durable_objects = environment
+                .durable_objects
+                .as_ref()
+                .map(|durable_objects| durable_objects.for_session(preview));
 
             // inherit site configuration
             if let Some(site) = &environment.site {
@@ -386,6 +514,36 @@ impl Manifest {
 
             // don't inherit vars
             target.vars = environment.vars.clone();
+
+            // an environment's own hooks replace the top-level ones
+            if let Some(hooks) = &environment.hooks {
+                target.hooks = Some(hooks.clone());
+            }
+
+            // inherit the deploy checks
+            if let Some(deploy) = &environment.deploy {
+                target.deploy = Some(deploy.clone());
+            }
+
+            // don't inherit R2 notifications, each environment consumes its own queue
+            target.r2_notifications = environment.r2_notifications.clone().unwrap_or_default();
+
+            // don't inherit queue consumers either
+            target.queue_consumers = consumers(environment.queues.as_ref());
+
+            // inherit tags
+            if let Some(tags) = &environment.tags {
+                target.tags = Some(tags.clone());
+            }
+        }
+
+        // `main` stands in for a `[build]` section when the entrypoint is an ES module
+        if let Some(main) = &self.main {
+            if target.build.is_none() {
+                target.build = Builder::from_main(main);
+            } else {
+                log::info!("`main` is ignored in favor of the [build] section");
+            }
         }
 
         Ok(target)
@@ -491,19 +649,19 @@ impl Manifest {
             if has_top_level_fields {
                 needs_new_line = true;
                 for top_level_field in top_level_fields {
-                    println!("- {}", top_level_field);
+                    StdOut::message(&format!("- {}", top_level_field));
                 }
             }
 
             if has_env_fields {
                 for (env_name, env_fields) in env_fields {
                     if needs_new_line {
-                        println!();
+                        StdOut::message("");
                     }
-                    println!("[env.{}]", env_name);
+                    StdOut::message(&format!("[env.{}]", env_name));
                     needs_new_line = true;
                     for env_field in env_fields {
-                        println!("  - {}", env_field);
+                        StdOut::message(&format!("  - {}", env_field));
                     }
                 }
             }
@@ -551,7 +709,7 @@ impl LazyAccountId {
     /// Return the `account_id` in `wrangler.toml`, if present.
     ///
     /// Use this with caution; prefer `maybe_load` instead where possible.
-    fn if_present(&self) -> Option<&String> {
+    pub(super) fn if_present(&self) -> Option<&String> {
         self.0.get()
     }
 
@@ -651,8 +809,15 @@ fn check_for_duplicate_names(manifest: &Manifest) -> Result<()> {
     Ok(())
 }
 
+fn consumers(queues: Option<&Queues>) -> Vec<QueueConsumer> {
+    queues
+        .map(|queues| queues.consumers.clone())
+        .unwrap_or_default()
+}
+
 fn get_namespaces(
     kv_namespaces: Option<Vec<ConfigKvNamespace>>,
+    environment_name: Option<&str>,
     preview: bool,
 ) -> Result<Vec<KvNamespace>> {
     if let Some(namespaces) = kv_namespaces {
@@ -669,7 +834,17 @@ fn get_namespaces(
                         binding: ns.binding.to_string(),
                     })
                 } else {
-                    anyhow::bail!("In order to preview a worker with KV namespaces, you must designate a preview_id in your configuration file for each KV namespace you'd like to preview.")
+                    let (scope, env_flag) = match environment_name {
+                        Some(env) => (format!("[env.{}] ", env), format!(" --env {}", env)),
+                        None => (String::new(), String::new()),
+                    };
+                    anyhow::bail!(
+                        "In order to preview a worker with KV namespaces, you must designate a preview_id in your configuration file for each KV namespace you'd like to preview. {}{} has none, create one with `wrangler kv:namespace create {} --preview{}`",
+                        scope,
+                        ns.binding,
+                        ns.binding,
+                        env_flag
+                    )
                 }
             } else if let Some(id) = &ns.id {
                 Ok(KvNamespace {
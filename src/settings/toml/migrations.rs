@@ -1,69 +1,192 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct Migrations {
     pub migrations: Vec<MigrationConfig>,
+    /// The migration tag of the deployed script, the migrations up to it were already applied.
+    /// Filled in right before uploading.
+    #[serde(skip)]
+    pub old_tag: Option<String>,
 }
 
 impl Migrations {
-    pub fn api_migration(&self) -> Result<ApiMigration, anyhow::Error> {
-        // TODO: make api call to get most recent tag, and coalesce tags afterwards.
-        // For now, migrations will only ever have a single adhoc migration in it.
-        match &self.migrations {
-            migrations if migrations.len() == 1 => Ok(ApiMigration {
-                old_tag: None,
-                new_tag: None,
-                migration: migrations[0].migration.clone(),
-            }),
-            migrations => Self::coalesce_migrations(migrations),
+    /// What to send along with the script, or `None` if every migration was already applied.
+    pub fn api_migration(&self) -> Result<Option<ApiMigration>> {
+        // a migration from the command line has no tag, and is sent as is
+        if let [adhoc] = self.migrations.as_slice() {
+            if adhoc.tag.is_none() {
+                return Ok(Some(ApiMigration {
+                    old_tag: None,
+                    new_tag: None,
+                    steps: ApiMigrationSteps::Single(adhoc.migration.clone()),
+                }));
+            }
         }
+
+        let pending = self.pending()?;
+        let new_tag = match pending.last() {
+            Some(last) => last.tag.clone(),
+            None => return Ok(None),
+        };
+        let steps = match pending.as_slice() {
+            [single] => ApiMigrationSteps::Single(single.migration.clone()),
+            multiple => ApiMigrationSteps::Multiple {
+                steps: multiple.iter().map(|m| m.migration.clone()).collect(),
+            },
+        };
+        Ok(Some(ApiMigration {
+            old_tag: self.old_tag.clone(),
+            new_tag,
+            steps,
+        }))
     }
 
-    fn coalesce_migrations(_migrations: &[MigrationConfig]) -> Result<ApiMigration, anyhow::Error> {
-        unimplemented!()
+    /// The migrations after `old_tag`, in order. Every migration needs a unique tag so the API
+    /// can tell how far a script got.
+    pub fn pending(&self) -> Result<Vec<&MigrationConfig>> {
+        let mut tags = HashSet::new();
+        for migration in &self.migrations {
+            match &migration.tag {
+                None => anyhow::bail!("Every [[migrations]] entry needs a tag"),
+                Some(tag) if !tags.insert(tag) => {
+                    anyhow::bail!("The migration tag {:?} is used more than once", tag)
+                }
+                Some(_) => {}
+            }
+        }
+
+        let applied = match &self.old_tag {
+            None => 0,
+            Some(old_tag) => match self
+                .migrations
+                .iter()
+                .position(|m| m.tag.as_ref() == Some(old_tag))
+            {
+                Some(index) => index + 1,
+                None => anyhow::bail!(
+                    "The deployed script is at migration {:?}, which isn't in your configuration file",
+                    old_tag
+                ),
+            },
+        };
+        Ok(self.migrations[applied..].iter().collect())
     }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct MigrationConfig {
     pub tag: Option<String>,
     #[serde(flatten)]
     pub migration: Migration,
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct ApiMigration {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub old_tag: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub new_tag: Option<String>,
     #[serde(flatten)]
-    pub migration: Migration,
+    pub steps: ApiMigrationSteps,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+/// A single migration is sent inline, several as a list of `steps`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ApiMigrationSteps {
+    Single(Migration),
+    Multiple { steps: Vec<Migration> },
+}
+
+impl ApiMigrationSteps {
+    pub fn migrations(&self) -> Vec<&Migration> {
+        match self {
+            ApiMigrationSteps::Single(migration) => vec![migration],
+            ApiMigrationSteps::Multiple { steps } => steps.iter().collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct Migration {
     #[serde(flatten)]
     pub durable_objects: DurableObjectsMigration,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct DurableObjectsMigration {
+    #[serde(default)]
     pub new_classes: Vec<String>,
+    #[serde(default)]
     pub deleted_classes: Vec<String>,
+    #[serde(default)]
     pub renamed_classes: Vec<RenameClass>,
+    #[serde(default)]
     pub transferred_classes: Vec<TransferClass>,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct RenameClass {
     pub from: String,
     pub to: String,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
 pub struct TransferClass {
     pub from: String,
     pub from_script: String,
     pub to: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tagged(tag: &str, new_class: &str) -> MigrationConfig {
+        MigrationConfig {
+            tag: Some(tag.to_string()),
+            migration: Migration {
+                durable_objects: DurableObjectsMigration {
+                    new_classes: vec![new_class.to_string()],
+                    ..DurableObjectsMigration::default()
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn only_migrations_after_the_deployed_tag_are_sent() {
+        let mut migrations = Migrations {
+            migrations: vec![tagged("v1", "A"), tagged("v2", "B"), tagged("v3", "C")],
+            old_tag: Some("v1".to_string()),
+        };
+        let api = migrations.api_migration().unwrap().unwrap();
+        assert_eq!(api.old_tag.as_deref(), Some("v1"));
+        assert_eq!(api.new_tag.as_deref(), Some("v3"));
+        assert_eq!(api.steps.migrations().len(), 2);
+        let json = serde_json::to_value(&api).unwrap();
+        assert_eq!(json["steps"][0]["new_classes"][0], "B");
+
+        migrations.old_tag = Some("v3".to_string());
+        assert_eq!(migrations.api_migration().unwrap(), None);
+
+        migrations.old_tag = Some("v9".to_string());
+        assert!(migrations.api_migration().is_err());
+    }
+
+    #[test]
+    fn a_single_migration_is_sent_inline() {
+        let migrations = Migrations {
+            migrations: vec![tagged("v1", "A")],
+            old_tag: None,
+        };
+        let json = serde_json::to_value(migrations.api_migration().unwrap()).unwrap();
+        assert_eq!(json["new_tag"], "v1");
+        assert_eq!(json["new_classes"][0], "A");
+        assert!(json.get("old_tag").is_none());
+    }
+}
@@ -1,10 +1,17 @@
 mod builder;
+mod deploy_config;
 mod dev;
 mod durable_objects;
+mod editor;
 mod environment;
+pub mod explain;
+mod hooks;
 mod kv_namespace;
 mod manifest;
 pub mod migrations;
+mod queues;
+mod r2_bucket;
+mod r2_notification;
 mod route;
 mod site;
 mod target;
@@ -12,19 +19,30 @@ mod target_type;
 mod triggers;
 
 pub use builder::{ModuleRule, UploadFormat};
-pub use durable_objects::{DurableObjects, DurableObjectsClass};
+pub use deploy_config::{parse_duration, DeployConfig, DeployLock, HealthCheck};
+pub use dev::{CacheEmulation, DevCache, Mock};
+pub use durable_objects::{
+    check_environments, DurableObjects, DurableObjectsClass, DurableObjectsReport, Jurisdiction,
+    LocationHint,
+};
+pub use editor::ConfigEditor;
+pub use hooks::{Hooks, SentryConfig};
 pub use kv_namespace::{ConfigKvNamespace, KvNamespace};
 pub use manifest::Manifest;
-pub use route::{Route, RouteConfig};
+pub use queues::{QueueConsumer, Queues};
+pub use r2_bucket::R2Bucket;
+pub use r2_notification::{R2EventType, R2Notification};
+pub use route::{lint_patterns, Route, RouteConfig, RoutePattern};
 pub use site::Site;
 pub use target::Target;
 pub use target_type::TargetType;
 
 use anyhow::anyhow;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Debug, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum UsageModel {
     Bundled,
@@ -0,0 +1,76 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The queues a worker consumes, `[[queues.consumers]]` in the configuration file.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct Queues {
+    #[serde(default)]
+    pub consumers: Vec<QueueConsumer>,
+}
+
+/// How the worker consumes a queue. Settings left out keep the defaults of the Queues API.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct QueueConsumer {
+    pub queue: String,
+    /// Where messages go once they've been retried `max_retries` times
+    pub dead_letter_queue: Option<String>,
+    pub max_retries: Option<u32>,
+    /// Seconds to wait before a message is retried
+    pub retry_delay: Option<u32>,
+}
+
+// The limits of the Queues API
+const MAX_RETRIES: u32 = 100;
+const MAX_RETRY_DELAY: u32 = 43200;
+
+impl QueueConsumer {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(max_retries) = self.max_retries {
+            anyhow::ensure!(
+                max_retries <= MAX_RETRIES,
+                "max_retries of the consumer of {} is {}, it can be at most {}",
+                self.queue,
+                max_retries,
+                MAX_RETRIES
+            );
+        }
+        if let Some(retry_delay) = self.retry_delay {
+            anyhow::ensure!(
+                retry_delay <= MAX_RETRY_DELAY,
+                "retry_delay of the consumer of {} is {} seconds, it can be at most {} (12 hours)",
+                self.queue,
+                retry_delay,
+                MAX_RETRY_DELAY
+            );
+        }
+        if let Some(dead_letter_queue) = &self.dead_letter_queue {
+            anyhow::ensure!(
+                *dead_letter_queue != self.queue,
+                "The dead_letter_queue of {} can't be the queue itself",
+                self.queue
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn consumer(max_retries: u32, dead_letter_queue: &str) -> QueueConsumer {
+        QueueConsumer {
+            queue: "jobs".to_string(),
+            dead_letter_queue: Some(dead_letter_queue.to_string()),
+            max_retries: Some(max_retries),
+            retry_delay: None,
+        }
+    }
+
+    #[test]
+    fn it_checks_the_limits_of_the_queues_api() {
+        assert!(consumer(3, "failed-jobs").validate().is_ok());
+        assert!(consumer(101, "failed-jobs").validate().is_err());
+        assert!(consumer(3, "jobs").validate().is_err());
+    }
+}
@@ -0,0 +1,17 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::binding::Binding;
+
+/// Binds an R2 bucket to the worker as `binding`.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct R2Bucket {
+    pub binding: String,
+    pub bucket_name: String,
+}
+
+impl R2Bucket {
+    pub fn binding(&self) -> Binding {
+        Binding::new_r2_bucket(self.binding.clone(), self.bucket_name.clone())
+    }
+}
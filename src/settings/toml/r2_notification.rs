@@ -0,0 +1,57 @@
+use std::fmt;
+use std::str::FromStr;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Sends the events of an R2 bucket to a queue, so a worker consuming the queue can process
+/// new or deleted objects.
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+pub struct R2Notification {
+    pub bucket: String,
+    pub queue: String,
+    pub event_types: Vec<R2EventType>,
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum R2EventType {
+    ObjectCreate,
+    ObjectDelete,
+}
+
+impl R2EventType {
+    /// The R2 actions that raise this event.
+    pub fn actions(self) -> &'static [&'static str] {
+        match self {
+            R2EventType::ObjectCreate => &["PutObject", "CopyObject", "CompleteMultipartUpload"],
+            R2EventType::ObjectDelete => &["DeleteObject", "LifecycleDeletion"],
+        }
+    }
+}
+
+impl fmt::Display for R2EventType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            R2EventType::ObjectCreate => write!(f, "object-create"),
+            R2EventType::ObjectDelete => write!(f, "object-delete"),
+        }
+    }
+}
+
+impl FromStr for R2EventType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "object-create" => Ok(R2EventType::ObjectCreate),
+            "object-delete" => Ok(R2EventType::ObjectDelete),
+            _ => anyhow::bail!(
+                "{} isn't an R2 event type, use object-create or object-delete",
+                s
+            ),
+        }
+    }
+}
@@ -49,3 +49,219 @@ impl RouteConfig {
         self.has_routes_defined() && self.zone_id.is_some()
     }
 }
+
+/// A route pattern split into the parts the edge matches on, e.g. `*example.com/api/*`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoutePattern {
+    pub scheme: Option<String>,
+    pub host: String,
+    /// Everything from the first `/`, empty when the pattern has no path
+    pub path: String,
+}
+
+impl RoutePattern {
+    pub fn parse(pattern: &str) -> RoutePattern {
+        let (scheme, rest) = match pattern.split_once("://") {
+            Some((scheme, rest)) => (Some(scheme.to_lowercase()), rest),
+            None => (None, pattern),
+        };
+        let (host, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, ""),
+        };
+        RoutePattern {
+            scheme,
+            host: host.to_lowercase(),
+            path: path.to_string(),
+        }
+    }
+
+    /// Whether some request would match both patterns.
+    pub fn overlaps(&self, other: &RoutePattern) -> bool {
+        let schemes = match (&self.scheme, &other.scheme) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        };
+        schemes
+            && wildcards_overlap(&self.host, &other.host, |a, b| a.ends_with(b))
+            && wildcards_overlap(&self.path, &other.path, |a, b| a.starts_with(b))
+    }
+
+    /// Whether every request `other` matches is also matched by this pattern.
+    pub fn covers(&self, other: &RoutePattern) -> bool {
+        let schemes = match (&self.scheme, &other.scheme) {
+            (Some(a), Some(b)) => a == b,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+        schemes
+            && wildcard_covers(&self.host, &other.host, |a, b| a.ends_with(b))
+            && wildcard_covers(&self.path, &other.path, |a, b| a.starts_with(b))
+    }
+
+    // Why no request could ever match the pattern, if that's the case
+    fn never_matches(&self) -> Option<&'static str> {
+        let host = self.host.trim_start_matches('*');
+        let path = self.path.strip_suffix('*').unwrap_or(&self.path);
+        if self.host.is_empty() || host.is_empty() {
+            Some("it has no hostname")
+        } else if host.contains('*') {
+            Some("hostnames can only have a wildcard at the start")
+        } else if host.contains(':') {
+            Some("routes can't include a port")
+        } else if path.contains('*') {
+            Some("paths can only have a wildcard at the end")
+        } else if path.contains('?') || path.contains('#') {
+            Some("routes never include a query string or fragment")
+        } else if matches!(&self.scheme, Some(scheme) if scheme != "http" && scheme != "https") {
+            Some("only http and https requests reach workers")
+        } else {
+            None
+        }
+    }
+}
+
+// Hosts starting and paths ending with `*` match on a suffix or prefix, anything else matches
+// exactly. `contains(a, b)` tells whether `a` extends `b`.
+fn wildcard_parts(part: &str) -> (&str, bool) {
+    match part.strip_prefix('*').or_else(|| part.strip_suffix('*')) {
+        Some(fixed) => (fixed, true),
+        None => (part, false),
+    }
+}
+
+fn wildcards_overlap(a: &str, b: &str, contains: fn(&str, &str) -> bool) -> bool {
+    match (wildcard_parts(a), wildcard_parts(b)) {
+        ((a, true), (b, true)) => contains(a, b) || contains(b, a),
+        ((a, true), (b, false)) => contains(b, a),
+        ((a, false), (b, true)) => contains(a, b),
+        ((a, false), (b, false)) => a == b,
+    }
+}
+
+fn wildcard_covers(a: &str, b: &str, contains: fn(&str, &str) -> bool) -> bool {
+    match (wildcard_parts(a), wildcard_parts(b)) {
+        ((a, true), (b, _)) => contains(b, a),
+        ((a, false), (b, false)) => a == b,
+        (_, (_, true)) => false,
+    }
+}
+
+/// Warnings about route patterns that won't do what they look like they do: patterns that
+/// never match, that only match a single URL, that leave out the zone apex, or that another
+/// pattern in the same list already matches.
+pub fn lint_patterns(patterns: &[String]) -> Vec<String> {
+    let parsed: Vec<RoutePattern> = patterns.iter().map(|p| RoutePattern::parse(p)).collect();
+    let mut warnings = Vec::new();
+
+    for (i, (pattern, route)) in patterns.iter().zip(&parsed).enumerate() {
+        if let Some(reason) = route.never_matches() {
+            warnings.push(format!("Route {} will never match, {}", pattern, reason));
+            continue;
+        }
+        if patterns[..i].contains(pattern) {
+            warnings.push(format!("Route {} is listed more than once", pattern));
+            continue;
+        }
+
+        if route.path.is_empty() {
+            warnings.push(format!(
+                "Route {} has no path, so it only matches requests for /. Use {}/* to match every path",
+                pattern, pattern
+            ));
+        } else if route.path.ends_with('/') && route.path.len() > 1 {
+            warnings.push(format!(
+                "Route {} only matches that exact path, not the ones below it. Use {}* to match those too",
+                pattern, pattern
+            ));
+        }
+
+        if let Some(apex) = route.host.strip_prefix("*.") {
+            let apex_route = RoutePattern {
+                host: apex.to_string(),
+                ..route.clone()
+            };
+            if !parsed.iter().any(|other| other.covers(&apex_route)) {
+                warnings.push(format!(
+                    "Route {} matches the subdomains of {} but not {} itself. Use {} to match both",
+                    pattern,
+                    apex,
+                    apex,
+                    pattern.replacen("*.", "*", 1)
+                ));
+            }
+        }
+
+        // of two patterns matching the same requests, only the later one is redundant
+        let covering = patterns
+            .iter()
+            .zip(&parsed)
+            .enumerate()
+            .find(|(j, (_, other))| {
+                *j != i && other.covers(route) && (*j < i || !route.covers(other))
+            });
+        if let Some((_, (other, _))) = covering {
+            warnings.push(format!(
+                "Route {} is redundant, {} already matches every request it does",
+                pattern, other
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(patterns: &[&str]) -> Vec<String> {
+        lint_patterns(&patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn patterns_overlap_on_wildcards() {
+        let all = RoutePattern::parse("*example.com/*");
+        let api = RoutePattern::parse("api.example.com/v1/*");
+        let other = RoutePattern::parse("example.org/*");
+        assert!(all.covers(&api));
+        assert!(!api.covers(&all));
+        assert!(api.overlaps(&all));
+        assert!(!other.overlaps(&all));
+        assert!(
+            !RoutePattern::parse("*.example.com/*").covers(&RoutePattern::parse("example.com/"))
+        );
+        assert!(!RoutePattern::parse("https://example.com/*")
+            .overlaps(&RoutePattern::parse("http://example.com/*")));
+    }
+
+    #[test]
+    fn good_patterns_have_no_warnings() {
+        assert!(lint(&["*example.com/*", "example.org/api/*"]).is_empty());
+    }
+
+    #[test]
+    fn suspicious_patterns_are_reported() {
+        assert_eq!(
+            lint(&[
+                "example.com/api/*/users",
+                "example.com:8080/*",
+                "example.com",
+                "example.com/docs/",
+                "*.example.org/*",
+                "example.net/*",
+                "example.net/blog/*",
+                "example.net/*",
+            ]),
+            vec![
+                "Route example.com/api/*/users will never match, paths can only have a wildcard at the end",
+                "Route example.com:8080/* will never match, routes can't include a port",
+                "Route example.com has no path, so it only matches requests for /. Use example.com/* to match every path",
+                "Route example.com/docs/ only matches that exact path, not the ones below it. Use example.com/docs/* to match those too",
+                "Route *.example.org/* matches the subdomains of example.org but not example.org itself. Use *example.org/* to match both",
+                "Route example.net/blog/* is redundant, example.net/* already matches every request it does",
+                "Route example.net/* is listed more than once",
+            ]
+        );
+    }
+}
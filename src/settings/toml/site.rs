@@ -1,15 +1,17 @@
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
 
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::commands::generate::run_generate;
 
 const SITE_ENTRY_POINT: &str = "workers-site";
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, PartialEq, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Site {
     pub bucket: PathBuf,
@@ -45,11 +47,18 @@ impl Site {
 
         if !entry_point.exists() {
             log::info!("Generating a new workers site project");
-            run_generate(entry_point.file_name().unwrap().to_str().unwrap(), template)?;
+            run_generate(
+                entry_point.file_name().unwrap().to_str().unwrap(),
+                template,
+                &HashMap::new(),
+            )?;
 
             // This step is to prevent having a git repo within a git repo after
             // generating the scaffold into an existing project.
-            fs::remove_dir_all(&entry_point.join(".git"))?;
+            let git_dir = entry_point.join(".git");
+            if git_dir.exists() {
+                fs::remove_dir_all(&git_dir)?;
+            }
         }
 
         Ok(())
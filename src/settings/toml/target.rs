@@ -1,10 +1,16 @@
+use super::deploy_config::DeployConfig;
 use super::durable_objects::DurableObjects;
+use super::hooks::Hooks;
 use super::kv_namespace::KvNamespace;
 use super::manifest::LazyAccountId;
+use super::queues::QueueConsumer;
+use super::r2_bucket::R2Bucket;
+use super::r2_notification::R2Notification;
 use super::site::Site;
 use super::target_type::TargetType;
 use super::UsageModel;
 use super::{builder::Builder, migrations::Migrations};
+use crate::upload::Package;
 
 use std::collections::HashMap;
 use std::env;
@@ -15,11 +21,13 @@ use std::path::PathBuf;
 pub struct Target {
     pub account_id: LazyAccountId,
     pub kv_namespaces: Vec<KvNamespace>,
+    pub r2_buckets: Vec<R2Bucket>,
     pub durable_objects: Option<DurableObjects>,
     pub migrations: Option<Migrations>,
     pub name: String,
     pub target_type: TargetType,
     pub webpack_config: Option<String>,
+    pub main: Option<PathBuf>,
     pub build: Option<Builder>,
     pub site: Option<Site>,
     pub vars: Option<HashMap<String, String>>,
@@ -28,6 +36,12 @@ pub struct Target {
     pub wasm_modules: Option<HashMap<String, PathBuf>>,
     pub compatibility_date: Option<String>,
     pub compatibility_flags: Vec<String>,
+    pub hooks: Option<Hooks>,
+    pub deploy: Option<DeployConfig>,
+    /// Replace the script's tags when set, leave them alone otherwise.
+    pub tags: Option<Vec<String>>,
+    pub r2_notifications: Vec<R2Notification>,
+    pub queue_consumers: Vec<QueueConsumer>,
 }
 
 impl Target {
@@ -35,6 +49,24 @@ impl Target {
         self.kv_namespaces.push(kv_namespace);
     }
 
+    /// The entrypoint of a service-worker script: `main` if it was set,
+    /// otherwise the `main` field of `package.json`.
+    pub fn service_worker_entry(&self) -> anyhow::Result<PathBuf> {
+        let package_dir = self.package_dir()?;
+        if let Some(main) = &self.main {
+            let path = package_dir.join(main);
+            anyhow::ensure!(
+                path.is_file(),
+                "The entrypoint of your Worker ({}) could not be found.",
+                main.display()
+            );
+            return Ok(path);
+        }
+
+        let package = Package::new(&package_dir)?;
+        Ok(package_dir.join(package.main(&package_dir)?))
+    }
+
     pub fn package_dir(&self) -> Result<PathBuf, std::io::Error> {
         // if `site` is configured, we want to isolate worker code
         // and build artifacts away from static site application code.
@@ -2,9 +2,10 @@ use std::fmt;
 use std::str::FromStr;
 
 use anyhow::anyhow;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum TargetType {
     JavaScript,
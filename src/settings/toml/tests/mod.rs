@@ -126,6 +126,155 @@ fn it_uses_env_name_when_provided() {
     assert_eq!(manifest.worker_name(Some(TEST_ENV_NAME)), custom_env_name);
 }
 
+#[test]
+fn environments_inherit_tags_unless_they_set_their_own() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        tags = ["team-payments", "tier-1"]
+
+        [env.staging]
+
+        [env.experiment]
+        tags = []
+        "#,
+    )
+    .unwrap();
+
+    let tags = |env| manifest.get_target(env, false).unwrap().tags;
+    assert_eq!(
+        tags(Some("staging")),
+        Some(vec!["team-payments".to_string(), "tier-1".to_string()])
+    );
+    assert_eq!(tags(Some("experiment")), Some(vec![]));
+}
+
+#[test]
+fn environments_only_inherit_what_inherit_allows() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        account_id = "abc"
+        workers_dev = true
+        tags = ["team-payments"]
+        triggers = { crons = ["0 * * * *"] }
+
+        [env.isolated]
+        inherit = false
+
+        [env.partial]
+        inherit = ["account_id", "workers_dev"]
+
+        [env.typo]
+        inherit = ["vars"]
+        "#,
+    )
+    .unwrap();
+
+    let isolated = manifest.get_target(Some("isolated"), false).unwrap();
+    assert_eq!(isolated.tags, None);
+    assert!(manifest.get_deployments(Some("isolated")).is_err());
+
+    let partial = manifest.get_target(Some("partial"), false).unwrap();
+    assert_eq!(partial.tags, None);
+    assert_eq!(partial.account_id.load().unwrap(), "abc");
+    let deployments = manifest.get_deployments(Some("partial")).unwrap();
+    assert!(matches!(
+        deployments.as_slice(),
+        [crate::deploy::DeployTarget::Zoneless(_)]
+    ));
+
+    assert!(manifest.get_target(Some("typo"), false).is_err());
+}
+
+#[test]
+fn previews_of_environments_use_their_own_settings() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        account_id = "abc"
+        workers_dev = true
+        kv_namespaces = [{ binding = "CACHE", id = "production" }]
+        dev = { port = 8000, local_protocol = "https" }
+
+        [env.staging]
+        kv_namespaces = [{ binding = "CACHE", id = "staging", preview_id = "staging-preview" }]
+        dev = { port = 9000 }
+
+        [env.qa]
+        kv_namespaces = [{ binding = "CACHE", id = "qa" }]
+        "#,
+    )
+    .unwrap();
+
+    let staging = manifest.get_target(Some("staging"), true).unwrap();
+    assert_eq!(staging.kv_namespaces[0].id, "staging-preview");
+    let dev = manifest.dev_config(Some("staging")).unwrap().unwrap();
+    assert_eq!(dev.port, Some(9000));
+    assert!(dev.local_protocol.unwrap().is_https());
+
+    let error = manifest.get_target(Some("qa"), true).unwrap_err();
+    assert!(error.to_string().contains("[env.qa] CACHE has none"));
+    assert!(manifest.get_target(None, true).is_err());
+}
+
+#[test]
+fn only_environments_can_be_protected() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        account_id = "abc"
+        workers_dev = true
+
+        [env.production]
+        protected = true
+
+        [env.staging]
+        "#,
+    )
+    .unwrap();
+
+    assert!(manifest.is_protected(Some("production")).unwrap());
+    assert!(!manifest.is_protected(Some("staging")).unwrap());
+    assert!(!manifest.is_protected(None).unwrap());
+    assert!(manifest.is_protected(Some("typo")).is_err());
+}
+
+#[test]
+fn r2_buckets_are_not_inherited() {
+    let manifest = Manifest::from_str(
+        r#"
+        name = "worker"
+        type = "javascript"
+        workers_dev = true
+        r2_buckets = [{ binding = "UPLOADS", bucket_name = "uploads" }]
+
+        [env.staging]
+        r2_buckets = [{ binding = "UPLOADS", bucket_name = "uploads-staging" }]
+
+        [env.qa]
+        "#,
+    )
+    .unwrap();
+
+    let target = manifest.get_target(None, false).unwrap();
+    assert_eq!(
+        target.r2_buckets,
+        vec![R2Bucket {
+            binding: "UPLOADS".to_string(),
+            bucket_name: "uploads".to_string(),
+        }]
+    );
+    let staging = manifest.get_target(Some("staging"), false).unwrap();
+    assert_eq!(staging.r2_buckets[0].bucket_name, "uploads-staging");
+    let qa = manifest.get_target(Some("qa"), false).unwrap();
+    assert!(qa.r2_buckets.is_empty());
+}
+
 fn base_fixture_path() -> PathBuf {
     let current_dir = env::current_dir().unwrap();
 
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash64;
+
+use crate::cache;
+use crate::settings::get_wrangler_home_dir;
+
+/// When a file was last modified and how big it is. A file with the same stamp as last time
+/// is assumed to have the same contents, and so the same key.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct FileStamp {
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+impl FileStamp {
+    pub fn of(path: &Path) -> Result<FileStamp> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH)?;
+        Ok(FileStamp {
+            size: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+        })
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Entry {
+    stamp: FileStamp,
+    key: String,
+}
+
+/// The keys of a site's files as of the last publish, kept in `~/.wrangler/cache` so files
+/// that didn't change and are already uploaded don't have to be read and hashed again.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct HashCache {
+    /// Keys are generated differently by other versions, so their caches aren't used
+    version: String,
+    entries: HashMap<String, Entry>,
+}
+
+impl HashCache {
+    /// The cache for the site in `bucket`, empty if there's none or caching is off.
+    pub fn load(bucket: &Path) -> HashCache {
+        if !cache::enabled() {
+            return HashCache::default();
+        }
+        fs::read(cache_path(bucket))
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<HashCache>(&contents).ok())
+            .filter(|hashes| hashes.version == env!("CARGO_PKG_VERSION"))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&mut self, bucket: &Path) -> Result<()> {
+        if !cache::enabled() {
+            return Ok(());
+        }
+        self.version = env!("CARGO_PKG_VERSION").to_string();
+        let path = cache_path(bucket);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    pub fn key(&self, path: &Path, stamp: &FileStamp) -> Option<String> {
+        self.entries
+            .get(&path.display().to_string())
+            .filter(|entry| &entry.stamp == stamp)
+            .map(|entry| entry.key.clone())
+    }
+
+    pub fn insert(&mut self, path: &Path, stamp: FileStamp, key: String) {
+        self.entries
+            .insert(path.display().to_string(), Entry { stamp, key });
+    }
+}
+
+fn cache_path(bucket: &Path) -> PathBuf {
+    let bucket = bucket
+        .canonicalize()
+        .unwrap_or_else(|_| bucket.to_path_buf());
+    let mut hasher = XxHash64::default();
+    hasher.write(bucket.display().to_string().as_bytes());
+    get_wrangler_home_dir()
+        .join("cache")
+        .join(format!("sites-{:x}.json", hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_only_reused_for_unchanged_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index.html");
+        fs::write(&path, "<h1>hi</h1>").unwrap();
+        let stamp = FileStamp::of(&path).unwrap();
+
+        let mut hashes = HashCache::default();
+        hashes.insert(&path, stamp.clone(), "index.abc.html".to_string());
+        assert_eq!(
+            hashes.key(&path, &stamp),
+            Some("index.abc.html".to_string())
+        );
+
+        let grown = FileStamp {
+            size: stamp.size + 1,
+            ..stamp
+        };
+        assert_eq!(hashes.key(&path, &grown), None);
+    }
+}
@@ -1,16 +1,18 @@
 extern crate base64;
 
+pub(crate) mod hash_cache;
 mod manifest;
 mod sync;
 
+use hash_cache::FileStamp;
+pub use hash_cache::HashCache;
 pub use manifest::AssetManifest;
 pub use sync::sync;
 
 use std::collections::HashSet;
-use std::ffi::OsString;
 use std::fs;
 use std::hash::Hasher;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use ignore::overrides::{Override, OverrideBuilder};
@@ -21,6 +23,8 @@ use twox_hash::XxHash64;
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
 
 use crate::kv::namespace::{upsert, UpsertedNamespace};
+use crate::parallel;
+use crate::paths;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::{KvNamespace, Target};
 use crate::terminal::message::{Message, StdErr};
@@ -66,6 +70,17 @@ pub fn directory_keys_values(
     target: &Target,
     directory: &Path,
     exclude: Option<&HashSet<String>>,
+) -> Result<(Vec<KeyValuePair>, AssetManifest, Vec<String>)> {
+    directory_keys_values_cached(target, directory, exclude, &mut HashCache::default())
+}
+
+// Like `directory_keys_values`, but files `hashes` knows the key of aren't read at all when
+// that key is excluded. Only the values of files that need uploading are kept in memory.
+pub fn directory_keys_values_cached(
+    target: &Target,
+    directory: &Path,
+    exclude: Option<&HashSet<String>>,
+    hashes: &mut HashCache,
 ) -> Result<(Vec<KeyValuePair>, AssetManifest, Vec<String>)> {
     match fs::metadata(directory) {
         Ok(ref file_type) if file_type.is_dir() => {
@@ -77,6 +92,7 @@ pub fn directory_keys_values(
                 ProgressStyle::default_spinner().template("{spinner}   Preparing {msg}...");
             let spinner = ProgressBar::new_spinner().with_style(spinner_style);
 
+            let mut paths: Vec<PathBuf> = Vec::new();
             for entry in dir_walker {
                 spinner.tick();
                 let entry = entry.unwrap();
@@ -86,36 +102,60 @@ pub fn directory_keys_values(
 
                     file_list.push(path.to_str().unwrap().to_string());
                     validate_file_size(&path)?;
+                    paths.push(path.to_path_buf());
+                }
+            }
 
-                    let value = std::fs::read(path)?;
-
-                    // Need to base64 encode value
-                    let b64_value = base64::encode(&value);
-
-                    let (url_safe_path, key) =
-                        generate_path_and_key(path, directory, Some(b64_value.clone()))?;
-
-                    validate_key_size(&key)?;
-
-                    // asset manifest should always contain all files
-                    asset_manifest.insert(url_safe_path, key.clone());
-
-                    // skip uploading existing keys, if configured to do so
-                    if let Some(remote_keys) = exclude {
-                        if remote_keys.contains(&key) {
-                            continue;
-                        }
+            // Reading and hashing is the slow part for big sites, so files are read concurrently
+            let bucket = directory.to_path_buf();
+            let exclude = exclude.cloned().unwrap_or_default();
+            let known = std::mem::take(hashes);
+            let files = parallel::map(paths, parallel::FILE_READS, move |path| {
+                let stamp = FileStamp::of(&path)?;
+                if let Some(key) = known.key(&path, &stamp) {
+                    if exclude.contains(&key) {
+                        let url_safe_path = paths::relative_name(&path, &bucket)?;
+                        return Ok((path, stamp, url_safe_path, key, None));
                     }
+                }
 
+                let value = std::fs::read(&path)?;
+
+                // Need to base64 encode value
+                let b64_value = base64::encode(&value);
+
+                let (url_safe_path, key) =
+                    generate_path_and_key(&path, &bucket, Some(b64_value.clone()))?;
+
+                validate_key_size(&key)?;
+                // skip uploading existing keys, if configured to do so
+                let value = if exclude.contains(&key) {
+                    None
+                } else {
+                    Some(b64_value)
+                };
+                Ok((path, stamp, url_safe_path, key, value))
+            })?;
+            spinner.finish_and_clear();
+
+            // Only the files that are still there are remembered
+            let mut current = HashCache::default();
+            for (path, stamp, url_safe_path, key, value) in files {
+                current.insert(&path, stamp, key.clone());
+                // asset manifest should always contain all files
+                asset_manifest.insert(url_safe_path, key.clone());
+
+                if let Some(value) = value {
                     upload_vec.push(KeyValuePair {
-                        key: key.clone(),
-                        value: b64_value,
+                        key,
+                        value,
                         expiration: None,
                         expiration_ttl: None,
                         base64: Some(true),
                     });
                 }
             }
+            *hashes = current;
             Ok((upload_vec, asset_manifest, file_list))
         }
         Ok(_file_type) => {
@@ -221,28 +261,10 @@ fn build_ignore(target: &Target, directory: &Path) -> Result<Override> {
     Ok(exclude)
 }
 
-// Courtesy of Steve Klabnik's PoC :) Used for bulk operations (write, delete)
+// Courtesy of Steve Klabnik's PoC :) Used for bulk operations (write, delete). Keys use `/`
+// as separators on every OS, because that's how URLs work.
 fn generate_url_safe_path(path: &Path) -> Result<String> {
-    // first, we have to re-build the paths: if we're on Windows, we have paths with
-    // `\` as separators. But we want to use `/` as separators. Because that's how URLs
-    // work.
-    let mut path_with_forward_slash = OsString::new();
-
-    for (i, component) in path.components().enumerate() {
-        // we don't want a leading `/`, so skip that
-        if i > 0 {
-            path_with_forward_slash.push("/");
-        }
-
-        path_with_forward_slash.push(component);
-    }
-
-    // if we have a non-utf8 path here, it will fail, but that's not realistically going to happen
-    let path = path_with_forward_slash
-        .to_str()
-        .unwrap_or_else(|| panic!("found a non-UTF-8 path, {:?}", path_with_forward_slash));
-
-    Ok(path.to_string())
+    Ok(paths::slash_name(path))
 }
 
 // Adds the SHA-256 hash of the path's file contents to the url-safe path of a file to
@@ -255,9 +277,8 @@ pub fn generate_path_and_key(
     value: Option<String>,
 ) -> Result<(String, String)> {
     // strip the bucket directory from both paths for ease of reference.
-    let relative_path = path.strip_prefix(directory).unwrap();
-
-    let url_safe_path = generate_url_safe_path(relative_path)?;
+    let url_safe_path = paths::relative_name(path, directory)?;
+    let relative_path = Path::new(&url_safe_path);
     let path_with_hash = if let Some(value) = value {
         let digest = get_digest(value);
         // it is ok to truncate the digest here because
@@ -321,11 +342,13 @@ mod tests {
         Target {
             account_id: None.into(),
             kv_namespaces: Vec::new(),
+            r2_buckets: Vec::new(),
             durable_objects: None,
             migrations: None,
             name: "".to_string(),
             target_type: TargetType::JavaScript,
             webpack_config: None,
+            main: None,
             site: Some(site),
             build: None,
             vars: None,
@@ -334,6 +357,11 @@ mod tests {
             wasm_modules: None,
             compatibility_date: None,
             compatibility_flags: Vec::new(),
+            hooks: None,
+            deploy: None,
+            tags: None,
+            r2_notifications: Vec::new(),
+            queue_consumers: Vec::new(),
         }
     }
 
@@ -677,4 +705,16 @@ mod tests {
         assert_eq!(path, expected_path);
         assert!(expected_key_regex.is_match(&key));
     }
+
+    #[cfg(windows)]
+    #[test]
+    fn it_generates_the_same_keys_on_windows() {
+        let path = Path::new(r"\\?\C:\site\build\path\to\asset.ext");
+        let directory = Path::new(r"C:\site\build");
+        let (path, key) = generate_path_and_key(path, directory, Some("".to_string())).unwrap();
+        let expected_key_regex = Regex::new(r"^path/to/asset\.[0-9a-f]{10}\.ext").unwrap();
+
+        assert_eq!(path, "path/to/asset.ext");
+        assert!(expected_key_regex.is_match(&key));
+    }
 }
@@ -4,8 +4,8 @@ use std::path::Path;
 use anyhow::Result;
 use cloudflare::endpoints::workerskv::write_bulk::KeyValuePair;
 
-use super::directory_keys_values;
 use super::manifest::AssetManifest;
+use super::{directory_keys_values_cached, HashCache};
 use crate::commands::kv;
 use crate::http;
 use crate::kv::key::KeyList;
@@ -38,8 +38,12 @@ pub fn sync(
         }
     }
 
+    let mut hashes = HashCache::load(path);
     let (diff_files_to_upload, asset_manifest, _): (Vec<KeyValuePair>, AssetManifest, _) =
-        directory_keys_values(target, path, Some(&remote_keys))?;
+        directory_keys_values_cached(target, path, Some(&remote_keys), &mut hashes)?;
+    if let Err(e) = hashes.save(path) {
+        log::debug!("could not save the hashes of the site's files: {}", e);
+    }
 
     // Now delete files from Workers KV that exist in remote but no longer exist locally.
     // Get local keys
@@ -0,0 +1,295 @@
+//! Just enough of the [Source Map v3](https://sourcemaps.info/spec.html) format to map
+//! stack frames reported by the Workers runtime back to the sources they were built from.
+
+use std::fmt;
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use crate::build::hooks::BuildArtifacts;
+use crate::settings::toml::Target;
+
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// `at fn (file:line:column)` or `at file:line:column`
+static STACK_FRAME: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^([ \t]*at[ \t]+(?:[^(\n]*\()?)([^()\s]+):(\d+):(\d+)(\)?)[ \t]*$").unwrap()
+});
+
+#[derive(Deserialize)]
+struct RawSourceMap {
+    sources: Vec<String>,
+    #[serde(default)]
+    names: Vec<String>,
+    mappings: String,
+    #[serde(rename = "sourceRoot", default)]
+    source_root: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct Segment {
+    column: i64,
+    original: Option<(usize, i64, i64, Option<usize>)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    names: Vec<String>,
+    lines: Vec<Vec<Segment>>,
+}
+
+/// A position in an original source file. Lines and columns are 1-based, like the
+/// positions in a stack trace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OriginalLocation {
+    pub source: String,
+    pub line: i64,
+    pub column: i64,
+    pub name: Option<String>,
+}
+
+impl fmt::Display for OriginalLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.source, self.line, self.column)
+    }
+}
+
+impl SourceMap {
+    pub fn parse(json: &str) -> Result<SourceMap> {
+        let raw: RawSourceMap = serde_json::from_str(json)?;
+        let root = raw.source_root.unwrap_or_default();
+        let sources = raw
+            .sources
+            .into_iter()
+            .map(|source| {
+                if root.is_empty() {
+                    source
+                } else {
+                    format!("{}/{}", root.trim_end_matches('/'), source)
+                }
+            })
+            .collect();
+
+        // Every field but the generated column is relative to its value in the previous segment
+        let (mut source, mut line, mut column, mut name) = (0i64, 0i64, 0i64, 0i64);
+        let mut lines = Vec::new();
+        for generated_line in raw.mappings.split(';') {
+            let mut generated_column = 0i64;
+            let mut segments = Vec::new();
+            for segment in generated_line.split(',').filter(|s| !s.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                generated_column += fields[0];
+                let original = if fields.len() >= 4 {
+                    source += fields[1];
+                    line += fields[2];
+                    column += fields[3];
+                    let name = if fields.len() >= 5 {
+                        name += fields[4];
+                        Some(name as usize)
+                    } else {
+                        None
+                    };
+                    Some((source as usize, line, column, name))
+                } else {
+                    None
+                };
+                segments.push(Segment {
+                    column: generated_column,
+                    original,
+                });
+            }
+            lines.push(segments);
+        }
+
+        Ok(SourceMap {
+            sources,
+            names: raw.names,
+            lines,
+        })
+    }
+
+    /// Looks up a 1-based generated line and column.
+    pub fn lookup(&self, line: i64, column: i64) -> Option<OriginalLocation> {
+        let segments = self.lines.get((line - 1) as usize)?;
+        let segment = segments
+            .iter()
+            .take_while(|segment| segment.column <= column - 1)
+            .last()?;
+        let (source, line, column, name) = segment.original?;
+
+        Some(OriginalLocation {
+            source: self.sources.get(source)?.clone(),
+            line: line + 1,
+            column: column + 1,
+            name: name.and_then(|n| self.names.get(n).cloned()),
+        })
+    }
+}
+
+// Source map values are 32 bit, which takes at most 7 digits of 5 bits
+const MAX_VLQ_SHIFT: u32 = 30;
+
+fn decode_vlq(segment: &str) -> Result<Vec<i64>> {
+    let mut values = Vec::new();
+    let mut value = 0i64;
+    let mut shift = 0;
+
+    for c in segment.bytes() {
+        let digit = BASE64_ALPHABET
+            .iter()
+            .position(|&b| b == c)
+            .ok_or_else(|| anyhow!("invalid character {:?} in source map", c as char))?
+            as i64;
+
+        value += (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            if shift > MAX_VLQ_SHIFT {
+                anyhow::bail!("value too large in source map segment {:?}", segment)
+            }
+        } else {
+            let negative = value & 1 == 1;
+            value >>= 1;
+            values.push(if negative { -value } else { value });
+            value = 0;
+            shift = 0;
+        }
+    }
+
+    if shift != 0 {
+        anyhow::bail!("unfinished value in source map segment {:?}", segment)
+    }
+    if values.is_empty() {
+        anyhow::bail!("empty segment in source map")
+    }
+    Ok(values)
+}
+
+/// The source maps of a build, keyed by the name of the bundle they describe.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMaps {
+    maps: Vec<(String, SourceMap)>,
+}
+
+impl SourceMaps {
+    /// Loads the source maps emitted by the last build of `target`. Missing or unreadable
+    /// source maps are skipped, since they only ever make output nicer.
+    pub fn load(target: &Target) -> SourceMaps {
+        let artifacts = match BuildArtifacts::collect(target) {
+            Ok(artifacts) => artifacts,
+            Err(e) => {
+                log::info!("not loading source maps: {}", e);
+                return SourceMaps::default();
+            }
+        };
+
+        let mut maps = Vec::new();
+        for artifact in artifacts.source_maps {
+            let parsed = fs::read_to_string(&artifact.path)
+                .map_err(Into::into)
+                .and_then(|json| SourceMap::parse(&json));
+            match parsed {
+                Ok(map) => maps.push((file_name(artifact.name.trim_end_matches(".map")), map)),
+                Err(e) => log::info!("could not load {}: {}", artifact.path.display(), e),
+            }
+        }
+
+        SourceMaps { maps }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.maps.is_empty()
+    }
+
+    /// Maps a 1-based position in a bundle. When there's only one source map, it's used
+    /// regardless of the file name, since the runtime doesn't always report the bundle's name.
+    pub fn lookup(&self, file: &str, line: i64, column: i64) -> Option<OriginalLocation> {
+        let file = file_name(file);
+        let map = match self.maps.iter().find(|(bundle, _)| *bundle == file) {
+            Some((_, map)) => map,
+            None if self.maps.len() == 1 => &self.maps[0].1,
+            None => return None,
+        };
+        map.lookup(line, column)
+    }
+
+    /// Rewrites every `at fn (file:line:column)` frame of a stack trace that can be mapped.
+    pub fn map_stack(&self, stack: &str) -> String {
+        if self.is_empty() {
+            return stack.to_string();
+        }
+
+        STACK_FRAME
+            .replace_all(stack, |caps: &Captures| {
+                let line = caps[3].parse().unwrap_or_default();
+                let column = caps[4].parse().unwrap_or_default();
+                match self.lookup(&caps[2], line, column) {
+                    Some(location) => format!("{}{}{}", &caps[1], location, &caps[5]),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+}
+
+fn file_name(path: &str) -> String {
+    path.rsplit(|c| c == '/' || c == '\\')
+        .next()
+        .unwrap_or(path)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_maps(map: &str) -> SourceMaps {
+        SourceMaps {
+            maps: vec![("index.js".to_string(), SourceMap::parse(map).unwrap())],
+        }
+    }
+
+    #[test]
+    fn it_decodes_vlq() {
+        assert_eq!(decode_vlq("AAAA").unwrap(), vec![0, 0, 0, 0]);
+        assert_eq!(decode_vlq("D").unwrap(), vec![-1]);
+        assert_eq!(decode_vlq("gB").unwrap(), vec![16]);
+        assert!(decode_vlq("!").is_err());
+        assert!(decode_vlq("gggggggggggggA").is_err());
+        assert!(decode_vlq("g").is_err());
+    }
+
+    #[test]
+    fn it_looks_up_locations() {
+        let map = SourceMap::parse(
+            r#"{"version":3,"sources":["src/a.ts"],"names":["handle"],"mappings":"AAAA;AACAA,IAAI"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            map.lookup(2, 1),
+            Some(OriginalLocation {
+                source: "src/a.ts".to_string(),
+                line: 2,
+                column: 1,
+                name: Some("handle".to_string()),
+            })
+        );
+        assert_eq!(map.lookup(2, 6).unwrap().to_string(), "src/a.ts:2:5");
+        assert_eq!(map.lookup(3, 1), None);
+    }
+
+    #[test]
+    fn it_maps_stack_frames() {
+        let maps = source_maps(r#"{"version":3,"sources":["src/a.ts"],"mappings":"AAAA;AACA"}"#);
+        let stack = "Error: oops\n    at handle (index.js:2:1)\n    at unknown.js";
+        assert_eq!(
+            maps.map_stack(stack),
+            "Error: oops\n    at handle (src/a.ts:2:1)\n    at unknown.js"
+        );
+    }
+}
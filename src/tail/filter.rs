@@ -0,0 +1,168 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Values accepted by `wrangler tail --status`.
+pub const STATUSES: &[&str] = &["ok", "error", "canceled", "exceededCpu"];
+
+/// Filters restricting which events a tail receives. They are sent to the API along with the
+/// tail so that filtered events never leave the edge, and are checked again locally in case
+/// the API doesn't apply them.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TailFilters {
+    outcomes: Vec<String>,
+    // The tail API can't filter by route, so routes are only ever checked locally.
+    #[serde(skip)]
+    routes: Vec<String>,
+}
+
+impl TailFilters {
+    pub fn new(statuses: &[String], routes: &[String]) -> TailFilters {
+        let mut outcomes: Vec<String> = Vec::new();
+        for status in statuses {
+            // "error" covers every outcome of a request that threw
+            let mapped = match status.as_str() {
+                "error" => vec!["exception", "unknown"],
+                other => vec![other],
+            };
+            for outcome in mapped {
+                if !outcomes.iter().any(|o| o == outcome) {
+                    outcomes.push(outcome.to_string());
+                }
+            }
+        }
+
+        TailFilters {
+            outcomes,
+            routes: routes.to_vec(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty() && self.routes.is_empty()
+    }
+
+    /// The filter payload for the tail API.
+    pub fn api_filters(&self) -> Vec<Value> {
+        let mut filters = Vec::new();
+        if !self.outcomes.is_empty() {
+            filters.push(json!({ "outcome": self.outcomes }));
+        }
+        filters
+    }
+
+    pub fn matches(&self, event: &Value) -> bool {
+        let outcome = match event.get("outcome").and_then(Value::as_str) {
+            Some(outcome) if !self.outcomes.is_empty() => {
+                self.outcomes.iter().any(|o| o == outcome)
+            }
+            _ => true,
+        };
+
+        // Events without a request, e.g. from cron triggers, never matched a route
+        let route = match event.pointer("/event/request/url").and_then(Value::as_str) {
+            _ if self.routes.is_empty() => true,
+            Some(url) => self.routes.iter().any(|route| route_matches(route, url)),
+            None => false,
+        };
+
+        outcome && route
+    }
+}
+
+/// Matches a request URL against a route pattern the way the edge does: the scheme, query
+/// string and fragment are ignored, and `*` matches anything.
+fn route_matches(pattern: &str, url: &str) -> bool {
+    let url = without_scheme(url);
+    let url = url.split(|c| c == '?' || c == '#').next().unwrap_or(url);
+
+    let mut pattern = without_scheme(pattern).to_string();
+    if !pattern.contains('/') {
+        pattern.push('/');
+    }
+
+    glob_match(pattern.as_bytes(), url.as_bytes())
+}
+
+fn without_scheme(url: &str) -> &str {
+    match url.find("://") {
+        Some(i) => &url[i + 3..],
+        None => url,
+    }
+}
+
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was seen, and how much of the text it has consumed so far
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_matches_exceptions() {
+        let filters = TailFilters::new(&["error".to_string()], &[]);
+        assert!(filters.matches(&json!({ "outcome": "exception" })));
+        assert!(!filters.matches(&json!({ "outcome": "ok" })));
+    }
+
+    #[test]
+    fn no_filters_match_everything() {
+        let filters = TailFilters::new(&[], &[]);
+        assert!(filters.api_filters().is_empty());
+        assert!(filters.matches(&json!({ "outcome": "canceled" })));
+    }
+
+    #[test]
+    fn api_filters_list_outcomes() {
+        let filters = TailFilters::new(&["canceled".to_string(), "exceededCpu".to_string()], &[]);
+        assert_eq!(
+            filters.api_filters(),
+            vec![json!({ "outcome": ["canceled", "exceededCpu"] })]
+        );
+    }
+
+    #[test]
+    fn routes_match_request_urls() {
+        let filters = TailFilters::new(&[], &["example.com/api/*".to_string()]);
+        let event = |url: &str| json!({ "outcome": "ok", "event": { "request": { "url": url } } });
+
+        assert!(filters.matches(&event("https://example.com/api/users?id=1")));
+        assert!(!filters.matches(&event("https://example.com/about")));
+        assert!(!filters.matches(&json!({ "outcome": "ok", "event": {} })));
+        assert!(filters.api_filters().is_empty());
+    }
+
+    #[test]
+    fn route_patterns_follow_edge_semantics() {
+        assert!(route_matches(
+            "*.example.com/*",
+            "https://shop.example.com/cart"
+        ));
+        assert!(route_matches("example.com", "http://example.com/"));
+        assert!(!route_matches("example.com", "http://example.com/about"));
+        assert!(route_matches(
+            "https://example.com/*",
+            "http://example.com/a/b"
+        ));
+    }
+}
@@ -1,48 +1,83 @@
-use crate::terminal::{colored_json_string, emoji, styles};
+use super::filter::TailFilters;
+use super::sink::{ExecSink, LogSink};
+use super::TailOptions;
+use crate::sourcemap::SourceMaps;
+use crate::terminal::{console, emoji, styles};
 use anyhow::Result;
 use hyper::server::conn::AddrIncoming;
 use hyper::server::Builder;
 use hyper::service::{make_service_fn, service_fn};
 use hyper::{Body, Method, Request, Response, Server, StatusCode};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::convert::TryInto;
-use tokio::sync::oneshot::Receiver;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot::{Receiver, Sender};
+
+const BYTES_PER_MEGABYTE: u64 = 1024 * 1024;
 
 pub struct LogServer {
     server: Builder<AddrIncoming>,
     shutdown_rx: Receiver<()>,
-    format: String,
+    printer: LogPrinter,
 }
 
-/// LogServer is just a basic HTTP server running locally; it listens for POST requests on the root
-/// path and simply prints the JSON body of each request as its own line to STDOUT.
+/// LogServer is just a basic HTTP server running locally; it listens for POST requests and simply
+/// prints the JSON body of each request as its own line to STDOUT. Each tail posts to the path of
+/// its script, e.g. `/my-worker`, so events can be told apart when tailing several scripts.
 impl LogServer {
-    pub fn new(port: u16, shutdown_rx: Receiver<()>, format: String) -> LogServer {
+    pub fn new(
+        port: u16,
+        shutdown_rx: Receiver<()>,
+        short_circuit: Sender<()>,
+        options: &TailOptions,
+        source_maps: SourceMaps,
+    ) -> Result<LogServer> {
         // Start HTTP echo server that prints whatever is posted to it.
         let addr = ([127, 0, 0, 1], port).into();
 
         let server = Server::bind(&addr);
 
-        LogServer {
+        let sink = match &options.out {
+            Some(path) => Some(Arc::new(Mutex::new(LogSink::new(
+                path,
+                options.max_size * BYTES_PER_MEGABYTE,
+            )?))),
+            None => None,
+        };
+
+        let exec = match &options.exec {
+            Some(command) => Some(Arc::new(Mutex::new(ExecSink::spawn(command)?))),
+            None => None,
+        };
+
+        Ok(LogServer {
             server,
             shutdown_rx,
-            format,
-        }
+            printer: LogPrinter {
+                format: options.format.clone(),
+                show_script_name: options.show_script_name,
+                filters: options.filters.clone(),
+                sink,
+                exec,
+                short_circuit: Arc::new(Mutex::new(Some(short_circuit))),
+                source_maps: Arc::new(source_maps),
+            },
+        })
     }
 
     pub async fn run(self) -> Result<()> {
-        let format = self.format;
-
-        let server_fn_gen = |format: String| {
-            service_fn(move |req: Request<Body>| {
-                let format = format.clone();
-                print_logs(req, format)
-            })
-        };
+        let printer = self.printer;
 
         let service = make_service_fn(move |_| {
-            let format = format.clone();
-            async move { Ok::<_, hyper::Error>(server_fn_gen(format)) }
+            let printer = printer.clone();
+            async move {
+                Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
+                    let printer = printer.clone();
+                    async move { printer.handle(req).await }
+                }))
+            }
         });
 
         let server = self.server.serve(service);
@@ -61,101 +96,174 @@ impl LogServer {
     }
 }
 
-async fn print_logs(req: Request<Body>, format: String) -> Result<Response<Body>> {
-    match format.as_str() {
-        "pretty" => print_logs_pretty(req).await,
-        "json" => print_logs_json(req).await,
-        _ => unreachable!(),
-    }
+/// LogPrinter renders each event posted to the LogServer in the requested format, or pipes it
+/// to the `--exec` subprocess, and additionally appends it to the output file when one was given.
+#[derive(Clone)]
+struct LogPrinter {
+    format: String,
+    show_script_name: bool,
+    filters: TailFilters,
+    sink: Option<Arc<Mutex<LogSink>>>,
+    exec: Option<Arc<Mutex<ExecSink>>>,
+    /// Stops the tail once whatever we're writing to goes away.
+    short_circuit: Arc<Mutex<Option<Sender<()>>>>,
+    /// Maps stack frames in exceptions and logs back to the sources of the local build.
+    source_maps: Arc<SourceMaps>,
 }
 
-async fn print_logs_json(req: Request<Body>) -> Result<Response<Body>> {
-    match (req.method(), req.uri().path()) {
-        (&Method::POST, "/") => {
-            let whole_body = hyper::body::to_bytes(req.into_body()).await?;
-            println!(
-                "{}",
-                std::str::from_utf8(&whole_body).expect("failed to deserialize tail log body")
-            );
+impl LogPrinter {
+    async fn handle(&self, req: Request<Body>) -> Result<Response<Body>> {
+        match (req.method(), req.uri().path()) {
+            (&Method::POST, path) => {
+                let script_name = path.trim_start_matches('/').to_string();
+                let whole_body = hyper::body::to_bytes(req.into_body()).await?;
+                let body =
+                    std::str::from_utf8(&whole_body).expect("failed to deserialize tail log body");
 
-            Ok(Response::new(Body::from("Success")))
+                let mut event = serde_json::from_str::<Value>(body).ok();
+                if let Some(event) = &mut event {
+                    if !self.filters.matches(event) {
+                        return Ok(Response::new(Body::from("Success")));
+                    }
+                    if self.show_script_name {
+                        tag_script_name(event, &script_name);
+                    }
+                }
+
+                let line = match &event {
+                    Some(event) => event.to_string(),
+                    // NDJSON needs every event on a single line
+                    None => body.replace('\n', " "),
+                };
+                if let Some(sink) = &self.sink {
+                    if let Err(e) = sink.lock().unwrap().write_line(&line) {
+                        eprintln!("{}", styles::warning(format!("Failed to write log: {}", e)));
+                    }
+                }
+
+                let written = match (&self.exec, self.format.as_str()) {
+                    (Some(exec), _) => exec.lock().unwrap().write_line(&line),
+                    (None, "pretty") => {
+                        write_stdout(&self.render_pretty(&whole_body, &script_name)?)
+                    }
+                    (None, "json") if self.show_script_name => write_stdout(&[line]),
+                    (None, "json") => write_stdout(&[body.to_string()]),
+                    _ => unreachable!(),
+                };
+
+                if let Err(e) = written {
+                    if e.kind() == io::ErrorKind::BrokenPipe {
+                        // The reader went away (e.g. `wrangler tail | head`), which is a normal
+                        // way to stop tailing rather than an error.
+                        if let Some(tx) = self.short_circuit.lock().unwrap().take() {
+                            eprintln!("Output closed, stopping tail.");
+                            tx.send(()).ok();
+                        }
+                    } else {
+                        return Err(e.into());
+                    }
+                }
+
+                Ok(Response::new(Body::from("Success")))
+            }
+            _ => {
+                let mut bad_request = Response::default();
+                *bad_request.status_mut() = StatusCode::BAD_REQUEST;
+                Ok(bad_request)
+            }
         }
-        _ => {
-            let mut bad_request = Response::default();
-            *bad_request.status_mut() = StatusCode::BAD_REQUEST;
-            Ok(bad_request)
+    }
+}
+
+fn write_stdout(lines: &[String]) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in lines {
+        writeln!(stdout, "{}", line)?;
+    }
+    stdout.flush()
+}
+
+// Events of several scripts arrive on one server, so each is labelled with the script whose
+// tail posted it, unless the event already says
+fn tag_script_name(event: &mut Value, script_name: &str) {
+    if let Value::Object(event) = event {
+        if !script_name.is_empty() {
+            event
+                .entry("scriptName")
+                .or_insert_with(|| Value::String(script_name.to_string()));
         }
     }
 }
 
-async fn print_logs_pretty(req: Request<Body>) -> Result<Response<Body>> {
-    match (req.method(), req.uri().path()) {
-        (&Method::POST, "/") => {
-            let whole_body = hyper::body::to_bytes(req.into_body()).await?;
-
-            let parsed = serde_json::from_slice::<LogResponse>(&whole_body).map_err(|e| {
-                println!("{}", styles::warning("Error parsing response body!"));
-                println!(
-                    "This is not a problem with your worker, it's a problem with Wrangler.\nPlease file an issue on our GitHub page, with a minimal reproducible example of\nthe script that caused this error and a description of what happened."
-                );
-                e
-            })?;
-
-            let secs = (parsed.event_timestamp / 1000).try_into().unwrap();
-
-            let timestamp = chrono::NaiveDateTime::from_timestamp(secs, 0);
-
-            println!(
-                "{}{} {} --> {} @ {} UTC",
-                emoji::EYES,
-                parsed.event.request.method,
-                styles::url(parsed.event.request.url),
-                parsed.outcome.to_uppercase(),
-                timestamp.time()
+impl LogPrinter {
+    fn render_pretty(&self, whole_body: &[u8], script_name: &str) -> Result<Vec<String>> {
+        let parsed = serde_json::from_slice::<LogResponse>(whole_body).map_err(|e| {
+            eprintln!("{}", styles::warning("Error parsing response body!"));
+            eprintln!(
+                "This is not a problem with your worker, it's a problem with Wrangler.\nPlease file an issue on our GitHub page, with a minimal reproducible example of\nthe script that caused this error and a description of what happened."
             );
+            e
+        })?;
 
-            if !parsed.exceptions.is_empty() {
-                println!("  Exceptions:");
-                parsed.exceptions.iter().for_each(|exception| {
-                    println!(
-                        "\t{} {}",
-                        emoji::X,
-                        styles::warning(format!("{}: {}", exception.name, exception.message))
-                    );
-                });
-            }
+        // When tailing several scripts, prefix every line so the streams can be told apart.
+        let name = parsed.script_name.as_deref().unwrap_or(script_name);
+        let prefix = if self.show_script_name && !name.is_empty() {
+            format!("{} ", styles::cyan(format!("[{}]", name)))
+        } else {
+            String::new()
+        };
 
-            if !parsed.logs.is_empty() {
-                println!("  Logs:");
-                parsed.logs.iter().for_each(|log| {
-                    let message = colored_json_string(&log.message);
-                    let messages = if let Ok(m) = message {
-                        m
-                    } else {
-                        "Error: Failed to convert encoded message to string".to_string()
-                    };
-
-                    let output = match log.level.as_str() {
-                        "assert" | "error" => format!("{} {}", emoji::X, styles::warning(messages)),
-                        "warn" => format!("{} {}", emoji::WARN, styles::highlight(messages)),
-                        "trace" | "debug" => {
-                            format!("{}{}", emoji::MICROSCOPE, styles::cyan(messages))
-                        }
-                        _ => format!("{} {}", emoji::FILES, styles::bold(messages)),
-                    };
+        Ok(render_pretty(&parsed, &self.source_maps)
+            .into_iter()
+            .map(|line| format!("{}{}", prefix, line))
+            .collect())
+    }
+}
+
+fn render_pretty(parsed: &LogResponse, source_maps: &SourceMaps) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    let secs = (parsed.event_timestamp / 1000).try_into().unwrap();
+
+    let timestamp = chrono::NaiveDateTime::from_timestamp(secs, 0);
+
+    lines.push(format!(
+        "{}{} {} --> {} @ {} UTC",
+        emoji::EYES,
+        parsed.event.request.method,
+        styles::url(&parsed.event.request.url),
+        parsed.outcome.to_uppercase(),
+        timestamp.time()
+    ));
 
-                    println!("\t{}", output);
-                });
+    if !parsed.exceptions.is_empty() {
+        lines.push("  Exceptions:".to_string());
+        parsed.exceptions.iter().for_each(|exception| {
+            lines.push(format!(
+                "\t{} {}",
+                emoji::X,
+                styles::warning(format!("{}: {}", exception.name, exception.message))
+            ));
+
+            // The first line of a stack repeats the name and message printed above
+            if let Some(stack) = &exception.stack {
+                for frame in source_maps.map_stack(stack).lines().skip(1) {
+                    lines.push(format!("\t  {}", frame.trim()));
+                }
             }
+        });
+    }
 
-            Ok(Response::new(Body::from("Success")))
-        }
-        _ => {
-            let mut bad_request = Response::default();
-            *bad_request.status_mut() = StatusCode::BAD_REQUEST;
-            Ok(bad_request)
-        }
+    if !parsed.logs.is_empty() {
+        lines.push("  Logs:".to_string());
+        parsed.logs.iter().for_each(|log| {
+            let message = source_maps.map_stack(&console::format_args(&log.message));
+            lines.push(format!("\t{}", console::format_log(&log.level, &message)));
+        });
     }
+
+    lines
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -174,6 +282,8 @@ struct LogResponse {
 struct LogException {
     name: String,
     message: String,
+    #[serde(default)]
+    stack: Option<String>,
     timestamp: usize,
 }
 
@@ -196,3 +306,20 @@ struct RequestEventData {
     // headers: bruh,
     // cf: RequestEventCfData, // lol
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn events_are_tagged_with_the_script_that_posted_them() {
+        let mut event = json!({ "outcome": "ok" });
+        tag_script_name(&mut event, "worker-a");
+        assert_eq!(event["scriptName"], "worker-a");
+
+        let mut event = json!({ "outcome": "ok", "scriptName": "worker-b" });
+        tag_script_name(&mut event, "worker-a");
+        assert_eq!(event["scriptName"], "worker-b");
+    }
+}
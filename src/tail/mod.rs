@@ -10,16 +10,22 @@
 ///        exception logging to the Trace Worker, which POSTs each batch of logs as a JSON
 ///        payload to the provided Tunnel URL.
 ///     5. Upon receipt, the LogServer prints the payload of each POST request to STDOUT.
+mod filter;
 mod log_server;
 mod session;
 mod shutdown;
+mod sink;
 mod tunnel;
 
+pub use filter::{TailFilters, STATUSES};
 use log_server::LogServer;
 use session::Session;
 use shutdown::ShutdownHandler;
 use tunnel::Tunnel;
 
+use std::path::PathBuf;
+use std::time::Duration;
+
 use anyhow::Result;
 use console::style;
 use tokio::runtime::Runtime as TokioRuntime;
@@ -27,21 +33,40 @@ use which::which;
 
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::sourcemap::SourceMaps;
 use crate::terminal::emoji;
 
 pub struct Tail;
 
+/// Options controlling how tailed events are rendered and where they are written.
+#[derive(Clone, Debug)]
+pub struct TailOptions {
+    /// Either "json" or "pretty".
+    pub format: String,
+    /// A file each event is appended to as a line of JSON.
+    pub out: Option<PathBuf>,
+    /// Size in megabytes after which `out` is rotated.
+    pub max_size: u64,
+    /// Prefix pretty output with the script each event came from.
+    pub show_script_name: bool,
+    pub filters: TailFilters,
+    /// A command each event is piped to as a line of JSON, instead of printing it.
+    pub exec: Option<String>,
+}
+
 impl Tail {
+    /// Tails every script in `scripts` over a single tunnel and log server.
     pub fn run(
         target: Target,
+        scripts: Vec<String>,
         user: GlobalUser,
-        format: String,
+        options: TailOptions,
         tunnel_port: u16,
         metrics_port: u16,
         verbose: bool,
     ) -> Result<()> {
         is_cloudflared_installed()?;
-        print_startup_message(&target.name, tunnel_port, metrics_port);
+        print_startup_message(&scripts, tunnel_port, metrics_port);
 
         // Loading the token creates a nested runtime because it goes through reqwest.
         // Make sure it's loaded before creating our own runtime; nested runtimes will panic.
@@ -50,39 +75,71 @@ impl Tail {
         let runtime = TokioRuntime::new()?;
 
         runtime.block_on(async {
-            // Create three [one-shot](https://docs.rs/tokio/0.2.16/tokio/sync#oneshot-channel)
+            // Create [one-shot](https://docs.rs/tokio/0.2.16/tokio/sync#oneshot-channel)
             // channels for handling ctrl-c. Each channel has two parts:
             // tx: Transmitter
             // rx: Receiver
-            let (tx, rx) = tokio::sync::oneshot::channel(); // shutdown short circuit
             let mut shutdown_handler = ShutdownHandler::new();
             let log_rx = shutdown_handler.subscribe();
-            let session_rx = shutdown_handler.subscribe();
             let tunnel_rx = shutdown_handler.subscribe();
-
-            let listener = tokio::spawn(shutdown_handler.run(rx));
+            let (respawned_tx, respawned_rx) = tokio::sync::watch::channel(0);
+
+            // Every session gets a short circuit, so any one of them failing to start
+            // shuts down the whole tail.
+            let mut short_circuits = Vec::new();
+            let mut sessions = Vec::new();
+            for script in &scripts {
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                short_circuits.push(rx);
+
+                let mut script_target = target.clone();
+                script_target.name = script.clone();
+
+                // Register the tail with the Workers API and send periodic heartbeats
+                sessions.push(tokio::spawn(Session::run(
+                    script_target,
+                    user.clone(),
+                    options.filters.clone(),
+                    shutdown_handler.subscribe(),
+                    tx,
+                    respawned_rx.clone(),
+                    metrics_port,
+                    verbose,
+                )));
+            }
 
             // Spin up a local http server to receive logs
-            let log_server = tokio::spawn(LogServer::new(tunnel_port, log_rx, format).run());
+            let (log_tx, rx) = tokio::sync::oneshot::channel();
+            short_circuits.push(rx);
+            let mut options = options;
+            options.show_script_name = scripts.len() > 1;
+            // The local build only describes the configured script, so only map its stack traces
+            let source_maps = if scripts == [target.name.clone()] {
+                SourceMaps::load(&target)
+            } else {
+                SourceMaps::default()
+            };
+            let log_server = tokio::spawn(
+                LogServer::new(tunnel_port, log_rx, log_tx, &options, source_maps)?.run(),
+            );
+
+            let listener = tokio::spawn(shutdown_handler.run(short_circuits));
 
             // Spin up a new cloudflared tunnel to connect trace worker to local server
-            let tunnel_process = Tunnel::new(tunnel_port, metrics_port, verbose)?;
+            let tunnel_process = Tunnel::new(tunnel_port, metrics_port, verbose, respawned_tx)?;
             let tunnel = tokio::spawn(tunnel_process.run(tunnel_rx));
 
-            // Register the tail with the Workers API and send periodic heartbeats
-            let session = tokio::spawn(Session::run(
-                target,
-                user,
-                session_rx,
-                tx,
-                metrics_port,
-                verbose,
-            ));
+            let sessions = async {
+                for session in sessions {
+                    session.await??;
+                }
+                Ok::<_, anyhow::Error>(())
+            };
 
             let res = tokio::try_join!(
                 async { listener.await? },
                 async { log_server.await? },
-                async { session.await? },
+                sessions,
                 async { tunnel.await? }
             );
 
@@ -94,6 +151,15 @@ impl Tail {
     }
 }
 
+/// Longest we'll wait between attempts to re-establish a dropped tail or tunnel.
+pub(crate) const MAX_RECONNECT_DELAY_SECS: u64 = 60;
+
+/// Exponential backoff for reconnect attempts: 1s, 2s, 4s, ... capped at a minute.
+fn reconnect_delay(attempt: u32) -> Duration {
+    let secs = 2u64.saturating_pow(attempt).min(MAX_RECONNECT_DELAY_SECS);
+    Duration::from_secs(secs)
+}
+
 fn is_cloudflared_installed() -> Result<()> {
     // this can be removed once we automatically install cloudflared
     if which("cloudflared").is_err() {
@@ -106,15 +172,36 @@ fn is_cloudflared_installed() -> Result<()> {
     }
 }
 
-fn print_startup_message(worker_name: &str, tunnel_port: u16, metrics_port: u16) {
+fn print_startup_message(scripts: &[String], tunnel_port: u16, metrics_port: u16) {
+    let scripts = scripts
+        .iter()
+        .map(|script| format!("\"{}\"", script))
+        .collect::<Vec<_>>()
+        .join(", ");
+
     // Note that we use eprintln!() throughout this module; this is because we want any
     // helpful output to not be mixed with actual log JSON output, so we use this macro
     // to print messages to stderr instead of stdout (where log output is printed).
     eprintln!(
-        "{} Setting up log streaming from Worker script \"{}\". Using ports {} and {}.",
+        "{} Setting up log streaming from Worker script {}. Using ports {} and {}.",
         emoji::TAIL,
-        worker_name,
+        scripts,
         tunnel_port,
         metrics_port,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_delay_backs_off() {
+        assert_eq!(reconnect_delay(0), Duration::from_secs(1));
+        assert_eq!(reconnect_delay(3), Duration::from_secs(8));
+        assert_eq!(
+            reconnect_delay(40),
+            Duration::from_secs(MAX_RECONNECT_DELAY_SECS)
+        );
+    }
+}
@@ -1,23 +1,32 @@
 use std::str;
 use std::time::Duration;
 
-use anyhow::{anyhow, bail, Result};
+use anyhow::{anyhow, Result};
 use regex::Regex;
 use tokio::sync::oneshot::{Receiver, Sender};
+use tokio::sync::watch;
 use tokio::time::sleep;
 
-use cloudflare::endpoints::workers::{CreateTail, CreateTailParams, DeleteTail, SendTailHeartbeat};
+use cloudflare::endpoints::workers::{DeleteTail, SendTailHeartbeat};
+use cloudflare::framework::endpoint::{Endpoint, Method};
+use cloudflare::framework::response::ApiResult;
 use cloudflare::framework::HttpApiClientConfig;
 use cloudflare::framework::{async_api, async_api::ApiClient};
+use serde::{Deserialize, Serialize};
 
 use crate::http;
 use crate::settings::global_user::GlobalUser;
 use crate::settings::toml::Target;
+use crate::terminal::emoji;
+
+use super::filter::TailFilters;
+use super::reconnect_delay;
 
 const KEEP_ALIVE_INTERVAL: u64 = 60;
 
 pub struct Session {
     tail_id: Option<String>,
+    filters: TailFilters,
 }
 
 /// Session is responsible for interacting with the Workers API to establish and maintain the tail
@@ -28,17 +37,22 @@ impl Session {
     pub async fn run(
         target: Target,
         user: GlobalUser,
+        filters: TailFilters,
         shutdown_rx: Receiver<()>,
         tx: Sender<()>,
+        respawned: watch::Receiver<u32>,
         metrics_port: u16,
         verbose: bool,
     ) -> Result<()> {
         // During the start process we'll populate the tail with the response from the API.
-        let mut session = Session { tail_id: None };
+        let mut session = Session {
+            tail_id: None,
+            filters,
+        };
         // We need to exit on a shutdown command without waiting for API calls to complete.
         tokio::select! {
             _ = shutdown_rx => { session.close(&user, &target).await }
-            result = session.start(&target, &user, tx, metrics_port, verbose) => { result }
+            result = session.start(&target, &user, tx, respawned, metrics_port, verbose) => { result }
         }
     }
 
@@ -46,8 +60,12 @@ impl Session {
         // The API will clean up tails after about 10 minutes of inactivity, or 24 hours of
         // activity but since we limit the number of tails allowed on a single script we should at
         // least try to delete them as we go.
+        let client = http::cf_v4_api_client_async(&user, HttpApiClientConfig::default())?;
+        self.delete(target, &client).await
+    }
+
+    async fn delete(&self, target: &Target, client: &async_api::Client) -> Result<()> {
         if let Some(tail_id) = &self.tail_id {
-            let client = http::cf_v4_api_client_async(&user, HttpApiClientConfig::default())?;
             client
                 .request(&DeleteTail {
                     account_identifier: target.account_id.load()?,
@@ -65,6 +83,7 @@ impl Session {
         target: &Target,
         user: &GlobalUser,
         tx: Sender<()>,
+        mut respawned: watch::Receiver<u32>,
         metrics_port: u16,
         _verbose: bool,
     ) -> Result<()> {
@@ -72,55 +91,152 @@ impl Session {
 
         let client = http::cf_v4_api_client_async(user, HttpApiClientConfig::default())?;
 
+        // Failing to set up the tail in the first place is almost always a configuration
+        // problem, so there's no point in retrying it.
+        if let Err(e) = self.connect(target, &client, metrics_port).await {
+            tx.send(()).unwrap();
+            return Err(e);
+        }
+
+        eprintln!("Now prepared to stream logs");
+
+        // Loop indefinitely to send "heartbeat" to API and keep log streaming alive.
+        // This should loop forever until SIGINT is issued or Wrangler process is killed
+        // through other means. If the tail expired, the connection dropped or cloudflared came
+        // back with a new tunnel URL, set up a new one.
+        let duration = Duration::from_secs(KEEP_ALIVE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = sleep(duration) => {
+                    let tail_id = self.tail_id.clone().unwrap_or_default();
+                    if let Err(e) = send_heartbeat(&target, &client, &tail_id).await {
+                        self.reconnect(target, &client, metrics_port, e).await;
+                    }
+                }
+                changed = respawned.changed() => {
+                    // The tunnel only goes away when shutting down
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                    let reason = anyhow!("cloudflared restarted with a new tunnel URL");
+                    self.reconnect(target, &client, metrics_port, reason).await;
+                }
+            }
+        }
+    }
+
+    /// Registers a new tail pointing at the current tunnel URL.
+    async fn connect(
+        &mut self,
+        target: &Target,
+        client: &async_api::Client,
+        metrics_port: u16,
+    ) -> Result<()> {
         // Hit http://localhost:8081/metrics to grab the generated tunnel name. cloudflared
         // can take a minute to start, so use exponential backoff
         let retry_strategy = tokio_retry::strategy::ExponentialBackoff::from_millis(100).take(5);
-        let result =
-            tokio_retry::Retry::spawn(retry_strategy, || get_tunnel_url(metrics_port)).await;
+        let url = tokio_retry::Retry::spawn(retry_strategy, || get_tunnel_url(metrics_port))
+            .await
+            .map_err(|_| anyhow!("Could not extract tunnel url from cloudflared"))?;
+        // the log server tells the tails of several scripts apart by the path they post to
+        let url = format!("{}/{}", url, target.name);
 
-        if let Ok(url_) = result {
-            let url = Some(url_);
-            let response = client
-                .request(&CreateTail {
-                    account_identifier: target.account_id.load()?,
-                    script_name: &target.name,
-                    params: CreateTailParams { url },
-                })
-                .await;
-
-            match response {
-                Ok(success) => {
-                    eprintln!("Now prepared to stream logs");
-
-                    let tail_id = success.result.id;
-                    self.tail_id = Some(tail_id.clone());
-
-                    // Loop indefinitely to send "heartbeat" to API and keep log streaming alive.
-                    // This should loop forever until SIGINT is issued or Wrangler process is killed
-                    // through other means.
-                    let duration = Duration::from_millis(1000 * KEEP_ALIVE_INTERVAL);
-                    let mut delay = sleep(duration);
-
-                    loop {
-                        delay.await;
-                        let heartbeat_result = send_heartbeat(&target, &client, &tail_id).await;
-                        if heartbeat_result.is_err() {
-                            return heartbeat_result;
-                        }
-                        delay = sleep(duration);
-                    }
+        let response = client
+            .request(&CreateTail {
+                account_identifier: target.account_id.load()?,
+                script_name: &target.name,
+                params: CreateTailParams {
+                    url,
+                    filters: self.filters.api_filters(),
+                },
+            })
+            .await;
+
+        match response {
+            Ok(success) => {
+                self.tail_id = Some(success.result.id);
+                Ok(())
+            }
+            Err(e) => Err(http::api_error(e, Some(&tail_help))),
+        }
+    }
+
+    /// Keeps trying to set up a new tail, backing off between attempts, until it succeeds.
+    async fn reconnect(
+        &mut self,
+        target: &Target,
+        client: &async_api::Client,
+        metrics_port: u16,
+        reason: anyhow::Error,
+    ) {
+        eprintln!("{}Lost connection to the tail: {}", emoji::WARN, reason);
+
+        // The old tail has most likely expired already, but don't leave it behind if it hasn't.
+        self.delete(target, client).await.ok();
+        self.tail_id = None;
+
+        let mut attempt = 0;
+        loop {
+            let delay = reconnect_delay(attempt);
+            eprintln!(
+                "Reconnecting in {}s (attempt {})...",
+                delay.as_secs(),
+                attempt + 1
+            );
+            sleep(delay).await;
+
+            match self.connect(target, client, metrics_port).await {
+                Ok(_) => {
+                    eprintln!("{}Reconnected, streaming logs again", emoji::TAIL);
+                    return;
                 }
                 Err(e) => {
-                    tx.send(()).unwrap();
-                    bail!(http::format_error(e, Some(&tail_help)))
+                    eprintln!("Failed to reconnect: {}", e);
+                    attempt += 1;
                 }
             }
-        } else {
-            bail!("Could not extract tunnel url from cloudflared");
         }
     }
 }
 
+// cloudflare-rs doesn't know about tail filters yet, so we bring our own CreateTail.
+struct CreateTail<'a> {
+    account_identifier: &'a str,
+    script_name: &'a str,
+    params: CreateTailParams,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct CreateTailParams {
+    url: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    filters: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TailResult {
+    id: String,
+}
+
+impl ApiResult for TailResult {}
+
+impl<'a> Endpoint<TailResult, (), CreateTailParams> for CreateTail<'a> {
+    fn method(&self) -> Method {
+        Method::Post
+    }
+
+    fn path(&self) -> String {
+        format!(
+            "accounts/{}/workers/scripts/{}/tails",
+            self.account_identifier, self.script_name
+        )
+    }
+
+    fn body(&self) -> Option<CreateTailParams> {
+        Some(self.params.clone())
+    }
+}
+
 async fn get_tunnel_url(metrics_port: u16) -> Result<String> {
     let metrics_url = format!("http://localhost:{}/metrics", metrics_port);
     let url_regex = Regex::new("userHostname=\"(https://[a-z.-]+)\"").unwrap();
@@ -145,7 +261,7 @@ async fn send_heartbeat(target: &Target, client: &async_api::Client, tail_id: &s
 
     match response {
         Ok(_) => Ok(()),
-        Err(e) => Err(anyhow!(http::format_error(e, Some(&tail_help)))),
+        Err(e) => Err(http::api_error(e, Some(&tail_help))),
     }
 }
 
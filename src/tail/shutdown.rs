@@ -18,8 +18,18 @@ impl ShutdownHandler {
     }
 
     /// ShutdownHandler waits on a ctrl_c from the system, or a short circuit command from the top
-    /// level error handler, and sends messages to each registered transmitter when it is received.
-    pub async fn run(self, short_circuit: Receiver<()>) -> Result<()> {
+    /// level error handler of any session, and sends messages to each registered transmitter when
+    /// it is received.
+    pub async fn run(self, short_circuits: Vec<Receiver<()>>) -> Result<()> {
+        // select_all panics when given an empty list, so wait forever instead.
+        let short_circuit = async {
+            if short_circuits.is_empty() {
+                futures_util::future::pending::<()>().await
+            } else {
+                futures_util::future::select_all(short_circuits).await;
+            }
+        };
+
         tokio::select! {
             _ = tokio::signal::ctrl_c() => {}
             _ = short_circuit => {}
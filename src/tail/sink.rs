@@ -0,0 +1,151 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Stdio};
+
+use anyhow::{anyhow, Result};
+
+use crate::build::shell_command;
+
+/// Number of rotated files kept next to the active one (`tail.ndjson.1` ... `tail.ndjson.5`).
+const ROTATED_FILES: usize = 5;
+
+/// LogSink appends each tail event as a line of JSON to a file, rotating it once it grows
+/// past `max_bytes` so long-running tails don't fill up the disk.
+pub struct LogSink {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+}
+
+impl LogSink {
+    pub fn new(path: &Path, max_bytes: u64) -> Result<LogSink> {
+        let file = open(path)?;
+        let written = file.metadata()?.len();
+
+        Ok(LogSink {
+            path: path.to_path_buf(),
+            file,
+            written,
+            max_bytes,
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        if self.written > 0 && self.written + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        writeln!(self.file, "{}", line)?;
+        self.file.flush()?;
+        self.written += line.len() as u64 + 1;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        for n in (1..ROTATED_FILES).rev() {
+            let from = rotated_path(&self.path, n);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+
+        self.file = open(&self.path)?;
+        self.written = 0;
+
+        Ok(())
+    }
+}
+
+/// ExecSink feeds each tail event as a line of JSON to the stdin of a subprocess, e.g.
+/// `wrangler tail --exec "jq .logs"`.
+pub struct ExecSink {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl ExecSink {
+    pub fn spawn(command: &str) -> Result<ExecSink> {
+        let mut child = shell_command(command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow!("could not run `{}`: {}", command, e))?;
+        let stdin = child.stdin.take();
+
+        Ok(ExecSink { child, stdin })
+    }
+
+    /// Writes and flushes a single line, so the subprocess sees every event as soon as it
+    /// arrives. Returns a `BrokenPipe` error once the subprocess has exited.
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        match &mut self.stdin {
+            Some(stdin) => {
+                writeln!(stdin, "{}", line)?;
+                stdin.flush()
+            }
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+}
+
+impl Drop for ExecSink {
+    // Closing stdin signals EOF, giving the subprocess a chance to flush its own output.
+    fn drop(&mut self) {
+        self.stdin.take();
+        self.child.wait().ok();
+    }
+}
+
+fn open(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow!("could not open {} for writing: {}", path.display(), e))
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", n));
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn it_rotates_when_full() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tail.ndjson");
+        let mut sink = LogSink::new(&path, 16).unwrap();
+
+        sink.write_line("{\"a\":1}").unwrap();
+        sink.write_line("{\"b\":2}").unwrap();
+        sink.write_line("{\"c\":3}").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "{\"a\":1}\n{\"b\":2}\n"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"c\":3}\n");
+    }
+
+    #[test]
+    fn it_keeps_a_bounded_number_of_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tail.ndjson");
+        let mut sink = LogSink::new(&path, 1).unwrap();
+
+        for i in 0..(ROTATED_FILES + 3) {
+            sink.write_line(&i.to_string()).unwrap();
+        }
+
+        assert!(rotated_path(&path, ROTATED_FILES).exists());
+        assert!(!rotated_path(&path, ROTATED_FILES + 1).exists());
+    }
+}
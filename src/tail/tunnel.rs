@@ -1,15 +1,25 @@
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::str;
+use std::time::{Duration, Instant};
 
 use tokio::process::Child;
 use tokio::process::Command;
 use tokio::sync::oneshot::Receiver;
+use tokio::sync::watch;
+use tokio::time::sleep;
 
 use anyhow::Result;
 
+use super::{reconnect_delay, MAX_RECONNECT_DELAY_SECS};
+
 pub struct Tunnel {
     child: Child,
+    tunnel_port: u16,
+    metrics_port: u16,
+    verbose: bool,
+    /// Counts restarts of cloudflared, each of which comes with a new tunnel URL
+    respawned: watch::Sender<u32>,
 }
 
 /// Tunnel wraps a child process that runs cloudflared and forwards requests from the Trace Worker
@@ -18,29 +28,68 @@ pub struct Tunnel {
 /// and wait on its output; otherwise we leave an orphaned process when wrangler exits and this
 /// causes problems if it still exists the next time we start up a tail.
 impl Tunnel {
-    pub fn new(tunnel_port: u16, metrics_port: u16, verbose: bool) -> Result<Tunnel> {
-        let tool_name = PathBuf::from("cloudflared");
-        // TODO: Finally get cloudflared release binaries distributed on GitHub so we could
-        // simply uncomment the line below.
-        // let binary_path = install::install(tool_name, "cloudflare")?.binary(tool_name)?;
-
-        let tunnel_url = format!("localhost:{}", tunnel_port);
-        let metrics_url = format!("localhost:{}", metrics_port);
-        let args = ["tunnel", "--url", &tunnel_url, "--metrics", &metrics_url];
-
-        let mut command = command(&args, &tool_name, verbose);
-        let command_name = format!("{:?}", command);
+    pub fn new(
+        tunnel_port: u16,
+        metrics_port: u16,
+        verbose: bool,
+        respawned: watch::Sender<u32>,
+    ) -> Result<Tunnel> {
+        let child = spawn(tunnel_port, metrics_port, verbose)?;
 
-        let child = command
-            .spawn()
-            .unwrap_or_else(|_| panic!("{} failed to spawn", command_name));
-
-        Ok(Tunnel { child })
+        Ok(Tunnel {
+            child,
+            tunnel_port,
+            metrics_port,
+            verbose,
+            respawned,
+        })
     }
 
-    pub async fn run(self, shutdown_rx: Receiver<()>) -> Result<()> {
-        shutdown_rx.await?;
-        self.shutdown().await
+    /// Waits for the shutdown signal, restarting cloudflared in the meantime if it exits on
+    /// its own. Every restart is announced on `respawned`, so the sessions register their
+    /// tails with the new tunnel URL.
+    pub async fn run(mut self, mut shutdown_rx: Receiver<()>) -> Result<()> {
+        let mut attempt = 0;
+        let mut restarts = 0;
+        let mut started = Instant::now();
+        loop {
+            tokio::select! {
+                _ = &mut shutdown_rx => return self.shutdown().await,
+                status = self.child.wait() => {
+                    // A tunnel that stayed up for a while exited for a new reason, so it
+                    // doesn't have to wait out the backoff of earlier crashes.
+                    if started.elapsed() >= Duration::from_secs(MAX_RECONNECT_DELAY_SECS) {
+                        attempt = 0;
+                    }
+                    let mut failure = format!(
+                        "cloudflared exited unexpectedly ({})",
+                        status.map(|s| s.to_string()).unwrap_or_else(|e| e.to_string())
+                    );
+                    // A restart that fails to spawn cloudflared is one more attempt, backed
+                    // off like the crashes.
+                    loop {
+                        let delay = reconnect_delay(attempt);
+                        eprintln!("{}; restarting it in {}s...", failure, delay.as_secs());
+                        tokio::select! {
+                            _ = &mut shutdown_rx => return Ok(()),
+                            _ = sleep(delay) => {}
+                        }
+                        attempt += 1;
+                        match spawn(self.tunnel_port, self.metrics_port, self.verbose) {
+                            Ok(child) => {
+                                self.child = child;
+                                break;
+                            }
+                            Err(e) => failure = e.to_string(),
+                        }
+                    }
+                    started = Instant::now();
+                    restarts += 1;
+                    // No session listening means there's nothing to re-register.
+                    self.respawned.send(restarts).ok();
+                }
+            }
+        }
     }
 
     /// shutdown is relatively simple, it sends a second `kill` signal to the child process,
@@ -65,6 +114,24 @@ impl Tunnel {
     }
 }
 
+fn spawn(tunnel_port: u16, metrics_port: u16, verbose: bool) -> Result<Child> {
+    let tool_name = PathBuf::from("cloudflared");
+    // TODO: Finally get cloudflared release binaries distributed on GitHub so we could
+    // simply uncomment the line below.
+    // let binary_path = install::install(tool_name, "cloudflare")?.binary(tool_name)?;
+
+    let tunnel_url = format!("localhost:{}", tunnel_port);
+    let metrics_url = format!("localhost:{}", metrics_port);
+    let args = ["tunnel", "--url", &tunnel_url, "--metrics", &metrics_url];
+
+    let mut command = command(&args, &tool_name, verbose);
+    let command_name = format!("{:?}", command);
+
+    command
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("{} failed to spawn: {}", command_name, e))
+}
+
 // TODO: let's not clumsily copy this from commands/build/mod.rs
 // We definitely want to keep the check for RUST_LOG=info below so we avoid
 // spamming user terminal with default cloudflared output (which is pretty darn sizable.)
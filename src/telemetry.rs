@@ -0,0 +1,174 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::http;
+use crate::settings::get_wrangler_home_dir;
+use crate::terminal::interactive;
+use crate::terminal::message::{self, Message, Output, StdErr};
+
+/// Where events are sent: each one is POSTed as the JSON of `Event`, and the response is
+/// ignored. Set `WRANGLER_TELEMETRY_URL` to send them somewhere else, e.g. to look at what's
+/// sent.
+pub const TELEMETRY_URL: &str = "https://api.cloudflare.com/client/v4/wrangler/events";
+// Telemetry must never make a command noticeably slower
+const SEND_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Whether the user agreed to send anonymous usage metrics, asked once and kept in
+/// `~/.wrangler/telemetry.toml`.
+#[derive(Debug, Deserialize, Serialize)]
+struct Consent {
+    enabled: bool,
+    /// Tells events from the same install apart, and nothing else.
+    anonymous_id: Uuid,
+    decided_at: SystemTime,
+}
+
+/// What gets sent for each command: never arguments, paths, names or credentials.
+#[derive(Debug, Serialize)]
+struct Event<'a> {
+    anonymous_id: Uuid,
+    command: &'a str,
+    duration_ms: u128,
+    success: bool,
+    wrangler_version: &'static str,
+    os: &'static str,
+}
+
+/// `WRANGLER_TELEMETRY=0` (or `false`/`off`) and `DO_NOT_TRACK=1` turn telemetry off, whatever
+/// was answered before.
+pub fn disabled_by_env() -> bool {
+    let off = |var: &str, values: &[&str]| {
+        env::var(var)
+            .map(|value| values.contains(&value.to_lowercase().as_str()))
+            .unwrap_or(false)
+    };
+    off("WRANGLER_TELEMETRY", &["0", "false", "off"]) || off("DO_NOT_TRACK", &["1", "true"])
+}
+
+pub fn is_enabled() -> bool {
+    !disabled_by_env() && load().map(|consent| consent.enabled).unwrap_or(false)
+}
+
+/// On the first interactive run, asks whether to send anonymous usage metrics. Nothing is
+/// sent until the user says yes, and nobody is asked in CI or with `--json` or `--yes`.
+pub fn ask_for_consent() {
+    if disabled_by_env()
+        || load().is_some()
+        || !interactive::is_interactive()
+        || message::output() == Output::Json
+        || interactive::assumes_yes()
+    {
+        return;
+    }
+
+    StdErr::message(concat!(
+        "Would you like to help improve wrangler by sending anonymous usage metrics?\n",
+        "This is the command you ran (not its arguments), how long it took, whether it ",
+        "succeeded, your wrangler version and OS.\n",
+        "You can change your mind with `wrangler telemetry enable|disable`."
+    ));
+    let enabled = interactive::confirm("Send anonymous usage metrics?").unwrap_or(false);
+    if let Err(e) = set_enabled(enabled) {
+        log::debug!("could not save telemetry consent: {}", e);
+    }
+}
+
+pub fn set_enabled(enabled: bool) -> Result<()> {
+    let anonymous_id = load()
+        .map(|consent| consent.anonymous_id)
+        .unwrap_or_else(Uuid::new_v4);
+    let consent = Consent {
+        enabled,
+        anonymous_id,
+        decided_at: SystemTime::now(),
+    };
+
+    let path = consent_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string(&consent)?)?;
+    Ok(())
+}
+
+/// Sends an event for a finished command if the user opted in, without waiting for it to be
+/// sent. An event that isn't sent by the time wrangler exits is dropped, and failures are only
+/// logged.
+pub fn record(command: &str, duration: Duration, success: bool) {
+    if command.is_empty() || disabled_by_env() {
+        return;
+    }
+    let consent = match load() {
+        Some(consent) if consent.enabled => consent,
+        _ => return,
+    };
+
+    let event = Event {
+        anonymous_id: consent.anonymous_id,
+        command,
+        duration_ms: duration.as_millis(),
+        success,
+        wrangler_version: env!("CARGO_PKG_VERSION"),
+        os: env::consts::OS,
+    };
+    let body = match serde_json::to_vec(&event) {
+        Ok(body) => body,
+        Err(e) => return log::debug!("could not serialize telemetry: {}", e),
+    };
+    let url = env::var("WRANGLER_TELEMETRY_URL").unwrap_or_else(|_| TELEMETRY_URL.to_string());
+    thread::spawn(move || {
+        let sent = http::client()
+            .post(&url)
+            .timeout(SEND_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send();
+        if let Err(e) = sent {
+            log::debug!("could not send telemetry: {}", e);
+        }
+    });
+}
+
+/// The command that ran, e.g. `kv:key put`, without any of its arguments.
+pub fn command_name(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let (name, Some(subcommand)) = matches.subcommand() {
+        names.push(name);
+        matches = subcommand;
+    }
+    names.join(" ")
+}
+
+fn load() -> Option<Consent> {
+    let contents = fs::read_to_string(consent_path()).ok()?;
+    toml::from_str(&contents).ok()
+}
+
+fn consent_path() -> PathBuf {
+    get_wrangler_home_dir().join("telemetry.toml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{App, SubCommand};
+
+    #[test]
+    fn command_names_leave_out_arguments() {
+        let app = App::new("wrangler").subcommand(
+            SubCommand::with_name("kv:key")
+                .subcommand(SubCommand::with_name("put").arg(clap::Arg::with_name("key"))),
+        );
+        let matches = app.get_matches_from(vec!["wrangler", "kv:key", "put", "secret-key"]);
+        assert_eq!(command_name(&matches), "kv:key put");
+    }
+}
@@ -0,0 +1,36 @@
+use std::ffi::OsStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns off ANSI styling and emoji everywhere, for `--no-color` and `NO_COLOR`.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+    console::set_colors_enabled(false);
+    console::set_colors_enabled_stderr(false);
+}
+
+pub fn enabled() -> bool {
+    !DISABLED.load(Ordering::Relaxed)
+}
+
+/// Whether the `NO_COLOR` environment variable asks for plain output, see https://no-color.org.
+pub fn disabled_by_env() -> bool {
+    no_color_requested(std::env::var_os("NO_COLOR").as_deref())
+}
+
+fn no_color_requested(value: Option<&OsStr>) -> bool {
+    matches!(value, Some(value) if !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_no_color_is_ignored() {
+        assert!(!no_color_requested(None));
+        assert!(!no_color_requested(Some(OsStr::new(""))));
+        assert!(no_color_requested(Some(OsStr::new("1"))));
+    }
+}
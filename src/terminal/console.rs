@@ -0,0 +1,53 @@
+use serde_json::Value;
+
+use super::{colored_json_compact, emoji, styles};
+
+/// Renders the arguments of a `console` call the way a browser console would: strings as-is,
+/// everything else as JSON, separated by spaces.
+pub fn format_args(args: &Value) -> String {
+    let args = match args {
+        Value::Array(args) => args.iter().collect(),
+        arg => vec![arg],
+    };
+
+    args.into_iter()
+        .map(|arg| match arg {
+            Value::String(s) => s.clone(),
+            arg => colored_json_compact(arg).unwrap_or_else(|_| arg.to_string()),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Styles a rendered `console` message according to the method that logged it.
+pub fn format_log(level: &str, message: &str) -> String {
+    match level {
+        "assert" | "error" => format!("{} {}", emoji::X, styles::warning(message)),
+        "warn" | "warning" => format!("{} {}", emoji::WARN, styles::highlight(message)),
+        "trace" | "debug" => format!("{}{}", emoji::MICROSCOPE, styles::cyan(message)),
+        _ => format!("{} {}", emoji::FILES, styles::bold(message)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn strings_are_not_quoted() {
+        assert_eq!(format_args(&json!(["hello", "world"])), "hello world");
+    }
+
+    #[test]
+    fn other_values_are_json() {
+        assert_eq!(
+            strip(&format_args(&json!(["count", 1, {"a": true}]))),
+            r#"count 1 {"a":true}"#
+        );
+    }
+
+    fn strip(s: &str) -> String {
+        console::strip_ansi_codes(s).into_owned()
+    }
+}
@@ -11,7 +11,23 @@
 
 #![allow(missing_docs)]
 
-use console::Emoji;
+use std::fmt;
+
+use super::theme;
+
+/// An emoji with a plain fallback, used when color is off or the output style isn't emoji.
+#[derive(Clone, Copy)]
+pub struct Emoji<'a, 'b>(pub &'a str, pub &'b str);
+
+impl fmt::Display for Emoji<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if theme::shows_emoji() {
+            fmt::Display::fmt(&console::Emoji(self.0, self.1), f)
+        } else {
+            f.write_str(self.1)
+        }
+    }
+}
 
 pub static DOWN: Emoji = Emoji("⬇️ ", "");
 pub static EAR: Emoji = Emoji("👂 ", "");
@@ -0,0 +1,132 @@
+//! Machine-readable progress events for GUI wrappers and editor extensions, written as one
+//! JSON object per line to the file descriptor given with `--progress-fd`.
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use chrono::Utc;
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+
+static SINK: OnceCell<Mutex<File>> = OnceCell::new();
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    CommandStarted {
+        command: &'a str,
+    },
+    CommandFinished {
+        command: &'a str,
+        success: bool,
+    },
+    BuildStarted {
+        script: &'a str,
+    },
+    BuildFinished {
+        script: &'a str,
+        success: bool,
+    },
+    UploadStarted {
+        script: &'a str,
+    },
+    UploadFinished {
+        script: &'a str,
+        success: bool,
+    },
+    /// Uploads of many items, e.g. the files of a site or the keys of a bulk put
+    UploadProgress {
+        label: &'a str,
+        unit: &'a str,
+        items: u64,
+        total_items: u64,
+        bytes: u64,
+        total_bytes: u64,
+        percent: u8,
+    },
+    PublishStarted {
+        script: &'a str,
+        environment: Option<&'a str>,
+    },
+    PublishFinished {
+        script: &'a str,
+        environment: Option<&'a str>,
+        success: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    /// RFC 3339
+    time: String,
+    #[serde(flatten)]
+    event: &'a Event<'a>,
+}
+
+/// Sends the events to the already open file descriptor `fd`, starting with `command_started`.
+#[cfg(unix)]
+pub fn open_fd(fd: i32, command: &str) -> Result<()> {
+    use std::os::unix::io::FromRawFd;
+
+    anyhow::ensure!(fd > 0, "--progress-fd can't be stdin");
+    // the descriptor stays open until the process exits
+    let mut file = unsafe { File::from_raw_fd(fd) };
+    writeln!(file, "{}", line(&Event::CommandStarted { command })?)
+        .map_err(|e| anyhow::anyhow!("Could not write progress events to fd {}: {}", fd, e))?;
+    let _ = SINK.set(Mutex::new(file));
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn open_fd(_fd: i32, _command: &str) -> Result<()> {
+    anyhow::bail!("--progress-fd is only supported on Unix")
+}
+
+/// Writes an event if `--progress-fd` was given. A reader that went away doesn't stop the
+/// command, so failures are only logged.
+pub fn emit(event: Event) {
+    let sink = match SINK.get() {
+        Some(sink) => sink,
+        None => return,
+    };
+    let result = line(&event).and_then(|line| {
+        let mut file = sink.lock().unwrap();
+        writeln!(file, "{}", line)?;
+        Ok(())
+    });
+    if let Err(e) = result {
+        log::debug!("could not write a progress event: {}", e);
+    }
+}
+
+fn line(event: &Event) -> Result<String> {
+    Ok(serde_json::to_string(&Line {
+        time: Utc::now().to_rfc3339(),
+        event,
+    })?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_tagged_json_lines() {
+        let line = line(&Event::UploadProgress {
+            label: "Uploading site files",
+            unit: "files",
+            items: 4,
+            total_items: 10,
+            bytes: 400,
+            total_bytes: 1000,
+            percent: 40,
+        })
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["event"], "upload_progress");
+        assert_eq!(value["percent"], 40);
+        assert!(value["time"].is_string());
+    }
+}
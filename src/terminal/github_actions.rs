@@ -0,0 +1,133 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use regex::Regex;
+
+use crate::error::{classify, ErrorCategory};
+
+/// Whether wrangler is running in a GitHub Actions workflow.
+pub fn enabled() -> bool {
+    env::var("GITHUB_ACTIONS").as_deref() == Ok("true")
+}
+
+/// The workflow command that shows a configuration or build error as an annotation, pointing
+/// at the line of the configuration file when the error names one.
+pub fn error_annotation(e: &anyhow::Error, config_path: &Path) -> Option<String> {
+    let message = console::strip_ansi_codes(&format!("{:#}", e)).into_owned();
+    let (category, _) = classify(e);
+    let title = match category {
+        ErrorCategory::Config => "Invalid configuration",
+        ErrorCategory::Build => "Build failed",
+        _ => return None,
+    };
+
+    let mut properties = format!("title={}", title);
+    if category == ErrorCategory::Config {
+        properties.push_str(&format!(
+            ",file={}",
+            escape_property(&config_path.to_string_lossy())
+        ));
+        let contents = fs::read_to_string(config_path).unwrap_or_default();
+        if let Some(line) = error_line(&message, &contents) {
+            properties.push_str(&format!(",line={}", line));
+        }
+    }
+    Some(format!("::error {}::{}", properties, escape_data(&message)))
+}
+
+// Parse errors say where they are, and unknown or invalid fields can be looked up by name
+fn error_line(message: &str, contents: &str) -> Option<usize> {
+    let position = Regex::new(r"at line (\d+)").unwrap();
+    if let Some(captures) = position.captures(message) {
+        return captures[1].parse().ok();
+    }
+
+    let field = Regex::new(r"(?:field|key) `([^`]+)`").unwrap();
+    let name = field.captures(message)?.get(1)?.as_str().to_string();
+    contents
+        .lines()
+        .position(|line| {
+            let line = line.trim_start();
+            let assigned = line.starts_with(&name)
+                && line[name.len()..]
+                    .trim_start()
+                    .starts_with(|c: char| c == '=' || c == ']');
+            let table = line
+                .trim_start_matches('[')
+                .starts_with(&format!("{}]", name));
+            assigned || table
+        })
+        .map(|index| index + 1)
+}
+
+fn escape_data(data: &str) -> String {
+    data.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(property: &str) -> String {
+    escape_data(property)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Sets the step outputs, e.g. the URL a worker was published to, for later steps of the
+/// workflow. Does nothing outside GitHub Actions.
+pub fn set_outputs(outputs: &[(&str, &str)]) -> Result<()> {
+    let path = match env::var_os("GITHUB_OUTPUT") {
+        Some(path) if enabled() => path,
+        _ => return Ok(()),
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for (name, value) in outputs {
+        file.write_all(output_line(name, value).as_bytes())?;
+    }
+    Ok(())
+}
+
+fn output_line(name: &str, value: &str) -> String {
+    if value.contains('\n') {
+        let delimiter = format!("ghadelimiter_{}", uuid::Uuid::new_v4().to_simple());
+        format!("{}<<{}\n{}\n{}\n", name, delimiter, value, delimiter)
+    } else {
+        format!("{}={}\n", name, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_found_in_the_config() {
+        let contents = "name = \"worker\"\ntype = \"javascript\"\nzone-id = \"abc\"\n[site]\n";
+        assert_eq!(
+            error_line("expected `=`, found newline at line 4 column 1", contents),
+            Some(4)
+        );
+        assert_eq!(
+            error_line("unknown field `zone-id`, expected one of ...", contents),
+            Some(3)
+        );
+        assert_eq!(
+            error_line("invalid type for field `site`", contents),
+            Some(4)
+        );
+        assert_eq!(error_line("something went wrong", contents), None);
+    }
+
+    #[test]
+    fn annotations_are_escaped() {
+        assert_eq!(escape_data("50% done\nnext"), "50%25 done%0Anext");
+        assert_eq!(escape_property("C:\\a,b"), "C%3A\\a%2Cb");
+        assert_eq!(
+            output_line("url", "https://a.workers.dev"),
+            "url=https://a.workers.dev\n"
+        );
+        assert!(output_line("urls", "a\nb").starts_with("urls<<ghadelimiter_"));
+    }
+}
@@ -1,28 +1,86 @@
 use anyhow::Result;
 use atty::Stream;
-use std::io::{self, Read};
+use std::io::{self, BufRead, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::message::{Message, StdOut};
+
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// With `--non-interactive`, wrangler never waits on a person: prompts fail with an error
+/// instead, and input is only ever read from a pipe.
+pub fn set_non_interactive(non_interactive: bool) {
+    NON_INTERACTIVE.store(non_interactive, Ordering::Relaxed);
+}
+
+/// Whether there's someone to answer prompts. This is never the case when stdin isn't a
+/// terminal, e.g. in CI, even without `--non-interactive`.
+pub fn is_interactive() -> bool {
+    !NON_INTERACTIVE.load(Ordering::Relaxed) && atty::is(Stream::Stdin)
+}
+
+/// With `--yes`, every confirmation is answered with yes without asking.
+pub fn set_assume_yes(assume_yes: bool) {
+    ASSUME_YES.store(assume_yes, Ordering::Relaxed);
+}
+
+pub fn assumes_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
 
 // For interactively handling reading in a string
-pub fn get_user_input(prompt_string: &str) -> String {
-    println!("{}", prompt_string);
+pub fn get_user_input(prompt_string: &str) -> Result<String> {
+    if !is_interactive() {
+        return read_piped_input(prompt_string, false).map(strip_trailing_whitespace);
+    }
+
+    StdOut::message(prompt_string);
     let mut input: String = read!("{}\n");
     input = strip_trailing_whitespace(input);
-    input
+    Ok(input)
+}
+
+pub fn get_user_input_multi_line(prompt_string: &str) -> Result<String> {
+    get_raw_user_input_multi_line(prompt_string).map(strip_trailing_whitespace)
 }
 
-pub fn get_user_input_multi_line(prompt_string: &str) -> String {
-    println!("{}", prompt_string);
+/// Like `get_user_input_multi_line`, but keeps the trailing whitespace, e.g. the newline `echo`
+/// adds, for callers that want to know it was there.
+pub fn get_raw_user_input_multi_line(prompt_string: &str) -> Result<String> {
     // are we reading from user input?
-    let mut input = if atty::is(Stream::Stdin) {
-        read!("{}\n")
+    if is_interactive() {
+        StdOut::message(prompt_string);
+        let input: String = read!("{}\n");
+        Ok(input)
     } else {
         // or is this data from a pipe? (support newlines)
-        let mut tmp = String::new();
-        let _ = io::stdin().read_to_string(&mut tmp);
-        tmp
-    };
-    input = strip_trailing_whitespace(input);
-    input
+        read_piped_input(prompt_string, true)
+    }
+}
+
+fn read_piped_input(prompt_string: &str, multi_line: bool) -> Result<String> {
+    if atty::is(Stream::Stdin) {
+        anyhow::bail!(
+            "\"{}\" needs an answer, but wrangler is running non-interactively. Pipe the value to stdin instead.",
+            prompt_string.trim_end_matches(|c: char| c == ':' || c.is_whitespace())
+        )
+    }
+
+    let mut input = String::new();
+    if multi_line {
+        io::stdin().read_to_string(&mut input)?;
+    } else {
+        io::stdin().lock().read_line(&mut input)?;
+    }
+
+    if input.trim_end().is_empty() {
+        anyhow::bail!(
+            "\"{}\" needs an answer, but nothing was piped to stdin.",
+            prompt_string.trim_end_matches(|c: char| c == ':' || c.is_whitespace())
+        )
+    }
+    Ok(input)
 }
 
 fn strip_trailing_whitespace(mut input: String) -> String {
@@ -37,10 +95,27 @@ const NO: &str = "n";
 // For interactively handling destructive commands (and discouraging accidental deletes).
 // Input like "yes", "Yes", "no", "No" will be accepted, thanks to the whitespace-stripping
 // and lowercasing logic below.
+// When stdin isn't a terminal the answer is read from it, so `yes | wrangler ...` works; only
+// `--non-interactive` refuses to ask.
 // TODO: loop this to retry until valid input is received.
 pub fn confirm(prompt_string: &str) -> Result<bool> {
-    println!("{} [y/n]", prompt_string);
-    let mut response: String = read!("{}\n");
+    if assumes_yes() {
+        StdOut::info(&format!("{} yes (--yes)", prompt_string));
+        return Ok(true);
+    }
+    if NON_INTERACTIVE.load(Ordering::Relaxed) {
+        anyhow::bail!(
+            "Can't ask \"{}\" because wrangler is running non-interactively. Pass --yes to confirm.",
+            prompt_string
+        )
+    }
+
+    StdOut::message(&format!("{} [y/n]", prompt_string));
+    let mut response: String = if atty::is(Stream::Stdin) {
+        read!("{}\n")
+    } else {
+        read_piped_input(prompt_string, false)?
+    };
     response = response.split_whitespace().collect(); // remove whitespace
     response.make_ascii_lowercase(); // ensure response is all lowercase
     response.truncate(INTERACTIVE_RESPONSE_LEN); // at this point, all valid input will be "y" or "n"
@@ -51,10 +126,57 @@ pub fn confirm(prompt_string: &str) -> Result<bool> {
     }
 }
 
+/// Asks the user to pick one of `options`, by number or by name. An empty answer picks
+/// `default`.
+pub fn select(prompt_string: &str, options: &[&str], default: usize) -> Result<usize> {
+    let mut prompt = prompt_string.to_string();
+    for (i, option) in options.iter().enumerate() {
+        let marker = if i == default { "*" } else { " " };
+        prompt.push_str(&format!("\n {} {}) {}", marker, i + 1, option));
+    }
+
+    let answer = get_user_input(&prompt)?;
+    match parse_choice(&answer, options, default) {
+        Some(choice) => Ok(choice),
+        None => anyhow::bail!(
+            "\"{}\" isn't one of the options, pick a number from 1 to {}",
+            answer,
+            options.len()
+        ),
+    }
+}
+
+fn parse_choice(answer: &str, options: &[&str], default: usize) -> Option<usize> {
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Some(default);
+    }
+    if let Ok(n) = answer.parse::<usize>() {
+        return if (1..=options.len()).contains(&n) {
+            Some(n - 1)
+        } else {
+            None
+        };
+    }
+    options
+        .iter()
+        .position(|option| option.to_lowercase().starts_with(&answer.to_lowercase()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn it_parses_choices() {
+        let options = ["JavaScript", "TypeScript", "Rust"];
+        assert_eq!(parse_choice("", &options, 1), Some(1));
+        assert_eq!(parse_choice("3", &options, 0), Some(2));
+        assert_eq!(parse_choice("typescript", &options, 0), Some(1));
+        assert_eq!(parse_choice("4", &options, 0), None);
+        assert_eq!(parse_choice("go", &options, 0), None);
+    }
+
     #[test]
     fn it_trims_user_input_right_whitespace_chars() {
         let test_str = "mysecret\r".to_string();
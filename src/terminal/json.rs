@@ -1,19 +1,39 @@
-use colored_json::{Color, ColoredFormatter, PrettyFormatter, Styler};
+use colored_json::{
+    Color, ColorMode, ColoredFormatter, CompactFormatter, Output, PrettyFormatter, Styler,
+};
+
+use super::color;
 
 pub fn colored_json_string(value: &serde_json::Value) -> Result<String, serde_json::Error> {
-    let formatter = ColoredFormatter::with_styler(
-        PrettyFormatter::new(),
-        Styler {
-            key: Color::Green.normal(),
-            string_value: Color::Blue.bold(),
-            integer_value: Color::Purple.bold(),
-            float_value: Color::Purple.italic(),
-            object_brackets: Color::Yellow.bold(),
-            array_brackets: Color::Cyan.bold(),
-            bool_value: Color::Red.bold(),
-            ..Default::default()
-        },
-    );
+    let formatter = ColoredFormatter::with_styler(PrettyFormatter::new(), styler());
+
+    formatter.to_colored_json(&value, color_mode())
+}
+
+/// Like `colored_json_string`, but on a single line.
+pub fn colored_json_compact(value: &serde_json::Value) -> Result<String, serde_json::Error> {
+    let formatter = ColoredFormatter::with_styler(CompactFormatter {}, styler());
+
+    formatter.to_colored_json(&value, color_mode())
+}
+
+fn color_mode() -> ColorMode {
+    if color::enabled() {
+        ColorMode::Auto(Output::StdOut)
+    } else {
+        ColorMode::Off
+    }
+}
 
-    formatter.to_colored_json_auto(&value)
+fn styler() -> Styler {
+    Styler {
+        key: Color::Green.normal(),
+        string_value: Color::Blue.bold(),
+        integer_value: Color::Purple.bold(),
+        float_value: Color::Purple.italic(),
+        object_brackets: Color::Yellow.bold(),
+        array_brackets: Color::Cyan.bold(),
+        bool_value: Color::Red.bold(),
+        ..Default::default()
+    }
 }
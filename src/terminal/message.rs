@@ -1,4 +1,7 @@
-use super::emoji;
+use super::color;
+use super::theme::{self, Kind, Style};
+
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use billboard::{Billboard, BorderColor, BorderStyle};
 use serde::Serialize;
@@ -8,41 +11,83 @@ pub enum Output {
     Json,
     PlainText,
 }
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Sets the output mode for the whole process. With `Output::Json`, stdout is reserved for
+/// the JSON results of a command, and every message meant for humans goes to stderr.
+pub fn set_output(output: Output) {
+    JSON_OUTPUT.store(output == Output::Json, Ordering::Relaxed);
+}
+
+pub fn output() -> Output {
+    if JSON_OUTPUT.load(Ordering::Relaxed) {
+        Output::Json
+    } else {
+        Output::PlainText
+    }
+}
+
+/// With `--quiet`, informational messages are dropped; warnings, errors and results are not.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
 pub trait Message {
     fn message(msg: &str);
 
     fn info(msg: &str) {
-        let msg = format!("{} {}", emoji::INFO, msg);
+        if is_quiet() {
+            return;
+        }
+        let msg = theme::decorate(Kind::Info, msg);
         Self::message(&msg);
     }
 
     fn warn(msg: &str) {
-        let msg = format!("{} {}", emoji::WARN, msg);
+        let msg = theme::decorate(Kind::Warn, msg);
         Self::message(&msg);
     }
 
     fn success(msg: &str) {
-        let msg = format!("{} {}", emoji::SPARKLES, msg);
+        if is_quiet() {
+            return;
+        }
+        let msg = theme::decorate(Kind::Success, msg);
         Self::message(&msg);
     }
 
     fn user_error(msg: &str) {
-        let msg = format!("{} {}", emoji::EYES, msg);
+        let msg = theme::decorate(Kind::UserError, msg);
         Self::message(&msg);
     }
 
     fn working(msg: &str) {
-        let msg = format!("{} {}", emoji::SWIRL, msg);
+        if is_quiet() {
+            return;
+        }
+        let msg = theme::decorate(Kind::Working, msg);
         Self::message(&msg);
     }
 
     fn preview(msg: &str) {
-        let msg = format!("{} {}", emoji::WORKER, msg);
+        if is_quiet() {
+            return;
+        }
+        let msg = theme::decorate(Kind::Preview, msg);
         Self::message(&msg);
     }
 
     fn help(msg: &str) {
-        let msg = format!("{} {}", emoji::SLEUTH, msg);
+        if is_quiet() {
+            return;
+        }
+        let msg = theme::decorate(Kind::Help, msg);
         Self::message(&msg);
     }
 
@@ -58,10 +103,23 @@ pub struct StdOut;
 
 impl Message for StdOut {
     fn message(msg: &str) {
-        println!("{}", msg);
+        if output() == Output::Json {
+            eprintln!("{}", msg);
+        } else {
+            println!("{}", msg);
+        }
     }
 
     fn billboard(msg: &str) {
+        if output() == Output::Json {
+            eprintln!("{}", msg);
+            return;
+        }
+        if !color::enabled() || theme::style() != Style::Emoji {
+            println!("{}", msg);
+            return;
+        }
+
         let billboard = Billboard::builder()
             .border_style(BorderStyle::Round)
             .border_color(BorderColor::Cyan)
@@ -71,6 +129,15 @@ impl Message for StdOut {
     }
 
     fn deprecation_warning(msg: &str) {
+        if output() == Output::Json {
+            eprintln!("{}", msg);
+            return;
+        }
+        if !color::enabled() || theme::style() != Style::Emoji {
+            println!("{}", msg);
+            return;
+        }
+
         let bb = Billboard::builder()
             .border_style(BorderStyle::Round)
             .border_color(BorderColor::Red)
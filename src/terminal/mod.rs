@@ -1,8 +1,17 @@
 mod browser;
+pub mod color;
+pub mod console;
 pub mod emoji;
+pub mod events;
+pub mod github_actions;
 pub mod interactive;
 mod json;
 pub mod message;
+pub mod progress;
+mod share;
 pub mod styles;
+pub mod table;
+pub mod theme;
 pub use browser::open_browser;
-pub use json::colored_json_string;
+pub use json::{colored_json_compact, colored_json_string};
+pub use share::share_url;
@@ -0,0 +1,163 @@
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use atty::Stream;
+use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
+
+use super::events::{self, Event};
+use super::message::{self, Message, StdErr};
+
+/// How often progress is logged when stderr isn't a terminal.
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Reports the progress of an upload in items (files, keys) and bytes. On a terminal this is
+/// a progress bar; otherwise, e.g. in CI logs, a line is printed every few seconds instead.
+/// With `--progress-fd`, each whole percent is also sent as an `upload_progress` event.
+pub struct Progress {
+    label: String,
+    unit: &'static str,
+    total_items: u64,
+    total_bytes: u64,
+    bar: Option<ProgressBar>,
+    state: Mutex<State>,
+}
+
+struct State {
+    items: u64,
+    bytes: u64,
+    last_logged: Instant,
+    last_percent: Option<u8>,
+}
+
+impl Progress {
+    pub fn new(label: &str, unit: &'static str, total_items: u64, total_bytes: u64) -> Progress {
+        let bar = if atty::is(Stream::Stderr) && !message::is_quiet() {
+            let bar = ProgressBar::new(total_items);
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template(&format!("{{wide_bar}} {{pos}}/{{len}} {} {{msg}}", unit)),
+            );
+            Some(bar)
+        } else {
+            None
+        };
+
+        let progress = Progress {
+            label: label.to_string(),
+            unit,
+            total_items,
+            total_bytes,
+            bar,
+            state: Mutex::new(State {
+                items: 0,
+                bytes: 0,
+                last_logged: Instant::now(),
+                last_percent: None,
+            }),
+        };
+        progress.update(0, 0);
+        progress
+    }
+
+    pub fn inc(&self, items: u64, bytes: u64) {
+        self.update(items, bytes);
+    }
+
+    pub fn finish(&self) {
+        let state = self.state.lock().unwrap();
+        match &self.bar {
+            Some(bar) => bar.finish_and_clear(),
+            None if message::is_quiet() => {}
+            None => StdErr::message(&self.line(&state)),
+        }
+    }
+
+    fn update(&self, items: u64, bytes: u64) {
+        let mut state = self.state.lock().unwrap();
+        state.items += items;
+        state.bytes += bytes;
+        self.emit(&mut state);
+
+        match &self.bar {
+            Some(bar) => {
+                bar.set_position(state.items);
+                bar.set_message(&self.bytes(&state));
+            }
+            None if message::is_quiet() => {}
+            None if state.items == 0 || state.last_logged.elapsed() >= LOG_INTERVAL => {
+                state.last_logged = Instant::now();
+                StdErr::message(&self.line(&state));
+            }
+            None => {}
+        }
+    }
+
+    fn emit(&self, state: &mut State) {
+        let percent = match (self.total_bytes, self.total_items) {
+            (0, 0) => 100,
+            (0, total) => state.items * 100 / total,
+            (total, _) => state.bytes * 100 / total,
+        }
+        .min(100) as u8;
+        if state.last_percent == Some(percent) {
+            return;
+        }
+        state.last_percent = Some(percent);
+        events::emit(Event::UploadProgress {
+            label: &self.label,
+            unit: self.unit,
+            items: state.items,
+            total_items: self.total_items,
+            bytes: state.bytes,
+            total_bytes: self.total_bytes,
+            percent,
+        });
+    }
+
+    fn line(&self, state: &State) -> String {
+        format!(
+            "{}: {}/{} {} {}",
+            self.label,
+            state.items,
+            self.total_items,
+            self.unit,
+            self.bytes(state)
+        )
+        .trim_end()
+        .to_string()
+    }
+
+    fn bytes(&self, state: &State) -> String {
+        if self.total_bytes == 0 {
+            String::new()
+        } else {
+            format!(
+                "({} / {})",
+                HumanBytes(state.bytes),
+                HumanBytes(self.total_bytes)
+            )
+        }
+    }
+}
+
+/// Counts the bytes read from `inner` as progress, for bodies that are sent as they're read,
+/// e.g. the multipart form of a script.
+pub struct ProgressReader<R> {
+    inner: R,
+    progress: Arc<Progress>,
+}
+
+impl<R> ProgressReader<R> {
+    pub fn new(inner: R, progress: Arc<Progress>) -> ProgressReader<R> {
+        ProgressReader { inner, progress }
+    }
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.progress.inc(0, read as u64);
+        Ok(read)
+    }
+}
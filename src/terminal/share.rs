@@ -0,0 +1,75 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+
+use crate::terminal::message::{Message, StdErr, StdOut};
+
+/// Prints `url` as a QR code and/or copies it to the clipboard. Neither is worth failing the
+/// command over, so problems are only warned about.
+pub fn share_url(url: &str, qr: bool, copy: bool) {
+    if qr {
+        match qr_code(url) {
+            Ok(code) => {
+                StdOut::info(&format!("Scan to open {}", url));
+                println!("{}", code);
+            }
+            Err(e) => StdErr::warn(&format!("Could not draw a QR code: {}", e)),
+        }
+    }
+    if copy {
+        match copy_to_clipboard(url) {
+            Ok(()) => StdOut::info(&format!("Copied {} to the clipboard", url)),
+            Err(e) => StdErr::warn(&format!("Could not copy to the clipboard: {}", e)),
+        }
+    }
+}
+
+fn qr_code(url: &str) -> Result<String> {
+    let code = QrCode::new(url.as_bytes()).map_err(|e| anyhow!("{}", e))?;
+    // terminals are mostly dark, so light modules on a dark background scan best
+    Ok(code
+        .render::<Dense1x2>()
+        .dark_color(Dense1x2::Light)
+        .light_color(Dense1x2::Dark)
+        .build())
+}
+
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    let candidates: &[(&str, &[&str])] = if cfg!(target_os = "windows") {
+        &[("clip", &[])]
+    } else if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+
+    for (program, args) in candidates {
+        let mut child = match Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+        if let Some(stdin) = child.stdin.as_mut() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        drop(child.stdin.take());
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    let programs: Vec<&str> = candidates.iter().map(|(program, _)| *program).collect();
+    anyhow::bail!("none of {} worked", programs.join(", "))
+}
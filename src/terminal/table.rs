@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::Result;
+use atty::Stream;
+use prettytable::{Cell, Row};
+use serde_json::{Map, Value};
+use structopt::StructOpt;
+
+use super::message::{self, Output};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// A table on a terminal, and JSON otherwise so scripts keep working.
+    Auto,
+    /// A table on a terminal, and tab-separated values when piped.
+    Table,
+    Tsv,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Format::Auto),
+            "table" => Ok(Format::Table),
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            _ => anyhow::bail!("{} isn't a format, use one of auto, table, tsv or json", s),
+        }
+    }
+}
+
+/// Flags shared by every command that lists resources.
+#[derive(Clone, Debug, StructOpt)]
+pub struct TableOptions {
+    /// Output format: auto, table, tsv or json
+    #[structopt(long, default_value = "auto")]
+    pub format: Format,
+
+    /// Sort by a column, prefix it with - to sort in descending order
+    #[structopt(long, allow_hyphen_values = true)]
+    pub sort: Option<String>,
+
+    /// Only print this many rows
+    #[structopt(long)]
+    pub limit: Option<usize>,
+
+    /// Comma-separated list of the columns to print
+    #[structopt(long, use_delimiter = true)]
+    pub columns: Option<Vec<String>>,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions {
+            format: Format::Auto,
+            sort: None,
+            limit: None,
+            columns: None,
+        }
+    }
+}
+
+/// A list of resources, each a JSON object. Tables and TSV show `columns`, picked from the
+/// fields of each object; JSON output keeps the objects whole unless `--columns` is given.
+pub struct Table {
+    columns: Vec<String>,
+    rows: Vec<Value>,
+}
+
+impl Table {
+    pub fn new(columns: &[&str], rows: Vec<Value>) -> Table {
+        Table {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows,
+        }
+    }
+
+    /// Builds the table from anything that serializes to a list of objects.
+    pub fn from_serialize<T: serde::Serialize>(columns: &[&str], rows: &[T]) -> Result<Table> {
+        let rows = rows
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Table::new(columns, rows))
+    }
+
+    pub fn print(self, options: &TableOptions) -> Result<()> {
+        let format = resolve(options.format, atty::is(Stream::Stdout));
+        let select_columns = options.columns.is_some();
+        let table = self.prepare(options)?;
+
+        match format {
+            Format::Json => println!("{}", table.to_json(select_columns)),
+            Format::Tsv => print!("{}", table.to_tsv()),
+            _ => {
+                table.to_pretty().printstd();
+            }
+        }
+        Ok(())
+    }
+
+    fn prepare(mut self, options: &TableOptions) -> Result<Table> {
+        if let Some(columns) = &options.columns {
+            self.columns = columns.iter().map(|c| c.trim().to_string()).collect();
+        }
+
+        if let Some(sort) = &options.sort {
+            let (column, descending) = match sort.strip_prefix('-') {
+                Some(column) => (column, true),
+                None => (sort.as_str(), false),
+            };
+            if !self.rows.iter().any(|row| row.get(column).is_some()) && !self.rows.is_empty() {
+                anyhow::bail!("Can't sort by {}, there's no such column", column);
+            }
+            self.rows.sort_by(|a, b| {
+                let ordering = compare(a.get(column), b.get(column));
+                if descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+
+        if let Some(limit) = options.limit {
+            self.rows.truncate(limit);
+        }
+        Ok(self)
+    }
+
+    fn to_json(&self, select_columns: bool) -> Value {
+        if !select_columns {
+            return Value::Array(self.rows.clone());
+        }
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut selected = Map::new();
+                for column in &self.columns {
+                    selected.insert(column.clone(), row.get(column).cloned().unwrap_or_default());
+                }
+                Value::Object(selected)
+            })
+            .collect();
+        Value::Array(rows)
+    }
+
+    fn to_tsv(&self) -> String {
+        let mut tsv = self.columns.join("\t");
+        tsv.push('\n');
+        for row in &self.rows {
+            let cells = self
+                .columns
+                .iter()
+                .map(|column| cell(row.get(column)).replace(|c| c == '\t' || c == '\n', " "))
+                .collect::<Vec<_>>();
+            tsv.push_str(&cells.join("\t"));
+            tsv.push('\n');
+        }
+        tsv
+    }
+
+    fn to_pretty(&self) -> prettytable::Table {
+        let mut table = prettytable::Table::new();
+        table.set_titles(Row::new(
+            self.columns
+                .iter()
+                .map(|column| Cell::new(column))
+                .collect(),
+        ));
+        for row in &self.rows {
+            table.add_row(Row::new(
+                self.columns
+                    .iter()
+                    .map(|column| Cell::new(&cell(row.get(column))))
+                    .collect(),
+            ));
+        }
+        table
+    }
+}
+
+fn resolve(format: Format, is_terminal: bool) -> Format {
+    match format {
+        _ if message::output() == Output::Json => Format::Json,
+        Format::Auto if is_terminal => Format::Table,
+        Format::Auto => Format::Json,
+        Format::Table if !is_terminal => Format::Tsv,
+        format => format,
+    }
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| cell(Some(value)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Some(value) => value.to_string(),
+    }
+}
+
+// Numbers sort numerically, everything else by its text; missing values go last
+fn compare(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a, b) {
+        (Some(Value::Number(a)), Some(Value::Number(b))) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (None, None) | (Some(Value::Null), Some(Value::Null)) => Ordering::Equal,
+        (None, _) | (Some(Value::Null), _) => Ordering::Greater,
+        (_, None) | (_, Some(Value::Null)) => Ordering::Less,
+        (a, b) => cell(a).cmp(&cell(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn table() -> Table {
+        Table::new(
+            &["name", "size"],
+            vec![
+                json!({ "name": "b", "size": 10, "extra": true }),
+                json!({ "name": "a", "size": 9 }),
+                json!({ "name": "c" }),
+            ],
+        )
+    }
+
+    fn options(sort: Option<&str>, limit: Option<usize>) -> TableOptions {
+        TableOptions {
+            sort: sort.map(str::to_string),
+            limit,
+            ..TableOptions::default()
+        }
+    }
+
+    #[test]
+    fn it_sorts_and_limits() {
+        let table = table().prepare(&options(Some("-size"), Some(2))).unwrap();
+        assert_eq!(table.to_tsv(), "name\tsize\nb\t10\na\t9\n");
+    }
+
+    #[test]
+    fn it_refuses_to_sort_by_an_unknown_column() {
+        assert!(table().prepare(&options(Some("nope"), None)).is_err());
+    }
+
+    #[test]
+    fn json_keeps_whole_objects_unless_columns_are_selected() {
+        let table = table();
+        assert_eq!(table.to_json(false)[0]["extra"], json!(true));
+        assert_eq!(table.to_json(true)[0], json!({ "name": "b", "size": 10 }));
+    }
+
+    #[test]
+    fn lists_are_comma_separated() {
+        assert_eq!(
+            cell(Some(&json!(["a.com/*", "b.com/*"]))),
+            "a.com/*, b.com/*"
+        );
+        assert_eq!(cell(Some(&json!([]))), "");
+    }
+
+    #[test]
+    fn piped_tables_are_tsv() {
+        assert_eq!(resolve(Format::Table, false), Format::Tsv);
+        assert_eq!(resolve(Format::Auto, false), Format::Json);
+        assert_eq!(resolve(Format::Auto, true), Format::Table);
+    }
+}
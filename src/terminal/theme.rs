@@ -0,0 +1,97 @@
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+
+use super::{color, emoji};
+
+/// How messages are decorated, from `[output] style` in the global config.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    /// An emoji in front of each message, the default
+    Emoji,
+    /// A text label in front of each message, e.g. `[warn]`, for CI logs and screen readers
+    Plain,
+    /// The messages alone
+    Minimal,
+}
+
+/// The kinds of messages of `terminal::message::Message`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Info,
+    Warn,
+    Success,
+    UserError,
+    Working,
+    Preview,
+    Help,
+}
+
+// Resolved once at startup, so messages don't read the global config
+static STYLE: OnceCell<Style> = OnceCell::new();
+
+/// Sets the style for the rest of the run. Only the first call has an effect.
+pub fn set_style(style: Style) {
+    let _ = STYLE.set(style);
+}
+
+pub fn style() -> Style {
+    STYLE.get().copied().unwrap_or(Style::Emoji)
+}
+
+/// Whether emoji are shown at all: only with the emoji style, and when color isn't off.
+pub fn shows_emoji() -> bool {
+    style() == Style::Emoji && color::enabled()
+}
+
+pub fn decorate(kind: Kind, msg: &str) -> String {
+    decorate_with(style(), kind, msg)
+}
+
+fn decorate_with(style: Style, kind: Kind, msg: &str) -> String {
+    match style {
+        Style::Emoji => {
+            let emoji = match kind {
+                Kind::Info => emoji::INFO,
+                Kind::Warn => emoji::WARN,
+                Kind::Success => emoji::SPARKLES,
+                Kind::UserError => emoji::EYES,
+                Kind::Working => emoji::SWIRL,
+                Kind::Preview => emoji::WORKER,
+                Kind::Help => emoji::SLEUTH,
+            };
+            format!("{} {}", emoji, msg)
+        }
+        Style::Plain => {
+            let label = match kind {
+                Kind::Info => "info",
+                Kind::Warn => "warn",
+                Kind::Success => "ok",
+                Kind::UserError => "error",
+                Kind::Working => "working",
+                Kind::Preview => "preview",
+                Kind::Help => "help",
+            };
+            format!("[{}] {}", label, msg)
+        }
+        Style::Minimal => msg.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn styles_decorate_messages() {
+        assert_eq!(
+            decorate_with(Style::Plain, Kind::Warn, "No routes"),
+            "[warn] No routes"
+        );
+        assert_eq!(
+            decorate_with(Style::Minimal, Kind::Warn, "No routes"),
+            "No routes"
+        );
+        assert!(decorate_with(Style::Emoji, Kind::Warn, "No routes").ends_with(" No routes"));
+    }
+}
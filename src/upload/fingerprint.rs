@@ -0,0 +1,235 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use twox_hash::XxHash64;
+
+use crate::build::hooks::BuildArtifacts;
+use crate::http;
+use crate::settings::get_wrangler_home_dir;
+use crate::settings::global_user::GlobalUser;
+use crate::settings::toml::{Target, TargetType};
+use crate::sites::AssetManifest;
+
+/// The script, modules and settings that go into an upload. Two uploads with the same
+/// fingerprint would deploy the same worker.
+#[derive(Serialize)]
+struct Inputs<'a> {
+    wrangler_version: &'static str,
+    target_type: &'a TargetType,
+    upload: Option<serde_json::Value>,
+    bundles: Vec<(String, String)>,
+    files: BTreeMap<String, String>,
+    kv_namespaces: Vec<serde_json::Value>,
+    // skipped when empty so the fingerprints of workers without buckets stay the same
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    r2_buckets: Vec<serde_json::Value>,
+    durable_objects: Option<serde_json::Value>,
+    migrations: Option<serde_json::Value>,
+    vars: Option<BTreeMap<&'a String, &'a String>>,
+    usage_model: Option<serde_json::Value>,
+    compatibility_date: &'a Option<String>,
+    compatibility_flags: &'a [String],
+    asset_manifest: Option<BTreeMap<&'a String, &'a String>>,
+}
+
+/// The last upload of each script, kept in `~/.wrangler/published.toml`.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Published {
+    #[serde(default)]
+    scripts: BTreeMap<String, Upload>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Upload {
+    pub fingerprint: String,
+    pub etag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScriptsResponse {
+    result: Vec<Script>,
+}
+
+#[derive(Deserialize)]
+struct Script {
+    id: String,
+    etag: Option<String>,
+}
+
+/// Fingerprints what `upload::script` would send for `target`.
+pub fn compute(target: &Target, asset_manifest: Option<&AssetManifest>) -> Result<String> {
+    let artifacts = BuildArtifacts::collect(target)?;
+    let bundles = artifacts
+        .bundles
+        .into_iter()
+        .map(|bundle| (bundle.name, bundle.hash))
+        .collect();
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for files in [&target.wasm_modules, &target.text_blobs].iter() {
+        if let Some(files) = files {
+            paths.extend(files.values().cloned());
+        }
+    }
+    if target.target_type == TargetType::Rust {
+        paths.extend(files_in(Path::new("./pkg")));
+    }
+    let mut files = BTreeMap::new();
+    for path in paths {
+        files.insert(path.display().to_string(), digest(&fs::read(&path)?));
+    }
+
+    let inputs = Inputs {
+        wrangler_version: env!("CARGO_PKG_VERSION"),
+        target_type: &target.target_type,
+        upload: target
+            .build
+            .as_ref()
+            .map(|build| serde_json::to_value(&build.upload))
+            .transpose()?,
+        bundles,
+        files,
+        kv_namespaces: target
+            .kv_namespaces
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?,
+        r2_buckets: target
+            .r2_buckets
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?,
+        durable_objects: target
+            .durable_objects
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?,
+        migrations: target
+            .migrations
+            .as_ref()
+            .map(serde_json::to_value)
+            .transpose()?,
+        vars: target.vars.as_ref().map(sorted),
+        usage_model: target
+            .usage_model
+            .map(|model| serde_json::to_value(&model))
+            .transpose()?,
+        compatibility_date: &target.compatibility_date,
+        compatibility_flags: &target.compatibility_flags,
+        asset_manifest: asset_manifest.map(sorted),
+    };
+
+    Ok(digest(serde_json::to_string(&inputs)?.as_bytes()))
+}
+
+/// What was recorded for the last upload of `target`, if it's still the version that's
+/// deployed. A script changed from anywhere else (the dashboard, another machine) has a new
+/// etag, so it doesn't count as unchanged.
+pub fn last_upload(target: &Target, user: &GlobalUser) -> Option<Upload> {
+    let account_id = target.account_id.load().ok()?;
+    let upload = load().scripts.remove(&key(account_id, &target.name))?;
+
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts",
+        account_id
+    );
+    let client = http::legacy_auth_client(user);
+    let response = http::trace::send(&client, client.get(&addr)).ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let deployed = response
+        .json::<ScriptsResponse>()
+        .ok()?
+        .result
+        .into_iter()
+        .find(|script| script.id == target.name)?;
+
+    if deployed.etag.is_some() && deployed.etag == upload.etag {
+        Some(upload)
+    } else {
+        None
+    }
+}
+
+/// Records a successful upload so the next `--skip-unchanged` publish can compare against it.
+pub fn save(target: &Target, upload: Upload) -> Result<()> {
+    let account_id = target.account_id.load()?;
+    let mut published = load();
+    published
+        .scripts
+        .insert(key(account_id, &target.name), upload);
+
+    let path = published_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string(&published)?)?;
+    Ok(())
+}
+
+fn load() -> Published {
+    fs::read_to_string(published_path())
+        .ok()
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn published_path() -> PathBuf {
+    get_wrangler_home_dir().join("published.toml")
+}
+
+fn key(account_id: &str, script_name: &str) -> String {
+    format!("{}/{}", account_id, script_name)
+}
+
+fn sorted(map: &HashMap<String, String>) -> BTreeMap<&String, &String> {
+    map.iter().collect()
+}
+
+fn files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                files.extend(files_in(&path));
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+fn digest(contents: &[u8]) -> String {
+    let mut hasher = XxHash64::default();
+    hasher.write(contents);
+    format!("{:x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn published_scripts_round_trip() {
+        let mut published = Published::default();
+        published.scripts.insert(
+            key("abc", "my-worker"),
+            Upload {
+                fingerprint: "f00".to_string(),
+                etag: Some("e7a9".to_string()),
+            },
+        );
+        let parsed: Published = toml::from_str(&toml::to_string(&published).unwrap()).unwrap();
+        assert_eq!(
+            parsed.scripts.get("abc/my-worker").unwrap().fingerprint,
+            "f00"
+        );
+    }
+}
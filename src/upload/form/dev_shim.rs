@@ -0,0 +1,529 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use once_cell::sync::OnceCell;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::settings::toml::{CacheEmulation, DevCache, Mock};
+
+/// The name of the module that wraps the main module of a modules worker.
+pub const SHIM_MODULE: &str = "__wrangler_dev_shim.mjs";
+
+/// The path that empties the emulated cache of a dev session.
+pub const CACHE_PURGE_PATH: &str = "/__cache/purge";
+
+/// The path that moves the stopped clock of a dev session forward by `?seconds=<n>`.
+pub const TIME_ADVANCE_PATH: &str = "/__time/advance";
+
+/// The path that fires the alarm of a Durable Object. `<id>` is the object's hex ID, or the
+/// name its ID is made from.
+pub const ALARM_PATH: &str = "/__do/<class>/<id>/alarm";
+
+// Matches `ALARM_PATH`, capturing the class and the ID
+const ALARM_ROUTE: &str = "^/__do/([^/]+)/([^/]+)/alarm$";
+
+// What the worker asks an object to fire its alarm with
+const OBJECT_ALARM_PATH: &str = "/__wrangler/alarm";
+
+static SHIM: OnceCell<DevShim> = OnceCell::new();
+
+/// What `wrangler dev` changes about the worker of its session. The edge sets `request.cf`,
+/// `CF-Connecting-IP` and `caches` itself, so a shim around the worker replaces them before
+/// its own code runs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DevShim {
+    /// Fields of `request.cf` to replace, e.g. `country` or `botManagement`.
+    pub cf: Map<String, Value>,
+    /// The `CF-Connecting-IP` header.
+    pub connecting_ip: Option<String>,
+    /// How `caches` behaves in place of the edge's cache.
+    pub cache: Option<DevCache>,
+    /// The responses outbound `fetch` calls get instead, most specific pattern first.
+    pub mocks: Vec<MockResponse>,
+    /// The time the worker's clock is stopped at until it's advanced. Every isolate of the
+    /// session keeps its own clock.
+    pub fake_time: Option<DateTime<Utc>>,
+    /// The Durable Object classes the worker exports, and the binding that reaches each. Their
+    /// alarms are logged when set and fired, and can be fired through `ALARM_PATH`.
+    pub alarms: BTreeMap<String, String>,
+}
+
+impl DevShim {
+    pub fn is_empty(&self) -> bool {
+        self.cf.is_empty()
+            && self.connecting_ip.is_none()
+            && self.cache.is_none()
+            && self.mocks.is_empty()
+            && self.fake_time.is_none()
+            && self.alarms.is_empty()
+    }
+
+    /// Wraps the scripts uploaded for the rest of the run in the shim. Only dev sessions should
+    /// ever set this, never a publish.
+    pub fn apply_to_uploads(self) {
+        if !self.is_empty() {
+            let _ = SHIM.set(self);
+        }
+    }
+
+    // Defines `__wranglerRequest`, which replaces the request before the worker sees it, and
+    // `__wranglerIntercept`, which answers the requests meant for wrangler itself
+    fn prelude(&self) -> String {
+        let config = json!({
+            "cf": self.cf,
+            "connectingIp": self.connecting_ip,
+            "cache": self.cache.as_ref().map(|cache| json!({
+                "disabled": cache.mode == CacheEmulation::Disabled,
+                "maxSize": cache.max_size(),
+            })),
+            "purgePath": CACHE_PURGE_PATH,
+            "mocks": self.mocks,
+            "fakeTime": self.fake_time.map(|time| time.timestamp_millis()),
+            "advancePath": TIME_ADVANCE_PATH,
+        });
+        format!(
+            r#"const __wranglerConfig = {};
+function __wranglerRequest(request) {{
+  const headers = new Headers(request.headers);
+  if (__wranglerConfig.connectingIp) {{
+    headers.set("CF-Connecting-IP", __wranglerConfig.connectingIp);
+  }}
+  return new Request(request, {{ headers, cf: {{ ...request.cf, ...__wranglerConfig.cf }} }});
+}}
+const __wranglerCaches = (() => {{
+  const config = __wranglerConfig.cache;
+  if (!config) {{
+    return undefined;
+  }}
+  const entries = new Map();
+  let size = 0;
+  const keyOf = (name, request) => name + " " + (typeof request === "string" ? request : request.url);
+  const remove = (key) => {{
+    const entry = entries.get(key);
+    if (entry) {{
+      size -= entry.body.byteLength;
+      entries.delete(key);
+    }}
+    return Boolean(entry);
+  }};
+  const open = (name) => ({{
+    async match(request) {{
+      const entry = config.disabled ? undefined : entries.get(keyOf(name, request));
+      return entry && new Response(entry.body, entry.init);
+    }},
+    async put(request, response) {{
+      const key = keyOf(name, request);
+      const body = await response.arrayBuffer();
+      if (config.disabled || body.byteLength > config.maxSize || /no-store/.test(response.headers.get("Cache-Control"))) {{
+        return;
+      }}
+      remove(key);
+      // Oldest first, like the map's own order
+      for (const oldest of entries.keys()) {{
+        if (size + body.byteLength <= config.maxSize) {{
+          break;
+        }}
+        remove(oldest);
+      }}
+      const init = {{ status: response.status, statusText: response.statusText, headers: [...response.headers] }};
+      entries.set(key, {{ body, init }});
+      size += body.byteLength;
+    }},
+    async delete(request) {{
+      return remove(keyOf(name, request));
+    }},
+  }});
+  const caches = {{ default: open(""), open: async (name) => open(name) }};
+  Object.defineProperty(globalThis, "caches", {{ value: caches }});
+  return {{
+    purge() {{
+      const purged = entries.size;
+      entries.clear();
+      size = 0;
+      return purged;
+    }},
+  }};
+}})();
+if (__wranglerConfig.mocks.length > 0) {{
+  const escape = (part) => part.replace(/[.+?^${{}}()|[\]\\]/g, "\\$&");
+  const mocks = __wranglerConfig.mocks.map((mock) => ({{
+    ...mock,
+    regex: new RegExp("^" + mock.pattern.split("*").map(escape).join(".*") + "$"),
+  }}));
+  const fetch_ = globalThis.fetch;
+  globalThis.fetch = function (input, init) {{
+    const url = input instanceof Request ? input.url : String(input);
+    const mock = mocks.find((mock) => mock.regex.test(url));
+    if (!mock) {{
+      return fetch_.call(this, input, init);
+    }}
+    const body = mock.body === null ? null : Uint8Array.from(atob(mock.body), (c) => c.charCodeAt(0));
+    const headers = mock.contentType ? {{ "Content-Type": mock.contentType }} : {{}};
+    return Promise.resolve(new Response(body, {{ status: mock.status, headers }}));
+  }};
+}}
+const __wranglerClock = (() => {{
+  if (__wranglerConfig.fakeTime === null) {{
+    return undefined;
+  }}
+  const RealDate = Date;
+  let now = __wranglerConfig.fakeTime;
+  class FakeDate extends RealDate {{
+    constructor(...args) {{
+      if (args.length === 0) {{
+        super(now);
+      }} else {{
+        super(...args);
+      }}
+    }}
+    static now() {{
+      return now;
+    }}
+  }}
+  globalThis.Date = FakeDate;
+  return {{
+    advance(seconds) {{
+      now += seconds * 1000;
+      return new RealDate(now).toISOString();
+    }},
+  }};
+}})();
+function __wranglerIntercept(request) {{
+  const url = new URL(request.url);
+  const json = (body) => new Response(JSON.stringify(body), {{ headers: {{ "Content-Type": "application/json" }} }});
+  if (__wranglerCaches && url.pathname === __wranglerConfig.purgePath) {{
+    return json({{ purged: __wranglerCaches.purge() }});
+  }}
+  if (__wranglerClock && url.pathname === __wranglerConfig.advancePath) {{
+    const seconds = Number(url.searchParams.get("seconds"));
+    if (!Number.isFinite(seconds)) {{
+      return new Response("seconds must be a number", {{ status: 400 }});
+    }}
+    return json({{ now: __wranglerClock.advance(seconds) }});
+  }}
+  return undefined;
+}}
+"#,
+            config
+        )
+    }
+
+    /// The shim to put in front of a service worker script: fetch listeners get the replaced
+    /// request.
+    pub fn service_worker_shim(&self) -> String {
+        format!(
+            r#"{}{{
+  const addEventListener_ = globalThis.addEventListener;
+  globalThis.addEventListener = function (type, listener, options) {{
+    if (type !== "fetch") {{
+      return addEventListener_.call(this, type, listener, options);
+    }}
+    return addEventListener_.call(this, type, (event) => {{
+      const response = __wranglerIntercept(event.request);
+      if (response) {{
+        return event.respondWith(response);
+      }}
+      Object.defineProperty(event, "request", {{ value: __wranglerRequest(event.request) }});
+      return listener(event);
+    }}, options);
+  }};
+}}
+"#,
+            self.prelude()
+        )
+    }
+
+    /// The module that becomes the main module of a modules worker in place of `main`, passing
+    /// the replaced request to its fetch handler. Durable Object classes with alarms are
+    /// wrapped to surface them, its other exports are exported as they are.
+    pub fn modules_shim(&self, main: &str) -> String {
+        let classes: String = self
+            .alarms
+            .keys()
+            .map(|class| {
+                format!(
+                    "export const {class} = __wranglerAlarms(__wranglerExports.{class}, \"{class}\");\n",
+                    class = class
+                )
+            })
+            .collect();
+        format!(
+            r#"import worker from "./{main}";
+import * as __wranglerExports from "./{main}";
+export * from "./{main}";
+{}{}{}
+export default {{
+  ...worker,
+  fetch(request, env, ctx) {{
+    return __wranglerIntercept(request)
+      || __wranglerFireAlarm(request, env)
+      || worker.fetch(__wranglerRequest(request), env, ctx);
+  }},
+}};
+"#,
+            self.prelude(),
+            self.alarms_prelude(),
+            classes,
+            main = main
+        )
+    }
+
+    // Defines `__wranglerAlarms`, which wraps a Durable Object class so it logs its alarms and
+    // fires one when asked to, and `__wranglerFireAlarm`, which asks an object to for requests
+    // to `ALARM_PATH`. The edge still schedules and fires alarms set the usual way.
+    fn alarms_prelude(&self) -> String {
+        let config = json!({
+            "bindings": self.alarms,
+            "route": ALARM_ROUTE,
+            "objectPath": OBJECT_ALARM_PATH,
+        });
+        format!(
+            r#"const __wranglerAlarmConfig = {};
+const __wranglerBound = (target, value) => (typeof value === "function" ? value.bind(target) : value);
+function __wranglerAlarms(Base, className) {{
+  if (typeof Base !== "function") {{
+    return Base;
+  }}
+  const Wrapped = class extends Base {{
+    constructor(state, env) {{
+      const name = () => `${{className}} ${{state.id}}`;
+      const storage = new Proxy(state.storage, {{
+        get(target, prop) {{
+          if (prop !== "setAlarm") {{
+            return __wranglerBound(target, Reflect.get(target, prop));
+          }}
+          return (time, options) => {{
+            console.log(`[wrangler] ${{name()}} set an alarm for ${{new Date(time).toISOString()}}`);
+            return target.setAlarm(time, options);
+          }};
+        }},
+      }});
+      super(new Proxy(state, {{
+        get(target, prop) {{
+          return prop === "storage" ? storage : __wranglerBound(target, Reflect.get(target, prop));
+        }},
+      }}), env);
+      this.__wranglerName = name;
+      this.__wranglerStorage = storage;
+    }}
+    async fetch(request) {{
+      if (new URL(request.url).pathname !== __wranglerAlarmConfig.objectPath) {{
+        return super.fetch(request);
+      }}
+      if (typeof this.alarm !== "function") {{
+        return new Response(`${{className}} has no alarm handler`, {{ status: 404 }});
+      }}
+      // Firing an alarm clears it, as the edge does before calling the handler
+      await this.__wranglerStorage.deleteAlarm();
+      await this.alarm();
+      return new Response(JSON.stringify({{ fired: this.__wranglerName() }}), {{
+        headers: {{ "Content-Type": "application/json" }},
+      }});
+    }}
+  }};
+  if (typeof Base.prototype.alarm === "function") {{
+    Wrapped.prototype.alarm = async function () {{
+      console.log(`[wrangler] ${{this.__wranglerName()}} alarm fired`);
+      return Base.prototype.alarm.call(this);
+    }};
+  }}
+  return Wrapped;
+}}
+function __wranglerFireAlarm(request, env) {{
+  const match = new URL(request.url).pathname.match(new RegExp(__wranglerAlarmConfig.route));
+  if (!match) {{
+    return undefined;
+  }}
+  const [className, name] = [decodeURIComponent(match[1]), decodeURIComponent(match[2])];
+  const binding = __wranglerAlarmConfig.bindings[className];
+  if (!binding) {{
+    return new Response(`${{className}} isn't a Durable Object class of this worker`, {{ status: 404 }});
+  }}
+  const namespace = env[binding];
+  const id = /^[0-9a-f]{{64}}$/.test(name) ? namespace.idFromString(name) : namespace.idFromName(name);
+  return namespace.get(id).fetch("https://durable-object" + __wranglerAlarmConfig.objectPath, {{ method: "POST" }});
+}}
+"#,
+            config
+        )
+    }
+}
+
+/// A response that outbound `fetch` calls to URLs matching `pattern` get.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockResponse {
+    pub pattern: String,
+    pub status: u16,
+    /// Base64, so fixtures needn't be text
+    pub body: Option<String>,
+    pub content_type: Option<String>,
+}
+
+impl MockResponse {
+    /// The responses `[dev.mocks]` describe, reading their fixtures, with the most specific,
+    /// i.e. longest, pattern first so it wins over the ones it overlaps.
+    pub fn from_config(mocks: &BTreeMap<String, Mock>) -> Result<Vec<MockResponse>> {
+        let mut responses = mocks
+            .iter()
+            .map(|(pattern, mock)| MockResponse::new(pattern, mock))
+            .collect::<Result<Vec<_>>>()?;
+        responses.sort_by_key(|response| std::cmp::Reverse(response.pattern.len()));
+        Ok(responses)
+    }
+
+    fn new(pattern: &str, mock: &Mock) -> Result<MockResponse> {
+        let pattern = pattern.to_string();
+        match mock {
+            Mock::Status(status) if (200..=599).contains(status) => Ok(MockResponse {
+                pattern,
+                status: *status,
+                body: None,
+                content_type: None,
+            }),
+            Mock::Status(status) => Err(anyhow!(
+                "The mock for {} has status {}, which isn't between 200 and 599",
+                pattern,
+                status
+            )),
+            Mock::Fixture(path) => {
+                let body = fs::read(path).map_err(|e| {
+                    anyhow!(
+                        "Could not read {}, the fixture of the mock for {}: {}",
+                        path.display(),
+                        pattern,
+                        e
+                    )
+                })?;
+                let content_type = match path.extension().and_then(|ext| ext.to_str()) {
+                    Some("json") => "application/json",
+                    Some("html") | Some("htm") => "text/html",
+                    Some("txt") => "text/plain",
+                    Some("xml") => "application/xml",
+                    Some("js") => "application/javascript",
+                    _ => "application/octet-stream",
+                };
+                Ok(MockResponse {
+                    pattern,
+                    status: 200,
+                    body: Some(base64::encode(body)),
+                    content_type: Some(content_type.to_string()),
+                })
+            }
+        }
+    }
+}
+
+/// The shim uploads of this run are wrapped in, if any.
+pub fn current() -> Option<&'static DevShim> {
+    SHIM.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn shims_carry_the_overrides() {
+        let mut cf = Map::new();
+        cf.insert("country".to_string(), json!("DE"));
+        let shim = DevShim {
+            cf,
+            connecting_ip: Some("203.0.113.7".to_string()),
+            cache: None,
+            mocks: Vec::new(),
+            fake_time: None,
+            alarms: BTreeMap::new(),
+        };
+
+        let script = shim.service_worker_shim();
+        assert!(script.contains(r#""cf":{"country":"DE"}"#));
+        assert!(script.contains(r#""connectingIp":"203.0.113.7""#));
+
+        let script = shim.modules_shim("src/index.mjs");
+        assert!(script.starts_with(
+            "import worker from \"./src/index.mjs\";\nimport * as __wranglerExports from \"./src/index.mjs\";\nexport * from \"./src/index.mjs\";\n"
+        ));
+        assert!(DevShim::default().is_empty());
+    }
+
+    #[test]
+    fn shims_carry_the_cache_emulation() {
+        let shim = DevShim {
+            cache: Some(DevCache {
+                mode: CacheEmulation::Memory,
+                max_size_mb: Some(2),
+            }),
+            ..DevShim::default()
+        };
+
+        assert!(!shim.is_empty());
+        assert!(shim
+            .service_worker_shim()
+            .contains(r#""cache":{"disabled":false,"maxSize":2097152}"#));
+    }
+
+    #[test]
+    fn more_specific_mocks_come_first() {
+        let mut mocks = BTreeMap::new();
+        mocks.insert("https://api.example.com/*".to_string(), Mock::Status(503));
+        mocks.insert(
+            "https://api.example.com/health".to_string(),
+            Mock::Status(204),
+        );
+
+        let responses = MockResponse::from_config(&mocks).unwrap();
+        let statuses: Vec<u16> = responses.iter().map(|response| response.status).collect();
+        assert_eq!(statuses, vec![204, 503]);
+
+        mocks.insert("https://example.com".to_string(), Mock::Status(42));
+        assert!(MockResponse::from_config(&mocks).is_err());
+    }
+
+    #[test]
+    fn shims_stop_the_clock_at_the_fake_time() {
+        let shim = DevShim {
+            fake_time: Some(Utc.ymd(2023, 1, 1).and_hms(0, 0, 0)),
+            ..DevShim::default()
+        };
+
+        assert!(shim
+            .modules_shim("index.mjs")
+            .contains(r#""fakeTime":1672531200000"#));
+    }
+
+    #[test]
+    fn durable_object_classes_are_wrapped_for_alarms() {
+        let mut alarms = BTreeMap::new();
+        alarms.insert("Room".to_string(), "ROOMS".to_string());
+        let shim = DevShim {
+            alarms,
+            ..DevShim::default()
+        };
+
+        assert!(!shim.is_empty());
+        let script = shim.modules_shim("index.mjs");
+        assert!(script
+            .contains("export const Room = __wranglerAlarms(__wranglerExports.Room, \"Room\");\n"));
+        assert!(script.contains(r#""bindings":{"Room":"ROOMS"}"#));
+        assert!(script.contains("function __wranglerAlarms(Base, className) {"));
+        assert!(script.contains("|| __wranglerFireAlarm(request, env)"));
+    }
+
+    #[test]
+    fn the_alarm_route_captures_the_class_and_id() {
+        let route = regex::Regex::new(ALARM_ROUTE).unwrap();
+        let captures = route.captures("/__do/Room/lobby/alarm").unwrap();
+        assert_eq!(&captures[1], "Room");
+        assert_eq!(&captures[2], "lobby");
+        assert!(!route.is_match("/__do/Room/alarm"));
+        assert!(!route.is_match("/__do/Room/lobby/alarm/now"));
+
+        let script = DevShim::default().modules_shim("index.mjs");
+        assert!(script.contains(r#""route":"^/__do/([^/]+)/([^/]+)/alarm$""#));
+    }
+}
@@ -1,21 +1,26 @@
+pub mod dev_shim;
 mod modules_worker;
 mod plain_text;
 mod project_assets;
 mod service_worker;
+mod settings;
 mod text_blob;
 mod wasm_module;
 
 use anyhow::Result;
 use reqwest::blocking::multipart::Form;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
+use crate::deploy::bindings::ScriptBinding;
 use crate::settings::binding;
 use crate::settings::toml::{Target, TargetType, UploadFormat, UsageModel};
 use crate::sites::AssetManifest;
 use crate::wranglerjs;
 
+pub use dev_shim::DevShim;
 use plain_text::PlainText;
 pub use project_assets::{ModuleConfig, ModuleType};
 use project_assets::{ModulesAssets, ServiceWorkerAssets};
@@ -23,17 +28,21 @@ use text_blob::TextBlob;
 use wasm_module::WasmModule;
 
 // TODO: https://github.com/cloudflare/wrangler/issues/1083
-use super::{krate, Package};
+use super::krate;
 
+/// Builds the upload form of the target's script. `annotations` describe the upload, e.g.
+/// where it was built from; previews don't have any.
 pub fn build(
     target: &Target,
     asset_manifest: Option<AssetManifest>,
     session_config: Option<serde_json::Value>,
+    annotations: Option<BTreeMap<String, String>>,
 ) -> Result<Form> {
     let target_type = &target.target_type;
     let compatibility_date = target.compatibility_date.clone();
     let compatibility_flags = target.compatibility_flags.clone();
     let kv_namespaces = &target.kv_namespaces;
+    let r2_buckets = &target.r2_buckets;
     let durable_object_classes = target
         .durable_objects
         .as_ref()
@@ -91,21 +100,20 @@ pub fn build(
                 compatibility_flags,
                 wasm_modules,
                 kv_namespaces: kv_namespaces.to_vec(),
+                r2_buckets: r2_buckets.to_vec(),
                 durable_object_classes,
                 text_blobs,
                 plain_texts,
                 usage_model,
             };
 
-            service_worker::build_form(&assets, session_config)
+            service_worker::build_form(&assets, session_config, annotations)
         }
         TargetType::JavaScript => match &target.build {
             Some(config) => match &config.upload {
                 UploadFormat::ServiceWorker {} => {
                     log::info!("Plain JavaScript project detected. Publishing...");
-                    let package_dir = target.package_dir()?;
-                    let package = Package::new(&package_dir)?;
-                    let script_path = package_dir.join(package.main(&package_dir)?);
+                    let script_path = target.service_worker_entry()?;
 
                     let assets = ServiceWorkerAssets {
                         script_path,
@@ -113,17 +121,18 @@ pub fn build(
                         compatibility_flags,
                         wasm_modules,
                         kv_namespaces: kv_namespaces.to_vec(),
+                        r2_buckets: r2_buckets.to_vec(),
                         durable_object_classes,
                         text_blobs,
                         plain_texts,
                         usage_model,
                     };
 
-                    service_worker::build_form(&assets, session_config)
+                    service_worker::build_form(&assets, session_config, annotations)
                 }
                 UploadFormat::Modules { main, dir, rules } => {
                     let migration = match &target.migrations {
-                        Some(migrations) => Some(migrations.api_migration()?),
+                        Some(migrations) => migrations.api_migration()?,
                         None => None,
                     };
 
@@ -133,20 +142,19 @@ pub fn build(
                         compatibility_flags,
                         module_config.get_modules()?,
                         kv_namespaces.to_vec(),
+                        r2_buckets.to_vec(),
                         durable_object_classes,
                         migration,
                         plain_texts,
                         usage_model,
                     )?;
 
-                    modules_worker::build_form(&assets, session_config)
+                    modules_worker::build_form(&assets, session_config, annotations)
                 }
             },
             None => {
                 log::info!("Plain JavaScript project detected. Publishing...");
-                let package_dir = target.package_dir()?;
-                let package = Package::new(&package_dir)?;
-                let script_path = package.main(&package_dir)?;
+                let script_path = target.service_worker_entry()?;
 
                 let assets = ServiceWorkerAssets {
                     script_path,
@@ -154,13 +162,14 @@ pub fn build(
                     compatibility_flags,
                     wasm_modules,
                     kv_namespaces: kv_namespaces.to_vec(),
+                    r2_buckets: r2_buckets.to_vec(),
                     durable_object_classes,
                     text_blobs,
                     plain_texts,
                     usage_model,
                 };
 
-                service_worker::build_form(&assets, session_config)
+                service_worker::build_form(&assets, session_config, annotations)
             }
         },
         TargetType::Webpack => {
@@ -184,17 +193,24 @@ pub fn build(
                 compatibility_flags,
                 wasm_modules,
                 kv_namespaces: kv_namespaces.to_vec(),
+                r2_buckets: r2_buckets.to_vec(),
                 durable_object_classes,
                 text_blobs,
                 plain_texts,
                 usage_model,
             };
 
-            service_worker::build_form(&assets, session_config)
+            service_worker::build_form(&assets, session_config, annotations)
         }
     }
 }
 
+/// Builds the form that changes the bindings, vars and compatibility settings of the deployed
+/// script, without uploading its code again.
+pub fn build_settings(target: &Target, deployed: &[ScriptBinding]) -> Result<Form> {
+    settings::build_form(target, deployed)
+}
+
 fn get_asset_manifest_blob(asset_manifest: AssetManifest) -> Result<String> {
     let asset_manifest = serde_json::to_string(&asset_manifest)?;
     Ok(asset_manifest)
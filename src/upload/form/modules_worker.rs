@@ -1,13 +1,16 @@
-use std::fs::File;
+use std::collections::BTreeMap;
+use std::fs;
 
 use anyhow::Result;
 use reqwest::blocking::multipart::{Form, Part};
 use serde::Serialize;
 
+use crate::parallel;
 use crate::settings::binding::Binding;
 use crate::settings::toml::migrations::ApiMigration;
 
-use super::{ModulesAssets, UsageModel};
+use super::dev_shim;
+use super::{ModuleType, ModulesAssets, UsageModel};
 
 #[derive(Serialize, Debug)]
 struct Metadata {
@@ -19,17 +22,20 @@ struct Metadata {
     pub compatibility_date: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub compatibility_flags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<BTreeMap<String, String>>,
 }
 
 pub fn build_form(
     assets: &ModulesAssets,
     session_config: Option<serde_json::Value>,
+    annotations: Option<BTreeMap<String, String>>,
 ) -> Result<Form> {
     let mut form = Form::new();
 
     // The preview service in particular streams the request form, and requires that the
     // "metadata" part be set first, so this order is important.
-    form = add_metadata(form, assets)?;
+    form = add_metadata(form, assets, annotations)?;
     form = add_files(form, assets)?;
     if let Some(session_config) = session_config {
         form = add_session_config(form, session_config)?
@@ -41,24 +47,48 @@ pub fn build_form(
     Ok(form)
 }
 
+// Projects can have hundreds of modules, so they're read concurrently. Reading them up front
+// also gives the request a known length instead of a chunked body.
 fn add_files(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
-    for (name, module) in &assets.manifest.modules {
-        let part = Part::reader(File::open(module.path.clone())?)
+    let paths = assets
+        .manifest
+        .modules
+        .values()
+        .map(|module| module.path.clone())
+        .collect();
+    let contents = parallel::map(paths, parallel::FILE_READS, |path| Ok(fs::read(path)?))?;
+
+    for ((name, module), contents) in assets.manifest.modules.iter().zip(contents) {
+        let part = Part::bytes(contents)
             .mime_str(module.module_type.content_type())?
             .file_name(name.clone());
         form = form.part(name.clone(), part);
     }
+    if let Some(overrides) = dev_shim::current() {
+        let part = Part::text(overrides.modules_shim(&assets.manifest.main))
+            .mime_str(ModuleType::ESModule.content_type())?
+            .file_name(dev_shim::SHIM_MODULE);
+        form = form.part(dev_shim::SHIM_MODULE, part);
+    }
     Ok(form)
 }
 
-fn add_metadata(mut form: Form, assets: &ModulesAssets) -> Result<Form> {
+fn add_metadata(
+    mut form: Form,
+    assets: &ModulesAssets,
+    annotations: Option<BTreeMap<String, String>>,
+) -> Result<Form> {
     let metadata_json = serde_json::json!(&Metadata {
-        main_module: assets.manifest.main.clone(),
+        main_module: match dev_shim::current() {
+            Some(_) => dev_shim::SHIM_MODULE.to_string(),
+            None => assets.manifest.main.clone(),
+        },
         bindings: assets.bindings(),
         migrations: assets.migration.clone(),
         usage_model: assets.usage_model,
         compatibility_date: assets.compatibility_date.clone(),
         compatibility_flags: assets.compatibility_flags.clone(),
+        annotations,
     });
 
     let metadata = Part::text(metadata_json.to_string())
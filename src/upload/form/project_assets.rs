@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use anyhow::{anyhow, Result};
 use globset::{Candidate, Glob, GlobBuilder, GlobSet, GlobSetBuilder};
 use ignore::WalkBuilder;
-use path_slash::PathExt; // Path::to_slash()
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::binding::Binding;
@@ -13,8 +13,9 @@ use super::text_blob::TextBlob;
 use super::wasm_module::WasmModule;
 use super::UsageModel;
 
+use crate::paths::relative_name;
 use crate::settings::toml::{
-    migrations::ApiMigration, DurableObjectsClass, KvNamespace, ModuleRule,
+    migrations::ApiMigration, DurableObjectsClass, KvNamespace, ModuleRule, R2Bucket,
 };
 use std::collections::{HashMap, HashSet};
 
@@ -25,6 +26,7 @@ pub struct ServiceWorkerAssets {
     pub compatibility_flags: Vec<String>,
     pub wasm_modules: Vec<WasmModule>,
     pub kv_namespaces: Vec<KvNamespace>,
+    pub r2_buckets: Vec<R2Bucket>,
     pub durable_object_classes: Vec<DurableObjectsClass>,
     pub text_blobs: Vec<TextBlob>,
     pub plain_texts: Vec<PlainText>,
@@ -43,6 +45,10 @@ impl ServiceWorkerAssets {
             let binding = kv.binding();
             bindings.push(binding);
         }
+        for bucket in &self.r2_buckets {
+            let binding = bucket.binding();
+            bindings.push(binding);
+        }
         for do_ns in &self.durable_object_classes {
             let binding = do_ns.binding();
             bindings.push(binding);
@@ -87,7 +93,7 @@ macro_rules! module_type {
     (pub enum $name:ident {
         $($globs:tt => $variant:ident($content_type:expr)),+,
     }) => {
-        #[derive(Clone, Copy, Debug, Deserialize, Hash, Serialize, PartialEq, PartialOrd, Eq, Ord)]
+        #[derive(Clone, Copy, Debug, Deserialize, Hash, JsonSchema, Serialize, PartialEq, PartialOrd, Eq, Ord)]
         pub enum $name {
             $($variant),+
         }
@@ -179,16 +185,11 @@ impl ModuleConfig {
         let processed_paths = paths
             .map(|p| {
                 let p = p.as_ref();
-                p.strip_prefix(upload_dir).map(|p_stripped_prefix| {
-                    let p_stripped_prefix: PathBuf = p_stripped_prefix.to_slash_lossy().into();
-                    // we convert the path used for matching and names to a slash path
-                    // so globs are the same on all platforms
-                    // to_slash_lossy() strips non-unicode characters in the path on windows
-                    // which couldn't be represented in JS anyways
-                    (p, p_stripped_prefix)
-                })
+                // the name used for matching and as the module name is a slash path,
+                // so globs and names are the same on all platforms
+                relative_name(p, upload_dir).map(|name| (p, name))
             })
-            .collect::<Result<Vec<(&Path, PathBuf)>, _>>()?;
+            .collect::<Result<Vec<(&Path, String)>>>()?;
 
         let mut final_types: HashSet<ModuleType> = HashSet::new();
         let modules: HashMap<_, _> = processed_paths
@@ -217,7 +218,7 @@ impl ModuleConfig {
                     match matches.len() {
                         0 => log::info!(
                             "{} skipped by rule {:?} => {}",
-                            path.display(),
+                            path,
                             globs,
                             module_type.name(),
                         ),
@@ -231,11 +232,11 @@ impl ModuleConfig {
                                 .collect::<Vec<_>>();
                             log::info!(
                                 "{} matched by these globs {:?} => {}",
-                                path.display(),
+                                path,
                                 matched_globs,
                                 module_type.name(),
                             );
-                            let module_name = format!("./{}", path.display());
+                            let module_name = format!("./{}", path);
                             match_result = Some((
                                 module_name,
                                 Module {
@@ -316,6 +317,7 @@ pub struct ModulesAssets {
     pub compatibility_flags: Vec<String>,
     pub manifest: ModuleManifest,
     pub kv_namespaces: Vec<KvNamespace>,
+    pub r2_buckets: Vec<R2Bucket>,
     pub durable_object_classes: Vec<DurableObjectsClass>,
     pub migration: Option<ApiMigration>,
     pub plain_texts: Vec<PlainText>,
@@ -329,6 +331,7 @@ impl ModulesAssets {
         compatibility_flags: Vec<String>,
         manifest: ModuleManifest,
         kv_namespaces: Vec<KvNamespace>,
+        r2_buckets: Vec<R2Bucket>,
         durable_object_classes: Vec<DurableObjectsClass>,
         migration: Option<ApiMigration>,
         plain_texts: Vec<PlainText>,
@@ -339,6 +342,7 @@ impl ModulesAssets {
             compatibility_flags,
             manifest,
             kv_namespaces,
+            r2_buckets,
             durable_object_classes,
             migration,
             plain_texts,
@@ -356,6 +360,10 @@ impl ModulesAssets {
             let binding = kv.binding();
             bindings.push(binding);
         }
+        for bucket in &self.r2_buckets {
+            let binding = bucket.binding();
+            bindings.push(binding);
+        }
         for class in &self.durable_object_classes {
             let binding = class.binding();
             bindings.push(binding);
@@ -443,6 +451,22 @@ mod tests {
         }
     }
 
+    #[cfg(windows)]
+    #[test]
+    fn windows_paths_get_the_same_names() -> Result<()> {
+        init();
+        test_success! {
+            ModuleConfig {
+                main: r"./foo/bar/index.mjs".to_string(),
+                dir: r"C:\worker\dist".into(),
+                rules: Vec::new(),
+            };
+            r"C:\worker\dist\foo\bar\index.mjs" => (r"./foo/bar/index.mjs", ESModule),
+            r"\\?\C:\worker\dist\foo\baz.cjs" => (r"./foo/baz.cjs", CommonJS),
+            r"C:\worker\dist\wat.txt" => None
+        }
+    }
+
     // The following two macros implement a simple DSL for specifying Vec<ModuleRule>
     // The first matches individual rules, and the second calls the first for each rule
     // The formats are:
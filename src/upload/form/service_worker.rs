@@ -1,9 +1,13 @@
+use std::collections::BTreeMap;
+use std::fs;
+
 use anyhow::Result;
 use reqwest::blocking::multipart::{Form, Part};
 use serde::Serialize;
 
 use crate::settings::binding::Binding;
 
+use super::dev_shim;
 use super::{ServiceWorkerAssets, UsageModel};
 
 #[derive(Serialize, Debug)]
@@ -15,17 +19,20 @@ struct Metadata {
     pub compatibility_date: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub compatibility_flags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<BTreeMap<String, String>>,
 }
 
 pub fn build_form(
     assets: &ServiceWorkerAssets,
     session_config: Option<serde_json::Value>,
+    annotations: Option<BTreeMap<String, String>>,
 ) -> Result<Form> {
     let mut form = Form::new();
 
     // The preview service in particular streams the request form, and requires that the
     // "metadata" part be set first, so this order is important.
-    form = add_metadata(form, assets)?;
+    form = add_metadata(form, assets, annotations)?;
     form = add_files(form, assets)?;
     if let Some(session_config) = session_config {
         form = add_session_config(form, session_config)?
@@ -38,7 +45,16 @@ pub fn build_form(
 }
 
 fn add_files(mut form: Form, assets: &ServiceWorkerAssets) -> Result<Form> {
-    form = form.file(assets.script_name()?, assets.script_path())?;
+    form = match dev_shim::current() {
+        Some(overrides) => {
+            let script = fs::read_to_string(assets.script_path())?;
+            let part = Part::text(format!("{}{}", overrides.service_worker_shim(), script))
+                .file_name(assets.script_name()?)
+                .mime_str("application/javascript")?;
+            form.part(assets.script_name()?, part)
+        }
+        None => form.file(assets.script_name()?, assets.script_path())?,
+    };
 
     for wasm_module in &assets.wasm_modules {
         form = form.file(wasm_module.filename(), wasm_module.path())?;
@@ -55,13 +71,18 @@ fn add_files(mut form: Form, assets: &ServiceWorkerAssets) -> Result<Form> {
     Ok(form)
 }
 
-fn add_metadata(mut form: Form, assets: &ServiceWorkerAssets) -> Result<Form> {
+fn add_metadata(
+    mut form: Form,
+    assets: &ServiceWorkerAssets,
+    annotations: Option<BTreeMap<String, String>>,
+) -> Result<Form> {
     let metadata_json = serde_json::json!(&Metadata {
         body_part: assets.script_name()?,
         bindings: assets.bindings(),
         usage_model: assets.usage_model,
         compatibility_date: assets.compatibility_date.clone(),
         compatibility_flags: assets.compatibility_flags.clone(),
+        annotations,
     });
 
     let metadata = Part::text(metadata_json.to_string())
@@ -0,0 +1,117 @@
+use anyhow::Result;
+use reqwest::blocking::multipart::{Form, Part};
+use serde::Serialize;
+
+use crate::deploy::bindings::ScriptBinding;
+use crate::settings::toml::Target;
+
+use super::binding::Binding;
+use super::plain_text::PlainText;
+use super::UsageModel;
+
+// Bindings that come with the code: they're kept as they are without it
+const CODE_BINDINGS: &[&str] = &["secret_text", "wasm_module", "text_blob"];
+const SITE_BINDINGS: &[&str] = &["__STATIC_CONTENT", "__STATIC_CONTENT_MANIFEST"];
+
+#[derive(Serialize, Debug)]
+struct Settings {
+    pub bindings: Vec<Binding>,
+    pub usage_model: Option<UsageModel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compatibility_date: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub compatibility_flags: Vec<String>,
+}
+
+/// Builds the form that updates the settings of the deployed script, without its code.
+/// `deployed` are the bindings it has now.
+pub fn build_form(target: &Target, deployed: &[ScriptBinding]) -> Result<Form> {
+    let settings = serde_json::json!(&Settings {
+        bindings: bindings(target, deployed)?,
+        usage_model: target.usage_model,
+        compatibility_date: target.compatibility_date.clone(),
+        compatibility_flags: target.compatibility_flags.clone(),
+    });
+
+    let part = Part::text(settings.to_string())
+        .file_name("settings.json")
+        .mime_str("application/json")?;
+    Ok(Form::new().part("settings", part))
+}
+
+fn bindings(target: &Target, deployed: &[ScriptBinding]) -> Result<Vec<Binding>> {
+    let mut bindings: Vec<Binding> = deployed
+        .iter()
+        .filter(|binding| {
+            CODE_BINDINGS.contains(&binding.binding_type.as_str())
+                || (target.site.is_some() && SITE_BINDINGS.contains(&binding.name.as_str()))
+        })
+        .map(|binding| Binding::new_inherit(binding.name.clone()))
+        .collect();
+
+    for kv in &target.kv_namespaces {
+        bindings.push(kv.binding());
+    }
+    for bucket in &target.r2_buckets {
+        bindings.push(bucket.binding());
+    }
+    for class in target
+        .durable_objects
+        .iter()
+        .flat_map(|durable_objects| durable_objects.classes.iter().flatten())
+    {
+        bindings.push(class.binding());
+    }
+    for (key, value) in target.vars.iter().flatten() {
+        bindings.push(PlainText::new(key.clone(), value.clone())?.binding());
+    }
+    Ok(bindings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::toml::Manifest;
+    use std::str::FromStr;
+
+    #[test]
+    fn bindings_that_come_with_the_code_are_kept() {
+        let manifest = Manifest::from_str(
+            r#"
+            name = "worker"
+            type = "javascript"
+            workers_dev = true
+            vars = { MODE = "production" }
+            kv_namespaces = [{ binding = "CACHE", id = "abc" }]
+            r2_buckets = [{ binding = "UPLOADS", bucket_name = "uploads" }]
+            "#,
+        )
+        .unwrap();
+        let target = manifest.get_target(None, false).unwrap();
+        let deployed = vec![
+            ScriptBinding {
+                binding_type: "secret_text".to_string(),
+                name: "API_KEY".to_string(),
+            },
+            ScriptBinding {
+                binding_type: "kv_namespace".to_string(),
+                name: "OLD_CACHE".to_string(),
+            },
+            ScriptBinding {
+                binding_type: "kv_namespace".to_string(),
+                name: "__STATIC_CONTENT".to_string(),
+            },
+        ];
+
+        let bindings = serde_json::to_value(bindings(&target, &deployed).unwrap()).unwrap();
+        assert_eq!(
+            bindings,
+            serde_json::json!([
+                { "type": "inherit", "name": "API_KEY" },
+                { "type": "kv_namespace", "name": "CACHE", "namespace_id": "abc" },
+                { "type": "r2_bucket", "name": "UPLOADS", "bucket_name": "uploads" },
+                { "type": "plain_text", "name": "MODE", "text": "production" },
+            ])
+        );
+    }
+}
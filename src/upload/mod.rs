@@ -1,45 +1,187 @@
+pub mod fingerprint;
 pub mod form;
 mod krate;
 pub mod package;
+pub mod provenance;
+
+use std::io::Cursor;
+use std::sync::Arc;
 
-use indicatif::{ProgressBar, ProgressStyle};
 pub use package::Package;
 
-use anyhow::Result;
-use reqwest::blocking::Client;
+use anyhow::{anyhow, Result};
+use reqwest::blocking::multipart::Form;
+use reqwest::blocking::{Body, Client};
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
+use serde::Deserialize;
 
+use crate::deploy::bindings::ScriptBinding;
+use crate::deploy::deployed;
+use crate::error::{ErrorCategory, WranglerError};
+use crate::http;
 use crate::settings::toml::Target;
 use crate::sites::AssetManifest;
+use crate::terminal::events::{self, Event};
+use crate::terminal::message::{Message, StdErr};
+use crate::terminal::progress::{Progress, ProgressReader};
+
+/// What the API reports back about an uploaded script.
+#[derive(Debug, Default, Deserialize)]
+pub struct UploadedScript {
+    /// Identifies this version of the script.
+    pub etag: Option<String>,
+    pub modified_on: Option<String>,
+    /// Set when the upload was skipped because nothing changed since the last one.
+    #[serde(skip)]
+    pub unchanged: bool,
+}
+
+#[derive(Deserialize)]
+struct UploadResponse {
+    result: UploadedScript,
+}
 
 pub fn script(
     client: &Client,
     target: &Target,
     asset_manifest: Option<AssetManifest>,
-) -> Result<()> {
+) -> Result<UploadedScript> {
     let worker_addr = format!(
         "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}",
         target.account_id.load()?,
         target.name,
     );
 
-    let script_upload_form = form::build(target, asset_manifest, None)?;
+    // only the migrations the deployed script hasn't had yet are sent
+    let mut target = target.clone();
+    if let Some(migrations) = &mut target.migrations {
+        if migrations.migrations.iter().any(|m| m.tag.is_some()) {
+            migrations.old_tag =
+                deployed::migration_tag(client, target.account_id.load()?, &target.name)?;
+        }
+    }
+
+    let annotations = provenance::Provenance::collect().annotations();
+    events::emit(Event::UploadStarted {
+        script: &target.name,
+    });
+    let mut attempt = 1;
+    let res = loop {
+        let script_upload_form = form::build(
+            &target,
+            asset_manifest.clone(),
+            None,
+            Some(annotations.clone()),
+        )?;
 
-    let style = ProgressStyle::default_spinner().template("{spinner}   {msg}");
-    let spinner = ProgressBar::new_spinner().with_style(style);
-    spinner.set_message("Uploading script...");
-    spinner.enable_steady_tick(20);
+        let (content_type, body) = multipart_body(client, &worker_addr, script_upload_form)?;
+        let size = body.len() as u64;
+        let progress = Arc::new(Progress::new("Uploading script", "scripts", 1, size));
+        let body = Body::sized(
+            ProgressReader::new(Cursor::new(body), progress.clone()),
+            size,
+        );
 
-    let res = client
-        .put(&worker_addr)
-        .multipart(script_upload_form)
-        .send()?;
+        let res = http::trace::send(
+            client,
+            client
+                .put(&worker_addr)
+                .header(CONTENT_TYPE, content_type)
+                .body(body),
+        );
+        if res.is_ok() {
+            progress.inc(1, 0);
+        }
+        progress.finish();
+        let res = res?;
 
-    spinner.finish_and_clear();
+        if !http::retry::wait_for_next_attempt(res.status(), attempt) {
+            break res;
+        }
+        attempt += 1;
+    };
+
+    let status = res.status();
+    events::emit(Event::UploadFinished {
+        script: &target.name,
+        success: status.is_success(),
+    });
+    let text = res.text()?;
+    if !status.is_success() {
+        return Err(WranglerError::new(
+            ErrorCategory::Api,
+            "api.upload_failed",
+            anyhow!(error_msg(text)),
+        ));
+    }
+
+    // The upload already succeeded, so an unexpected response body isn't worth failing over
+    Ok(serde_json::from_str::<UploadResponse>(&text)
+        .map(|response| response.result)
+        .unwrap_or_default())
+}
+
+// The bytes of a multipart form and its content type, which names the boundary between its
+// parts. reqwest only hands these out through a request, so one is built to get them.
+fn multipart_body(client: &Client, addr: &str, form: Form) -> Result<(HeaderValue, Vec<u8>)> {
+    let mut request = client.put(addr).multipart(form).build()?;
+    let content_type = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .cloned()
+        .ok_or_else(|| anyhow!("the script upload has no content type"))?;
+    let body = match request.body_mut() {
+        Some(body) => body.buffer()?.to_vec(),
+        None => Vec::new(),
+    };
+    Ok((content_type, body))
+}
 
+/// Updates the bindings, vars and compatibility settings of the deployed script to those of
+/// `target`, keeping its code. `deployed` are the bindings it has now.
+pub fn settings(client: &Client, target: &Target, deployed: &[ScriptBinding]) -> Result<()> {
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/settings",
+        target.account_id.load()?,
+        target.name,
+    );
+    let form = form::build_settings(target, deployed)?;
+    StdErr::working("Updating settings...");
+    let res = http::trace::send(client, client.patch(&addr).multipart(form))?;
     if !res.status().is_success() {
-        anyhow::bail!(error_msg(res.text()?))
+        return Err(WranglerError::new(
+            ErrorCategory::Api,
+            "api.settings_failed",
+            anyhow!(error_msg(res.text()?)),
+        ));
+    }
+    Ok(())
+}
+
+/// Sets the tags in the configuration file on the uploaded script, which `wrangler list --tag`
+/// filters on.
+pub fn tags(client: &Client, target: &Target) -> Result<()> {
+    let tags = match &target.tags {
+        Some(tags) => tags,
+        None => return Ok(()),
+    };
+    if let Some(tag) = tags.iter().find(|tag| tag.is_empty() || tag.contains(',')) {
+        anyhow::bail!("The tag {:?} can't be empty or contain commas", tag);
     }
 
+    let addr = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/workers/scripts/{}/tags",
+        target.account_id.load()?,
+        target.name,
+    );
+    let res = http::trace::send(client, client.put(&addr).json(tags))?;
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "Could not tag {}: {}",
+            target.name,
+            crate::format_api_errors(res.text()?)
+        );
+    }
     Ok(())
 }
 
@@ -13,6 +13,8 @@ pub struct Package {
     main: PathBuf,
     #[serde(default)]
     module: PathBuf,
+    #[serde(rename = "type")]
+    package_type: Option<String>,
 }
 impl Package {
     pub fn main(&self, package_dir: &Path) -> Result<PathBuf> {
@@ -27,6 +29,11 @@ impl Package {
             Ok(self.main.clone())
         }
     }
+
+    /// Whether `.js` files in this package are ES modules (`"type": "module"`).
+    pub fn is_module(&self) -> bool {
+        self.package_type.as_deref() == Some("module")
+    }
 }
 
 impl Package {
@@ -0,0 +1,153 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::process::Command;
+
+// The API caps the length of a deployment message
+const MAX_MESSAGE_LENGTH: usize = 100;
+
+/// Where a deployment was built from: the commit, branch and CI run, and the wrangler that
+/// published it. Sent with each upload, so a deployment can be traced back to its source.
+#[derive(Debug, Default, PartialEq)]
+pub struct Provenance {
+    pub commit: Option<String>,
+    pub branch: Option<String>,
+    pub run_url: Option<String>,
+    pub wrangler_version: String,
+}
+
+impl Provenance {
+    /// Reads the commit and branch from the CI environment, or from git outside CI.
+    pub fn collect() -> Provenance {
+        let mut provenance = from_env(|name| env::var(name).ok().filter(|v| !v.is_empty()));
+        if provenance.commit.is_none() {
+            provenance.commit = git(&["rev-parse", "HEAD"]);
+        }
+        if provenance.branch.is_none() {
+            provenance.branch =
+                git(&["rev-parse", "--abbrev-ref", "HEAD"]).filter(|branch| branch != "HEAD");
+        }
+        provenance
+    }
+
+    /// The script annotations that record the provenance.
+    pub fn annotations(&self) -> BTreeMap<String, String> {
+        let mut annotations = BTreeMap::new();
+        annotations.insert("workers/message".to_string(), self.message());
+        if let Some(commit) = &self.commit {
+            annotations.insert("workers/tag".to_string(), short(commit).to_string());
+        }
+        annotations
+    }
+
+    // e.g. "3f2a9c1b4e7d (main) wrangler 1.18.0 https://github.com/org/repo/actions/runs/1",
+    // leaving the run out when it doesn't fit
+    fn message(&self) -> String {
+        let mut message = String::new();
+        if let Some(commit) = &self.commit {
+            message.push_str(short(commit));
+            message.push(' ');
+        }
+        if let Some(branch) = &self.branch {
+            message.push_str(&format!("({}) ", branch));
+        }
+        message.push_str(&format!("wrangler {}", self.wrangler_version));
+        if let Some(run_url) = &self.run_url {
+            if message.len() + 1 + run_url.len() <= MAX_MESSAGE_LENGTH {
+                message.push(' ');
+                message.push_str(run_url);
+            }
+        }
+        message.chars().take(MAX_MESSAGE_LENGTH).collect()
+    }
+}
+
+fn short(commit: &str) -> &str {
+    commit.get(..12).unwrap_or(commit)
+}
+
+// GitHub Actions, GitLab CI, CircleCI and Buildkite
+fn from_env(var: impl Fn(&str) -> Option<String>) -> Provenance {
+    let first = |names: &[&str]| names.iter().find_map(|name| var(name));
+    let github_run = match (
+        var("GITHUB_SERVER_URL"),
+        var("GITHUB_REPOSITORY"),
+        var("GITHUB_RUN_ID"),
+    ) {
+        (Some(server), Some(repository), Some(run)) => {
+            Some(format!("{}/{}/actions/runs/{}", server, repository, run))
+        }
+        _ => None,
+    };
+
+    Provenance {
+        commit: first(&[
+            "GITHUB_SHA",
+            "CI_COMMIT_SHA",
+            "CIRCLE_SHA1",
+            "BUILDKITE_COMMIT",
+        ]),
+        branch: first(&[
+            "GITHUB_HEAD_REF",
+            "GITHUB_REF_NAME",
+            "CI_COMMIT_REF_NAME",
+            "CIRCLE_BRANCH",
+            "BUILDKITE_BRANCH",
+        ]),
+        run_url: github_run
+            .or_else(|| first(&["CI_JOB_URL", "CIRCLE_BUILD_URL", "BUILDKITE_BUILD_URL"])),
+        wrangler_version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_actions_runs_are_recorded() {
+        let vars: BTreeMap<&str, &str> = vec![
+            ("GITHUB_SHA", "3f2a9c1b4e7d8a6f5c0b9e2d1a3c4b5e6f7a8b9c"),
+            ("GITHUB_HEAD_REF", ""),
+            ("GITHUB_REF_NAME", "main"),
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "org/repo"),
+            ("GITHUB_RUN_ID", "42"),
+        ]
+        .into_iter()
+        .collect();
+        let provenance = from_env(|name| {
+            vars.get(name)
+                .map(|v| v.to_string())
+                .filter(|v| !v.is_empty())
+        });
+
+        assert_eq!(provenance.branch.as_deref(), Some("main"));
+        let annotations = provenance.annotations();
+        assert_eq!(annotations["workers/tag"], "3f2a9c1b4e7d");
+        assert_eq!(
+            annotations["workers/message"],
+            format!(
+                "3f2a9c1b4e7d (main) wrangler {} https://github.com/org/repo/actions/runs/42",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    #[test]
+    fn long_run_urls_are_left_out() {
+        let provenance = Provenance {
+            run_url: Some(format!("https://ci.example.com/{}", "x".repeat(100))),
+            wrangler_version: "1.18.0".to_string(),
+            ..Provenance::default()
+        };
+        assert_eq!(provenance.message(), "wrangler 1.18.0");
+    }
+}
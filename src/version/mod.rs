@@ -5,6 +5,7 @@ use std::sync::mpsc;
 use std::thread;
 use std::time::SystemTime;
 
+use crate::http;
 use crate::settings::get_wrangler_home_dir;
 
 use anyhow::Result;
@@ -135,8 +136,7 @@ fn get_latest_version_from_api(installed_version: &str) -> Result<Version> {
         installed_version,
         env!("CARGO_PKG_REPOSITORY")
     );
-    let client = reqwest::blocking::Client::builder().build()?;
-    let response = client
+    let response = http::client()
         .get(url)
         .header(USER_AGENT, user_agent)
         .send()?
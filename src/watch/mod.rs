@@ -1,16 +1,19 @@
 mod watcher;
 use ignore::overrides::OverrideBuilder;
 use ignore::WalkBuilder;
-pub use watcher::wait_for_changes;
+pub use watcher::{wait_for_changes, wait_for_relevant_changes};
 
-use crate::settings::toml::{Target, TargetType};
+use crate::settings::toml::{Target, TargetType, UploadFormat};
 use crate::terminal::message::{Message, StdOut};
+use crate::upload::fingerprint;
+use crate::upload::form::ModuleConfig;
 use crate::wranglerjs;
 use crate::{build::command, build_target};
 use crate::{commands, install};
 
 use anyhow::Result;
 use notify::{self, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, SendError, Sender};
 use std::thread;
 use std::time::Duration;
@@ -35,6 +38,9 @@ pub fn watch_and_build(
         TargetType::JavaScript => {
             let target = target.clone();
             thread::spawn::<_, Result<()>>(move || {
+                // What was uploaded last, so rebuilds that produce the same worker aren't
+                // uploaded again
+                let mut last_upload = fingerprint::compute(&target, None).ok();
                 let (watcher_tx, watcher_rx) = mpsc::channel();
                 let mut watcher = notify::watcher(watcher_tx, Duration::from_secs(1))?;
 
@@ -44,14 +50,17 @@ pub fn watch_and_build(
                         StdOut::info(&format!("watching {:?}", &JAVASCRIPT_PATH));
 
                         loop {
-                            match wait_for_changes(
+                            match wait_for_relevant_changes(
                                 &watcher_rx,
                                 refresh_session_sender.clone(),
                                 COOLDOWN_PERIOD,
+                                |path| is_build_input(&target, path),
                             ) {
                                 Ok(_path) => {
-                                    if let Some(tx) = tx.clone() {
-                                        tx.send(())?;
+                                    if changed(&target, &mut last_upload) {
+                                        if let Some(tx) = tx.clone() {
+                                            tx.send(())?;
+                                        }
                                     }
                                 }
                                 Err(e) => {
@@ -68,16 +77,19 @@ pub fn watch_and_build(
                         watcher.watch(config.watch_dir, notify::RecursiveMode::Recursive)?;
 
                         loop {
-                            match wait_for_changes(
+                            match wait_for_relevant_changes(
                                 &watcher_rx,
                                 refresh_session_sender.clone(),
                                 COOLDOWN_PERIOD,
+                                |path| is_build_input(&target, path),
                             ) {
                                 Ok(_path) => match build_target(&target) {
                                     Ok(output) => {
                                         StdOut::success(&output);
-                                        if let Some(tx) = tx.clone() {
-                                            tx.send(())?;
+                                        if changed(&target, &mut last_upload) {
+                                            if let Some(tx) = tx.clone() {
+                                                tx.send(())?;
+                                            }
                                         }
                                     }
                                     Err(e) => StdOut::user_error(&e.to_string()),
@@ -156,3 +168,62 @@ pub fn watch_and_build(
 
     Ok(())
 }
+
+// Whether a change to `path` can change what's uploaded for `target`, so edits to other files
+// (docs, editor swap files, the build output itself) don't trigger a rebuild. When in doubt,
+// e.g. for deleted files, it says yes.
+fn is_build_input(target: &Target, path: &Path) -> bool {
+    if !path.exists() {
+        return true;
+    }
+    let path = canonical(path);
+
+    match &target.build {
+        Some(build) => match &build.upload {
+            // Without a build command the modules are uploaded as they are
+            UploadFormat::Modules { main, dir, rules } if build.command.is_none() => {
+                ModuleConfig::new(main, dir, rules)
+                    .get_modules()
+                    .map(|manifest| {
+                        manifest
+                            .modules
+                            .values()
+                            .any(|module| canonical(&module.path) == path)
+                    })
+                    .unwrap_or(true)
+            }
+            UploadFormat::Modules { dir, .. } => !path.starts_with(canonical(dir)),
+            UploadFormat::ServiceWorker {} => true,
+        },
+        None => {
+            let mut inputs = match target.service_worker_entry() {
+                Ok(entry) => vec![entry],
+                Err(_) => return true,
+            };
+            for files in [&target.wasm_modules, &target.text_blobs].iter() {
+                if let Some(files) = files {
+                    inputs.extend(files.values().cloned());
+                }
+            }
+            inputs.iter().any(|input| canonical(input) == path)
+        }
+    }
+}
+
+// Compares what would be uploaded now with `last_upload`, and remembers it for next time
+fn changed(target: &Target, last_upload: &mut Option<String>) -> bool {
+    let current = match fingerprint::compute(target, None) {
+        Ok(current) => current,
+        Err(_) => return true,
+    };
+    if last_upload.as_ref() == Some(&current) {
+        StdOut::info("The worker didn't change, skipping the upload");
+        return false;
+    }
+    *last_upload = Some(current);
+    true
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
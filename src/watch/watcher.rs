@@ -1,6 +1,6 @@
 use notify::DebouncedEvent;
 use std::{
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::mpsc::{Receiver, Sender},
     time::Duration,
 };
@@ -16,6 +16,19 @@ pub fn wait_for_changes(
     check_channel: Option<Sender<Option<()>>>,
     cooldown: Duration,
 ) -> Result<PathBuf> {
+    wait_for_relevant_changes(rx, check_channel, cooldown, |_| true)
+}
+
+// Like `wait_for_changes`, but changes to paths `relevant` rejects are ignored
+pub fn wait_for_relevant_changes<F>(
+    rx: &Receiver<DebouncedEvent>,
+    check_channel: Option<Sender<Option<()>>>,
+    cooldown: Duration,
+    relevant: F,
+) -> Result<PathBuf>
+where
+    F: Fn(&Path) -> bool,
+{
     loop {
         let event = rx.recv()?;
         // Sending a None to the channel will only succeed if there is a
@@ -24,6 +37,10 @@ pub fn wait_for_changes(
             check_channel.send(None)?;
         }
         match get_changed_path_from_event(event) {
+            Ok(Some(path)) if !relevant(&path) => {
+                info!("Ignoring change to {}", path.display());
+                continue;
+            }
             Ok(Some(path)) => {
                 StdOut::working("Detected changes...");
                 // wait for cooldown